@@ -0,0 +1,570 @@
+//! Sealed-bid auction mechanisms
+//!
+//! Reference implementation of [`AuctionMechanism`] for procurement
+//! (reverse) auctions: agents bid the cost at which they will complete a task
+//! and the requester buys from the best-scoring bid. Scores combine the bid
+//! value with the promised quality, the promised completion time and the
+//! agent's [`Reputation`], so a slightly more expensive but better agent can
+//! win.
+//!
+//! Under [`PricingRule::SecondPrice`] the winner is paid the highest value it
+//! could have bid and still won (capped at `Budget::max_cost`), which makes
+//! bidding the true cost a dominant strategy. All arithmetic is integer-only
+//! so every node derives the same allocation from the same bids.
+//!
+//! Redundant execution buys the same task from several agents:
+//! [`SealedBidAuction::with_winners`] selects the best `k` bids, each paid
+//! against the best losing bid.
+
+use crate::{constants, errors::*, traits::*, types::*};
+use alloc::vec::Vec;
+
+/// Basis-point denominator used for weights and premiums.
+pub const BASIS_POINTS: u32 = 10_000;
+
+/// How the winning agent is paid.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum PricingRule {
+    /// Pay the winner its own bid value.
+    FirstPrice,
+    /// Pay the winner the threshold value at which it would stop winning
+    /// (Vickrey pricing); truthful bidding is a dominant strategy.
+    #[default]
+    SecondPrice,
+}
+
+/// Weights used to turn a bid's non-price attributes into a score bonus.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ScoringRule {
+    /// Weight of `Bid::quality_score`, in basis points.
+    pub quality_weight: u32,
+    /// Weight of the promised completion time, in basis points.
+    pub speed_weight: u32,
+    /// Weight of the agent's reputation, in basis points.
+    pub reputation_weight: u32,
+    /// Share of `Budget::max_cost` that a perfect bid is worth on top of its
+    /// price, in basis points.
+    pub quality_premium: u32,
+    /// Completion time (seconds) at or beyond which a bid earns no speed credit.
+    pub reference_completion_time: u64,
+}
+
+impl Default for ScoringRule {
+    fn default() -> Self {
+        Self {
+            quality_weight: 5_000,
+            speed_weight: 2_500,
+            reputation_weight: 2_500,
+            quality_premium: 2_000,
+            reference_completion_time: constants::task::DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+impl ScoringRule {
+    /// Ensure the weights sum to 100% and the premium is at most 100%.
+    pub fn validate(&self) -> Result<()> {
+        let total =
+            self.quality_weight as u64 + self.speed_weight as u64 + self.reputation_weight as u64;
+        if total != BASIS_POINTS as u64 {
+            return Err(CoreError::OutOfRange {
+                value: total as u128,
+                min: BASIS_POINTS as u128,
+                max: BASIS_POINTS as u128,
+            });
+        }
+        if self.quality_premium > BASIS_POINTS {
+            return Err(CoreError::OutOfRange {
+                value: self.quality_premium as u128,
+                min: 0,
+                max: BASIS_POINTS as u128,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Sealed-bid auction for a single task.
+///
+/// Bids are collected with [`AuctionMechanism::add_bid`] and cleared with
+/// [`AuctionMechanism::run_auction`]. Reputations are optional; agents without
+/// one are scored at [`constants::reputation::INITIAL_SCORE`].
+#[derive(Clone, Debug)]
+pub struct SealedBidAuction {
+    task_id: TaskId,
+    budget: Budget,
+    pricing: PricingRule,
+    scoring: ScoringRule,
+    reputations: Vec<(AgentId, Reputation)>,
+    bids: Vec<Bid>,
+    winners: usize,
+}
+
+impl SealedBidAuction {
+    /// Create a second-price auction for `task_id` constrained by `budget`.
+    pub fn new(task_id: TaskId, budget: Budget) -> Self {
+        Self {
+            task_id,
+            budget,
+            pricing: PricingRule::default(),
+            scoring: ScoringRule::default(),
+            reputations: Vec::new(),
+            bids: Vec::new(),
+            winners: 1,
+        }
+    }
+
+    /// Allocate the task to the best `count` bids (at least one).
+    pub fn with_winners(mut self, count: usize) -> Self {
+        self.winners = count.max(1);
+        self
+    }
+
+    /// Use the given pricing rule.
+    pub fn with_pricing(mut self, pricing: PricingRule) -> Self {
+        self.pricing = pricing;
+        self
+    }
+
+    /// Use the given scoring rule.
+    pub fn with_scoring(mut self, scoring: ScoringRule) -> Result<Self> {
+        scoring.validate()?;
+        self.scoring = scoring;
+        Ok(self)
+    }
+
+    /// Record the reputation of an agent, replacing any previous entry.
+    pub fn set_reputation(&mut self, agent_id: AgentId, reputation: Reputation) {
+        match self.reputations.iter_mut().find(|(id, _)| *id == agent_id) {
+            Some(entry) => entry.1 = reputation,
+            None => self.reputations.push((agent_id, reputation)),
+        }
+    }
+
+    /// Bids collected so far, in submission order.
+    pub fn bids(&self) -> &[Bid] {
+        &self.bids
+    }
+
+    /// Score of a bid: the requester's valuation (`max_cost` plus the quality
+    /// bonus) minus the bid value. Higher is better.
+    pub fn score(&self, bid: &Bid) -> u128 {
+        self.budget
+            .max_cost
+            .saturating_sub(bid.value)
+            .saturating_add(self.bonus(bid))
+    }
+
+    /// Amount the requester is willing to pay on top of the price for the
+    /// bid's quality, speed and the agent's reputation.
+    fn bonus(&self, bid: &Bid) -> u128 {
+        let quality = bid.quality_score.min(constants::reputation::MAX_SCORE) * 100;
+        let speed = self.speed_credit(bid.completion_time);
+        let reputation = self.reputation_credit(&bid.agent_id);
+
+        let weighted = (self.scoring.quality_weight as u64 * quality as u64
+            + self.scoring.speed_weight as u64 * speed as u64
+            + self.scoring.reputation_weight as u64 * reputation as u64)
+            / BASIS_POINTS as u64;
+
+        let premium = mul_bps(self.budget.max_cost, self.scoring.quality_premium);
+        mul_bps(premium, weighted as u32)
+    }
+
+    /// Speed credit in basis points: full credit for instant completion, none
+    /// at or beyond the reference completion time.
+    fn speed_credit(&self, completion_time: u64) -> u32 {
+        let reference = self.scoring.reference_completion_time;
+        if reference == 0 {
+            return 0;
+        }
+        let remaining = reference.saturating_sub(completion_time) as u128;
+        (remaining * BASIS_POINTS as u128 / reference as u128) as u32
+    }
+
+    /// Reputation credit in basis points: the mean of the four reputation
+    /// dimensions.
+    fn reputation_credit(&self, agent_id: &AgentId) -> u32 {
+        let mean = self
+            .reputations
+            .iter()
+            .find(|(id, _)| id == agent_id)
+            .map(|(_, r)| r.overall())
+            .unwrap_or(constants::reputation::INITIAL_SCORE);
+        mean * 100
+    }
+
+    /// Bid indices ordered best-first. Ties go to the earlier bid.
+    fn ranking(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.bids.len()).collect();
+        order.sort_by(|a, b| {
+            self.score(&self.bids[*b])
+                .cmp(&self.score(&self.bids[*a]))
+                .then(a.cmp(b))
+        });
+        order
+    }
+
+    /// Indices of the winning bids, best-first.
+    fn winning(&self) -> Vec<usize> {
+        let mut ranking = self.ranking();
+        ranking.truncate(self.winners);
+        ranking
+    }
+
+    /// Payment owed to the bid at `index` if it wins alongside `winners`
+    /// against every other bid.
+    fn clearing_payment(&self, index: usize, winners: &[usize]) -> u128 {
+        let bid = &self.bids[index];
+        match self.pricing {
+            PricingRule::FirstPrice => bid.value,
+            PricingRule::SecondPrice => {
+                // The reserve is a bid at the full budget with no bonus, which
+                // scores zero.
+                let runner_up = self
+                    .bids
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| *i != index && !winners.contains(i))
+                    .map(|(_, other)| self.score(other))
+                    .max()
+                    .unwrap_or(0);
+                let margin = self.score(bid).saturating_sub(runner_up);
+                bid.value.saturating_add(margin).min(self.budget.max_cost)
+            }
+        }
+    }
+}
+
+impl AuctionMechanism for SealedBidAuction {
+    fn add_bid(&mut self, bid: Bid) -> Result<()> {
+        if bid.task_id != self.task_id {
+            return Err(CoreError::InvalidFormat(
+                "bid targets a different task".into(),
+            ));
+        }
+        if bid.value > self.budget.max_cost {
+            return Err(CoreError::EconomicConstraintViolation {
+                constraint: alloc::format!(
+                    "bid value {} exceeds budget {}",
+                    bid.value,
+                    self.budget.max_cost
+                ),
+            });
+        }
+        if bid.quality_score > constants::reputation::MAX_SCORE {
+            return Err(CoreError::OutOfRange {
+                value: bid.quality_score as u128,
+                min: 0,
+                max: constants::reputation::MAX_SCORE as u128,
+            });
+        }
+        if self.bids.iter().any(|b| b.agent_id == bid.agent_id) {
+            return Err(CoreError::EconomicConstraintViolation {
+                constraint: "one sealed bid per agent".into(),
+            });
+        }
+        self.bids.push(bid);
+        Ok(())
+    }
+
+    fn run_auction(&self) -> Result<AuctionResult> {
+        if self.bids.len() < self.winners {
+            return Err(AuctionError::InsufficientBids {
+                available: self.bids.len(),
+                required: self.winners,
+            }
+            .into());
+        }
+        let winners = self.winning();
+        let mut result = AuctionResult {
+            allocations: Vec::with_capacity(winners.len()),
+            payments: Vec::with_capacity(winners.len()),
+            social_welfare: 0,
+        };
+        for &winner in &winners {
+            let bid = self.bids[winner].clone();
+            result
+                .payments
+                .push((bid.agent_id, self.clearing_payment(winner, &winners)));
+            result.social_welfare = result.social_welfare.saturating_add(self.score(&bid));
+            result.allocations.push(Allocation {
+                task_id: self.task_id,
+                agent_id: bid.agent_id,
+                bid,
+            });
+        }
+        Ok(result)
+    }
+
+    fn calculate_payments(&self, allocation: &Allocation) -> Result<PaymentSchedule> {
+        let index = self
+            .bids
+            .iter()
+            .position(|b| b.agent_id == allocation.agent_id)
+            .ok_or_else(|| CoreError::InvalidFormat("allocation has no matching bid".into()))?;
+        let payment = self.clearing_payment(index, &self.winning());
+
+        Ok(match &self.budget.payment_schedule {
+            PaymentSchedule::Milestone(tranches) => {
+                PaymentSchedule::Milestone(rescale_tranches(tranches, payment))
+            }
+            other => other.clone(),
+        })
+    }
+}
+
+/// `amount * bps / 10_000`, rounded down, without intermediate overflow.
+pub(crate) fn mul_bps(amount: u128, bps: u32) -> u128 {
+    let bps = bps.min(BASIS_POINTS) as u128;
+    let base = BASIS_POINTS as u128;
+    (amount / base) * bps + (amount % base) * bps / base
+}
+
+/// Scale milestone tranches so they sum to `payment`, keeping their relative
+/// weights. Rounding dust is added to the final tranche.
+pub fn rescale_tranches(tranches: &[(Milestone, u128)], payment: u128) -> Vec<(Milestone, u128)> {
+    let total: u128 = tranches.iter().map(|(_, amount)| *amount).sum();
+    if total == 0 {
+        return tranches.to_vec();
+    }
+    let mut out: Vec<(Milestone, u128)> = tranches
+        .iter()
+        .map(|(m, amount)| {
+            // Exact for realistic budgets; fall back to a coarser split if the
+            // product would overflow.
+            let scaled = amount
+                .checked_mul(payment)
+                .map(|v| v / total)
+                .unwrap_or_else(|| payment / total * amount);
+            (m.clone(), scaled)
+        })
+        .collect();
+    let assigned: u128 = out.iter().map(|(_, amount)| *amount).sum();
+    if let Some(last) = out.last_mut() {
+        last.1 = last.1.saturating_add(payment.saturating_sub(assigned));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const TASK: TaskId = TaskId::new([7u8; 32]);
+
+    fn budget(max_cost: u128) -> Budget {
+        Budget {
+            max_cost,
+            payment_schedule: PaymentSchedule::OnCompletion,
+            escrow_required: true,
+        }
+    }
+
+    fn bid(agent: u8, value: u128, quality_score: u32, completion_time: u64) -> Bid {
+        Bid {
+            agent_id: AgentId::new([agent; 32]),
+            task_id: TASK,
+            value,
+            quality_score,
+            completion_time,
+            guarantees: Vec::new(),
+        }
+    }
+
+    fn payment_to(result: &AuctionResult, agent: u8) -> Option<u128> {
+        result
+            .payments
+            .iter()
+            .find(|(id, _)| *id == AgentId::new([agent; 32]))
+            .map(|(_, amount)| *amount)
+    }
+
+    #[test]
+    fn second_price_pays_threshold() {
+        let mut auction = SealedBidAuction::new(TASK, budget(1_000));
+        auction.add_bid(bid(1, 600, 80, 600)).unwrap();
+        auction.add_bid(bid(2, 700, 80, 600)).unwrap();
+
+        let result = auction.run_auction().unwrap();
+        assert_eq!(result.allocations.len(), 1);
+        assert_eq!(result.allocations[0].agent_id, AgentId::new([1u8; 32]));
+        // Identical non-price attributes: the winner is paid the runner-up's value.
+        assert_eq!(payment_to(&result, 1), Some(700));
+        assert_eq!(result.social_welfare, auction.score(&bid(1, 600, 80, 600)));
+    }
+
+    #[test]
+    fn first_price_pays_own_bid() {
+        let mut auction =
+            SealedBidAuction::new(TASK, budget(1_000)).with_pricing(PricingRule::FirstPrice);
+        auction.add_bid(bid(1, 600, 80, 600)).unwrap();
+        auction.add_bid(bid(2, 700, 80, 600)).unwrap();
+
+        let result = auction.run_auction().unwrap();
+        assert_eq!(payment_to(&result, 1), Some(600));
+    }
+
+    #[test]
+    fn quality_and_reputation_can_outweigh_price() {
+        let mut auction = SealedBidAuction::new(TASK, budget(1_000));
+        auction.set_reputation(
+            AgentId::new([2u8; 32]),
+            Reputation {
+                quality: 100,
+                reliability: 100,
+                speed: 100,
+                cost_efficiency: 100,
+                specializations: Vec::new(),
+                stake: 0,
+            },
+        );
+        auction.add_bid(bid(1, 500, 10, 3_600)).unwrap();
+        auction.add_bid(bid(2, 550, 100, 60)).unwrap();
+
+        let result = auction.run_auction().unwrap();
+        assert_eq!(result.allocations[0].agent_id, AgentId::new([2u8; 32]));
+        let paid = payment_to(&result, 2).unwrap();
+        assert!((550..=1_000).contains(&paid));
+    }
+
+    #[test]
+    fn single_bidder_is_paid_the_reserve() {
+        let mut auction = SealedBidAuction::new(TASK, budget(1_000));
+        auction.add_bid(bid(1, 400, 50, 100)).unwrap();
+        let result = auction.run_auction().unwrap();
+        assert_eq!(payment_to(&result, 1), Some(1_000));
+    }
+
+    #[test]
+    fn rejects_invalid_bids() {
+        let mut auction = SealedBidAuction::new(TASK, budget(1_000));
+        assert!(auction.run_auction().is_err());
+        assert!(auction.add_bid(bid(1, 1_001, 50, 100)).is_err());
+        assert!(auction.add_bid(bid(1, 100, 101, 100)).is_err());
+
+        let mut foreign = bid(1, 100, 50, 100);
+        foreign.task_id = TaskId::new([9u8; 32]);
+        assert!(auction.add_bid(foreign).is_err());
+
+        auction.add_bid(bid(1, 100, 50, 100)).unwrap();
+        assert!(auction.add_bid(bid(1, 90, 50, 100)).is_err());
+    }
+
+    #[test]
+    fn multiple_winners_are_paid_against_best_loser() {
+        let mut auction = SealedBidAuction::new(TASK, budget(1_000)).with_winners(2);
+        auction.add_bid(bid(1, 600, 80, 600)).unwrap();
+        assert!(matches!(
+            auction.run_auction(),
+            Err(CoreError::EconomicConstraintViolation { .. })
+        ));
+
+        auction.add_bid(bid(2, 650, 80, 600)).unwrap();
+        auction.add_bid(bid(3, 700, 80, 600)).unwrap();
+        let result = auction.run_auction().unwrap();
+        let winners: Vec<_> = result.allocations.iter().map(|a| a.agent_id).collect();
+        assert_eq!(winners, [AgentId::new([1u8; 32]), AgentId::new([2u8; 32])]);
+        assert_eq!(payment_to(&result, 1), Some(700));
+        assert_eq!(payment_to(&result, 2), Some(700));
+        assert_eq!(payment_to(&result, 3), None);
+    }
+
+    #[test]
+    fn scoring_rule_must_sum_to_one() {
+        let rule = ScoringRule {
+            quality_weight: 5_000,
+            ..ScoringRule::default()
+        };
+        assert!(SealedBidAuction::new(TASK, budget(1))
+            .with_scoring(ScoringRule {
+                speed_weight: 0,
+                ..rule
+            })
+            .is_err());
+        assert!(SealedBidAuction::new(TASK, budget(1))
+            .with_scoring(rule)
+            .is_ok());
+    }
+
+    #[test]
+    fn milestone_payments_follow_clearing_price() {
+        let milestone = |id: u8| Milestone {
+            id: [id; 16],
+            description: "phase".into(),
+            criteria: "done".into(),
+        };
+        let mut auction = SealedBidAuction::new(
+            TASK,
+            Budget {
+                max_cost: 1_000,
+                payment_schedule: PaymentSchedule::Milestone(alloc::vec![
+                    (milestone(1), 300),
+                    (milestone(2), 700),
+                ]),
+                escrow_required: true,
+            },
+        )
+        .with_pricing(PricingRule::FirstPrice);
+        auction.add_bid(bid(1, 501, 50, 100)).unwrap();
+
+        let result = auction.run_auction().unwrap();
+        match auction.calculate_payments(&result.allocations[0]).unwrap() {
+            PaymentSchedule::Milestone(tranches) => {
+                assert_eq!(tranches[0].1, 150);
+                assert_eq!(tranches[1].1, 351);
+            }
+            other => panic!("unexpected schedule {other:?}"),
+        }
+    }
+
+    fn rival() -> impl Strategy<Value = (u128, u32, u64)> {
+        (0u128..=1_000_000, 0u32..=100, 0u64..=7_200)
+    }
+
+    proptest! {
+        #[test]
+        fn second_price_is_truthful(
+            true_cost in 0u128..=1_000_000,
+            misreport in 0u128..=1_000_000,
+            quality in 0u32..=100,
+            completion_time in 0u64..=7_200,
+            rivals in proptest::collection::vec(rival(), 0..6),
+        ) {
+            let utility = |value: u128| -> i128 {
+                let mut auction = SealedBidAuction::new(TASK, budget(1_000_000));
+                auction.add_bid(bid(0, value, quality, completion_time)).unwrap();
+                for (i, (v, q, t)) in rivals.iter().enumerate() {
+                    auction.add_bid(bid(i as u8 + 1, *v, *q, *t)).unwrap();
+                }
+                let result = auction.run_auction().unwrap();
+                match payment_to(&result, 0) {
+                    Some(paid) => paid as i128 - true_cost as i128,
+                    None => 0,
+                }
+            };
+            prop_assert!(utility(true_cost) >= utility(misreport));
+            prop_assert!(utility(true_cost) >= 0);
+        }
+
+        #[test]
+        fn payments_respect_budget(
+            max_cost in 1u128..=1_000_000_000,
+            bids in proptest::collection::vec(rival(), 1..8),
+            first_price in any::<bool>(),
+        ) {
+            let pricing = if first_price { PricingRule::FirstPrice } else { PricingRule::SecondPrice };
+            let mut auction = SealedBidAuction::new(TASK, budget(max_cost)).with_pricing(pricing);
+            for (i, (v, q, t)) in bids.iter().enumerate() {
+                let _ = auction.add_bid(bid(i as u8, v % (max_cost + 1), *q, *t));
+            }
+            let result = auction.run_auction().unwrap();
+            let winner = &result.allocations[0].bid;
+            let paid = payment_to(&result, winner.agent_id.as_bytes()[0]).unwrap();
+            prop_assert!(paid <= max_cost);
+            prop_assert!(paid >= winner.value);
+            prop_assert!(result.payments.len() == result.allocations.len());
+        }
+    }
+}
@@ -47,14 +47,23 @@ pub mod economics {
     /// Minimum stake for agents
     pub const MIN_AGENT_STAKE: u128 = 100 * 10_u128.pow(TOKEN_DECIMALS);
 
+    /// Unbonding period for agent stake in seconds (7 days)
+    pub const UNBONDING_PERIOD: u64 = 604800;
+
     /// Maximum inflation rate (5% per year)
     pub const MAX_INFLATION_RATE: f64 = 0.05;
 
     /// Transaction fee percentage (0.1%)
     pub const TRANSACTION_FEE_PERCENT: f64 = 0.001;
 
+    /// Transaction fee in basis points (0.1%)
+    pub const TRANSACTION_FEE_BPS: u32 = 10;
+
     /// Slashing percentage for violations (10%)
     pub const SLASHING_PERCENT: f64 = 0.10;
+
+    /// Slashing for violations in basis points (10%)
+    pub const SLASHING_BPS: u32 = 1_000;
 }
 
 /// Reputation constants
@@ -101,11 +110,17 @@ pub mod auction {
     /// Default bid duration (30 minutes)
     pub const DEFAULT_BID_DURATION: u64 = 1800;
 
+    /// Reveal phase for sealed bids after the bid window closes (5 minutes)
+    pub const DEFAULT_REVEAL_DURATION: u64 = 300;
+
     /// Maximum concurrent bids per agent
     pub const MAX_CONCURRENT_BIDS: usize = 100;
 
     /// Bid deposit percentage (1%)
     pub const BID_DEPOSIT_PERCENT: f64 = 0.01;
+
+    /// Bid deposit in basis points (1%)
+    pub const BID_DEPOSIT_BPS: u32 = 100;
 }
 
 /// Verification constants
@@ -124,6 +139,18 @@ pub mod verification {
     pub const MIN_VERIFIERS: u8 = 3;
 }
 
+/// Dispute constants
+pub mod dispute {
+    /// Time after completion during which a result may be disputed (24 hours)
+    pub const DISPUTE_WINDOW: u64 = 86400;
+
+    /// Maximum evidence items per dispute
+    pub const MAX_EVIDENCE_ITEMS: usize = 16;
+
+    /// Maximum size of a single evidence item (1 MB)
+    pub const MAX_EVIDENCE_SIZE: usize = 1024 * 1024;
+}
+
 /// Resource limits
 pub mod resources {
     /// Maximum CPU time per task (1 hour)
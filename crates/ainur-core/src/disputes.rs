@@ -0,0 +1,187 @@
+//! Dispute settlement
+//!
+//! A requester may contest an accepted result within
+//! [`constants::dispute::DISPUTE_WINDOW`]. The resolver settles the dispute
+//! with a [`DisputeResolution`]; [`DisputeResolution::settle`] turns it into
+//! the amounts refunded to the requester and slashed from the agent, using
+//! the [`RefundPolicy`] the agent guaranteed in its bid.
+//!
+//! The other guarantees of a bid are checked against the delivered result
+//! with [`Guarantee::breach`]; a breached guarantee also refunds the
+//! requester per the refund policy.
+
+use alloc::{format, string::String};
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+
+use crate::{auction::mul_bps, constants, Guarantee, RefundPolicy};
+
+/// How a dispute was settled.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Encode, Decode, TypeInfo, Serialize, Deserialize)]
+pub enum DisputeResolution {
+    /// The result stands; nothing is refunded.
+    Uphold,
+    /// The result is rejected and the requester refunded per the agent's
+    /// refund policy.
+    Refund,
+    /// The agent is at fault: the requester is refunded in full and the
+    /// agent slashed.
+    Slash,
+}
+
+/// Amounts moved by a settled dispute.
+#[derive(
+    Clone, Copy, PartialEq, Eq, Debug, Default, Encode, Decode, TypeInfo, Serialize, Deserialize,
+)]
+pub struct Settlement {
+    /// Returned to the requester.
+    pub refund: u128,
+    /// Taken from the agent as a penalty.
+    pub slashed: u128,
+}
+
+impl DisputeResolution {
+    /// Settle a dispute over a result the agent was paid `payment` for and
+    /// delivered `elapsed` seconds after allocation.
+    pub fn settle(&self, policy: &RefundPolicy, payment: u128, elapsed: u64) -> Settlement {
+        match self {
+            DisputeResolution::Uphold => Settlement::default(),
+            DisputeResolution::Refund => Settlement {
+                refund: policy.refund(payment, elapsed),
+                slashed: 0,
+            },
+            DisputeResolution::Slash => Settlement {
+                refund: payment,
+                slashed: slash_amount(payment),
+            },
+        }
+    }
+}
+
+impl RefundPolicy {
+    /// The refund policy among a bid's guarantees. Agents that offered none
+    /// refund in full.
+    pub fn from_guarantees(guarantees: &[Guarantee]) -> Self {
+        guarantees
+            .iter()
+            .find_map(|g| match g {
+                Guarantee::RefundPolicy(policy) => Some(policy.clone()),
+                _ => None,
+            })
+            .unwrap_or(RefundPolicy::Full)
+    }
+
+    /// Amount of `payment` refunded for a result delivered `elapsed` seconds
+    /// after allocation. `TimeBased` applies the percentage of the largest
+    /// threshold `elapsed` has reached.
+    pub fn refund(&self, payment: u128, elapsed: u64) -> u128 {
+        let percent = match self {
+            RefundPolicy::None => 0,
+            RefundPolicy::Full => 100,
+            RefundPolicy::Partial(percent) => *percent,
+            RefundPolicy::TimeBased(steps) => steps
+                .iter()
+                .filter(|(threshold, _)| elapsed >= *threshold)
+                .max_by_key(|(threshold, _)| *threshold)
+                .map(|(_, percent)| *percent)
+                .unwrap_or(0),
+        };
+        percent_of(payment, percent.min(100))
+    }
+}
+
+impl Guarantee {
+    /// How a result delivered `elapsed` seconds after allocation, which
+    /// verifiers scored `quality` (0-100), breaches this guarantee, if it
+    /// does. Free-form SLAs and refund policies cannot be breached by a
+    /// result.
+    pub fn breach(&self, elapsed: u64, quality: u32) -> Option<String> {
+        match self {
+            Guarantee::CompletionTime(promised) if elapsed > *promised => {
+                Some(format!("completed in {elapsed}s, {promised}s guaranteed"))
+            }
+            Guarantee::QualityScore(promised) if quality < *promised => {
+                Some(format!("quality {quality}, {promised} guaranteed"))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Penalty for a slashed agent: [`constants::economics::SLASHING_BPS`] of
+/// `payment`.
+pub fn slash_amount(payment: u128) -> u128 {
+    mul_bps(payment, constants::economics::SLASHING_BPS)
+}
+
+/// `amount * percent / 100`, rounded down, without intermediate overflow.
+fn percent_of(amount: u128, percent: u32) -> u128 {
+    let percent = percent as u128;
+    (amount / 100) * percent + (amount % 100) * percent / 100
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn refunds_follow_policy() {
+        assert_eq!(RefundPolicy::None.refund(1_000, 0), 0);
+        assert_eq!(RefundPolicy::Full.refund(1_000, 0), 1_000);
+        assert_eq!(RefundPolicy::Partial(40).refund(1_000, 0), 400);
+        assert_eq!(RefundPolicy::Partial(250).refund(1_000, 0), 1_000);
+
+        let late = RefundPolicy::TimeBased(vec![(600, 50), (60, 10)]);
+        assert_eq!(late.refund(1_000, 30), 0);
+        assert_eq!(late.refund(1_000, 60), 100);
+        assert_eq!(late.refund(1_000, 3_600), 500);
+    }
+
+    #[test]
+    fn guarantees_are_breached_by_late_or_poor_results() {
+        let on_time = Guarantee::CompletionTime(60);
+        assert_eq!(on_time.breach(60, 0), None);
+        assert!(on_time.breach(61, 100).is_some());
+
+        let quality = Guarantee::QualityScore(90);
+        assert_eq!(quality.breach(u64::MAX, 90), None);
+        assert!(quality.breach(0, 89).is_some());
+
+        assert_eq!(Guarantee::SLA("99.9%".into()).breach(u64::MAX, 0), None);
+        assert_eq!(
+            Guarantee::RefundPolicy(RefundPolicy::Full).breach(u64::MAX, 0),
+            None
+        );
+    }
+
+    #[test]
+    fn resolutions_settle_amounts() {
+        let policy = RefundPolicy::from_guarantees(&[
+            Guarantee::QualityScore(90),
+            Guarantee::RefundPolicy(RefundPolicy::Partial(30)),
+        ]);
+        assert_eq!(policy, RefundPolicy::Partial(30));
+        assert_eq!(RefundPolicy::from_guarantees(&[]), RefundPolicy::Full);
+
+        assert_eq!(
+            DisputeResolution::Uphold.settle(&policy, 1_000, 0),
+            Settlement::default()
+        );
+        assert_eq!(
+            DisputeResolution::Refund.settle(&policy, 1_000, 0),
+            Settlement {
+                refund: 300,
+                slashed: 0
+            }
+        );
+        assert_eq!(
+            DisputeResolution::Slash.settle(&policy, 1_000, 0),
+            Settlement {
+                refund: 1_000,
+                slashed: 100
+            }
+        );
+    }
+}
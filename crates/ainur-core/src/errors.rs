@@ -81,6 +81,14 @@ pub enum ReputationError {
     SlashingTriggered { reason: String },
 }
 
+impl From<ReputationError> for CoreError {
+    fn from(err: ReputationError) -> Self {
+        CoreError::EconomicConstraintViolation {
+            constraint: err.to_string(),
+        }
+    }
+}
+
 /// Task-related errors
 #[derive(Error, Debug)]
 pub enum TaskError {
@@ -125,6 +133,14 @@ pub enum AuctionError {
     BiddingExpired,
 }
 
+impl From<AuctionError> for CoreError {
+    fn from(err: AuctionError) -> Self {
+        CoreError::EconomicConstraintViolation {
+            constraint: err.to_string(),
+        }
+    }
+}
+
 /// Verification-related errors
 #[derive(Error, Debug)]
 pub enum VerificationError {
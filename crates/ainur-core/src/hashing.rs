@@ -0,0 +1,121 @@
+//! Canonical content hashing
+//!
+//! Every protocol hash is `blake2_256` over the SCALE encoding of the value,
+//! matching `BlakeTwo256::hash_of` in the runtime, so off-chain components
+//! and the pallets derive the same bytes for the same content.
+
+use crate::types::*;
+use blake2::{digest::consts::U32, Blake2b, Digest};
+use parity_scale_codec::Encode;
+
+/// 256-bit BLAKE2b digest.
+pub fn blake2_256(data: &[u8]) -> [u8; 32] {
+    Blake2b::<U32>::digest(data).into()
+}
+
+/// `blake2_256` of the SCALE encoding of `value`.
+pub fn hash_of<T: Encode + ?Sized>(value: &T) -> [u8; 32] {
+    value.using_encoded(blake2_256)
+}
+
+/// Commitment for a sealed bid of `agent_id` on `value` for `task_id`: the
+/// hash of `(task_id, agent_id, value, nonce)`. Binding the task and agent
+/// keeps a commitment from being copied onto another task or bidder.
+pub fn bid_commitment(task_id: &str, agent_id: &str, value: u128, nonce: &[u8; 32]) -> [u8; 32] {
+    hash_of(&(task_id, agent_id, value, nonce))
+}
+
+impl Task {
+    /// Identifier derived from the task's content and a caller-chosen `salt`,
+    /// which tells apart otherwise identical submissions. The current `id`
+    /// is not part of the hash, nor are the budget and deadline, which the
+    /// requester may amend while the task is open.
+    pub fn derive_id(&self, salt: u128) -> TaskId {
+        TaskId::new(hash_of(&(
+            &self.requester,
+            &self.specification,
+            &self.requirements,
+            &self.verification_level,
+            salt,
+        )))
+    }
+}
+
+impl TaskSpec {
+    /// Hash of the full specification, as committed to by `create_task`.
+    pub fn spec_hash(&self) -> [u8; 32] {
+        hash_of(self)
+    }
+}
+
+impl Bid {
+    /// Hash identifying this bid.
+    pub fn bid_hash(&self) -> [u8; 32] {
+        hash_of(self)
+    }
+}
+
+impl TaskResult {
+    /// Hash of the result, as submitted with `submit_result`.
+    pub fn result_hash(&self) -> [u8; 32] {
+        hash_of(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn blake2_256_known_vector() {
+        // BLAKE2b-256 of the empty string.
+        assert_eq!(
+            blake2_256(b""),
+            [
+                0x0e, 0x57, 0x51, 0xc0, 0x26, 0xe5, 0x43, 0xb2, 0xe8, 0xab, 0x2e, 0xb0, 0x60, 0x99,
+                0xda, 0xa1, 0xd1, 0xe5, 0xdf, 0x47, 0x77, 0x8f, 0x77, 0x87, 0xfa, 0xab, 0x45, 0xcd,
+                0xf1, 0x2f, 0xe3, 0xa8,
+            ]
+        );
+    }
+
+    #[test]
+    fn commitment_is_hash_of_scale_tuple() {
+        let nonce = [7u8; 32];
+        // SCALE strings are a compact length prefix (`len << 2`) and the bytes.
+        let mut encoded = vec![4u8 << 2];
+        encoded.extend_from_slice(b"task");
+        encoded.push(5u8 << 2);
+        encoded.extend_from_slice(b"agent");
+        encoded.extend_from_slice(&42u128.to_le_bytes());
+        encoded.extend_from_slice(&nonce);
+        let commitment = bid_commitment("task", "agent", 42, &nonce);
+        assert_eq!(commitment, blake2_256(&encoded));
+        assert_ne!(bid_commitment("task", "agent", 43, &nonce), commitment);
+        assert_ne!(bid_commitment("other", "agent", 42, &nonce), commitment);
+        assert_ne!(bid_commitment("task", "other", 42, &nonce), commitment);
+    }
+
+    #[test]
+    fn result_hash_covers_output() {
+        let result = TaskResult {
+            task_id: TaskId::new([1u8; 32]),
+            executor: AgentId::new([2u8; 32]),
+            output: vec![1, 2, 3],
+            proof: None,
+            resources_used: ResourceUsage {
+                cpu_time_ms: 0,
+                memory_bytes: 0,
+                storage_bytes: 0,
+                bandwidth_bytes: 0,
+                gpu_time_ms: None,
+            },
+            completed_at: 10,
+        };
+        let mut other = result.clone();
+        other.output.push(4);
+        assert_eq!(result.result_hash(), hash_of(&result));
+        assert_ne!(result.result_hash(), other.result_hash());
+    }
+}
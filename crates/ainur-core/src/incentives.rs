@@ -0,0 +1,160 @@
+//! Default economic mechanism
+//!
+//! [`StandardEconomics`] implements [`EconomicMechanism`] over a single
+//! amount at stake, such as an allocation's payment or a task's budget.
+//! Rewards, penalties, fees and bid deposits are basis-point shares of that
+//! amount, computed with integer arithmetic only so every node derives the
+//! same figures.
+
+use crate::{auction::mul_bps, constants, errors::*, traits::*, BASIS_POINTS};
+
+/// Rewards, penalties and fees as fixed shares of an amount at stake.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct StandardEconomics {
+    amount: u128,
+}
+
+impl StandardEconomics {
+    /// Mechanism scaled to `amount`.
+    pub fn new(amount: u128) -> Self {
+        Self { amount }
+    }
+
+    /// The amount at stake.
+    pub fn amount(&self) -> u128 {
+        self.amount
+    }
+
+    /// Deposit locked with a bid: [`constants::auction::BID_DEPOSIT_BPS`] of
+    /// the amount.
+    pub fn bid_deposit(&self) -> u128 {
+        mul_bps(self.amount, constants::auction::BID_DEPOSIT_BPS)
+    }
+
+    /// Share of the amount slashed for `violation`. Failing a task,
+    /// providing false information and breaching an SLA are slashed
+    /// [`constants::economics::SLASHING_BPS`]; other violations are only
+    /// recorded.
+    pub fn penalty_bps(violation: &Violation) -> u32 {
+        match violation {
+            Violation::TaskFailure(_)
+            | Violation::FalseInformation(_)
+            | Violation::SLAViolation(_) => constants::economics::SLASHING_BPS,
+            Violation::Other(_) => 0,
+        }
+    }
+
+    /// Share of the amount charged as a fee on `transaction_type`.
+    /// Reputation updates are internal bookkeeping and free.
+    pub fn fee_bps(transaction_type: TransactionType) -> u32 {
+        match transaction_type {
+            TransactionType::TaskSubmission
+            | TransactionType::BidPlacement
+            | TransactionType::ResultSubmission
+            | TransactionType::DisputeFiling => constants::economics::TRANSACTION_FEE_BPS,
+            TransactionType::ReputationUpdate => 0,
+        }
+    }
+}
+
+impl EconomicMechanism for StandardEconomics {
+    /// The amount scaled by average quality and on-time rate; nothing until
+    /// a task has been completed.
+    fn calculate_rewards(&self, performance: &Performance) -> Result<u128> {
+        for bps in [performance.avg_quality, performance.on_time_rate] {
+            if bps > BASIS_POINTS {
+                return Err(CoreError::OutOfRange {
+                    value: bps as u128,
+                    min: 0,
+                    max: BASIS_POINTS as u128,
+                });
+            }
+        }
+        if performance.tasks_completed == 0 {
+            return Ok(0);
+        }
+        Ok(mul_bps(
+            mul_bps(self.amount, performance.avg_quality),
+            performance.on_time_rate,
+        ))
+    }
+
+    fn calculate_penalty(&self, violation: &Violation) -> Result<u128> {
+        Ok(mul_bps(self.amount, Self::penalty_bps(violation)))
+    }
+
+    fn calculate_fees(&self, transaction_type: TransactionType) -> Result<u128> {
+        Ok(mul_bps(self.amount, Self::fee_bps(transaction_type)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TaskId;
+    use alloc::string::String;
+
+    #[test]
+    fn penalties_fees_and_deposits_are_fixed_shares() {
+        let economics = StandardEconomics::new(1_000_000);
+        assert_eq!(economics.bid_deposit(), 10_000);
+        assert_eq!(
+            economics
+                .calculate_penalty(&Violation::TaskFailure(TaskId::new([0; 32])))
+                .unwrap(),
+            100_000
+        );
+        assert_eq!(
+            economics
+                .calculate_penalty(&Violation::SLAViolation(String::new()))
+                .unwrap(),
+            100_000
+        );
+        assert_eq!(
+            economics
+                .calculate_penalty(&Violation::Other(String::new()))
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            economics
+                .calculate_fees(TransactionType::ResultSubmission)
+                .unwrap(),
+            1_000
+        );
+        assert_eq!(
+            economics
+                .calculate_fees(TransactionType::ReputationUpdate)
+                .unwrap(),
+            0
+        );
+        // No overflow at the top of the range.
+        assert_eq!(
+            StandardEconomics::new(u128::MAX).bid_deposit(),
+            u128::MAX / 100
+        );
+    }
+
+    #[test]
+    fn rewards_scale_with_quality_and_punctuality() {
+        let economics = StandardEconomics::new(10_000);
+        let performance = Performance {
+            tasks_completed: 4,
+            avg_quality: 9_000,
+            on_time_rate: 5_000,
+        };
+        assert_eq!(economics.calculate_rewards(&performance).unwrap(), 4_500);
+        assert_eq!(
+            economics
+                .calculate_rewards(&Performance::default())
+                .unwrap(),
+            0
+        );
+        assert!(economics
+            .calculate_rewards(&Performance {
+                avg_quality: BASIS_POINTS + 1,
+                ..performance
+            })
+            .is_err());
+    }
+}
@@ -7,15 +7,31 @@
 
 extern crate alloc;
 
+pub mod auction;
 pub mod constants;
+pub mod disputes;
 pub mod errors;
+pub mod hashing;
+pub mod incentives;
+pub mod matching;
+pub mod reputation;
+pub mod signing;
 pub mod traits;
 pub mod types;
+pub mod verifier;
 
+pub use auction::*;
 pub use constants::*;
+pub use disputes::*;
 pub use errors::*;
+pub use hashing::*;
+pub use incentives::*;
+pub use matching::*;
+pub use reputation::*;
+pub use signing::*;
 pub use traits::*;
 pub use types::*;
+pub use verifier::*;
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,222 @@
+//! Capability matching between task [`Requirements`] and agents.
+//!
+//! An [`AgentProfile`] describes what an agent advertises: its capabilities
+//! and the resources it has available. [`AgentProfile::check`] explains why an
+//! agent cannot take a task, and [`eligible_agents`] filters a set of profiles
+//! down to the candidates for a task.
+
+use alloc::{format, vec::Vec};
+
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AgentId, Capability, CoreError, HardwareType, Requirements, Result, Task, TaskExecutor,
+};
+
+impl Requirements {
+    /// Requirements that every agent satisfies.
+    pub fn none() -> Self {
+        Self {
+            min_memory: None,
+            min_cpu_cores: None,
+            gpu_required: false,
+            min_bandwidth: None,
+            capabilities: Vec::new(),
+        }
+    }
+
+    /// Whether these requirements place no constraint on agents.
+    pub fn is_unconstrained(&self) -> bool {
+        *self == Self::none()
+    }
+}
+
+/// Resources an agent declares when registering. Undeclared resources never
+/// satisfy a minimum.
+#[derive(
+    Clone, PartialEq, Eq, Debug, Default, Encode, Decode, TypeInfo, Serialize, Deserialize,
+)]
+pub struct AgentResources {
+    /// Available memory in bytes
+    #[serde(default)]
+    pub memory: Option<u64>,
+    /// Available CPU cores
+    #[serde(default)]
+    pub cpu_cores: Option<u32>,
+    /// Available bandwidth in bytes/second
+    #[serde(default)]
+    pub bandwidth: Option<u64>,
+}
+
+/// What an agent offers, as far as matching is concerned.
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode, TypeInfo, Serialize, Deserialize)]
+pub struct AgentProfile {
+    pub agent_id: AgentId,
+    pub capabilities: Vec<Capability>,
+    pub resources: AgentResources,
+}
+
+impl AgentProfile {
+    /// Whether the agent advertises GPU-class acceleration.
+    pub fn has_gpu(&self) -> bool {
+        self.capabilities.iter().any(|c| {
+            matches!(
+                c,
+                Capability::Hardware(
+                    HardwareType::NvidiaGPU(_) | HardwareType::AmdGPU(_) | HardwareType::TPU(_)
+                )
+            )
+        })
+    }
+
+    /// Whether the agent offers `required`. Hardware requirements with an
+    /// empty model match any model of the same kind.
+    pub fn provides(&self, required: &Capability) -> bool {
+        self.capabilities
+            .iter()
+            .any(|offered| capability_satisfies(offered, required))
+    }
+
+    /// Check the agent against `requirements`, naming the first unmet one.
+    pub fn check(&self, requirements: &Requirements) -> Result<()> {
+        let missing = |what| Err(CoreError::MissingCapability(what));
+
+        if let Some(min) = requirements.min_memory {
+            if self.resources.memory.unwrap_or(0) < min {
+                return missing(format!("at least {min} bytes of memory"));
+            }
+        }
+        if let Some(min) = requirements.min_cpu_cores {
+            if self.resources.cpu_cores.unwrap_or(0) < min {
+                return missing(format!("at least {min} CPU cores"));
+            }
+        }
+        if let Some(min) = requirements.min_bandwidth {
+            if self.resources.bandwidth.unwrap_or(0) < min {
+                return missing(format!("at least {min} bytes/s of bandwidth"));
+            }
+        }
+        if requirements.gpu_required && !self.has_gpu() {
+            return missing("GPU acceleration".into());
+        }
+        if let Some(required) = requirements
+            .capabilities
+            .iter()
+            .find(|required| !self.provides(required))
+        {
+            return missing(format!("{required:?}"));
+        }
+        Ok(())
+    }
+}
+
+/// Profiles only answer the matching question; execution and estimation are
+/// left to the agent itself.
+impl TaskExecutor for AgentProfile {
+    fn can_execute(&self, task: &Task) -> bool {
+        self.check(&task.requirements).is_ok()
+    }
+
+    fn estimate(&self, _task: &Task) -> Result<crate::ExecutionEstimate> {
+        Err(CoreError::Custom(
+            "agent profiles do not estimate execution".into(),
+        ))
+    }
+
+    async fn execute(&mut self, _task: Task) -> Result<crate::TaskResult> {
+        Err(CoreError::Custom(
+            "agent profiles do not execute tasks".into(),
+        ))
+    }
+}
+
+/// Profiles in `agents` able to execute `task`, in their original order.
+pub fn eligible_agents<'a>(task: &Task, agents: &'a [AgentProfile]) -> Vec<&'a AgentProfile> {
+    agents
+        .iter()
+        .filter(|agent| agent.can_execute(task))
+        .collect()
+}
+
+fn capability_satisfies(offered: &Capability, required: &Capability) -> bool {
+    match (offered, required) {
+        (Capability::Hardware(offered), Capability::Hardware(required)) => {
+            hardware_satisfies(offered, required)
+        }
+        _ => offered == required,
+    }
+}
+
+fn hardware_satisfies(offered: &HardwareType, required: &HardwareType) -> bool {
+    use HardwareType::*;
+    match (offered, required) {
+        (NvidiaGPU(o), NvidiaGPU(r))
+        | (AmdGPU(o), AmdGPU(r))
+        | (TPU(o), TPU(r))
+        | (FPGA(o), FPGA(r))
+        | (ASIC(o), ASIC(r))
+        | (Other(o), Other(r)) => r.is_empty() || o == r,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TEEType;
+    use alloc::{string::String, vec};
+
+    fn profile(capabilities: Vec<Capability>, resources: AgentResources) -> AgentProfile {
+        AgentProfile {
+            agent_id: AgentId::new([1u8; 32]),
+            capabilities,
+            resources,
+        }
+    }
+
+    #[test]
+    fn unconstrained_requirements_match_everyone() {
+        let agent = profile(vec![], AgentResources::default());
+        assert!(Requirements::none().is_unconstrained());
+        assert!(agent.check(&Requirements::none()).is_ok());
+    }
+
+    #[test]
+    fn resources_and_capabilities_are_enforced() {
+        let agent = profile(
+            vec![
+                Capability::TEE(TEEType::SGX),
+                Capability::Hardware(HardwareType::NvidiaGPU("a100".into())),
+            ],
+            AgentResources {
+                memory: Some(16 << 30),
+                cpu_cores: Some(8),
+                bandwidth: None,
+            },
+        );
+
+        let mut requirements = Requirements::none();
+        requirements.min_memory = Some(8 << 30);
+        requirements.min_cpu_cores = Some(8);
+        requirements.gpu_required = true;
+        requirements.capabilities = vec![
+            Capability::TEE(TEEType::SGX),
+            Capability::Hardware(HardwareType::NvidiaGPU(String::new())),
+        ];
+        assert!(agent.check(&requirements).is_ok());
+
+        requirements.min_bandwidth = Some(1);
+        assert!(matches!(
+            agent.check(&requirements),
+            Err(CoreError::MissingCapability(_))
+        ));
+
+        requirements.min_bandwidth = None;
+        requirements
+            .capabilities
+            .push(Capability::Hardware(HardwareType::NvidiaGPU("h100".into())));
+        assert!(agent.check(&requirements).is_err());
+    }
+}
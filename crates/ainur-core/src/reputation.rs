@@ -0,0 +1,528 @@
+//! Reputation tracking
+//!
+//! [`ReputationLedger`] is the reference [`ReputationSystem`]. Every completed
+//! task is summarised as an [`Assessment`] and folded into the agent's
+//! [`Reputation`] with an exponential moving average weighted by
+//! [`constants::reputation::UPDATE_WEIGHT`]. Assessments tagged with a
+//! [`Domain`] also feed the matching specialization score.
+//!
+//! Scores drift back toward [`constants::reputation::INITIAL_SCORE`] by
+//! [`constants::reputation::DAILY_DECAY_RATE`] for every day that passes, so
+//! stale reputation counts for less than recent work. The most recent
+//! assessments are kept so reputation over a shorter window can be
+//! recomputed with [`ReputationSystem::calculate_reputation`].
+
+use crate::{constants::reputation::*, errors::*, traits::*, types::*};
+use alloc::vec::Vec;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+
+/// Seconds in a decay period.
+pub const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Maximum number of assessments retained per agent.
+pub const MAX_HISTORY: usize = 256;
+
+impl Reputation {
+    /// Reputation of an agent with no track record.
+    pub fn initial() -> Self {
+        Self {
+            quality: INITIAL_SCORE,
+            reliability: INITIAL_SCORE,
+            speed: INITIAL_SCORE,
+            cost_efficiency: INITIAL_SCORE,
+            specializations: Vec::new(),
+            stake: 0,
+        }
+    }
+
+    /// Mean of the four score dimensions.
+    pub fn overall(&self) -> u32 {
+        (self.quality.min(MAX_SCORE)
+            + self.reliability.min(MAX_SCORE)
+            + self.speed.min(MAX_SCORE)
+            + self.cost_efficiency.min(MAX_SCORE))
+            / 4
+    }
+
+    /// Specialization score in `domain`, if the agent has one.
+    pub fn specialization(&self, domain: &Domain) -> Option<u32> {
+        self.specializations
+            .iter()
+            .find(|(d, _)| d == domain)
+            .map(|(_, score)| *score)
+    }
+}
+
+/// Observed performance on a single task, each score in `0..=MAX_SCORE`.
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode, TypeInfo, Serialize, Deserialize)]
+pub struct Assessment {
+    /// Quality of the delivered output.
+    pub quality: u32,
+    /// Whether the result arrived before the task deadline.
+    pub on_time: bool,
+    /// Speed relative to the promised completion time.
+    pub speed: u32,
+    /// How far below budget the task was delivered.
+    pub cost_efficiency: u32,
+    /// Domain the task belongs to, if known.
+    pub domain: Option<Domain>,
+}
+
+impl Assessment {
+    /// Assessment derived from the result alone. Quality reflects the
+    /// strength of the execution proof; speed and cost are neutral since the
+    /// result carries no bid or budget.
+    pub fn from_result(result: &TaskResult) -> Self {
+        let quality = match &result.proof {
+            Some(ExecutionProof::Combined { .. }) => MAX_SCORE,
+            Some(ExecutionProof::TEEAttestation(_)) | Some(ExecutionProof::ZKProof(_)) => 90,
+            Some(ExecutionProof::None) | None => 70,
+        };
+        Self {
+            quality,
+            on_time: true,
+            speed: INITIAL_SCORE,
+            cost_efficiency: INITIAL_SCORE,
+            domain: None,
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        for score in [self.quality, self.speed, self.cost_efficiency] {
+            if score > MAX_SCORE {
+                return Err(CoreError::OutOfRange {
+                    value: score as u128,
+                    min: MIN_SCORE as u128,
+                    max: MAX_SCORE as u128,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An assessment together with when and for which task it was made.
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode, TypeInfo, Serialize, Deserialize)]
+pub struct ReputationEvent {
+    pub task_id: TaskId,
+    pub timestamp: u64,
+    pub assessment: Assessment,
+}
+
+/// Current reputation of an agent plus its recent assessment history.
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode, TypeInfo, Serialize, Deserialize)]
+pub struct AgentReputation {
+    pub reputation: Reputation,
+    /// Most recent assessments, oldest first, capped at [`MAX_HISTORY`].
+    pub history: Vec<ReputationEvent>,
+    /// Timestamp up to which decay has been applied.
+    pub updated_at: u64,
+}
+
+impl AgentReputation {
+    /// Fresh record for an agent first seen at `now`.
+    pub fn new(now: u64) -> Self {
+        Self {
+            reputation: Reputation::initial(),
+            history: Vec::new(),
+            updated_at: now,
+        }
+    }
+
+    fn apply(&mut self, event: ReputationEvent) {
+        let a = &event.assessment;
+        let r = &mut self.reputation;
+        r.quality = ema(r.quality, a.quality);
+        r.reliability = ema(r.reliability, if a.on_time { MAX_SCORE } else { MIN_SCORE });
+        r.speed = ema(r.speed, a.speed);
+        r.cost_efficiency = ema(r.cost_efficiency, a.cost_efficiency);
+        if let Some(domain) = &a.domain {
+            match r.specializations.iter_mut().find(|(d, _)| d == domain) {
+                Some(entry) => entry.1 = ema(entry.1, a.quality),
+                None => r
+                    .specializations
+                    .push((domain.clone(), ema(INITIAL_SCORE, a.quality))),
+            }
+        }
+
+        self.history.push(event);
+        if self.history.len() > MAX_HISTORY {
+            let excess = self.history.len() - MAX_HISTORY;
+            self.history.drain(..excess);
+        }
+    }
+
+    /// Keep `retain` of each score's distance from the initial score.
+    fn decay(&mut self, retain: f64) {
+        let r = &mut self.reputation;
+        for score in [
+            &mut r.quality,
+            &mut r.reliability,
+            &mut r.speed,
+            &mut r.cost_efficiency,
+        ] {
+            *score = toward_initial(*score, retain);
+        }
+        for (_, score) in r.specializations.iter_mut() {
+            *score = toward_initial(*score, retain);
+        }
+    }
+
+    /// Apply daily decay, compounded, for every full day elapsed since the
+    /// last update.
+    fn decay_to(&mut self, now: u64) {
+        let days = now.saturating_sub(self.updated_at) / SECONDS_PER_DAY;
+        if days == 0 {
+            return;
+        }
+        let mut retain = 1.0;
+        // After a few years every score has converged; skip the remainder.
+        for _ in 0..days.min(3650) {
+            retain *= 1.0 - DAILY_DECAY_RATE;
+        }
+        self.decay(retain);
+        self.updated_at += days * SECONDS_PER_DAY;
+    }
+}
+
+/// In-memory reputation store implementing [`ReputationSystem`].
+///
+/// The ledger has no clock of its own: time advances with the timestamps of
+/// recorded results or explicitly through [`ReputationLedger::advance_to`].
+#[derive(Clone, Debug, Default)]
+pub struct ReputationLedger {
+    agents: Vec<(AgentId, AgentReputation)>,
+    now: u64,
+}
+
+impl ReputationLedger {
+    /// Create an empty ledger.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Latest timestamp the ledger has seen.
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Load a previously persisted record, replacing any existing entry.
+    pub fn insert(&mut self, agent_id: AgentId, record: AgentReputation) {
+        self.now = self.now.max(record.updated_at);
+        match self.agents.iter_mut().find(|(id, _)| *id == agent_id) {
+            Some(entry) => entry.1 = record,
+            None => self.agents.push((agent_id, record)),
+        }
+    }
+
+    /// Record for `agent_id`, if the agent has any history.
+    pub fn get(&self, agent_id: &AgentId) -> Option<&AgentReputation> {
+        self.agents
+            .iter()
+            .find(|(id, _)| id == agent_id)
+            .map(|(_, record)| record)
+    }
+
+    /// All tracked agents.
+    pub fn agents(&self) -> impl Iterator<Item = &(AgentId, AgentReputation)> {
+        self.agents.iter()
+    }
+
+    /// Move the clock forward, decaying every agent for the days elapsed.
+    pub fn advance_to(&mut self, now: u64) {
+        if now <= self.now {
+            return;
+        }
+        self.now = now;
+        for (_, record) in self.agents.iter_mut() {
+            record.decay_to(now);
+        }
+    }
+
+    /// Fold an assessment for `task_id` completed at `timestamp` into the
+    /// agent's reputation.
+    pub fn record(
+        &mut self,
+        agent_id: AgentId,
+        task_id: TaskId,
+        timestamp: u64,
+        assessment: Assessment,
+    ) -> Result<()> {
+        assessment.validate()?;
+        self.advance_to(timestamp);
+        let now = self.now;
+        let index = match self.agents.iter().position(|(id, _)| *id == agent_id) {
+            Some(index) => index,
+            None => {
+                self.agents.push((agent_id, AgentReputation::new(now)));
+                self.agents.len() - 1
+            }
+        };
+        self.agents[index].1.apply(ReputationEvent {
+            task_id,
+            timestamp,
+            assessment,
+        });
+        Ok(())
+    }
+
+    /// Highest-reputation agents regardless of domain, ranked by
+    /// [`Reputation::overall`].
+    pub fn top_overall(&self, count: usize) -> Vec<(AgentId, Reputation)> {
+        let mut ranked: Vec<_> = self
+            .agents
+            .iter()
+            .map(|(id, record)| (*id, record.reputation.clone()))
+            .collect();
+        ranked.sort_by_key(|(_, r)| core::cmp::Reverse(r.overall()));
+        ranked.truncate(count);
+        ranked
+    }
+}
+
+impl ReputationSystem for ReputationLedger {
+    /// Reputation recomputed from the assessments made within the last
+    /// `window` seconds, ignoring decay. A `window` of 0 returns the current
+    /// reputation. Agents without history start at the initial score.
+    fn calculate_reputation(&self, agent_id: &AgentId, window: u64) -> Result<Reputation> {
+        let Some(record) = self.get(agent_id) else {
+            return Ok(Reputation::initial());
+        };
+        if window == 0 {
+            return Ok(record.reputation.clone());
+        }
+
+        let since = self.now.saturating_sub(window);
+        let mut windowed = AgentReputation::new(since);
+        windowed.reputation.stake = record.reputation.stake;
+        for event in record.history.iter().filter(|e| e.timestamp >= since) {
+            windowed.apply(event.clone());
+        }
+        Ok(windowed.reputation)
+    }
+
+    fn update_reputation(&mut self, agent_id: &AgentId, result: &TaskResult) -> Result<()> {
+        self.record(
+            *agent_id,
+            result.task_id,
+            result.completed_at,
+            Assessment::from_result(result),
+        )
+    }
+
+    /// Move every score `decay_rate` of the way back toward the initial score.
+    fn apply_decay(&mut self, decay_rate: f64) -> Result<()> {
+        if !(0.0..=1.0).contains(&decay_rate) {
+            return Err(CoreError::InvalidFormat(
+                "decay rate must be within [0, 1]".into(),
+            ));
+        }
+        for (_, record) in self.agents.iter_mut() {
+            record.decay(1.0 - decay_rate);
+        }
+        Ok(())
+    }
+
+    /// Agents specialised in `domain`, best first. Ties are broken by
+    /// overall reputation.
+    fn get_top_agents(&self, domain: &Domain, count: usize) -> Result<Vec<(AgentId, Reputation)>> {
+        let mut ranked: Vec<_> = self
+            .agents
+            .iter()
+            .filter_map(|(id, record)| {
+                let score = record.reputation.specialization(domain)?;
+                Some((score, *id, record.reputation.clone()))
+            })
+            .collect();
+        ranked.sort_by(|(sa, _, a), (sb, _, b)| {
+            sb.cmp(sa).then_with(|| b.overall().cmp(&a.overall()))
+        });
+        Ok(ranked
+            .into_iter()
+            .take(count)
+            .map(|(_, id, reputation)| (id, reputation))
+            .collect())
+    }
+}
+
+/// Exponential moving average step toward `observed`.
+fn ema(current: u32, observed: u32) -> u32 {
+    let next = current as f64 * (1.0 - UPDATE_WEIGHT) + observed as f64 * UPDATE_WEIGHT;
+    round(next).min(MAX_SCORE)
+}
+
+/// Shrink the distance between `score` and the initial score to `retain` of
+/// itself. The result is truncated toward the initial score so repeated small
+/// decays still make progress instead of rounding back to the same value.
+fn toward_initial(score: u32, retain: f64) -> u32 {
+    let distance = score.abs_diff(INITIAL_SCORE);
+    let kept = (distance as f64 * retain) as u32;
+    if score >= INITIAL_SCORE {
+        INITIAL_SCORE + kept
+    } else {
+        INITIAL_SCORE - kept
+    }
+}
+
+/// Round a non-negative value to the nearest integer without `std`.
+fn round(value: f64) -> u32 {
+    (value + 0.5) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agent(n: u8) -> AgentId {
+        AgentId::new([n; 32])
+    }
+
+    fn assessment(quality: u32, on_time: bool, domain: Option<Domain>) -> Assessment {
+        Assessment {
+            quality,
+            on_time,
+            speed: quality,
+            cost_efficiency: quality,
+            domain,
+        }
+    }
+
+    #[test]
+    fn results_move_scores_toward_observations() {
+        let mut ledger = ReputationLedger::new();
+        for i in 0..20 {
+            ledger
+                .record(
+                    agent(1),
+                    TaskId::new([i; 32]),
+                    1_000,
+                    assessment(100, true, None),
+                )
+                .unwrap();
+        }
+        let good = ledger.calculate_reputation(&agent(1), 0).unwrap();
+        assert!(good.quality > 90 && good.reliability > 90);
+
+        ledger
+            .record(
+                agent(2),
+                TaskId::new([0; 32]),
+                1_000,
+                assessment(0, false, None),
+            )
+            .unwrap();
+        let bad = ledger.calculate_reputation(&agent(2), 0).unwrap();
+        assert_eq!(bad.quality, 45);
+        assert_eq!(bad.reliability, 45);
+
+        let unknown = ledger.calculate_reputation(&agent(3), 0).unwrap();
+        assert_eq!(unknown, Reputation::initial());
+    }
+
+    #[test]
+    fn scores_decay_toward_initial_over_time() {
+        let mut ledger = ReputationLedger::new();
+        for i in 0..30 {
+            ledger
+                .record(
+                    agent(1),
+                    TaskId::new([i; 32]),
+                    0,
+                    assessment(100, true, None),
+                )
+                .unwrap();
+        }
+        let before = ledger.get(&agent(1)).unwrap().reputation.quality;
+        ledger.advance_to(365 * SECONDS_PER_DAY);
+        let after = ledger.get(&agent(1)).unwrap().reputation.quality;
+        assert!(after < before);
+        assert!(after >= INITIAL_SCORE);
+
+        assert!(ledger.apply_decay(1.5).is_err());
+        ledger.apply_decay(1.0).unwrap();
+        assert_eq!(
+            ledger.get(&agent(1)).unwrap().reputation,
+            Reputation::initial()
+        );
+    }
+
+    #[test]
+    fn windowed_reputation_ignores_old_results() {
+        let mut ledger = ReputationLedger::new();
+        ledger
+            .record(
+                agent(1),
+                TaskId::new([1; 32]),
+                0,
+                assessment(0, false, None),
+            )
+            .unwrap();
+        ledger
+            .record(
+                agent(1),
+                TaskId::new([2; 32]),
+                10 * SECONDS_PER_DAY,
+                assessment(100, true, None),
+            )
+            .unwrap();
+        let recent = ledger
+            .calculate_reputation(&agent(1), SECONDS_PER_DAY)
+            .unwrap();
+        assert_eq!(recent.quality, 55);
+        assert_eq!(recent.reliability, 55);
+    }
+
+    #[test]
+    fn top_agents_are_ranked_by_domain() {
+        let mut ledger = ReputationLedger::new();
+        ledger
+            .record(
+                agent(1),
+                TaskId::new([1; 32]),
+                0,
+                assessment(60, true, Some(Domain::NLP)),
+            )
+            .unwrap();
+        ledger
+            .record(
+                agent(2),
+                TaskId::new([2; 32]),
+                0,
+                assessment(100, true, Some(Domain::NLP)),
+            )
+            .unwrap();
+        ledger
+            .record(
+                agent(3),
+                TaskId::new([3; 32]),
+                0,
+                assessment(100, true, Some(Domain::Vision)),
+            )
+            .unwrap();
+
+        let top = ledger.get_top_agents(&Domain::NLP, 5).unwrap();
+        let ids: Vec<_> = top.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![agent(2), agent(1)]);
+        assert_eq!(ledger.get_top_agents(&Domain::NLP, 1).unwrap().len(), 1);
+        assert!(ledger
+            .get_top_agents(&Domain::CodeGen, 5)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn rejects_out_of_range_assessments() {
+        let mut ledger = ReputationLedger::new();
+        assert!(ledger
+            .record(
+                agent(1),
+                TaskId::new([1; 32]),
+                0,
+                assessment(101, true, None)
+            )
+            .is_err());
+        assert!(ledger.get(&agent(1)).is_none());
+    }
+}
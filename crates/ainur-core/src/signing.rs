@@ -0,0 +1,238 @@
+//! Request signing
+//!
+//! Agents and requesters authenticate API requests with ed25519. The signed
+//! bytes are [`SIGNING_CONTEXT`] followed by the SCALE encoding of a
+//! [`SignedRequest`]: the [`SignedMessage`] itself, a single-use nonce and an
+//! expiry. The enum tag keeps a signature for one kind of request from being
+//! replayed as another, and the nonce and expiry keep it from being replayed
+//! at all once the verifier has seen it.
+
+use crate::{
+    errors::*, AgentResources, Capability, DisputeResolution, Domain, Guarantee, OutputFormat,
+    PaymentSchedule, Requirements, VerificationLevel,
+};
+use alloc::{string::String, vec::Vec};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+
+/// Domain separator prepended to every signed message.
+pub const SIGNING_CONTEXT: &[u8] = b"ainur/request/v2";
+
+/// Length of an ed25519 signature in bytes.
+pub const SIGNATURE_LENGTH: usize = 64;
+
+/// Length of a request nonce in bytes.
+pub const NONCE_LENGTH: usize = 16;
+
+/// Canonical content of a signed API request.
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode, TypeInfo)]
+pub enum SignedMessage {
+    /// Task submission, signed by the requester. Covers every submitted
+    /// field, so none can be altered without invalidating the signature.
+    SubmitTask {
+        client_task_id: Option<String>,
+        requester_id: String,
+        description: String,
+        task_type: String,
+        input: Vec<u8>,
+        max_budget: u128,
+        deadline: u64,
+        bid_window_secs: Option<u64>,
+        domain: Option<Domain>,
+        requirements: Requirements,
+        output_format: Option<OutputFormat>,
+        verification_level: Option<VerificationLevel>,
+        payment_schedule: Option<PaymentSchedule>,
+        escrow_required: Option<bool>,
+        /// `(description, criteria, amount)` of each milestone.
+        milestones: Vec<(String, String, u128)>,
+    },
+    /// Open or sealed bid, signed by the bidding agent.
+    SubmitBid {
+        task_id: String,
+        agent_id: String,
+        value: Option<u128>,
+        commitment: Option<[u8; 32]>,
+        quality_score: u32,
+        completion_time: u64,
+        guarantees: Vec<Guarantee>,
+    },
+    /// Task result, signed by the executing agent.
+    SubmitResult {
+        task_id: String,
+        agent_id: String,
+        output: Vec<u8>,
+    },
+    /// Dispute over a task's result, signed by the requester. Evidence is
+    /// covered by the `hash_of` each item's content.
+    FileDispute {
+        task_id: String,
+        requester_id: String,
+        reason: String,
+        evidence: Vec<[u8; 32]>,
+    },
+    /// Completion of a task milestone with partial output, signed by the
+    /// executing agent.
+    CompleteMilestone {
+        task_id: String,
+        milestone_id: String,
+        agent_id: String,
+        output: Vec<u8>,
+    },
+    /// Approval or rejection of a milestone completion, signed by the
+    /// requester.
+    ReviewMilestone {
+        completion_id: String,
+        requester_id: String,
+        approved: bool,
+    },
+    /// Cancellation of a task still open for bids, signed by the requester.
+    CancelTask {
+        task_id: String,
+        requester_id: String,
+    },
+    /// Budget raise or deadline extension of a task still open for bids,
+    /// signed by the requester.
+    UpdateTask {
+        task_id: String,
+        requester_id: String,
+        max_budget: Option<u128>,
+        deadline: Option<u64>,
+    },
+    /// Stake bonded by an agent from its account.
+    BondStake { agent_id: String, amount: u128 },
+    /// Bonded stake an agent starts unbonding.
+    UnbondStake { agent_id: String, amount: u128 },
+    /// Webhook registration, signed by the requester. `events` are the
+    /// subscribed event kinds by name.
+    RegisterWebhook {
+        requester_id: String,
+        url: String,
+        task_id: Option<String>,
+        events: Vec<String>,
+    },
+    /// Agent registration. Re-registering an agent that already has a
+    /// public key, including rotating that key, is signed by the key on
+    /// record.
+    RegisterAgent {
+        agent_id: String,
+        label: String,
+        capabilities: Vec<Capability>,
+        domains: Vec<Domain>,
+        public_key: Option<[u8; 32]>,
+        endpoints: Vec<String>,
+        verification_level: VerificationLevel,
+        attestation: Option<String>,
+        resources: AgentResources,
+    },
+    /// Resolution of a dispute, signed by a configured arbiter.
+    ResolveDispute {
+        dispute_id: String,
+        arbiter_id: String,
+        resolution: DisputeResolution,
+        notes: Option<String>,
+    },
+    /// Withdrawal of an agent's stake that finished unbonding.
+    WithdrawStake { agent_id: String },
+    /// Listing of a requester's webhooks, or with `webhook_id` of one
+    /// webhook's deliveries, signed by the requester.
+    ListWebhooks {
+        requester_id: String,
+        webhook_id: Option<String>,
+    },
+    /// Requester key registration. A first registration is signed by the
+    /// key being registered; rotating it is signed by the key on record.
+    RegisterRequester {
+        requester_id: String,
+        public_key: [u8; 32],
+    },
+}
+
+/// A [`SignedMessage`] bound to a `nonce` the signer never reuses and the
+/// unix time `expires_at` after which it is no longer accepted.
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode, TypeInfo)]
+pub struct SignedRequest {
+    pub message: SignedMessage,
+    pub nonce: [u8; NONCE_LENGTH],
+    pub expires_at: u64,
+}
+
+impl SignedRequest {
+    /// Bytes covered by the signature.
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = SIGNING_CONTEXT.to_vec();
+        self.encode_to(&mut bytes);
+        bytes
+    }
+
+    /// Sign the request.
+    pub fn sign(&self, key: &SigningKey) -> [u8; SIGNATURE_LENGTH] {
+        key.sign(&self.signing_bytes()).to_bytes()
+    }
+
+    /// Check `signature` over the request against `public_key`, rejecting
+    /// weak keys and malleable signatures. Expiry and nonce reuse are for the
+    /// caller to check.
+    pub fn verify(&self, public_key: &[u8; 32], signature: &[u8]) -> Result<()> {
+        let key = VerifyingKey::from_bytes(public_key)
+            .map_err(|_| CoreError::CryptoError("invalid ed25519 public key".into()))?;
+        let signature = Signature::from_slice(signature).map_err(|_| CoreError::InvalidLength {
+            expected: SIGNATURE_LENGTH,
+            actual: signature.len(),
+        })?;
+        key.verify_strict(&self.signing_bytes(), &signature)
+            .map_err(|_| CoreError::CryptoError("signature verification failed".into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(output: &[u8], nonce: u8) -> SignedRequest {
+        SignedRequest {
+            message: SignedMessage::SubmitResult {
+                task_id: "task".into(),
+                agent_id: "agent".into(),
+                output: output.to_vec(),
+            },
+            nonce: [nonce; NONCE_LENGTH],
+            expires_at: 1_000,
+        }
+    }
+
+    #[test]
+    fn signatures_cover_the_whole_request() {
+        let key = SigningKey::from_bytes(&[3u8; 32]);
+        let public = key.verifying_key().to_bytes();
+        let signature = result(b"out", 1).sign(&key);
+
+        assert!(result(b"out", 1).verify(&public, &signature).is_ok());
+        assert!(result(b"tampered", 1).verify(&public, &signature).is_err());
+        assert!(result(b"out", 2).verify(&public, &signature).is_err());
+        let mut extended = result(b"out", 1);
+        extended.expires_at += 1;
+        assert!(extended.verify(&public, &signature).is_err());
+
+        let other = SigningKey::from_bytes(&[4u8; 32])
+            .verifying_key()
+            .to_bytes();
+        assert!(result(b"out", 1).verify(&other, &signature).is_err());
+        assert!(matches!(
+            result(b"out", 1).verify(&public, &signature[..10]),
+            Err(CoreError::InvalidLength { .. })
+        ));
+    }
+
+    #[test]
+    fn weak_keys_are_refused() {
+        // The identity point accepts the all-zero signature over anything
+        // under lax verification.
+        let mut identity = [0u8; 32];
+        identity[0] = 1;
+        let mut signature = [0u8; SIGNATURE_LENGTH];
+        signature[0] = 1;
+        assert!(result(b"out", 1).verify(&identity, &signature).is_err());
+    }
+}
@@ -2,6 +2,9 @@
 
 use crate::{errors::*, types::*};
 use alloc::vec::Vec;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
 
 /// Trait for entities that can execute tasks
 pub trait TaskExecutor {
@@ -115,17 +118,20 @@ pub trait EconomicMechanism {
     fn calculate_fees(&self, transaction_type: TransactionType) -> Result<u128>;
 }
 
-/// Performance metrics
+/// Performance metrics, in fixed point so every node computes the same
+/// rewards
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
 pub struct Performance {
     /// Tasks completed successfully
     pub tasks_completed: u64,
-    /// Average quality score
-    pub avg_quality: f64,
-    /// On-time completion rate
-    pub on_time_rate: f64,
+    /// Average quality score (basis points)
+    pub avg_quality: u32,
+    /// On-time completion rate (basis points)
+    pub on_time_rate: u32,
 }
 
 /// Protocol violations
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode, TypeInfo, Serialize, Deserialize)]
 pub enum Violation {
     /// Failed to complete task
     TaskFailure(TaskId),
@@ -138,6 +144,7 @@ pub enum Violation {
 }
 
 /// Transaction types in the protocol
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum TransactionType {
     /// Task submission
     TaskSubmission,
@@ -45,7 +45,7 @@ pub struct TaskId([u8; 32]);
 
 impl TaskId {
     /// Create a new task ID from bytes
-    pub fn new(bytes: [u8; 32]) -> Self {
+    pub const fn new(bytes: [u8; 32]) -> Self {
         Self(bytes)
     }
 
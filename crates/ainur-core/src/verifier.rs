@@ -0,0 +1,451 @@
+//! Result verification
+//!
+//! [`VerifierRegistry`] decides whether a [`TaskResult`] is accepted. It runs
+//! every baseline verifier (those reporting [`VerificationLevel::None`], such
+//! as [`FormatVerifier`]) plus the verifier registered for the task's
+//! verification level. `Consensus(n)` tasks are checked by a
+//! [`ConsensusVerifier`] built from the results collected for the task.
+
+use crate::{constants::verification::*, errors::*, hashing::hash_of, traits::*, types::*};
+use alloc::{boxed::Box, format, string::String, vec, vec::Vec};
+use core::mem::discriminant;
+
+/// Accepts every result. Registered for `None` and `BestEffort` tasks.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoopVerifier;
+
+impl Verifier for NoopVerifier {
+    fn verify(&self, _task: &Task, _result: &TaskResult) -> Result<VerificationResult> {
+        Ok(VerificationResult {
+            is_valid: true,
+            confidence: 0,
+            report: VerificationReport::Simple(true),
+        })
+    }
+
+    fn verification_level(&self) -> VerificationLevel {
+        VerificationLevel::BestEffort
+    }
+}
+
+/// Checks the output against the task's [`OutputFormat`]: size limits, UTF-8
+/// for text, well-formed JSON, and for `Structured(schema)` a JSON Schema
+/// subset (`type`, `required`, `properties`, `items`, `enum`).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FormatVerifier;
+
+impl Verifier for FormatVerifier {
+    fn verify(&self, task: &Task, result: &TaskResult) -> Result<VerificationResult> {
+        let check = format!("output_format:{:?}", task.specification.output_format);
+        let outcome = check_format(&task.specification.output_format, &result.output);
+        let (is_valid, notes) = match outcome {
+            Ok(()) => (true, String::new()),
+            Err(reason) => (false, reason),
+        };
+        Ok(VerificationResult {
+            is_valid,
+            confidence: if is_valid { 100 } else { 0 },
+            report: VerificationReport::Detailed {
+                checks_performed: vec![check],
+                evidence: Vec::new(),
+                notes,
+            },
+        })
+    }
+
+    /// Baseline check applied to every task.
+    fn verification_level(&self) -> VerificationLevel {
+        VerificationLevel::None
+    }
+}
+
+fn check_format(format: &OutputFormat, output: &[u8]) -> core::result::Result<(), String> {
+    if output.len() > crate::constants::task::MAX_OUTPUT_SIZE {
+        return Err(format!(
+            "output exceeds {} bytes",
+            crate::constants::task::MAX_OUTPUT_SIZE
+        ));
+    }
+    match format {
+        OutputFormat::Binary => Ok(()),
+        OutputFormat::Text => core::str::from_utf8(output)
+            .map(|_| ())
+            .map_err(|_| "output is not valid UTF-8 text".into()),
+        OutputFormat::Json => parse_json(output).map(|_| ()),
+        OutputFormat::Structured(schema) => {
+            let schema: serde_json::Value = serde_json::from_str(schema)
+                .map_err(|e| format!("output schema is not valid JSON: {e}"))?;
+            check_schema(&schema, &parse_json(output)?, "$")
+        }
+    }
+}
+
+fn parse_json(output: &[u8]) -> core::result::Result<serde_json::Value, String> {
+    serde_json::from_slice(output).map_err(|e| format!("output is not valid JSON: {e}"))
+}
+
+fn check_schema(
+    schema: &serde_json::Value,
+    value: &serde_json::Value,
+    path: &str,
+) -> core::result::Result<(), String> {
+    use serde_json::Value;
+
+    if let Some(expected) = schema.get("type").and_then(Value::as_str) {
+        let matches = match expected {
+            "object" => value.is_object(),
+            "array" => value.is_array(),
+            "string" => value.is_string(),
+            "number" => value.is_number(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "boolean" => value.is_boolean(),
+            "null" => value.is_null(),
+            _ => return Err(format!("{path}: unsupported schema type {expected}")),
+        };
+        if !matches {
+            return Err(format!("{path}: expected {expected}"));
+        }
+    }
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            return Err(format!("{path}: value not in enum"));
+        }
+    }
+    if let Some(required) = schema.get("required").and_then(Value::as_array) {
+        for key in required.iter().filter_map(Value::as_str) {
+            if value.get(key).is_none() {
+                return Err(format!("{path}: missing required property {key}"));
+            }
+        }
+    }
+    if let (Some(properties), Some(object)) = (
+        schema.get("properties").and_then(Value::as_object),
+        value.as_object(),
+    ) {
+        for (key, property_schema) in properties {
+            if let Some(property) = object.get(key) {
+                check_schema(property_schema, property, &format!("{path}.{key}"))?;
+            }
+        }
+    }
+    if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+        for (i, item) in array.iter().enumerate() {
+            check_schema(items, item, &format!("{path}[{i}]"))?;
+        }
+    }
+    Ok(())
+}
+
+/// Redundant-execution verifier for `Consensus(n)` tasks.
+///
+/// A result is accepted once the results collected for the task include
+/// more than [`CONSENSUS_THRESHOLD_NUMERATOR`]/[`CONSENSUS_THRESHOLD_DENOMINATOR`]
+/// of `n` outputs identical to it, each from a different executor.
+#[derive(Clone, Debug)]
+pub struct ConsensusVerifier {
+    required: u8,
+    results: Vec<TaskResult>,
+}
+
+impl ConsensusVerifier {
+    /// Verifier for `Consensus(required)` over the `results` collected so far.
+    pub fn new(required: u8, results: Vec<TaskResult>) -> Self {
+        Self { required, results }
+    }
+
+    /// Number of executors whose output agrees with `output`.
+    pub fn agreeing(&self, output: &[u8]) -> u32 {
+        let mut executors: Vec<AgentId> = self
+            .results
+            .iter()
+            .filter(|r| r.output == output)
+            .map(|r| r.executor)
+            .collect();
+        executors.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+        executors.dedup();
+        executors.len() as u32
+    }
+
+    /// Agreeing results needed out of `required` to accept an output.
+    pub fn threshold(&self) -> u32 {
+        u32::from(self.required) * CONSENSUS_THRESHOLD_NUMERATOR / CONSENSUS_THRESHOLD_DENOMINATOR
+            + 1
+    }
+}
+
+impl Verifier for ConsensusVerifier {
+    fn verify(&self, _task: &Task, result: &TaskResult) -> Result<VerificationResult> {
+        let agreeing = self.agreeing(&result.output);
+        let threshold = self.threshold();
+        let is_valid = agreeing >= threshold;
+        Ok(VerificationResult {
+            is_valid,
+            confidence: (agreeing * 100 / u32::from(self.required).max(1)).min(100),
+            report: VerificationReport::Detailed {
+                checks_performed: vec![format!("consensus:{}", self.required)],
+                evidence: hash_of(&result.output).to_vec(),
+                notes: format!(
+                    "{agreeing} of {} results agree; {threshold} required",
+                    self.required
+                ),
+            },
+        })
+    }
+
+    fn verification_level(&self) -> VerificationLevel {
+        VerificationLevel::Consensus(self.required)
+    }
+}
+
+/// Verifiers available to the orchestrator, keyed by verification level.
+pub struct VerifierRegistry {
+    verifiers: Vec<Box<dyn Verifier + Send + Sync>>,
+}
+
+impl Default for VerifierRegistry {
+    /// Format checks for every task and [`NoopVerifier`] for best-effort
+    /// tasks. `Consensus(n)` is always available.
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register(Box::new(FormatVerifier));
+        registry.register(Box::new(NoopVerifier));
+        registry
+    }
+}
+
+impl VerifierRegistry {
+    /// Registry with no verifiers; only `Consensus(n)` tasks can be verified.
+    pub fn new() -> Self {
+        Self {
+            verifiers: Vec::new(),
+        }
+    }
+
+    /// Add a verifier. Verifiers reporting `VerificationLevel::None` run for
+    /// every task.
+    pub fn register(&mut self, verifier: Box<dyn Verifier + Send + Sync>) {
+        self.verifiers.push(verifier);
+    }
+
+    /// Whether results for tasks at `level` can be verified.
+    pub fn supports(&self, level: &VerificationLevel) -> bool {
+        matches!(level, VerificationLevel::Consensus(n) if *n > 0)
+            || self.level_verifier(level).is_some()
+    }
+
+    /// Verify `result` for `task`. `collected` holds every result received
+    /// for the task so far, including `result`; it is only consulted for
+    /// `Consensus(n)` tasks.
+    pub fn verify(
+        &self,
+        task: &Task,
+        result: &TaskResult,
+        collected: &[TaskResult],
+    ) -> Result<VerificationResult> {
+        let mut checks = Vec::new();
+        let mut failures = Vec::new();
+        self.run_baseline(task, result, &mut checks, &mut failures)?;
+
+        let level = match &task.verification_level {
+            VerificationLevel::Consensus(n) if *n > 0 => {
+                ConsensusVerifier::new(*n, collected.to_vec()).verify(task, result)?
+            }
+            level => self
+                .level_verifier(level)
+                .ok_or_else(|| CoreError::VerificationFailed {
+                    reason: format!("no verifier registered for {level:?}"),
+                })?
+                .verify(task, result)?,
+        };
+        let confidence = level.confidence;
+        record(level, &mut checks, &mut failures);
+
+        Ok(VerificationResult {
+            is_valid: failures.is_empty(),
+            confidence: if failures.is_empty() { confidence } else { 0 },
+            report: VerificationReport::Detailed {
+                checks_performed: checks,
+                evidence: Vec::new(),
+                notes: failures.join("; "),
+            },
+        })
+    }
+
+    /// Run only the baseline verifiers. Used to screen each result of a
+    /// `Consensus(n)` task before it counts towards agreement.
+    pub fn verify_baseline(&self, task: &Task, result: &TaskResult) -> Result<VerificationResult> {
+        let mut checks = Vec::new();
+        let mut failures = Vec::new();
+        self.run_baseline(task, result, &mut checks, &mut failures)?;
+        Ok(VerificationResult {
+            is_valid: failures.is_empty(),
+            confidence: if failures.is_empty() { 100 } else { 0 },
+            report: VerificationReport::Detailed {
+                checks_performed: checks,
+                evidence: Vec::new(),
+                notes: failures.join("; "),
+            },
+        })
+    }
+
+    fn run_baseline(
+        &self,
+        task: &Task,
+        result: &TaskResult,
+        checks: &mut Vec<String>,
+        failures: &mut Vec<String>,
+    ) -> Result<()> {
+        let baseline = self
+            .verifiers
+            .iter()
+            .filter(|v| v.verification_level() == VerificationLevel::None);
+        for verifier in baseline {
+            record(verifier.verify(task, result)?, checks, failures);
+        }
+        Ok(())
+    }
+
+    /// Verifier specific to `level`. `None` tasks fall back to the
+    /// best-effort verifier.
+    fn level_verifier(&self, level: &VerificationLevel) -> Option<&(dyn Verifier + Send + Sync)> {
+        let wanted = match level {
+            VerificationLevel::None => VerificationLevel::BestEffort,
+            other => other.clone(),
+        };
+        self.verifiers
+            .iter()
+            .find(|v| discriminant(&v.verification_level()) == discriminant(&wanted))
+            .map(|v| v.as_ref())
+    }
+}
+
+fn record(outcome: VerificationResult, checks: &mut Vec<String>, failures: &mut Vec<String>) {
+    let (performed, notes) = match outcome.report {
+        VerificationReport::Simple(_) => (Vec::new(), String::new()),
+        VerificationReport::Detailed {
+            checks_performed,
+            notes,
+            ..
+        } => (checks_performed, notes),
+    };
+    if !outcome.is_valid {
+        failures.push(if notes.is_empty() {
+            performed.join(", ")
+        } else {
+            notes
+        });
+    }
+    checks.extend(performed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(format: OutputFormat, level: VerificationLevel) -> Task {
+        Task {
+            id: TaskId::new([1u8; 32]),
+            requester: AgentId::new([2u8; 32]),
+            specification: TaskSpec {
+                description: "verify me".into(),
+                task_type: TaskType::Custom("test".into()),
+                input: Vec::new(),
+                output_format: format,
+                metadata: Vec::new(),
+            },
+            requirements: Requirements::none(),
+            budget: Budget {
+                max_cost: 100,
+                payment_schedule: PaymentSchedule::OnCompletion,
+                escrow_required: false,
+            },
+            deadline: 0,
+            verification_level: level,
+        }
+    }
+
+    fn result(executor: u8, output: &[u8]) -> TaskResult {
+        TaskResult {
+            task_id: TaskId::new([1u8; 32]),
+            executor: AgentId::new([executor; 32]),
+            output: output.to_vec(),
+            proof: None,
+            resources_used: ResourceUsage {
+                cpu_time_ms: 0,
+                memory_bytes: 0,
+                storage_bytes: 0,
+                bandwidth_bytes: 0,
+                gpu_time_ms: None,
+            },
+            completed_at: 0,
+        }
+    }
+
+    #[test]
+    fn structured_output_is_checked_against_schema() {
+        let schema = r#"{"type":"object","required":["label"],"properties":{"label":{"type":"string","enum":["cat","dog"]},"score":{"type":"number"}}}"#;
+        let registry = VerifierRegistry::default();
+        let task = task(
+            OutputFormat::Structured(schema.into()),
+            VerificationLevel::BestEffort,
+        );
+
+        let ok = result(1, br#"{"label":"cat","score":0.9}"#);
+        assert!(registry.verify(&task, &ok, &[]).unwrap().is_valid);
+
+        for bad in [
+            &br#"{"score":0.9}"#[..],
+            br#"{"label":"bird"}"#,
+            br#"{"label":"cat","score":"high"}"#,
+            b"not json",
+        ] {
+            assert!(
+                !registry
+                    .verify(&task, &result(1, bad), &[])
+                    .unwrap()
+                    .is_valid
+            );
+        }
+    }
+
+    #[test]
+    fn consensus_requires_two_thirds_agreement() {
+        let registry = VerifierRegistry::default();
+        let task = task(OutputFormat::Binary, VerificationLevel::Consensus(3));
+        let a = result(1, b"42");
+        let b = result(2, b"42");
+        let c = result(3, b"41");
+
+        assert!(
+            !registry
+                .verify(&task, &a, &[a.clone(), b.clone()])
+                .unwrap()
+                .is_valid
+        );
+        let collected = [a.clone(), b.clone(), c.clone(), result(4, b"42")];
+        assert!(registry.verify(&task, &a, &collected).unwrap().is_valid);
+        assert!(!registry.verify(&task, &c, &collected).unwrap().is_valid);
+
+        // The same executor reporting twice counts once.
+        assert!(
+            !registry
+                .verify(&task, &a, &[a.clone(), a.clone(), a.clone()])
+                .unwrap()
+                .is_valid
+        );
+    }
+
+    #[test]
+    fn unsupported_levels_are_reported() {
+        let registry = VerifierRegistry::default();
+        assert!(registry.supports(&VerificationLevel::None));
+        assert!(registry.supports(&VerificationLevel::Consensus(3)));
+        assert!(!registry.supports(&VerificationLevel::ZKProof));
+
+        let task = task(OutputFormat::Binary, VerificationLevel::ZKProof);
+        assert!(matches!(
+            registry.verify(&task, &result(1, b""), &[]),
+            Err(CoreError::VerificationFailed { .. })
+        ));
+    }
+}
@@ -0,0 +1,7 @@
+-- Explicit task lifecycle: open -> bidding -> allocated -> executing -> completed/failed/expired/cancelled/disputed.
+UPDATE tasks SET status = 'open' WHERE status = 'pending';
+
+ALTER TABLE tasks
+    ADD CONSTRAINT tasks_status_chk CHECK (
+        status IN ('open', 'bidding', 'allocated', 'executing', 'completed', 'failed', 'expired', 'cancelled', 'disputed')
+    );
@@ -0,0 +1,13 @@
+-- Winning agents chosen by the allocator when a task's bid window closes.
+CREATE TABLE IF NOT EXISTS allocations (
+    id UUID PRIMARY KEY,
+    task_id UUID NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    agent_id TEXT NOT NULL,
+    bid_id UUID NOT NULL REFERENCES bids(id) ON DELETE CASCADE,
+    payment NUMERIC(39,0) NOT NULL,
+    allocated_at TIMESTAMPTZ NOT NULL,
+    stored_json JSONB NOT NULL,
+    UNIQUE (task_id, agent_id)
+);
+
+CREATE INDEX IF NOT EXISTS allocations_task_idx ON allocations (task_id);
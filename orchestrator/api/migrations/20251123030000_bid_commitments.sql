@@ -0,0 +1,4 @@
+-- Commit-reveal bidding: sealed bids store their commitment; the nonce column
+-- (added in bid_result_enrichment) is filled on reveal.
+ALTER TABLE bids
+    ADD COLUMN IF NOT EXISTS commitment TEXT;
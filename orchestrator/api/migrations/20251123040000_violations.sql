@@ -0,0 +1,12 @@
+-- Protocol violations recorded against agents (e.g. missed task deadlines).
+CREATE TABLE IF NOT EXISTS violations (
+    id UUID PRIMARY KEY,
+    agent_id TEXT NOT NULL,
+    task_id UUID NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL,
+    stored_json JSONB NOT NULL,
+    UNIQUE (task_id, agent_id, kind)
+);
+
+CREATE INDEX IF NOT EXISTS violations_agent_idx ON violations (agent_id);
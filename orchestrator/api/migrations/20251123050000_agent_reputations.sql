@@ -0,0 +1,14 @@
+-- Per-agent reputation maintained by the orchestrator from accepted results.
+-- Score columns mirror stored_json for ordering and dashboards.
+CREATE TABLE IF NOT EXISTS agent_reputations (
+    agent_id TEXT PRIMARY KEY,
+    quality INTEGER NOT NULL,
+    reliability INTEGER NOT NULL,
+    speed INTEGER NOT NULL,
+    cost_efficiency INTEGER NOT NULL,
+    overall INTEGER NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    stored_json JSONB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS agent_reputations_overall_idx ON agent_reputations (overall DESC);
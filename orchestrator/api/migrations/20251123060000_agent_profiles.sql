@@ -0,0 +1,14 @@
+-- Structured agent registrations: capabilities, domains, public key and endpoints.
+-- Rows ingested from chain events before registration keep stored_json NULL.
+ALTER TABLE agents
+    ADD COLUMN IF NOT EXISTS capabilities JSONB NOT NULL DEFAULT '[]'::jsonb,
+    ADD COLUMN IF NOT EXISTS domains JSONB NOT NULL DEFAULT '[]'::jsonb,
+    ADD COLUMN IF NOT EXISTS public_key TEXT,
+    ADD COLUMN IF NOT EXISTS endpoints JSONB NOT NULL DEFAULT '[]'::jsonb,
+    ADD COLUMN IF NOT EXISTS verification_level TEXT NOT NULL DEFAULT 'best_effort',
+    ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    ADD COLUMN IF NOT EXISTS stored_json JSONB;
+
+CREATE UNIQUE INDEX IF NOT EXISTS agents_public_key_idx ON agents (public_key) WHERE public_key IS NOT NULL;
+CREATE INDEX IF NOT EXISTS agents_capabilities_idx ON agents USING GIN (capabilities);
+CREATE INDEX IF NOT EXISTS agents_domains_idx ON agents USING GIN (domains);
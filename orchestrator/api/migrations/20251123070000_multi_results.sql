@@ -0,0 +1,6 @@
+-- Consensus(n) tasks collect one result per allocated agent, so results are
+-- unique per (task_id, agent_id) only; drop any single-result-per-task key.
+ALTER TABLE results DROP CONSTRAINT IF EXISTS results_task_id_key;
+DROP INDEX IF EXISTS results_task_id_key;
+
+CREATE INDEX IF NOT EXISTS results_task_idx ON results (task_id, created_at);
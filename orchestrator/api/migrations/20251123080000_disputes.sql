@@ -0,0 +1,17 @@
+-- Disputes over task results, filed through the API or mirrored from
+-- Commitments::CommitmentDisputed (those have no local task).
+CREATE TABLE IF NOT EXISTS disputes (
+    id UUID PRIMARY KEY,
+    task_id UUID REFERENCES tasks(id) ON DELETE CASCADE,
+    requester_id TEXT NOT NULL,
+    status TEXT NOT NULL CHECK (status IN ('open', 'resolved')),
+    resolution TEXT CHECK (resolution IN ('uphold', 'refund', 'slash')),
+    filed_at TIMESTAMPTZ NOT NULL,
+    resolved_at TIMESTAMPTZ,
+    chain_commitment_id BIGINT UNIQUE,
+    stored_json JSONB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS disputes_task_idx ON disputes (task_id);
+-- At most one open dispute per task.
+CREATE UNIQUE INDEX IF NOT EXISTS disputes_open_task_idx ON disputes (task_id) WHERE status = 'open';
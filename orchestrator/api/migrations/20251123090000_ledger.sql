@@ -0,0 +1,28 @@
+-- Internal double-entry ledger. The entries of a transaction sum to zero;
+-- amounts are in protocol units with credits positive. Requesters and agents
+-- hold `user:<id>` accounts; the system accounts are `escrow:<task_id>`,
+-- `deposit:<bid_id>`, `stake:<agent_id>`, `unbonding:<agent_id>`, `treasury`
+-- and `external`.
+CREATE TABLE IF NOT EXISTS ledger_transactions (
+    id UUID PRIMARY KEY,
+    seq BIGSERIAL NOT NULL UNIQUE,
+    task_id UUID,
+    kind TEXT NOT NULL CHECK (kind IN (
+        'escrow_lock', 'payment', 'refund', 'slash', 'fee', 'deposit',
+        'bond', 'unbond', 'withdrawal', 'funding'
+    )),
+    amount NUMERIC(39,0) NOT NULL CHECK (amount >= 0),
+    created_at TIMESTAMPTZ NOT NULL,
+    stored_json JSONB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS ledger_transactions_task_idx ON ledger_transactions (task_id);
+
+CREATE TABLE IF NOT EXISTS ledger_entries (
+    transaction_id UUID NOT NULL REFERENCES ledger_transactions(id) ON DELETE CASCADE,
+    account TEXT NOT NULL,
+    amount NUMERIC(40,0) NOT NULL,
+    PRIMARY KEY (transaction_id, account)
+);
+
+CREATE INDEX IF NOT EXISTS ledger_entries_account_idx ON ledger_entries (account);
@@ -0,0 +1,18 @@
+-- Milestone completions reported by agents with partial output, approved or
+-- rejected by the requester. `milestone_id` is the milestone's UUID.
+CREATE TABLE IF NOT EXISTS milestone_completions (
+    id UUID PRIMARY KEY,
+    task_id UUID NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    milestone_id TEXT NOT NULL,
+    agent_id TEXT NOT NULL,
+    status TEXT NOT NULL CHECK (status IN ('pending', 'approved', 'rejected')),
+    submitted_at TIMESTAMPTZ NOT NULL,
+    reviewed_at TIMESTAMPTZ,
+    stored_json JSONB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS milestone_completions_task_idx ON milestone_completions (task_id);
+-- A rejected completion may be resubmitted; otherwise one per agent and milestone.
+CREATE UNIQUE INDEX IF NOT EXISTS milestone_completions_live_idx
+    ON milestone_completions (task_id, milestone_id, agent_id)
+    WHERE status <> 'rejected';
@@ -0,0 +1,2 @@
+-- Bids are looked up per agent to enforce the concurrent bid limit.
+CREATE INDEX IF NOT EXISTS bids_agent_idx ON bids ((stored_json->>'agent_id'));
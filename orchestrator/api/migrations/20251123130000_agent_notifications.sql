@@ -0,0 +1,13 @@
+-- Notices to agents about tasks they bid on (cancellations, budget raises,
+-- deadline extensions).
+CREATE TABLE IF NOT EXISTS agent_notifications (
+    id UUID PRIMARY KEY,
+    seq BIGSERIAL NOT NULL UNIQUE,
+    agent_id TEXT NOT NULL,
+    task_id UUID NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL CHECK (kind IN ('task_cancelled', 'task_updated')),
+    created_at TIMESTAMPTZ NOT NULL,
+    stored_json JSONB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS agent_notifications_agent_idx ON agent_notifications (agent_id);
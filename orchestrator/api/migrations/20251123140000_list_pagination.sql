@@ -0,0 +1,5 @@
+-- Keyset pagination: tasks are listed newest first and bids oldest first,
+-- both with `id` breaking ties. Agents page on their primary key.
+CREATE INDEX IF NOT EXISTS tasks_created_idx ON tasks (created_at DESC, id DESC);
+CREATE INDEX IF NOT EXISTS tasks_requester_idx ON tasks (requester_id);
+CREATE INDEX IF NOT EXISTS bids_task_created_idx ON bids (task_id, created_at, id);
@@ -0,0 +1,9 @@
+-- Idempotency keys claimed by task, bid and result submissions. A key whose
+-- stored_json has no response belongs to a request still in progress.
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    key TEXT PRIMARY KEY,
+    request_hash TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    completed_at TIMESTAMPTZ,
+    stored_json JSONB NOT NULL
+);
@@ -0,0 +1,10 @@
+-- Nonces of signed requests, kept until the signature expires so a captured
+-- request cannot be replayed.
+CREATE TABLE IF NOT EXISTS request_nonces (
+    signer_id TEXT NOT NULL,
+    nonce TEXT NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (signer_id, nonce)
+);
+
+CREATE INDEX IF NOT EXISTS request_nonces_expires_at_idx ON request_nonces (expires_at);
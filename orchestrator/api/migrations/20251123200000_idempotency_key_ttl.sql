@@ -0,0 +1,3 @@
+-- Idempotency keys expire a day after they were claimed and are pruned by
+-- creation time.
+CREATE INDEX IF NOT EXISTS idempotency_keys_created_at_idx ON idempotency_keys (created_at);
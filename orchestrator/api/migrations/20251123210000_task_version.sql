@@ -0,0 +1,3 @@
+-- Tasks carry a version bumped on every write; status changes only apply to
+-- the version they were read at.
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS version BIGINT NOT NULL DEFAULT 0;
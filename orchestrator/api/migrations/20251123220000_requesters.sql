@@ -0,0 +1,7 @@
+-- Public keys requesters sign their requests with.
+CREATE TABLE IF NOT EXISTS requesters (
+    id TEXT PRIMARY KEY,
+    public_key TEXT NOT NULL,
+    registered_at TIMESTAMPTZ NOT NULL,
+    stored_json JSONB NOT NULL
+);
@@ -0,0 +1,586 @@
+//! Background allocator that closes bid windows and picks winning bids.
+//!
+//! Every poll the allocator scans tasks that are still open for bids, and for
+//! each task whose bid window has elapsed runs a [`SealedBidAuction`] over the
+//! stored bids. The winning allocation is persisted, the task moves to
+//! `allocated`, and (with `chain-bridge`) a `TaskMarket::allocate_task`
+//! extrinsic is enqueued through the outbox. Tasks with sealed bids that are
+//! still unrevealed wait for the reveal phase to end; bids that were never
+//! revealed are then rejected. `Consensus(n)` tasks are allocated to the best
+//! `n` bids and stay open until that many valid bids are in. Agents of
+//! `Upfront` tasks are paid through the ledger as soon as they are allocated,
+//! and the deposits of losing bids are returned. Each allocated agent is
+//! announced on the event bus. Bids are scored with their agents' stored
+//! reputation.
+//!
+//! Allocations are stored before the task moves to `allocated`, once the
+//! task has been claimed with a versioned write so that a concurrent cancel
+//! or amendment cannot slip in between. A task that already has allocations
+//! is not auctioned again; the allocator finishes the steps a failed pass
+//! left undone.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use ainur_core::{AuctionMechanism, PricingRule, SealedBidAuction};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::error::ApiError;
+use crate::events::EventBus;
+use crate::ledger;
+use crate::model::{
+    current_unix_timestamp, EventKind, StoredAllocation, StoredBid, StoredTask, TaskStatus,
+};
+use crate::reputation;
+use crate::storage::Storage;
+#[cfg(feature = "chain-bridge")]
+use crate::{chain, storage::ChainEventSink};
+#[cfg(all(feature = "chain-bridge", feature = "postgres"))]
+use sqlx::{Pool, Postgres};
+
+/// Closes bid windows and allocates tasks to the winning bidder.
+pub struct Allocator {
+    storage: Arc<dyn Storage>,
+    pricing: PricingRule,
+    events: Option<EventBus>,
+    #[cfg(feature = "chain-bridge")]
+    chain_sink: Option<Arc<dyn ChainEventSink>>,
+    #[cfg(all(feature = "chain-bridge", feature = "postgres"))]
+    pg_pool: Option<Pool<Postgres>>,
+}
+
+impl Allocator {
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self {
+            storage,
+            pricing: PricingRule::default(),
+            events: None,
+            #[cfg(feature = "chain-bridge")]
+            chain_sink: None,
+            #[cfg(all(feature = "chain-bridge", feature = "postgres"))]
+            pg_pool: None,
+        }
+    }
+
+    /// Select the pricing rule used to compute the winner's payment.
+    pub fn with_pricing(mut self, pricing: PricingRule) -> Self {
+        self.pricing = pricing;
+        self
+    }
+
+    /// Publish a `task_allocated` event for every allocated agent.
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Enqueue `TaskMarket::allocate_task` for every allocation made.
+    #[cfg(feature = "chain-bridge")]
+    pub fn with_chain_sink(mut self, sink: Arc<dyn ChainEventSink>) -> Self {
+        self.chain_sink = Some(sink);
+        self
+    }
+
+    /// Pool used to resolve on-chain task ids for outbox payloads.
+    #[cfg(all(feature = "chain-bridge", feature = "postgres"))]
+    pub fn with_pg_pool(mut self, pool: Option<Pool<Postgres>>) -> Self {
+        self.pg_pool = pool;
+        self
+    }
+
+    /// Poll forever, allocating tasks whose bid window has closed.
+    pub async fn run(self, poll_ms: u64) -> Result<(), ApiError> {
+        loop {
+            match self.allocate_due(current_unix_timestamp()).await {
+                Ok(0) => {}
+                Ok(n) => info!("allocated {n} task(s)"),
+                Err(err) => warn!("allocator iteration failed: {err}"),
+            }
+            tokio::time::sleep(Duration::from_millis(poll_ms)).await;
+        }
+    }
+
+    /// Allocate every task whose bid window has closed as of `now`, returning
+    /// the number of tasks allocated. Failures on individual tasks are logged
+    /// and retried on the next pass.
+    pub async fn allocate_due(&self, now: u64) -> Result<usize, ApiError> {
+        let tasks = self
+            .storage
+            .list_tasks_with_status(&[TaskStatus::Open, TaskStatus::Bidding])
+            .await?;
+
+        let mut allocated = 0;
+        // Overdue tasks are left to the deadline sweeper.
+        for task in tasks
+            .into_iter()
+            .filter(|t| t.bid_window_closed(now) && !t.is_overdue(now))
+        {
+            let task_id = task.id.clone();
+            match self.allocate_task(task, now).await {
+                Ok(Some(_)) => allocated += 1,
+                Ok(None) => {}
+                Err(err) => warn!("failed to allocate task {task_id}: {err}"),
+            }
+        }
+        Ok(allocated)
+    }
+
+    /// Run the auction for a single task. Returns `None` when no valid bid was
+    /// received, leaving the task open until its deadline, or while sealed
+    /// bids may still be revealed.
+    pub async fn allocate_task(
+        &self,
+        mut task: StoredTask,
+        now: u64,
+    ) -> Result<Option<Vec<StoredAllocation>>, ApiError> {
+        let bids = self.storage.get_bids_for_task(&task.id).await?;
+        let existing = self.storage.get_allocations_for_task(&task.id).await?;
+        if !existing.is_empty() {
+            info!("task {}: completing an earlier allocation", task.id);
+            return self.finish(task, &bids, existing).await.map(Some);
+        }
+        if bids.iter().any(|b| !b.revealed) && !task.reveal_window_closed(now) {
+            return Ok(None);
+        }
+        let allocations = match self.run_auction(&task, bids.clone(), now).await? {
+            Some(allocations) => allocations,
+            None => return Ok(None),
+        };
+        // Claim the task before allocating it: a cancel or amendment that
+        // read it earlier now conflicts, and one stored since fails this pass.
+        self.storage.update_task(&mut task).await?;
+        for allocation in &allocations {
+            self.storage.insert_allocation(allocation.clone()).await?;
+        }
+        self.finish(task, &bids, allocations).await.map(Some)
+    }
+
+    /// Pick the winning bids of `task`, or `None` while there are not enough
+    /// valid bids.
+    async fn run_auction(
+        &self,
+        task: &StoredTask,
+        mut bids: Vec<StoredBid>,
+        now: u64,
+    ) -> Result<Option<Vec<StoredAllocation>>, ApiError> {
+        // Earlier bids take precedence on ties and duplicate agents.
+        bids.sort_by_key(|b| b.created_at);
+
+        let mut auction = SealedBidAuction::new(task.task.id, task.task.budget.clone())
+            .with_pricing(self.pricing)
+            .with_winners(task.redundancy());
+        let mut accepted = Vec::with_capacity(bids.len());
+        for stored in &bids {
+            if !stored.revealed {
+                warn!("task {}: rejecting unrevealed bid {}", task.id, stored.id);
+                continue;
+            }
+            match auction.add_bid(stored.bid.clone()) {
+                Ok(()) => accepted.push(stored),
+                Err(err) => {
+                    warn!("task {}: ignoring bid {}: {err}", task.id, stored.id);
+                    continue;
+                }
+            }
+            if let Some(record) =
+                reputation::agent_reputation(&self.storage, &stored.agent_id, now).await?
+            {
+                auction.set_reputation(stored.bid.agent_id, record.reputation);
+            }
+        }
+        if accepted.is_empty() {
+            debug!("task {}: bid window closed without valid bids", task.id);
+            return Ok(None);
+        }
+        if accepted.len() < task.redundancy() {
+            debug!(
+                "task {}: {} valid bid(s), {} executors required",
+                task.id,
+                accepted.len(),
+                task.redundancy()
+            );
+            return Ok(None);
+        }
+
+        let outcome = auction.run_auction()?;
+        let mut allocations = Vec::with_capacity(outcome.allocations.len());
+        for allocation in &outcome.allocations {
+            let stored = accepted
+                .iter()
+                .find(|b| b.bid.agent_id == allocation.agent_id)
+                .ok_or_else(|| {
+                    ApiError::Internal(format!("task {}: winning bid not found", task.id))
+                })?;
+            let payment = outcome
+                .payments
+                .iter()
+                .find(|(agent, _)| *agent == allocation.agent_id)
+                .map(|(_, amount)| *amount)
+                .unwrap_or(allocation.bid.value);
+            allocations.push(StoredAllocation {
+                id: Uuid::new_v4().to_string(),
+                task_id: task.id.clone(),
+                agent_id: stored.agent_id.clone(),
+                bid_id: stored.id.clone(),
+                payment,
+                social_welfare: outcome.social_welfare,
+                allocated_at: now,
+            });
+        }
+        Ok(Some(allocations))
+    }
+
+    /// Pay upfront agents, return the losing deposits and move `task` to
+    /// `allocated`. The payments only cover what is still owed, so a pass
+    /// that failed part-way can simply be repeated.
+    async fn finish(
+        &self,
+        mut task: StoredTask,
+        bids: &[StoredBid],
+        allocations: Vec<StoredAllocation>,
+    ) -> Result<Vec<StoredAllocation>, ApiError> {
+        let allocated_at = allocations
+            .iter()
+            .map(|a| a.allocated_at)
+            .max()
+            .unwrap_or_default();
+        ledger::pay_upfront(&self.storage, &task, &allocations, allocated_at).await?;
+        for bid in bids
+            .iter()
+            .filter(|b| !allocations.iter().any(|a| a.bid_id == b.id))
+        {
+            ledger::release_bid_deposit(&self.storage, &task, bid, allocated_at).await?;
+        }
+        task.transition_to(TaskStatus::Allocated)?;
+        self.storage.update_task(&mut task).await?;
+        if let Some(events) = &self.events {
+            for allocation in &allocations {
+                events.publish(
+                    EventKind::TaskAllocated,
+                    &task.id,
+                    Some(&allocation.agent_id),
+                    serde_json::json!({
+                        "bid_id": allocation.bid_id,
+                        "payment": allocation.payment,
+                    }),
+                );
+            }
+        }
+
+        #[cfg(feature = "chain-bridge")]
+        self.enqueue_allocation(&task).await;
+
+        Ok(allocations)
+    }
+
+    #[cfg(feature = "chain-bridge")]
+    async fn enqueue_allocation(&self, task: &StoredTask) {
+        let Some(sink) = self.chain_sink.clone() else {
+            return;
+        };
+        #[cfg(feature = "postgres")]
+        let (task_chain_id, _) = chain::lookup_chain_ids(self.pg_pool.as_ref(), &task.id, "").await;
+        #[cfg(not(feature = "postgres"))]
+        let task_chain_id = 0_i64;
+
+        let correlation_id = Uuid::new_v4().to_string();
+        let payload_json = serde_json::json!({ "task_id": task_chain_id });
+        if let Err(err) = chain::validate_outbox_payload(
+            "TaskMarket",
+            "allocate_task",
+            Some(payload_json.to_string().as_str()),
+        ) {
+            warn!("skipping outbox enqueue for allocation: {err}");
+        } else if let Err(err) = chain::record_outbound_extrinsic(
+            sink,
+            &correlation_id,
+            "TaskMarket",
+            "allocate_task",
+            Some(&payload_json.to_string()),
+        )
+        .await
+        {
+            warn!("failed to enqueue task allocation: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{
+        BidRevealRequest, BidSubmissionRequest, StoredReputation, TaskSubmissionRequest,
+    };
+    use crate::storage::InMemoryStorage;
+    use ainur_core::{bid_commitment, AgentReputation};
+    use base64::{engine::general_purpose, Engine as _};
+
+    async fn seed_task(storage: &Arc<dyn Storage>, bids: &[(&str, u128)]) -> StoredTask {
+        let mut task = StoredTask::from_submission(TaskSubmissionRequest {
+            client_task_id: None,
+            requester_id: "requester".into(),
+            description: "allocate me".into(),
+            task_type: "echo".into(),
+            input_base64: general_purpose::STANDARD.encode("hi"),
+            max_budget: 100,
+            deadline: 0,
+            bid_window_secs: None,
+            domain: None,
+            requirements: Default::default(),
+            output_format: None,
+            verification_level: None,
+            payment_schedule: None,
+            milestones: Vec::new(),
+            escrow_required: None,
+            signature: None,
+        })
+        .unwrap();
+        for (agent, value) in bids {
+            let bid = StoredBid::from_submission(
+                BidSubmissionRequest {
+                    task_id: task.id.clone(),
+                    agent_id: (*agent).into(),
+                    value: Some(*value),
+                    commitment: None,
+                    quality_score: 80,
+                    completion_time: 60,
+                    guarantees: Vec::new(),
+                    signature: None,
+                },
+                &task,
+            )
+            .unwrap();
+            storage.insert_bid(bid).await.unwrap();
+        }
+        if !bids.is_empty() {
+            task.transition_to(TaskStatus::Bidding).unwrap();
+        }
+        storage.insert_task(task.clone()).await.unwrap();
+        task
+    }
+
+    #[tokio::test]
+    async fn allocates_once_window_closes() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let task = seed_task(&storage, &[("agent-a", 40), ("agent-b", 25)]).await;
+        let allocator = Allocator::new(storage.clone());
+
+        let before_close = task.bid_window_closes_at() - 1;
+        assert_eq!(allocator.allocate_due(before_close).await.unwrap(), 0);
+        assert_eq!(
+            allocator
+                .allocate_due(task.bid_window_closes_at())
+                .await
+                .unwrap(),
+            1
+        );
+
+        let allocations = storage.get_allocations_for_task(&task.id).await.unwrap();
+        assert_eq!(allocations.len(), 1);
+        assert_eq!(allocations[0].agent_id, "agent-b");
+        // Second-price: the winner is paid the runner-up's bid.
+        assert_eq!(allocations[0].payment, 40);
+        let stored = storage.get_task(&task.id).await.unwrap();
+        assert_eq!(stored.status, TaskStatus::Allocated);
+
+        // Allocated tasks are not picked up again.
+        assert_eq!(allocator.allocate_due(u64::MAX).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn tasks_changed_since_they_were_read_are_not_allocated() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let task = seed_task(&storage, &[("agent-a", 40)]).await;
+        let mut cancelled = task.clone();
+        cancelled.transition_to(TaskStatus::Cancelled).unwrap();
+        storage.update_task(&mut cancelled).await.unwrap();
+
+        let allocator = Allocator::new(storage.clone());
+        assert!(matches!(
+            allocator.allocate_task(task.clone(), u64::MAX).await,
+            Err(ApiError::Conflict(_))
+        ));
+        assert!(storage
+            .get_allocations_for_task(&task.id)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            storage.get_task(&task.id).await.unwrap().status,
+            TaskStatus::Cancelled
+        );
+    }
+
+    #[tokio::test]
+    async fn stored_reputation_feeds_the_auction() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        // Without reputation the cheaper, earlier bid wins.
+        let task = seed_task(&storage, &[("agent-a", 40), ("agent-b", 41)]).await;
+        let now = task.bid_window_closes_at();
+        let mut record = AgentReputation::new(now);
+        record.reputation.quality = 100;
+        record.reputation.reliability = 100;
+        record.reputation.speed = 100;
+        record.reputation.cost_efficiency = 100;
+        storage
+            .upsert_reputation(StoredReputation {
+                agent_id: "agent-b".into(),
+                record,
+            })
+            .await
+            .unwrap();
+
+        Allocator::new(storage.clone())
+            .allocate_due(now)
+            .await
+            .unwrap();
+        let allocations = storage.get_allocations_for_task(&task.id).await.unwrap();
+        assert_eq!(allocations[0].agent_id, "agent-b");
+        assert_eq!(allocations[0].allocated_at, now);
+    }
+
+    #[tokio::test]
+    async fn interrupted_allocations_are_completed_not_rerun() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let task = seed_task(&storage, &[("agent-a", 40), ("agent-b", 25)]).await;
+        let bids = storage.get_bids_for_task(&task.id).await.unwrap();
+        // A pass that stored its allocation but failed before the task moved.
+        storage
+            .insert_allocation(StoredAllocation {
+                id: Uuid::new_v4().to_string(),
+                task_id: task.id.clone(),
+                agent_id: "agent-a".into(),
+                bid_id: bids
+                    .iter()
+                    .find(|b| b.agent_id == "agent-a")
+                    .unwrap()
+                    .id
+                    .clone(),
+                payment: 40,
+                social_welfare: 60,
+                allocated_at: 5,
+            })
+            .await
+            .unwrap();
+
+        let allocator = Allocator::new(storage.clone());
+        assert_eq!(allocator.allocate_due(u64::MAX).await.unwrap(), 1);
+        let allocations = storage.get_allocations_for_task(&task.id).await.unwrap();
+        assert_eq!(allocations.len(), 1);
+        assert_eq!(allocations[0].agent_id, "agent-a");
+        assert_eq!(
+            storage.get_task(&task.id).await.unwrap().status,
+            TaskStatus::Allocated
+        );
+    }
+
+    #[tokio::test]
+    async fn consensus_tasks_wait_for_enough_executors() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let mut task = seed_task(&storage, &[("agent-a", 40)]).await;
+        task.task.verification_level = ainur_core::VerificationLevel::Consensus(2);
+        storage.upsert_task(task.clone()).await.unwrap();
+        let allocator = Allocator::new(storage.clone());
+
+        assert_eq!(allocator.allocate_due(u64::MAX).await.unwrap(), 0);
+
+        let bid = StoredBid::from_submission(
+            BidSubmissionRequest {
+                task_id: task.id.clone(),
+                agent_id: "agent-b".into(),
+                value: Some(30),
+                commitment: None,
+                quality_score: 80,
+                completion_time: 60,
+                guarantees: Vec::new(),
+                signature: None,
+            },
+            &task,
+        )
+        .unwrap();
+        storage.insert_bid(bid).await.unwrap();
+        assert_eq!(allocator.allocate_due(u64::MAX).await.unwrap(), 1);
+
+        let mut winners: Vec<_> = storage
+            .get_allocations_for_task(&task.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|a| a.agent_id)
+            .collect();
+        winners.sort();
+        assert_eq!(winners, ["agent-a", "agent-b"]);
+    }
+
+    #[tokio::test]
+    async fn tasks_without_bids_stay_open() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let task = seed_task(&storage, &[]).await;
+        let allocator = Allocator::new(storage.clone()).with_pricing(PricingRule::FirstPrice);
+
+        assert_eq!(allocator.allocate_due(u64::MAX).await.unwrap(), 0);
+        let stored = storage.get_task(&task.id).await.unwrap();
+        assert_eq!(stored.status, TaskStatus::Open);
+        assert!(storage
+            .get_allocations_for_task(&task.id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn sealed_bids_wait_for_reveal_phase() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let task = seed_task(&storage, &[("agent-a", 40)]).await;
+        let sealed = |agent: &str, value: u128, nonce: [u8; 32]| {
+            StoredBid::from_submission(
+                BidSubmissionRequest {
+                    task_id: task.id.clone(),
+                    agent_id: agent.into(),
+                    value: None,
+                    commitment: Some(hex::encode(bid_commitment(&task.id, agent, value, &nonce))),
+                    quality_score: 80,
+                    completion_time: 60,
+                    guarantees: Vec::new(),
+                    signature: None,
+                },
+                &task,
+            )
+            .unwrap()
+        };
+        let mut revealed = sealed("agent-b", 30, [1u8; 32]);
+        revealed
+            .reveal(&BidRevealRequest {
+                value: 30,
+                nonce: hex::encode([1u8; 32]),
+            })
+            .unwrap();
+        storage.insert_bid(revealed).await.unwrap();
+        // Never revealed; would otherwise win with a placeholder value of 0.
+        storage
+            .insert_bid(sealed("agent-c", 10, [2u8; 32]))
+            .await
+            .unwrap();
+        let allocator = Allocator::new(storage.clone());
+
+        assert_eq!(
+            allocator
+                .allocate_due(task.bid_window_closes_at())
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            allocator
+                .allocate_due(task.reveal_window_closes_at())
+                .await
+                .unwrap(),
+            1
+        );
+
+        let allocations = storage.get_allocations_for_task(&task.id).await.unwrap();
+        assert_eq!(allocations.len(), 1);
+        assert_eq!(allocations[0].agent_id, "agent-b");
+    }
+}
@@ -0,0 +1,415 @@
+//! Cancelling and amending tasks.
+//!
+//! While a task is still open for bids its requester may cancel it, which
+//! refunds its escrow and every bid deposit (see `ledger::release_escrow`),
+//! or amend it: raise the budget, locking the difference in escrow, or push
+//! back a deadline. Milestone tranches are rescaled to a raised budget. Every
+//! agent that bid on the task is notified of the change.
+//!
+//! Neither is possible once the bid window has closed. Both store the task
+//! with [`Storage::update_task`], so they conflict with an allocator pass,
+//! or with each other, that changed the task since it was read.
+
+use std::sync::Arc;
+
+use ainur_core::{rescale_tranches, PaymentSchedule};
+use uuid::Uuid;
+
+use crate::error::ApiError;
+use crate::ledger;
+use crate::model::{
+    agent_key, NotificationKind, StoredNotification, StoredTask, TaskCancelRequest, TaskStatus,
+    TaskUpdateRequest,
+};
+use crate::storage::Storage;
+
+/// Cancel `task_id` on behalf of its requester. Cancelling a cancelled task
+/// again releases whatever escrow an earlier, failed cancel left behind.
+pub async fn cancel(
+    storage: &Arc<dyn Storage>,
+    task_id: &str,
+    request: TaskCancelRequest,
+    now: u64,
+) -> Result<StoredTask, ApiError> {
+    let mut task = storage.get_task(task_id).await?;
+    ensure_requester(&task, &request.requester_id, "cancel")?;
+    if task.status == TaskStatus::Cancelled {
+        ledger::release_escrow(storage, &task, now).await?;
+        return Ok(task);
+    }
+    // Only open and bidding tasks may move to `cancelled`.
+    task.transition_to(TaskStatus::Cancelled)?;
+    ensure_unallocated(storage, &task, "cancelled", now).await?;
+    // The status is stored before the escrow is released, so an allocator
+    // pass that read the task earlier conflicts instead of paying from it.
+    storage.update_task(&mut task).await?;
+    ledger::release_escrow(storage, &task, now).await?;
+
+    let message = match request.reason {
+        Some(reason) => format!("task {task_id} was cancelled: {reason}"),
+        None => format!("task {task_id} was cancelled"),
+    };
+    notify_bidders(
+        storage,
+        &task,
+        NotificationKind::TaskCancelled,
+        message,
+        now,
+    )
+    .await?;
+    Ok(task)
+}
+
+/// Raise the budget and/or extend the deadline of `task_id` on behalf of its
+/// requester.
+pub async fn update(
+    storage: &Arc<dyn Storage>,
+    task_id: &str,
+    request: TaskUpdateRequest,
+    now: u64,
+) -> Result<StoredTask, ApiError> {
+    let mut task = storage.get_task(task_id).await?;
+    ensure_requester(&task, &request.requester_id, "update")?;
+    if !task.status.is_open_for_bids() {
+        return Err(ApiError::BadRequest(format!(
+            "task {task_id} is {} and can no longer be updated",
+            task.status
+        )));
+    }
+    ensure_unallocated(storage, &task, "updated", now).await?;
+    if request.max_budget.is_none() && request.deadline.is_none() {
+        return Err(ApiError::BadRequest(
+            "max_budget or deadline must be provided".to_string(),
+        ));
+    }
+
+    let mut changes = Vec::new();
+    let previous_budget = task.task.budget.max_cost;
+    if let Some(budget) = request.max_budget {
+        if budget <= previous_budget {
+            return Err(ApiError::BadRequest(format!(
+                "max_budget can only be raised above {previous_budget}"
+            )));
+        }
+        task.task.budget.max_cost = budget;
+        if let PaymentSchedule::Milestone(tranches) = &mut task.task.budget.payment_schedule {
+            *tranches = rescale_tranches(tranches, budget);
+        }
+        changes.push(format!("budget raised to {budget}"));
+    }
+    if let Some(deadline) = request.deadline {
+        if task.task.deadline == 0 {
+            return Err(ApiError::BadRequest(format!(
+                "task {task_id} has no deadline to extend"
+            )));
+        }
+        if deadline <= task.task.deadline.max(now) {
+            return Err(ApiError::BadRequest(format!(
+                "deadline can only be extended past {}",
+                task.task.deadline.max(now)
+            )));
+        }
+        task.task.deadline = deadline;
+        changes.push(format!("deadline extended to {deadline}"));
+    }
+
+    // The task id does not cover the budget or deadline, so it stays valid.
+    // The extra escrow is locked first and handed back if the amended task
+    // cannot be stored, e.g. because it was cancelled or allocated meanwhile.
+    ledger::raise_escrow(storage, &task, previous_budget, now).await?;
+    if let Err(err) = storage.update_task(&mut task).await {
+        ledger::return_raised_escrow(storage, &task, previous_budget, now).await?;
+        return Err(err);
+    }
+
+    let message = format!("task {task_id}: {}", changes.join(", "));
+    notify_bidders(storage, &task, NotificationKind::TaskUpdated, message, now).await?;
+    Ok(task)
+}
+
+/// Once the bid window has closed the allocator may pick the task up, so it
+/// can no longer be cancelled or amended.
+async fn ensure_unallocated(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    action: &str,
+    now: u64,
+) -> Result<(), ApiError> {
+    if task.bid_window_closed(now) {
+        return Err(ApiError::BadRequest(format!(
+            "the bid window of task {} closed at {} and it can no longer be {action}",
+            task.id,
+            task.bid_window_closes_at()
+        )));
+    }
+    if !storage.get_allocations_for_task(&task.id).await?.is_empty() {
+        return Err(ApiError::BadRequest(format!(
+            "task {} has been allocated and can no longer be {action}",
+            task.id
+        )));
+    }
+    Ok(())
+}
+
+fn ensure_requester(task: &StoredTask, requester_id: &str, action: &str) -> Result<(), ApiError> {
+    if agent_key(requester_id) != task.task.requester {
+        return Err(ApiError::Forbidden(format!(
+            "only the requester of task {} may {action} it",
+            task.id
+        )));
+    }
+    Ok(())
+}
+
+/// Notify every agent with a bid on `task`, once each.
+async fn notify_bidders(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    kind: NotificationKind,
+    message: String,
+    now: u64,
+) -> Result<(), ApiError> {
+    let mut agents: Vec<String> = storage
+        .get_bids_for_task(&task.id)
+        .await?
+        .into_iter()
+        .map(|bid| bid.agent_id)
+        .collect();
+    agents.sort();
+    agents.dedup();
+    for agent_id in agents {
+        storage
+            .insert_notification(StoredNotification {
+                id: Uuid::new_v4().to_string(),
+                agent_id,
+                task_id: task.id.clone(),
+                kind,
+                message: message.clone(),
+                created_at: now,
+            })
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{
+        BidSubmissionRequest, MilestoneSubmission, StoredBid, TaskSubmissionRequest,
+    };
+    use crate::staking;
+    use crate::storage::InMemoryStorage;
+    use ainur_core::constants;
+
+    async fn open_task(
+        storage: &Arc<dyn Storage>,
+        milestones: Vec<MilestoneSubmission>,
+    ) -> StoredTask {
+        let task = StoredTask::from_submission(TaskSubmissionRequest {
+            client_task_id: None,
+            requester_id: "requester".into(),
+            description: "amend me".into(),
+            task_type: "echo".into(),
+            input_base64: String::new(),
+            max_budget: 1_000_000,
+            deadline: 1_000,
+            bid_window_secs: None,
+            domain: None,
+            requirements: Default::default(),
+            output_format: None,
+            verification_level: None,
+            payment_schedule: None,
+            milestones,
+            escrow_required: None,
+            signature: None,
+        })
+        .unwrap();
+        storage.insert_task(task.clone()).await.unwrap();
+        ledger::fund(storage, "user:requester", 1_000_000, 0)
+            .await
+            .unwrap();
+        ledger::lock_escrow(storage, &task, 0).await.unwrap();
+        task
+    }
+
+    async fn place_bid(storage: &Arc<dyn Storage>, task: &StoredTask, agent_id: &str) {
+        let stake = constants::economics::MIN_AGENT_STAKE;
+        // The stake and the bid placement fee.
+        ledger::fund(storage, &ledger::user_account(agent_id), stake + 1_000, 0)
+            .await
+            .unwrap();
+        staking::bond(storage, agent_id, stake, 0).await.unwrap();
+        let bid = StoredBid::from_submission(
+            BidSubmissionRequest {
+                task_id: task.id.clone(),
+                agent_id: agent_id.into(),
+                value: Some(500_000),
+                commitment: None,
+                quality_score: 90,
+                completion_time: 60,
+                guarantees: Vec::new(),
+                signature: None,
+            },
+            task,
+        )
+        .unwrap();
+        storage.insert_bid(bid.clone()).await.unwrap();
+        ledger::lock_bid_deposit(storage, task, &bid, 0)
+            .await
+            .unwrap();
+    }
+
+    async fn balance_of(storage: &Arc<dyn Storage>, id: &str) -> i128 {
+        ledger::account(storage, id).await.unwrap().balance
+    }
+
+    fn cancel_request(requester_id: &str) -> TaskCancelRequest {
+        TaskCancelRequest {
+            requester_id: requester_id.into(),
+            reason: Some("no longer needed".into()),
+            signature: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn cancelling_refunds_escrow_and_deposits_and_notifies_bidders() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let task = open_task(&storage, Vec::new()).await;
+        place_bid(&storage, &task, "agent").await;
+        assert!(matches!(
+            cancel(&storage, &task.id, cancel_request("someone else"), 10).await,
+            Err(ApiError::Forbidden(_))
+        ));
+
+        let cancelled = cancel(&storage, &task.id, cancel_request("requester"), 10)
+            .await
+            .unwrap();
+        assert_eq!(cancelled.status, TaskStatus::Cancelled);
+        assert_eq!(balance_of(&storage, "user:requester").await, 1_000_000);
+        assert_eq!(
+            balance_of(&storage, &ledger::escrow_account(&task.id)).await,
+            0
+        );
+        let stake = staking::stake(&storage, "agent", 10).await.unwrap();
+        assert_eq!(
+            (stake.free, stake.locked),
+            (constants::economics::MIN_AGENT_STAKE, 0)
+        );
+        let notifications = storage.get_notifications_for_agent("agent").await.unwrap();
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].kind, NotificationKind::TaskCancelled);
+        assert!(notifications[0].message.contains("no longer needed"));
+
+        // Cancelling again only retries the release: nothing more is
+        // refunded and bidders are not notified twice.
+        let again = cancel(&storage, &task.id, cancel_request("requester"), 20)
+            .await
+            .unwrap();
+        assert_eq!(again.status, TaskStatus::Cancelled);
+        assert_eq!(balance_of(&storage, "user:requester").await, 1_000_000);
+        assert_eq!(
+            storage
+                .get_notifications_for_agent("agent")
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn tasks_cannot_be_cancelled_or_updated_once_the_bid_window_closes() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let task = open_task(&storage, Vec::new()).await;
+        let closed = task.bid_window_closes_at();
+        assert!(matches!(
+            cancel(&storage, &task.id, cancel_request("requester"), closed).await,
+            Err(ApiError::BadRequest(_))
+        ));
+        let raise = TaskUpdateRequest {
+            requester_id: "requester".into(),
+            max_budget: Some(2_000_000),
+            deadline: None,
+            signature: None,
+        };
+        assert!(matches!(
+            update(&storage, &task.id, raise, closed).await,
+            Err(ApiError::BadRequest(_))
+        ));
+        let stored = storage.get_task(&task.id).await.unwrap();
+        assert_eq!(
+            (stored.status, stored.task.budget.max_cost),
+            (TaskStatus::Open, 1_000_000)
+        );
+        assert_eq!(
+            balance_of(&storage, &ledger::escrow_account(&task.id)).await,
+            1_000_000
+        );
+    }
+
+    #[tokio::test]
+    async fn updates_raise_escrow_rescale_milestones_and_extend_deadlines() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let milestones = vec![
+            MilestoneSubmission {
+                description: "draft".into(),
+                criteria: "outline".into(),
+                amount: 250_000,
+            },
+            MilestoneSubmission {
+                description: "final".into(),
+                criteria: "done".into(),
+                amount: 750_000,
+            },
+        ];
+        let task = open_task(&storage, milestones).await;
+        place_bid(&storage, &task, "agent").await;
+        let update_request = |max_budget, deadline| TaskUpdateRequest {
+            requester_id: "requester".into(),
+            max_budget,
+            deadline,
+            signature: None,
+        };
+        for rejected in [
+            update_request(None, None),
+            update_request(Some(1_000_000), None),
+            update_request(None, Some(500)),
+        ] {
+            assert!(matches!(
+                update(&storage, &task.id, rejected, 10).await,
+                Err(ApiError::BadRequest(_))
+            ));
+        }
+
+        ledger::fund(&storage, "user:requester", 1_000_000, 0)
+            .await
+            .unwrap();
+        let updated = update(
+            &storage,
+            &task.id,
+            update_request(Some(2_000_000), Some(5_000)),
+            10,
+        )
+        .await
+        .unwrap();
+        assert_eq!(updated.task.budget.max_cost, 2_000_000);
+        assert_eq!(updated.task.deadline, 5_000);
+        assert_eq!(updated.task.id, task.task.id);
+        let salt = Uuid::parse_str(&task.id).unwrap().as_u128();
+        assert_eq!(updated.task.derive_id(salt), updated.task.id);
+        let PaymentSchedule::Milestone(tranches) = &updated.task.budget.payment_schedule else {
+            panic!("expected a milestone schedule");
+        };
+        let amounts: Vec<u128> = tranches.iter().map(|(_, amount)| *amount).collect();
+        assert_eq!(amounts, vec![500_000, 1_500_000]);
+        assert_eq!(
+            balance_of(&storage, &ledger::escrow_account(&task.id)).await,
+            2_000_000
+        );
+        assert_eq!(balance_of(&storage, "user:requester").await, 0);
+        let notifications = storage.get_notifications_for_agent("agent").await.unwrap();
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].kind, NotificationKind::TaskUpdated);
+    }
+}
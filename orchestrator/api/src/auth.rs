@@ -0,0 +1,406 @@
+//! API key authentication and role-based access.
+//!
+//! Keys are configured through `API_KEYS` as comma-separated `role:token`
+//! pairs and sent as `Authorization: Bearer <token>`. The event streams, which
+//! browsers open without custom headers, also take the token as an
+//! `access_token` query parameter. Only blake3 hashes of the tokens are kept.
+//! Each route requires a [`Role`] (see [`access`]): reads are open to every
+//! key, writes to the requester or agent role acting on them, and the faucet,
+//! outbox, local execution, dispute resolution and account funding to
+//! operators. Admin keys may do everything. Keys only grant roles; which
+//! requester or agent a request acts for is still proven by its signature.
+//! Without any keys configured, requester, agent and read routes are open, as
+//! in development, but operator and admin routes are refused.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::extract::{MatchedPath, Query, Request, State};
+use axum::http::{header::AUTHORIZATION, HeaderMap, Method};
+use axum::middleware::Next;
+use axum::response::Response;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ApiError;
+
+/// What a key may do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Submits, amends and disputes tasks and manages webhooks.
+    Requester,
+    /// Registers agents, stakes, bids and reports results.
+    Agent,
+    /// Runs the node: faucet, outbox, local execution, dispute resolution,
+    /// account funding.
+    Operator,
+    /// Everything.
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Requester => "requester",
+            Role::Agent => "agent",
+            Role::Operator => "operator",
+            Role::Admin => "admin",
+        }
+    }
+
+    pub fn parse(role: &str) -> Option<Self> {
+        match role.trim().to_ascii_lowercase().as_str() {
+            "requester" => Some(Role::Requester),
+            "agent" => Some(Role::Agent),
+            "operator" => Some(Role::Operator),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+
+    /// Whether a key with this role may use a route requiring `required`.
+    pub fn allows(&self, required: Role) -> bool {
+        *self == required || *self == Role::Admin
+    }
+}
+
+/// Who may call a route.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// Anyone, with or without a key.
+    Public,
+    /// Any valid key.
+    Authenticated,
+    /// Keys with this role, or admin keys.
+    Role(Role),
+}
+
+/// The caller behind a valid key, added to the request extensions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Principal {
+    pub role: Role,
+}
+
+/// Routes that browsers open through `EventSource` or `WebSocket`, which
+/// cannot send an `Authorization` header.
+const QUERY_TOKEN_ROUTES: &[&str] = &["/v1/events", "/v1/events/ws"];
+
+/// The `access_token` query parameter accepted on [`QUERY_TOKEN_ROUTES`].
+#[derive(Debug, Deserialize)]
+struct TokenQuery {
+    access_token: Option<String>,
+}
+
+/// Configured API keys, by token hash.
+#[derive(Debug, Clone, Default)]
+pub struct ApiKeys {
+    keys: Arc<HashMap<[u8; 32], Role>>,
+}
+
+impl ApiKeys {
+    /// Parse comma-separated `role:token` pairs. Tokens must be at least 16
+    /// characters.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut keys = HashMap::new();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (role, token) = entry
+                .split_once(':')
+                .ok_or_else(|| "API key entries must look like role:token".to_string())?;
+            let role = Role::parse(role).ok_or_else(|| format!("unknown API key role {role}"))?;
+            let token = token.trim();
+            if token.len() < 16 {
+                return Err(format!(
+                    "{} API key must be at least 16 characters",
+                    role.as_str()
+                ));
+            }
+            keys.insert(*blake3::hash(token.as_bytes()).as_bytes(), role);
+        }
+        Ok(Self {
+            keys: Arc::new(keys),
+        })
+    }
+
+    /// Whether any key is configured; without keys only operator and admin
+    /// routes are closed.
+    pub fn is_enabled(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    /// The principal behind the bearer token in `headers`, or else behind
+    /// `query_token`, if any.
+    pub fn principal(
+        &self,
+        headers: &HeaderMap,
+        query_token: Option<&str>,
+    ) -> Result<Option<Principal>, ApiError> {
+        let token = match headers.get(AUTHORIZATION) {
+            Some(value) => value
+                .to_str()
+                .ok()
+                .and_then(|v| v.strip_prefix("Bearer "))
+                .map(str::trim)
+                .ok_or_else(|| {
+                    ApiError::Unauthorized("expected an Authorization: Bearer token".to_string())
+                })?,
+            None => match query_token {
+                Some(token) => token.trim(),
+                None => return Ok(None),
+            },
+        };
+        let role = self
+            .keys
+            .get(blake3::hash(token.as_bytes()).as_bytes())
+            .ok_or_else(|| ApiError::Unauthorized("invalid API key".to_string()))?;
+        Ok(Some(Principal { role: *role }))
+    }
+
+    /// Check the caller may use the route `path` with `method`.
+    pub fn authorize(
+        &self,
+        method: &Method,
+        path: &str,
+        headers: &HeaderMap,
+    ) -> Result<Option<Principal>, ApiError> {
+        self.authorize_with_query_token(method, path, headers, None)
+    }
+
+    /// [`authorize`](Self::authorize), also accepting the token from the
+    /// query string when `path` is one of the event streams.
+    pub fn authorize_with_query_token(
+        &self,
+        method: &Method,
+        path: &str,
+        headers: &HeaderMap,
+        query_token: Option<&str>,
+    ) -> Result<Option<Principal>, ApiError> {
+        let query_token = query_token.filter(|_| QUERY_TOKEN_ROUTES.contains(&path));
+        let access = access(method, path);
+        if !self.is_enabled() {
+            return match access {
+                Access::Role(role @ (Role::Operator | Role::Admin)) => {
+                    Err(ApiError::Forbidden(format!(
+                        "{method} {path} requires an {} key; configure API_KEYS",
+                        role.as_str()
+                    )))
+                }
+                _ => Ok(None),
+            };
+        }
+        if access == Access::Public {
+            return Ok(None);
+        }
+        let principal = self
+            .principal(headers, query_token)?
+            .ok_or_else(|| ApiError::Unauthorized("an API key is required".to_string()))?;
+        if let Access::Role(required) = access {
+            if !principal.role.allows(required) {
+                return Err(ApiError::Forbidden(format!(
+                    "{} keys may not {method} {path}; {} role required",
+                    principal.role.as_str(),
+                    required.as_str()
+                )));
+            }
+        }
+        Ok(Some(principal))
+    }
+}
+
+/// Access rule for the route template `path`, e.g. `/v1/tasks/:id`.
+/// Routes not listed here are admin-only.
+pub fn access(method: &Method, path: &str) -> Access {
+    match (method.as_str(), path) {
+        ("GET", "/health") => Access::Public,
+        ("GET", "/v1/outbox" | "/v1/outbox/:id") => Access::Role(Role::Operator),
+        ("GET", _) => Access::Authenticated,
+        (
+            "POST",
+            "/v1/tasks"
+            | "/v1/tasks/:id/cancel"
+            | "/v1/tasks/:id/disputes"
+            | "/v1/milestone-completions/:id/review"
+            | "/v1/webhooks"
+            | "/v1/requesters",
+        )
+        | ("PATCH", "/v1/tasks/:id") => Access::Role(Role::Requester),
+        (
+            "POST",
+            "/v1/agents"
+            | "/v1/agents/:id/stake"
+            | "/v1/agents/:id/unbond"
+            | "/v1/agents/:id/withdraw"
+            | "/v1/bids"
+            | "/v1/bids/:id/reveal"
+            | "/v1/results"
+            | "/v1/tasks/:id/milestones/:milestone_id/completions",
+        ) => Access::Role(Role::Agent),
+        (
+            "POST",
+            "/v1/faucet"
+            | "/v1/outbox"
+            | "/v1/tasks/:id/execute-local"
+            | "/v1/disputes/:id/resolve"
+            | "/v1/accounts/:id/fund",
+        ) => Access::Role(Role::Operator),
+        _ => Access::Role(Role::Admin),
+    }
+}
+
+/// Middleware enforcing [`access`] on every matched route.
+pub async fn require_api_key(
+    State(keys): State<ApiKeys>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let path = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let query_token = Query::<TokenQuery>::try_from_uri(request.uri())
+        .ok()
+        .and_then(|Query(query)| query.access_token);
+    if let Some(principal) = keys.authorize_with_query_token(
+        request.method(),
+        &path,
+        request.headers(),
+        query_token.as_deref(),
+    )? {
+        request.extensions_mut().insert(principal);
+    }
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    const REQUESTER: &str = "requester-token-0001";
+    const OPERATOR: &str = "operator-token-00001";
+    const ADMIN: &str = "admin-token-00000001";
+
+    fn keys() -> ApiKeys {
+        ApiKeys::parse(&format!(
+            "requester:{REQUESTER}, operator:{OPERATOR},admin:{ADMIN}"
+        ))
+        .unwrap()
+    }
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {token}")).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn keys_are_parsed_strictly() {
+        assert!(!ApiKeys::parse("").unwrap().is_enabled());
+        assert!(keys().is_enabled());
+        assert!(ApiKeys::parse("root:abcdefghijklmnopq").is_err());
+        assert!(ApiKeys::parse("operator:short").is_err());
+        assert!(ApiKeys::parse("operator").is_err());
+    }
+
+    #[test]
+    fn routes_require_the_right_role() {
+        let keys = keys();
+        let none = HeaderMap::new();
+        let post = Method::POST;
+
+        assert!(keys.authorize(&Method::GET, "/health", &none).is_ok());
+        assert!(matches!(
+            keys.authorize(&Method::GET, "/v1/tasks", &none),
+            Err(ApiError::Unauthorized(_))
+        ));
+        assert!(matches!(
+            keys.authorize(&Method::GET, "/v1/tasks", &bearer("wrong-token-000000")),
+            Err(ApiError::Unauthorized(_))
+        ));
+        assert_eq!(
+            keys.authorize(&Method::GET, "/v1/tasks", &bearer(REQUESTER))
+                .unwrap(),
+            Some(Principal {
+                role: Role::Requester
+            })
+        );
+        assert!(keys
+            .authorize(&post, "/v1/tasks", &bearer(REQUESTER))
+            .is_ok());
+        for path in ["/v1/faucet", "/v1/outbox", "/v1/bids"] {
+            assert!(matches!(
+                keys.authorize(&post, path, &bearer(REQUESTER)),
+                Err(ApiError::Forbidden(_))
+            ));
+        }
+        assert!(keys
+            .authorize(&post, "/v1/faucet", &bearer(OPERATOR))
+            .is_ok());
+        assert!(matches!(
+            keys.authorize(&post, "/v1/tasks", &bearer(OPERATOR)),
+            Err(ApiError::Forbidden(_))
+        ));
+        // Admin keys pass everywhere, including unlisted routes.
+        for (method, path) in [
+            (&post, "/v1/tasks"),
+            (&post, "/v1/bids"),
+            (&post, "/v1/outbox"),
+            (&Method::DELETE, "/v1/tasks/:id"),
+        ] {
+            assert!(keys.authorize(method, path, &bearer(ADMIN)).is_ok());
+        }
+        assert!(matches!(
+            keys.authorize(&Method::DELETE, "/v1/tasks/:id", &bearer(OPERATOR)),
+            Err(ApiError::Forbidden(_))
+        ));
+
+        // Event streams also take the token from the query string; other
+        // routes do not.
+        assert_eq!(
+            keys.authorize_with_query_token(&Method::GET, "/v1/events/ws", &none, Some(REQUESTER))
+                .unwrap(),
+            Some(Principal {
+                role: Role::Requester
+            })
+        );
+        assert!(matches!(
+            keys.authorize_with_query_token(
+                &Method::GET,
+                "/v1/events",
+                &none,
+                Some("wrong-token-000000")
+            ),
+            Err(ApiError::Unauthorized(_))
+        ));
+        assert!(matches!(
+            keys.authorize_with_query_token(&Method::GET, "/v1/tasks", &none, Some(REQUESTER)),
+            Err(ApiError::Unauthorized(_))
+        ));
+
+        // Without keys the API stays open, except for operator and admin
+        // routes.
+        let open = ApiKeys::default();
+        for (method, path) in [
+            (&Method::GET, "/v1/tasks"),
+            (&post, "/v1/tasks"),
+            (&post, "/v1/bids"),
+        ] {
+            assert_eq!(open.authorize(method, path, &none).unwrap(), None);
+        }
+        for (method, path) in [
+            (&post, "/v1/faucet"),
+            (&post, "/v1/disputes/:id/resolve"),
+            (&post, "/v1/tasks/:id/execute-local"),
+            (&post, "/v1/accounts/:id/fund"),
+            (&Method::DELETE, "/v1/tasks/:id"),
+        ] {
+            assert!(matches!(
+                open.authorize(method, path, &bearer(ADMIN)),
+                Err(ApiError::Forbidden(_))
+            ));
+        }
+    }
+}
@@ -5,7 +5,11 @@ use std::sync::Arc;
 use tracing::{debug, info, warn};
 
 use crate::error::ApiError;
-use crate::model::{StoredBid, StoredResult};
+use crate::events::EventBus;
+use crate::model::{
+    current_unix_timestamp, DisputeStatus, EventKind, StoredBid, StoredDispute, StoredResult,
+    TaskStatus,
+};
 use crate::storage::{ChainEventSink, Storage};
 use ainur_core::{AgentId, Bid, ExecutionProof, ResourceUsage, TaskId, TaskResult};
 use hex::ToHex;
@@ -31,12 +35,14 @@ static OUTBOX_RETRIED: AtomicU64 = AtomicU64::new(0);
 
 /// Lightweight chain replay worker that connects to the Temporal chain via Subxt,
 /// mirrors events into durable storage, and maintains a cursor for idempotent replay.
+/// Finalized `TaskMarket` events for mirrored tasks are published on `events`.
 pub async fn run_chain_replay(
     ws_url: String,
     metadata_path: Option<String>,
-    _storage: Arc<dyn Storage>,
+    storage: Arc<dyn Storage>,
     sink: Arc<dyn ChainEventSink>,
     #[cfg(feature = "postgres")] pg_pool: Option<Pool<Postgres>>,
+    events: EventBus,
 ) -> Result<(), ApiError> {
     if let Some(path) = metadata_path {
         info!("CHAIN_METADATA_PATH provided ({}); static metadata loading not yet wired, using live metadata from node", path);
@@ -219,7 +225,7 @@ pub async fn run_chain_replay(
                                 },
                                 "deadline": 0
                             },
-                            "status": "pending",
+                            "status": "open",
                             "created_at": created_at
                         });
                         let _ = sqlx::query(
@@ -241,7 +247,7 @@ pub async fn run_chain_replay(
                         .bind("")
                         .bind(ev.budget as i64)
                         .bind(0_i64)
-                        .bind("pending")
+                        .bind(TaskStatus::Open.as_str())
                         .bind(created_at)
                         .bind(created_at)
                         .bind(stored_json)
@@ -315,6 +321,10 @@ pub async fn run_chain_replay(
                                     guarantees: Vec::new(),
                                 },
                                 created_at: block_number.into(),
+                                commitment: None,
+                                nonce: None,
+                                // Sealed on chain; the value is unknown locally.
+                                revealed: false,
                             };
                             let stored_json =
                                 serde_json::to_value(&stored).unwrap_or_else(|_| json!({}));
@@ -385,6 +395,7 @@ pub async fn run_chain_replay(
                                     completed_at: block_number.into(),
                                 },
                                 created_at: block_number.into(),
+                                quality: None,
                             };
                             let stored_json =
                                 serde_json::to_value(&stored).unwrap_or_else(|_| json!({}));
@@ -392,7 +403,7 @@ pub async fn run_chain_replay(
                                 r#"
                                 INSERT INTO results (id, task_id, agent_id, output_base64, completed_at, proof, stored_json, chain_task_id, created_at)
                                 VALUES ($1, $2, $3, $4, to_timestamp($5), $6, $7, $8, now())
-                                ON CONFLICT (task_id) DO UPDATE SET
+                                ON CONFLICT (task_id, agent_id) DO UPDATE SET
                                     output_base64 = EXCLUDED.output_base64,
                                     completed_at = EXCLUDED.completed_at,
                                     stored_json = EXCLUDED.stored_json,
@@ -451,7 +462,7 @@ pub async fn run_chain_replay(
                             let _ = sqlx::query(
                                 r#"
                             UPDATE tasks
-                            SET status = 'pending', matched_agent = $2, updated_at = now()
+                            SET matched_agent = $2, updated_at = now()
                             WHERE chain_task_id = $1
                             "#,
                             )
@@ -467,6 +478,66 @@ pub async fn run_chain_replay(
                             )
                             .await;
                         }
+                    } else if pallet == "TaskMarket" && variant == "TaskAllocated" {
+                        let (task_id_opt, agent_id_opt) = extract_two_u64(&payload);
+                        if let (Some(task_id), Some(agent_id)) = (task_id_opt, agent_id_opt) {
+                            if let Err(err) = apply_chain_transition(
+                                &storage,
+                                pool,
+                                task_id as i64,
+                                TaskStatus::Allocated,
+                            )
+                            .await
+                            {
+                                warn!("chain replay: TaskAllocated for task {task_id}: {err}");
+                            }
+                            let _ = sqlx::query(
+                                r#"
+                            UPDATE tasks
+                            SET matched_agent = $2, updated_at = now()
+                            WHERE chain_task_id = $1
+                            "#,
+                            )
+                            .bind(task_id as i64)
+                            .bind(agent_id as i64)
+                            .execute(pool)
+                            .await;
+                        }
+                    } else if pallet == "TaskMarket" && variant == "TaskFailed" {
+                        if let Some(task_id) = extract_first_u64(&payload) {
+                            if let Err(err) = apply_chain_transition(
+                                &storage,
+                                pool,
+                                task_id as i64,
+                                TaskStatus::Failed,
+                            )
+                            .await
+                            {
+                                warn!("chain replay: TaskFailed for task {task_id}: {err}");
+                            }
+                        }
+                    } else if pallet == "Commitments" && variant == "CommitmentDisputed" {
+                        if let Some(commitment_id) = extract_first_u64(&payload) {
+                            if let Err(err) =
+                                mirror_chain_dispute(&storage, pool, commitment_id, &payload).await
+                            {
+                                warn!(
+                                    "chain replay: CommitmentDisputed for commitment {commitment_id}: {err}"
+                                );
+                            }
+                        }
+                    } else if pallet == "Commitments"
+                        && (variant == "CommitmentFinalized" || variant == "CommitmentCancelled")
+                    {
+                        if let Some(commitment_id) = extract_first_u64(&payload) {
+                            if let Err(err) =
+                                close_chain_dispute(&storage, pool, commitment_id, &variant).await
+                            {
+                                warn!(
+                                    "chain replay: {variant} for commitment {commitment_id}: {err}"
+                                );
+                            }
+                        }
                     }
                 }
                 sink.record_chain_event(
@@ -479,6 +550,28 @@ pub async fn run_chain_replay(
                 )
                 .await?;
 
+                #[cfg(feature = "postgres")]
+                let finalized_task = match &pg_pool {
+                    Some(pool) if pallet == "TaskMarket" => mirrored_task_id(pool, &payload).await,
+                    _ => None,
+                };
+                #[cfg(not(feature = "postgres"))]
+                let finalized_task: Option<String> = None;
+                if let Some(task_id) = finalized_task {
+                    events.publish(
+                        EventKind::ChainFinalized,
+                        &task_id,
+                        None,
+                        json!({
+                            "pallet": pallet,
+                            "variant": variant,
+                            "block": block_number,
+                            "event_index": idx,
+                            "tx_hash": correlation,
+                        }),
+                    );
+                }
+
                 max_cursor = (block_number.into(), idx);
             }
 
@@ -495,6 +588,172 @@ pub async fn run_chain_replay(
     }
 }
 
+/// Apply a lifecycle transition driven by a chain event to the task mirrored
+/// under `chain_task_id`. Replays of an already-applied event are no-ops;
+/// illegal transitions are surfaced to the caller and leave the task untouched.
+#[cfg(feature = "postgres")]
+async fn apply_chain_transition(
+    storage: &Arc<dyn Storage>,
+    pool: &Pool<Postgres>,
+    chain_task_id: i64,
+    next: TaskStatus,
+) -> Result<(), ApiError> {
+    let row = sqlx::query("SELECT id FROM tasks WHERE chain_task_id = $1 LIMIT 1")
+        .bind(chain_task_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to look up chain task: {e}")))?;
+    let Some(row) = row else {
+        debug!("chain task {chain_task_id} not mirrored locally; skipping {next} transition");
+        return Ok(());
+    };
+    let task_uuid: Uuid = row.get("id");
+
+    let mut task = storage.get_task(&task_uuid.to_string()).await?;
+    if task.status == next {
+        return Ok(());
+    }
+    task.transition_to(next)?;
+    storage.upsert_task(task).await
+}
+
+/// Orchestrator id of the task a `TaskMarket` event refers to; every such
+/// event carries the chain task id as its first field.
+#[cfg(feature = "postgres")]
+async fn mirrored_task_id(pool: &Pool<Postgres>, payload: &str) -> Option<String> {
+    let chain_task_id = extract_first_u64(payload)?;
+    let row = sqlx::query("SELECT id FROM tasks WHERE chain_task_id = $1 LIMIT 1")
+        .bind(chain_task_id as i64)
+        .fetch_optional(pool)
+        .await
+        .ok()??;
+    Some(row.get::<Uuid, _>("id").to_string())
+}
+
+/// Record a dispute raised on chain against `commitment_id`. Commitment
+/// events carry the commitment id followed by the chain task id; the dispute
+/// is linked to the task mirrored under that id, when there is one. Replays of
+/// an already-mirrored dispute are no-ops.
+#[cfg(feature = "postgres")]
+async fn mirror_chain_dispute(
+    storage: &Arc<dyn Storage>,
+    pool: &Pool<Postgres>,
+    commitment_id: u64,
+    payload: &str,
+) -> Result<(), ApiError> {
+    let existing = sqlx::query("SELECT id FROM disputes WHERE chain_commitment_id = $1")
+        .bind(commitment_id as i64)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to look up chain dispute: {e}")))?;
+    if existing.is_some() {
+        return Ok(());
+    }
+    let task = match extract_two_u64(payload).1 {
+        Some(chain_task_id) => {
+            let row = sqlx::query("SELECT id FROM tasks WHERE chain_task_id = $1 LIMIT 1")
+                .bind(chain_task_id as i64)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| ApiError::Internal(format!("failed to look up chain task: {e}")))?;
+            match row {
+                Some(row) => Some(
+                    storage
+                        .get_task(&row.get::<Uuid, _>("id").to_string())
+                        .await?,
+                ),
+                None => None,
+            }
+        }
+        None => None,
+    };
+    storage
+        .upsert_dispute(StoredDispute {
+            id: Uuid::new_v4().to_string(),
+            task_id: task.as_ref().map(|task| task.id.clone()),
+            requester_id: task
+                .as_ref()
+                .map(|task| task.requester_id.clone())
+                .unwrap_or_else(|| "chain".to_string()),
+            reason: format!("Commitments::CommitmentDisputed {payload}"),
+            evidence: Vec::new(),
+            status: DisputeStatus::Open,
+            resolution: None,
+            settlements: Vec::new(),
+            notes: None,
+            filed_at: current_unix_timestamp(),
+            resolved_at: None,
+            chain_commitment_id: Some(commitment_id),
+        })
+        .await
+}
+
+/// Close the mirrored dispute on `commitment_id` once the chain finalizes or
+/// cancels the commitment. The chain settled the funds, so the dispute records
+/// no resolution of its own.
+#[cfg(feature = "postgres")]
+async fn close_chain_dispute(
+    storage: &Arc<dyn Storage>,
+    pool: &Pool<Postgres>,
+    commitment_id: u64,
+    variant: &str,
+) -> Result<(), ApiError> {
+    let row = sqlx::query("SELECT id FROM disputes WHERE chain_commitment_id = $1")
+        .bind(commitment_id as i64)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to look up chain dispute: {e}")))?;
+    let Some(row) = row else {
+        return Ok(());
+    };
+    let mut dispute = storage
+        .get_dispute(&row.get::<Uuid, _>("id").to_string())
+        .await?;
+    if dispute.status != DisputeStatus::Open {
+        return Ok(());
+    }
+    dispute.status = DisputeStatus::Resolved;
+    dispute.notes = Some(format!("closed on chain by Commitments::{variant}"));
+    dispute.resolved_at = Some(current_unix_timestamp());
+    storage.upsert_dispute(dispute).await
+}
+
+/// Resolve the on-chain ids of a mirrored task and agent. Entities the chain
+/// has not assigned an id to yet resolve to 0.
+#[cfg(feature = "postgres")]
+pub async fn lookup_chain_ids(
+    pool: Option<&Pool<Postgres>>,
+    task_id: &str,
+    agent_id: &str,
+) -> (i64, i64) {
+    let Some(pool) = pool else {
+        return (0, 0);
+    };
+    let task_chain_id = match Uuid::parse_str(task_id) {
+        Ok(task_uuid) => {
+            sqlx::query_scalar::<_, Option<i64>>("SELECT chain_task_id FROM tasks WHERE id = $1")
+                .bind(task_uuid)
+                .fetch_optional(pool)
+                .await
+                .ok()
+                .flatten()
+                .flatten()
+                .unwrap_or(0)
+        }
+        Err(_) => 0,
+    };
+    let agent_chain_id =
+        sqlx::query_scalar::<_, Option<i64>>("SELECT chain_agent_id FROM agents WHERE id = $1")
+            .bind(agent_id)
+            .fetch_optional(pool)
+            .await
+            .ok()
+            .flatten()
+            .flatten()
+            .unwrap_or(0);
+    (task_chain_id, agent_chain_id)
+}
+
 /// Record an outbound extrinsic intent for correlation. This does not yet
 /// submit to the chain; it tracks the correlation_id for later status updates.
 pub async fn record_outbound_extrinsic(
@@ -1034,6 +1293,52 @@ async fn submit_extrinsic(
                 .map_err(|e| format!("finalize: {e}"))?;
             Ok(tx_hash)
         }
+        ("TaskMarket", "cancel_task") => {
+            let task_id = payload_val
+                .get("task_id")
+                .and_then(|v| v.as_u64())
+                .ok_or("missing task_id")?;
+            let tx = api::tx().task_market().cancel_task(task_id);
+            let progress = client
+                .tx()
+                .sign_and_submit_then_watch_default(&tx, signer)
+                .await
+                .map_err(|e| format!("submit: {e}"))?;
+            let tx_hash = format!("0x{}", hex::encode(progress.extrinsic_hash().as_ref()));
+            progress
+                .wait_for_finalized_success()
+                .await
+                .map_err(|e| format!("finalize: {e}"))?;
+            Ok(tx_hash)
+        }
+        ("TaskMarket", "update_task") => {
+            let task_id = payload_val
+                .get("task_id")
+                .and_then(|v| v.as_u64())
+                .ok_or("missing task_id")?;
+            let budget = payload_val
+                .get("budget")
+                .and_then(|v| v.as_u64())
+                .map(u128::from);
+            let deadline = payload_val
+                .get("deadline")
+                .and_then(|v| v.as_u64())
+                .map(|d| d as u32);
+            let tx = api::tx()
+                .task_market()
+                .update_task(task_id, budget, deadline);
+            let progress = client
+                .tx()
+                .sign_and_submit_then_watch_default(&tx, signer)
+                .await
+                .map_err(|e| format!("submit: {e}"))?;
+            let tx_hash = format!("0x{}", hex::encode(progress.extrinsic_hash().as_ref()));
+            progress
+                .wait_for_finalized_success()
+                .await
+                .map_err(|e| format!("finalize: {e}"))?;
+            Ok(tx_hash)
+        }
         ("TaskMarket", "submit_result") => {
             let task_id = payload_val
                 .get("task_id")
@@ -1182,6 +1487,22 @@ fn decode_payload(pallet: &str, call: &str, payload: Option<&str>) -> Result<(),
             let _ = get_u64(payload_val.get("task_id"), "task_id", u64::MAX);
             Ok(())
         }
+        ("TaskMarket", "cancel_task") => {
+            let _ = get_u64(payload_val.get("task_id"), "task_id", u64::MAX);
+            Ok(())
+        }
+        ("TaskMarket", "update_task") => {
+            let _ = get_u64(payload_val.get("task_id"), "task_id", u64::MAX);
+            let budget = payload_val.get("budget").filter(|v| !v.is_null());
+            let deadline = payload_val.get("deadline").filter(|v| !v.is_null());
+            if budget.is_none() && deadline.is_none() {
+                return Err("missing budget or deadline".into());
+            }
+            if deadline.is_some() {
+                get_u64(deadline, "deadline", u32::MAX as u64)?;
+            }
+            Ok(())
+        }
         ("TaskMarket", "submit_result") => {
             let _ = get_u64(payload_val.get("task_id"), "task_id", u64::MAX);
             let _ = get_u64(payload_val.get("agent_id"), "agent_id", u64::MAX);
@@ -1276,4 +1597,22 @@ mod tests {
         )
         .is_err());
     }
+
+    #[test]
+    fn validate_update_task_requires_a_change() {
+        let extend = serde_json::json!({ "task_id": 7, "budget": null, "deadline": 50 });
+        assert!(validate_outbox_payload(
+            "TaskMarket",
+            "update_task",
+            Some(extend.to_string().as_str())
+        )
+        .is_ok());
+        let empty = serde_json::json!({ "task_id": 7, "budget": null, "deadline": null });
+        assert!(validate_outbox_payload(
+            "TaskMarket",
+            "update_task",
+            Some(empty.to_string().as_str())
+        )
+        .is_err());
+    }
 }
@@ -1,3 +1,4 @@
+use ainur_core::PricingRule;
 use std::env;
 
 /// Minimal runtime configuration for the orchestrator.
@@ -23,6 +24,22 @@ pub struct AppConfig {
     pub metrics_bind: Option<String>,
     /// Optional backfill interval (ms) for correlation patching.
     pub backfill_interval_ms: u64,
+    /// Poll interval (ms) for the bid-window allocator.
+    pub allocator_poll_ms: u64,
+    /// Poll interval (ms) for the task deadline sweeper.
+    pub sweeper_poll_ms: u64,
+    /// Poll interval (ms) for due webhook deliveries.
+    pub webhook_poll_ms: u64,
+    /// Pricing rule applied when allocating tasks: "second_price" (default) or "first_price".
+    pub auction_pricing: PricingRule,
+    /// Reject unsigned requests, including from signers without a registered
+    /// public key. On unless `REQUIRE_SIGNATURES=false`.
+    pub require_signatures: bool,
+    /// Comma-separated `role:token` API keys; without any the API is open.
+    pub api_keys: Option<String>,
+    /// Comma-separated `arbiter_id:public_key` pairs allowed to resolve
+    /// disputes; without any, disputes cannot be resolved.
+    pub dispute_arbiters: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -57,6 +74,15 @@ impl AppConfig {
             _ => ExecutionEngineKind::Local,
         };
 
+        let auction_pricing = match env::var("AUCTION_PRICING")
+            .unwrap_or_else(|_| "second_price".into())
+            .to_lowercase()
+            .as_str()
+        {
+            "first_price" => PricingRule::FirstPrice,
+            _ => PricingRule::SecondPrice,
+        };
+
         Self {
             database_url: env::var("DATABASE_URL").ok(),
             db_max_connections,
@@ -71,6 +97,24 @@ impl AppConfig {
                 .ok()
                 .and_then(|v| v.parse::<u64>().ok())
                 .unwrap_or(10_000),
+            allocator_poll_ms: env::var("ALLOCATOR_POLL_MS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(1_000),
+            sweeper_poll_ms: env::var("SWEEPER_POLL_MS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(5_000),
+            webhook_poll_ms: env::var("WEBHOOK_POLL_MS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(1_000),
+            auction_pricing,
+            require_signatures: env::var("REQUIRE_SIGNATURES")
+                .map(|v| !matches!(v.to_lowercase().as_str(), "0" | "false" | "no"))
+                .unwrap_or(true),
+            api_keys: env::var("API_KEYS").ok(),
+            dispute_arbiters: env::var("DISPUTE_ARBITERS").ok(),
         }
     }
 }
@@ -0,0 +1,484 @@
+//! Dispute filing and resolution.
+//!
+//! The requester of a completed task may contest its accepted result within
+//! `constants::dispute::DISPUTE_WINDOW`, which moves the task to `disputed`.
+//! Resolving the dispute settles every agent whose result was accepted: an
+//! upheld result returns the task to `completed`; a refund or slash fails it,
+//! refunds the requester per the agent's `RefundPolicy` (in full when
+//! slashed), and records a failed assessment against the agent. Slashing also
+//! records a `Violation::FalseInformation`. Filing costs the requester the
+//! `DisputeFiling` fee. Refunds are booked against the agent's ledger account
+//! and slashes are taken like any other: from the bid deposit, then the
+//! stake and unbonding stake, then the account.
+//!
+//! Only the arbiters configured through `DISPUTE_ARBITERS` may resolve
+//! disputes, each signing its resolution with its configured key.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use ainur_core::{constants, DisputeResolution, RefundPolicy, TransactionType, Violation};
+use ed25519_dalek::VerifyingKey;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::error::ApiError;
+use crate::ledger;
+use crate::model::{
+    agent_key, AgentSettlement, DisputeFilingRequest, DisputeResolutionRequest, DisputeStatus,
+    StoredDispute, StoredViolation, TaskStatus,
+};
+use crate::reputation;
+use crate::signing;
+use crate::storage::Storage;
+use crate::verification;
+
+/// File a dispute over the accepted result of `task_id`.
+pub async fn file(
+    storage: &Arc<dyn Storage>,
+    task_id: &str,
+    filing: DisputeFilingRequest,
+    now: u64,
+) -> Result<StoredDispute, ApiError> {
+    let mut task = storage.get_task(task_id).await?;
+    if agent_key(&filing.requester_id) != task.task.requester {
+        return Err(ApiError::Forbidden(format!(
+            "only the requester of task {task_id} may dispute it"
+        )));
+    }
+    let dispute = StoredDispute::from_filing(filing, &task, now)?;
+
+    let results = storage.get_results_for_task(task_id).await?;
+    if let Some(accepted) = verification::accepted_result(&task, &results) {
+        let closes_at = accepted
+            .result
+            .completed_at
+            .saturating_add(constants::dispute::DISPUTE_WINDOW);
+        if now > closes_at {
+            return Err(ApiError::BadRequest(format!(
+                "the dispute window for task {task_id} closed at {closes_at}"
+            )));
+        }
+    }
+    task.transition_to(TaskStatus::Disputed)?;
+
+    // The fee is paid before anything is filed, and returned if filing fails.
+    let payer = task.requester_account();
+    ledger::charge_fee(storage, &task, &payer, TransactionType::DisputeFiling, now).await?;
+    let filed = async {
+        storage.upsert_dispute(dispute.clone()).await?;
+        storage.update_task(&mut task).await
+    }
+    .await;
+    if let Err(err) = filed {
+        ledger::return_fee(storage, &task, &payer, TransactionType::DisputeFiling, now).await?;
+        return Err(err);
+    }
+    Ok(dispute)
+}
+
+/// Arbiters allowed to resolve disputes, each with the ed25519 key that
+/// signs its resolutions.
+#[derive(Debug, Clone, Default)]
+pub struct Arbiters {
+    keys: Arc<HashMap<String, VerifyingKey>>,
+}
+
+impl Arbiters {
+    /// Parse comma-separated `arbiter_id:public_key` pairs, keys hex-encoded.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut keys = HashMap::new();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (id, key) = entry
+                .split_once(':')
+                .ok_or_else(|| "arbiter entries must look like id:public_key".to_string())?;
+            let bytes: [u8; 32] = hex::decode(key.trim().trim_start_matches("0x"))
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| format!("arbiter {id} needs a hex 32-byte public key"))?;
+            let key = VerifyingKey::from_bytes(&bytes)
+                .map_err(|_| format!("arbiter {id} has an invalid ed25519 key"))?;
+            keys.insert(id.trim().to_string(), key);
+        }
+        Ok(Self {
+            keys: Arc::new(keys),
+        })
+    }
+
+    /// Whether any arbiter is configured; without one no dispute can be
+    /// resolved locally.
+    pub fn is_enabled(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    /// Check that `request` is signed by the configured arbiter it names,
+    /// consuming its nonce.
+    async fn authorize(
+        &self,
+        storage: &Arc<dyn Storage>,
+        dispute_id: &str,
+        request: &DisputeResolutionRequest,
+    ) -> Result<(), ApiError> {
+        let Some(key) = self.keys.get(&request.arbiter_id) else {
+            return Err(ApiError::Forbidden(format!(
+                "{} is not a dispute arbiter",
+                request.arbiter_id
+            )));
+        };
+        let Some(signature) = &request.signature else {
+            return Err(ApiError::Unauthorized(
+                "dispute resolutions must be signed by the arbiter".into(),
+            ));
+        };
+        let message = request.signed_message(dispute_id);
+        signing::verify(&request.arbiter_id, key, &message, signature)?;
+        signing::consume_nonce(storage, &request.arbiter_id, Some(signature)).await
+    }
+}
+
+/// Settle dispute `dispute_id` as decided by one of `arbiters`.
+pub async fn resolve(
+    storage: &Arc<dyn Storage>,
+    arbiters: &Arbiters,
+    dispute_id: &str,
+    request: DisputeResolutionRequest,
+    now: u64,
+) -> Result<StoredDispute, ApiError> {
+    let mut dispute = storage.get_dispute(dispute_id).await?;
+    if dispute.status != DisputeStatus::Open {
+        return Err(ApiError::BadRequest(format!(
+            "dispute {dispute_id} is already resolved"
+        )));
+    }
+    let (Some(task_id), None) = (dispute.task_id.clone(), dispute.chain_commitment_id) else {
+        return Err(ApiError::BadRequest(format!(
+            "dispute {dispute_id} was raised on chain and is resolved there"
+        )));
+    };
+    arbiters.authorize(storage, dispute_id, &request).await?;
+    let mut task = storage.get_task(&task_id).await?;
+    let resolution = request.resolution;
+    task.transition_to(match resolution {
+        DisputeResolution::Uphold => TaskStatus::Completed,
+        DisputeResolution::Refund | DisputeResolution::Slash => TaskStatus::Failed,
+    })?;
+
+    let results = storage.get_results_for_task(&task_id).await?;
+    let report = verification::agreement(&task, &results);
+    let allocations = storage.get_allocations_for_task(&task_id).await?;
+    let mut settlements = Vec::new();
+    for result in results.iter().filter(|r| verification::agrees(&report, r)) {
+        let (payment, policy, elapsed) =
+            match allocations.iter().find(|a| a.agent_id == result.agent_id) {
+                Some(allocation) => {
+                    let policy = match storage.get_bid(&allocation.bid_id).await {
+                        Ok(bid) => RefundPolicy::from_guarantees(&bid.bid.guarantees),
+                        Err(_) => RefundPolicy::Full,
+                    };
+                    let elapsed = result
+                        .result
+                        .completed_at
+                        .saturating_sub(allocation.allocated_at);
+                    (allocation.payment, policy, elapsed)
+                }
+                // Locally executed tasks were never paid for.
+                None => (0, RefundPolicy::Full, 0),
+            };
+        let settlement = resolution.settle(&policy, payment, elapsed);
+        settlements.push(AgentSettlement {
+            agent_id: result.agent_id.clone(),
+            refund: settlement.refund,
+            slashed: settlement.slashed,
+        });
+    }
+
+    // Money moves first; a resolution that fails after it is retried
+    // without booking anything twice.
+    ledger::settle_dispute(storage, &task, &settlements, now).await?;
+
+    if resolution != DisputeResolution::Uphold {
+        for settlement in &settlements {
+            if resolution == DisputeResolution::Slash {
+                storage
+                    .insert_violation(StoredViolation {
+                        id: Uuid::new_v4().to_string(),
+                        agent_id: settlement.agent_id.clone(),
+                        task_id: task_id.clone(),
+                        violation: Violation::FalseInformation(format!(
+                            "dispute {}: {}",
+                            dispute.id, dispute.reason
+                        )),
+                        recorded_at: now,
+                    })
+                    .await?;
+            }
+            if let Err(err) =
+                reputation::record_failure(storage, &task, &settlement.agent_id, now).await
+            {
+                warn!(
+                    "failed to update reputation for {}: {err}",
+                    settlement.agent_id
+                );
+            }
+        }
+    }
+
+    dispute.status = DisputeStatus::Resolved;
+    dispute.resolution = Some(resolution);
+    dispute.settlements = settlements;
+    dispute.notes = request.notes;
+    dispute.resolved_at = Some(now);
+    storage.upsert_dispute(dispute.clone()).await?;
+    storage.update_task(&mut task).await?;
+    Ok(dispute)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{
+        current_unix_timestamp, EvidenceSubmission, LedgerTransactionKind, RequestSignature,
+        ResultSubmissionRequest, StoredAllocation, StoredResult, StoredTask, TaskSubmissionRequest,
+    };
+    use crate::storage::InMemoryStorage;
+    use base64::{engine::general_purpose, Engine as _};
+    use ed25519_dalek::SigningKey;
+
+    async fn completed_task(storage: &Arc<dyn Storage>) -> StoredTask {
+        let mut task = StoredTask::from_submission(TaskSubmissionRequest {
+            client_task_id: None,
+            requester_id: "requester".into(),
+            description: "dispute me".into(),
+            task_type: "echo".into(),
+            input_base64: String::new(),
+            max_budget: 100,
+            deadline: 0,
+            bid_window_secs: None,
+            domain: None,
+            requirements: Default::default(),
+            output_format: None,
+            verification_level: None,
+            payment_schedule: None,
+            milestones: Vec::new(),
+            escrow_required: None,
+            signature: None,
+        })
+        .unwrap();
+        for status in [
+            TaskStatus::Allocated,
+            TaskStatus::Executing,
+            TaskStatus::Completed,
+        ] {
+            task.transition_to(status).unwrap();
+        }
+        storage.insert_task(task.clone()).await.unwrap();
+        storage
+            .insert_allocation(StoredAllocation {
+                id: Uuid::new_v4().to_string(),
+                task_id: task.id.clone(),
+                agent_id: "agent".into(),
+                bid_id: Uuid::new_v4().to_string(),
+                payment: 80,
+                social_welfare: 0,
+                allocated_at: 0,
+            })
+            .await
+            .unwrap();
+        let result = StoredResult::from_submission(
+            ResultSubmissionRequest {
+                task_id: task.id.clone(),
+                agent_id: "agent".into(),
+                output_base64: general_purpose::STANDARD.encode("wrong"),
+                signature: None,
+            },
+            &task,
+        )
+        .unwrap();
+        storage.insert_result(result).await.unwrap();
+        task
+    }
+
+    const ARBITER: [u8; 32] = [4u8; 32];
+
+    fn arbiters() -> Arbiters {
+        let key = SigningKey::from_bytes(&ARBITER).verifying_key();
+        Arbiters::parse(&format!("arbiter:{}", hex::encode(key.to_bytes()))).unwrap()
+    }
+
+    fn resolution(
+        dispute_id: &str,
+        resolution: DisputeResolution,
+        secret: &[u8; 32],
+    ) -> DisputeResolutionRequest {
+        let mut request = DisputeResolutionRequest {
+            arbiter_id: "arbiter".into(),
+            resolution,
+            notes: Some("confirmed".into()),
+            signature: None,
+        };
+        let mut nonce = [0u8; 16];
+        nonce.copy_from_slice(&Uuid::new_v4().as_bytes()[..]);
+        request.signature = Some(RequestSignature::sign(
+            request.signed_message(dispute_id),
+            nonce,
+            current_unix_timestamp() + 60,
+            &SigningKey::from_bytes(secret),
+        ));
+        request
+    }
+
+    fn filing(requester_id: &str) -> DisputeFilingRequest {
+        DisputeFilingRequest {
+            requester_id: requester_id.into(),
+            reason: "output is wrong".into(),
+            evidence: vec![EvidenceSubmission {
+                description: "expected output".into(),
+                content_base64: general_purpose::STANDARD.encode("right"),
+            }],
+            signature: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn only_the_requester_may_dispute_within_the_window() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let task = completed_task(&storage).await;
+        let now = current_unix_timestamp();
+
+        assert!(matches!(
+            file(&storage, &task.id, filing("someone-else"), now).await,
+            Err(ApiError::Forbidden(_))
+        ));
+        let late = now + constants::dispute::DISPUTE_WINDOW + 60;
+        assert!(file(&storage, &task.id, filing("requester"), late)
+            .await
+            .is_err());
+
+        let dispute = file(&storage, &task.id, filing("requester"), now)
+            .await
+            .unwrap();
+        assert_eq!(dispute.evidence.len(), 1);
+        let stored = storage.get_task(&task.id).await.unwrap();
+        assert_eq!(stored.status, TaskStatus::Disputed);
+        // A disputed task cannot be disputed again.
+        assert!(file(&storage, &task.id, filing("requester"), now)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn slashing_refunds_in_full_and_records_a_violation() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let task = completed_task(&storage).await;
+        let now = current_unix_timestamp();
+        let dispute = file(&storage, &task.id, filing("requester"), now)
+            .await
+            .unwrap();
+        ledger::fund(&storage, "user:agent", 100, now)
+            .await
+            .unwrap();
+        crate::staking::bond(&storage, "agent", 100, now)
+            .await
+            .unwrap();
+
+        let resolved = resolve(
+            &storage,
+            &arbiters(),
+            &dispute.id,
+            resolution(&dispute.id, DisputeResolution::Slash, &ARBITER),
+            now,
+        )
+        .await
+        .unwrap();
+        assert_eq!(resolved.status, DisputeStatus::Resolved);
+        assert_eq!(resolved.settlements.len(), 1);
+        assert_eq!(resolved.settlements[0].refund, 80);
+        assert_eq!(resolved.settlements[0].slashed, 8);
+        // The slash comes out of the agent's stake, like any other slash.
+        let balance = |account: &'static str| {
+            let storage = storage.clone();
+            async move { ledger::account(&storage, account).await.unwrap().balance }
+        };
+        assert_eq!(balance("stake:agent").await, 92);
+        assert_eq!(balance("user:agent").await, -80);
+        assert_eq!(balance(ledger::TREASURY_ACCOUNT).await, 8);
+        assert_eq!(
+            storage.get_task(&task.id).await.unwrap().status,
+            TaskStatus::Failed
+        );
+        assert_eq!(
+            storage
+                .get_violations_for_agent("agent")
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Resolutions are final.
+        assert!(resolve(
+            &storage,
+            &arbiters(),
+            &dispute.id,
+            resolution(&dispute.id, DisputeResolution::Uphold, &ARBITER),
+            now,
+        )
+        .await
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn only_configured_arbiters_resolve_disputes() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let task = completed_task(&storage).await;
+        let now = current_unix_timestamp();
+        let dispute = file(&storage, &task.id, filing("requester"), now)
+            .await
+            .unwrap();
+
+        // Without arbiters, unsigned, or signed by another key: refused.
+        let signed = resolution(&dispute.id, DisputeResolution::Refund, &ARBITER);
+        assert!(matches!(
+            resolve(&storage, &Arbiters::default(), &dispute.id, signed, now).await,
+            Err(ApiError::Forbidden(_))
+        ));
+        let mut unsigned = resolution(&dispute.id, DisputeResolution::Refund, &ARBITER);
+        unsigned.signature = None;
+        assert!(matches!(
+            resolve(&storage, &arbiters(), &dispute.id, unsigned, now).await,
+            Err(ApiError::Unauthorized(_))
+        ));
+        let forged = resolution(&dispute.id, DisputeResolution::Refund, &[9u8; 32]);
+        assert!(matches!(
+            resolve(&storage, &arbiters(), &dispute.id, forged, now).await,
+            Err(ApiError::Unauthorized(_))
+        ));
+        let mut impostor = resolution(&dispute.id, DisputeResolution::Refund, &ARBITER);
+        impostor.arbiter_id = "someone-else".into();
+        assert!(matches!(
+            resolve(&storage, &arbiters(), &dispute.id, impostor, now).await,
+            Err(ApiError::Forbidden(_))
+        ));
+        assert_eq!(
+            storage.get_dispute(&dispute.id).await.unwrap().status,
+            DisputeStatus::Open
+        );
+        assert!(storage
+            .get_ledger_transactions_for_account("user:agent")
+            .await
+            .unwrap()
+            .iter()
+            .all(|tx| tx.kind != LedgerTransactionKind::Refund));
+
+        let resolved = resolve(
+            &storage,
+            &arbiters(),
+            &dispute.id,
+            resolution(&dispute.id, DisputeResolution::Refund, &ARBITER),
+            now,
+        )
+        .await
+        .unwrap();
+        assert_eq!(resolved.status, DisputeStatus::Resolved);
+    }
+}
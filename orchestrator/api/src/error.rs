@@ -18,10 +18,23 @@ pub enum ApiError {
     #[error("bad request: {0}")]
     BadRequest(String),
 
+    /// The request could not be authenticated (missing or bad signature).
+    #[error("unauthorized: {0}")]
+    Unauthorized(String),
+
+    /// The caller is authenticated but its role may not use the endpoint.
+    #[error("forbidden: {0}")]
+    Forbidden(String),
+
     /// A requested resource does not exist.
     #[error("not found: {0}")]
     NotFound(String),
 
+    /// The request clashes with an earlier one, e.g. an idempotency key
+    /// reused with a different body.
+    #[error("conflict: {0}")]
+    Conflict(String),
+
     /// An unexpected error occurred inside the orchestrator.
     #[error("internal server error: {0}")]
     Internal(String),
@@ -39,7 +52,10 @@ impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
         let (status, error, message) = match self {
             ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, "bad_request", msg),
+            ApiError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, "unauthorized", msg),
+            ApiError::Forbidden(msg) => (StatusCode::FORBIDDEN, "forbidden", msg),
             ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, "not_found", msg),
+            ApiError::Conflict(msg) => (StatusCode::CONFLICT, "conflict", msg),
             ApiError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error", msg),
         };
 
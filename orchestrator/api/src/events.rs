@@ -0,0 +1,200 @@
+//! Task lifecycle event bus.
+//!
+//! HTTP handlers, the allocator and the chain replay worker publish
+//! [`TaskEvent`]s here; `GET /v1/events` (SSE) and `GET /v1/events/ws`
+//! (WebSocket) stream them to clients. The bus keeps the last
+//! [`EVENT_HISTORY`] events so a client reconnecting with the id of the last
+//! event it saw gets whatever it missed first. Events are not persisted and
+//! ids restart from 1 when the orchestrator restarts.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::model::{current_unix_timestamp, EventKind, EventQuery, TaskEvent};
+
+/// Number of past events kept for clients resuming a stream.
+pub const EVENT_HISTORY: usize = 1024;
+
+/// Fan-out of task events to every open stream. Cloning shares the bus.
+#[derive(Clone)]
+pub struct EventBus {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    history: Mutex<History>,
+    sender: broadcast::Sender<TaskEvent>,
+}
+
+struct History {
+    last_id: u64,
+    events: VecDeque<TaskEvent>,
+    capacity: usize,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(EVENT_HISTORY)
+    }
+}
+
+impl EventBus {
+    /// Bus remembering the last `capacity` events.
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self {
+            inner: Arc::new(Inner {
+                history: Mutex::new(History {
+                    last_id: 0,
+                    events: VecDeque::with_capacity(capacity),
+                    capacity,
+                }),
+                sender,
+            }),
+        }
+    }
+
+    /// Publish an event about `task_id`, returning it with its id assigned.
+    pub fn publish(
+        &self,
+        kind: EventKind,
+        task_id: &str,
+        agent_id: Option<&str>,
+        data: serde_json::Value,
+    ) -> TaskEvent {
+        let mut history = self.inner.history.lock().expect("event history poisoned");
+        history.last_id += 1;
+        let event = TaskEvent {
+            id: history.last_id,
+            kind,
+            task_id: task_id.to_string(),
+            agent_id: agent_id.map(str::to_string),
+            data,
+            created_at: current_unix_timestamp(),
+        };
+        if history.events.len() == history.capacity {
+            history.events.pop_front();
+        }
+        if history.capacity > 0 {
+            history.events.push_back(event.clone());
+        }
+        // Sending under the history lock keeps streams and history in order.
+        // Nobody listening is not an error.
+        let _ = self.inner.sender.send(event.clone());
+        event
+    }
+
+    /// Stream of the events matching `query`, starting after
+    /// `query.last_event_id` when set and with new events otherwise.
+    pub fn subscribe(&self, query: EventQuery) -> Subscription {
+        let history = self.inner.history.lock().expect("event history poisoned");
+        let receiver = self.inner.sender.subscribe();
+        let last_id = query.last_event_id.unwrap_or(history.last_id);
+        drop(history);
+        Subscription {
+            bus: self.clone(),
+            receiver,
+            backlog: self.since(last_id),
+            last_id,
+            query,
+        }
+    }
+
+    fn since(&self, last_id: u64) -> VecDeque<TaskEvent> {
+        let history = self.inner.history.lock().expect("event history poisoned");
+        history
+            .events
+            .iter()
+            .filter(|e| e.id > last_id)
+            .cloned()
+            .collect()
+    }
+}
+
+/// One client's view of the bus.
+pub struct Subscription {
+    bus: EventBus,
+    receiver: broadcast::Receiver<TaskEvent>,
+    backlog: VecDeque<TaskEvent>,
+    last_id: u64,
+    query: EventQuery,
+}
+
+impl Subscription {
+    /// Next matching event, or `None` once the bus is gone.
+    pub async fn next(&mut self) -> Option<TaskEvent> {
+        loop {
+            let event = match self.backlog.pop_front() {
+                Some(event) => event,
+                None => match self.receiver.recv().await {
+                    Ok(event) => event,
+                    // A slow client catches up from history; events that
+                    // already fell out of it are skipped.
+                    Err(RecvError::Lagged(_)) => {
+                        self.backlog = self.bus.since(self.last_id);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return None,
+                },
+            };
+            // Events replayed from history may also arrive on the channel.
+            if event.id <= self.last_id {
+                continue;
+            }
+            self.last_id = event.id;
+            if self.query.matches(&event) {
+                return Some(event);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn subscriptions_filter_and_resume_from_the_last_event_id() {
+        let bus = EventBus::new(8);
+        let mut agent = bus.subscribe(EventQuery {
+            agent_id: Some("agent".into()),
+            ..Default::default()
+        });
+        bus.publish(EventKind::TaskCreated, "t1", None, json!({}));
+        bus.publish(EventKind::BidReceived, "t1", Some("other"), json!({}));
+        let bid = bus.publish(EventKind::BidReceived, "t1", Some("agent"), json!({}));
+        bus.publish(EventKind::TaskCreated, "t2", None, json!({}));
+        assert_eq!(agent.next().await, Some(bid));
+
+        // Resuming replays what came after the given id, filtered.
+        let mut resumed = bus.subscribe(EventQuery {
+            task_id: Some("t1".into()),
+            last_event_id: Some(1),
+            ..Default::default()
+        });
+        assert_eq!(resumed.next().await.map(|e| e.id), Some(2));
+        assert_eq!(resumed.next().await.map(|e| e.id), Some(3));
+        let allocated = bus.publish(EventKind::TaskAllocated, "t1", Some("agent"), json!({}));
+        assert_eq!(resumed.next().await, Some(allocated.clone()));
+        assert_eq!(agent.next().await, Some(allocated));
+    }
+
+    #[tokio::test]
+    async fn lagging_subscribers_catch_up_from_history() {
+        let bus = EventBus::new(4);
+        let mut all = bus.subscribe(EventQuery::default());
+        for _ in 0..6 {
+            bus.publish(EventKind::TaskCreated, "t", None, json!({}));
+        }
+        // The first two events fell out of the history.
+        let ids: Vec<u64> = [all.next().await, all.next().await]
+            .into_iter()
+            .flatten()
+            .map(|e| e.id)
+            .collect();
+        assert_eq!(ids, vec![3, 4]);
+    }
+}
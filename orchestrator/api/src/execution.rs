@@ -74,6 +74,7 @@ pub fn execute_and_build_result(
         task_id: task.id.clone(),
         agent_id,
         output_base64,
+        signature: None,
     };
 
     StoredResult::from_submission(submission, task)
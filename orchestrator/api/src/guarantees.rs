@@ -0,0 +1,321 @@
+//! Bid guarantees.
+//!
+//! Agents may back a bid with `Guarantee`s: a completion time, a minimum
+//! quality score, a free-form SLA and a `RefundPolicy`. Once an agent's
+//! result is accepted, the guarantees of its winning bid are checked with
+//! `Guarantee::breach` against the time since allocation and the verifiers'
+//! confidence in the result. Any breach records a `Violation::SLAViolation`,
+//! slashes the agent (see `ledger::slash`) and refunds the requester per the
+//! agent's refund policy, in full when it offered none. Guarantees are
+//! enforced before the task settles, while the bid deposit and escrow still
+//! back them.
+
+use std::sync::Arc;
+
+use ainur_core::{constants, RefundPolicy, Violation};
+use uuid::Uuid;
+
+use crate::error::ApiError;
+use crate::ledger;
+use crate::model::{StoredResult, StoredTask, StoredViolation};
+use crate::storage::Storage;
+
+/// Check the accepted `result` against the guarantees its agent bid with,
+/// returning the violation recorded for any breach.
+pub async fn enforce(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    result: &StoredResult,
+    now: u64,
+) -> Result<Option<StoredViolation>, ApiError> {
+    // Locally executed tasks have no allocation and no bid to hold to.
+    let Some(allocation) = storage
+        .get_allocations_for_task(&task.id)
+        .await?
+        .into_iter()
+        .find(|a| a.agent_id == result.agent_id)
+    else {
+        return Ok(None);
+    };
+    let bid = storage.get_bid(&allocation.bid_id).await?;
+    let elapsed = result
+        .result
+        .completed_at
+        .saturating_sub(allocation.allocated_at);
+    // Results no verifier scored are not held to quality guarantees.
+    let quality = result.quality.unwrap_or(constants::reputation::MAX_SCORE);
+    let breaches: Vec<String> = bid
+        .bid
+        .guarantees
+        .iter()
+        .filter_map(|g| g.breach(elapsed, quality))
+        .collect();
+    if breaches.is_empty() {
+        return Ok(None);
+    }
+
+    let violation = Violation::SLAViolation(format!("bid {}: {}", bid.id, breaches.join("; ")));
+    let stored = StoredViolation {
+        id: Uuid::new_v4().to_string(),
+        agent_id: result.agent_id.clone(),
+        task_id: task.id.clone(),
+        violation: violation.clone(),
+        recorded_at: now,
+    };
+    storage.insert_violation(stored.clone()).await?;
+    ledger::slash(storage, task, &allocation, &violation, now).await?;
+    let refund =
+        RefundPolicy::from_guarantees(&bid.bid.guarantees).refund(allocation.payment, elapsed);
+    ledger::refund_breach(storage, task, &allocation, refund, now).await?;
+    Ok(Some(stored))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{
+        current_unix_timestamp, BidSubmissionRequest, ResultSubmissionRequest, StoredAllocation,
+        StoredBid, TaskStatus, TaskSubmissionRequest,
+    };
+    use crate::staking;
+    use crate::storage::InMemoryStorage;
+    use ainur_core::{Guarantee, TransactionType};
+
+    async fn allocated_task(
+        storage: &Arc<dyn Storage>,
+        guarantees: Vec<Guarantee>,
+        allocated_at: u64,
+    ) -> StoredTask {
+        let mut task = StoredTask::from_submission(TaskSubmissionRequest {
+            client_task_id: None,
+            requester_id: "requester".into(),
+            description: "guarantee me".into(),
+            task_type: "echo".into(),
+            input_base64: String::new(),
+            max_budget: 1_000_000,
+            deadline: 0,
+            bid_window_secs: None,
+            domain: None,
+            requirements: Default::default(),
+            output_format: None,
+            verification_level: None,
+            payment_schedule: None,
+            milestones: Vec::new(),
+            escrow_required: Some(false),
+            signature: None,
+        })
+        .unwrap();
+        task.transition_to(TaskStatus::Allocated).unwrap();
+        storage.insert_task(task.clone()).await.unwrap();
+        let bid = StoredBid::from_submission(
+            BidSubmissionRequest {
+                task_id: task.id.clone(),
+                agent_id: "agent".into(),
+                value: Some(500_000),
+                commitment: None,
+                quality_score: 90,
+                completion_time: 60,
+                guarantees,
+                signature: None,
+            },
+            &task,
+        )
+        .unwrap();
+        storage.insert_bid(bid.clone()).await.unwrap();
+        storage
+            .insert_allocation(StoredAllocation {
+                id: Uuid::new_v4().to_string(),
+                task_id: task.id.clone(),
+                agent_id: "agent".into(),
+                bid_id: bid.id,
+                payment: 500_000,
+                social_welfare: 0,
+                allocated_at,
+            })
+            .await
+            .unwrap();
+        task
+    }
+
+    fn result(task: &StoredTask, quality: u32) -> StoredResult {
+        let mut result = StoredResult::from_submission(
+            ResultSubmissionRequest {
+                task_id: task.id.clone(),
+                agent_id: "agent".into(),
+                output_base64: String::new(),
+                signature: None,
+            },
+            task,
+        )
+        .unwrap();
+        result.quality = Some(quality);
+        result
+    }
+
+    async fn balance_of(storage: &Arc<dyn Storage>, id: &str) -> i128 {
+        ledger::account(storage, id).await.unwrap().balance
+    }
+
+    #[tokio::test]
+    async fn kept_guarantees_cost_nothing() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let now = current_unix_timestamp();
+        let task = allocated_task(
+            &storage,
+            vec![Guarantee::CompletionTime(60), Guarantee::QualityScore(80)],
+            now,
+        )
+        .await;
+
+        let violation = enforce(&storage, &task, &result(&task, 90), now)
+            .await
+            .unwrap();
+        assert!(violation.is_none());
+        assert!(ledger::account(&storage, "user:agent")
+            .await
+            .unwrap()
+            .entries
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn breaches_slash_and_refund_per_policy() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let now = current_unix_timestamp();
+        let task = allocated_task(
+            &storage,
+            vec![
+                Guarantee::CompletionTime(60),
+                Guarantee::QualityScore(80),
+                Guarantee::RefundPolicy(RefundPolicy::Partial(20)),
+            ],
+            now - 120,
+        )
+        .await;
+
+        let violation = enforce(&storage, &task, &result(&task, 70), now)
+            .await
+            .unwrap()
+            .unwrap();
+        let Violation::SLAViolation(reason) = &violation.violation else {
+            panic!("expected an SLA violation");
+        };
+        assert!(reason.contains("completed in 120s") && reason.contains("quality 70"));
+        assert_eq!(
+            storage
+                .get_violations_for_agent("agent")
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        // 10% of the payment is slashed and 20% refunded.
+        assert_eq!(balance_of(&storage, "user:agent").await, -150_000);
+        assert_eq!(balance_of(&storage, ledger::TREASURY_ACCOUNT).await, 50_000);
+        assert_eq!(balance_of(&storage, "user:requester").await, 100_000);
+
+        // Enforcing again changes nothing.
+        enforce(&storage, &task, &result(&task, 70), now)
+            .await
+            .unwrap();
+        assert_eq!(balance_of(&storage, "user:agent").await, -150_000);
+    }
+
+    #[tokio::test]
+    async fn breaches_are_taken_from_the_deposit_and_escrow_before_settling() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let now = current_unix_timestamp();
+        let mut task = StoredTask::from_submission(TaskSubmissionRequest {
+            client_task_id: None,
+            requester_id: "requester".into(),
+            description: "guarantee me".into(),
+            task_type: "echo".into(),
+            input_base64: String::new(),
+            max_budget: 1_000_000,
+            deadline: 0,
+            bid_window_secs: None,
+            domain: None,
+            requirements: Default::default(),
+            output_format: None,
+            verification_level: None,
+            payment_schedule: None,
+            milestones: Vec::new(),
+            escrow_required: Some(true),
+            signature: None,
+        })
+        .unwrap();
+        task.transition_to(TaskStatus::Allocated).unwrap();
+        storage.insert_task(task.clone()).await.unwrap();
+        ledger::fund(&storage, "user:requester", 1_000_000, now)
+            .await
+            .unwrap();
+        ledger::lock_escrow(&storage, &task, now).await.unwrap();
+        ledger::fund(&storage, "user:agent", 200_000, now)
+            .await
+            .unwrap();
+        staking::bond(&storage, "agent", 100_000, now)
+            .await
+            .unwrap();
+
+        let bid = StoredBid::from_submission(
+            BidSubmissionRequest {
+                task_id: task.id.clone(),
+                agent_id: "agent".into(),
+                value: Some(50_000),
+                commitment: None,
+                quality_score: 90,
+                completion_time: 60,
+                guarantees: vec![
+                    Guarantee::CompletionTime(60),
+                    Guarantee::RefundPolicy(RefundPolicy::Partial(20)),
+                ],
+                signature: None,
+            },
+            &task,
+        )
+        .unwrap();
+        storage.insert_bid(bid.clone()).await.unwrap();
+        ledger::lock_bid_deposit(&storage, &task, &bid, now)
+            .await
+            .unwrap();
+        let allocation = StoredAllocation {
+            id: Uuid::new_v4().to_string(),
+            task_id: task.id.clone(),
+            agent_id: "agent".into(),
+            bid_id: bid.id.clone(),
+            payment: 50_000,
+            social_welfare: 0,
+            allocated_at: now - 120,
+        };
+        storage.insert_allocation(allocation).await.unwrap();
+
+        // The order `report_result` settles an accepted result in.
+        let result = result(&task, 90);
+        enforce(&storage, &task, &result, now)
+            .await
+            .unwrap()
+            .unwrap();
+        let deposit = ledger::deposit_account(&bid.id);
+        // 10% of the payment is slashed out of the 10_000 deposit.
+        assert_eq!(balance_of(&storage, &deposit).await, 5_000);
+        ledger::settle(&storage, &task, &[&result], now)
+            .await
+            .unwrap();
+
+        // The rest of the deposit went back to the stake, and the 20% refund
+        // was withheld from the payment in escrow.
+        assert_eq!(balance_of(&storage, &deposit).await, 0);
+        assert_eq!(balance_of(&storage, "stake:agent").await, 95_000);
+        assert_eq!(
+            balance_of(&storage, &ledger::escrow_account(&task.id)).await,
+            0
+        );
+        assert_eq!(balance_of(&storage, "user:requester").await, 960_000);
+        let bid_fee = ledger::fee(TransactionType::BidPlacement, 1_000_000).unwrap();
+        let payment_fee = ledger::fee(TransactionType::ResultSubmission, 40_000).unwrap();
+        assert_eq!(
+            balance_of(&storage, "user:agent").await,
+            100_000 - bid_fee as i128 + 40_000 - payment_fee as i128
+        );
+    }
+}
@@ -0,0 +1,241 @@
+//! Idempotent submissions.
+//!
+//! Task, bid and result submissions may carry an `Idempotency-Key` header;
+//! tasks with a `client_task_id` use it as their key instead. Keys are scoped
+//! to the endpoint and the submitting requester or agent. The first request
+//! claims the key and its response is stored; a replay with the same body
+//! gets that response back, and one with a different body a conflict.
+//!
+//! A request rejected with anything but [`ApiError::Internal`] releases its
+//! key so it can be retried: handlers reject before booking anything, or roll
+//! back what they booked before returning. An internal error may strike after
+//! side effects, so the claim is kept and repeats conflict until the key
+//! expires. Keys expire [`IDEMPOTENCY_KEY_TTL`] after they were claimed.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use axum::http::HeaderMap;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::error::ApiError;
+use crate::model::{current_unix_timestamp, StoredIdempotencyKey};
+use crate::storage::Storage;
+
+/// Header carrying the client's idempotency key.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+/// Longest accepted key.
+pub const MAX_KEY_LEN: usize = 255;
+/// How long a key is kept after it was claimed, finished or not.
+pub const IDEMPOTENCY_KEY_TTL: u64 = 86_400;
+
+/// The `Idempotency-Key` header, if any.
+pub fn key_from_headers(headers: &HeaderMap) -> Result<Option<String>, ApiError> {
+    let Some(value) = headers.get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(None);
+    };
+    let key = value
+        .to_str()
+        .map_err(|_| ApiError::BadRequest("idempotency key must be ASCII".to_string()))?
+        .trim();
+    if key.is_empty() || key.len() > MAX_KEY_LEN {
+        return Err(ApiError::BadRequest(format!(
+            "idempotency key must be 1 to {MAX_KEY_LEN} characters"
+        )));
+    }
+    Ok(Some(key.to_string()))
+}
+
+/// Run `handler` once for `key` within `endpoint` and `principal`, replaying
+/// its response for repeats of the same `request`. Without a key the handler
+/// simply runs.
+pub async fn once<R, T, F, Fut>(
+    storage: &Arc<dyn Storage>,
+    endpoint: &str,
+    principal: &str,
+    key: Option<String>,
+    request: &R,
+    handler: F,
+) -> Result<T, ApiError>
+where
+    R: Serialize,
+    T: Serialize + DeserializeOwned,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T, ApiError>>,
+{
+    let Some(key) = key else {
+        return handler().await;
+    };
+    let body = serde_json::to_vec(request)
+        .map_err(|e| ApiError::Internal(format!("failed to encode request: {e}")))?;
+    let now = current_unix_timestamp();
+    let request_hash = record_hash(&body);
+    let record = StoredIdempotencyKey {
+        key: format!("{endpoint}:{principal}:{key}"),
+        request_hash: request_hash.clone(),
+        response: None,
+        created_at: now,
+    };
+    let scoped = record.key.clone();
+
+    let existing = storage
+        .reserve_idempotency_key(record, now.saturating_sub(IDEMPOTENCY_KEY_TTL))
+        .await?;
+    if let Some(existing) = existing {
+        if existing.request_hash != request_hash {
+            return Err(ApiError::Conflict(format!(
+                "idempotency key {key} was already used with a different request"
+            )));
+        }
+        let Some(response) = existing.response else {
+            return Err(ApiError::Conflict(format!(
+                "a request with idempotency key {key} is still in progress or failed part-way"
+            )));
+        };
+        return serde_json::from_value(response)
+            .map_err(|e| ApiError::Internal(format!("failed to decode stored response: {e}")));
+    }
+
+    match handler().await {
+        Ok(response) => {
+            let value = serde_json::to_value(&response)
+                .map_err(|e| ApiError::Internal(format!("failed to encode response: {e}")))?;
+            storage.complete_idempotency_key(&scoped, value).await?;
+            Ok(response)
+        }
+        Err(ApiError::Internal(err)) => Err(ApiError::Internal(err)),
+        Err(err) => {
+            storage.release_idempotency_key(&scoped).await?;
+            Err(err)
+        }
+    }
+}
+
+fn record_hash(body: &[u8]) -> String {
+    blake3::hash(body).to_hex().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryStorage;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    async fn submit(
+        storage: &Arc<dyn Storage>,
+        calls: &AtomicU32,
+        key: Option<&str>,
+        body: &str,
+        error: Option<ApiError>,
+    ) -> Result<u32, ApiError> {
+        once(
+            storage,
+            "tasks",
+            "requester",
+            key.map(str::to_string),
+            &body,
+            || async {
+                let n = calls.fetch_add(1, Ordering::SeqCst) + 1;
+                match error {
+                    Some(err) => Err(err),
+                    None => Ok(n),
+                }
+            },
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn replays_return_the_original_response_and_other_bodies_conflict() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let calls = AtomicU32::new(0);
+
+        assert_eq!(
+            submit(&storage, &calls, Some("k"), "a", None)
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            submit(&storage, &calls, Some("k"), "a", None)
+                .await
+                .unwrap(),
+            1
+        );
+        assert!(matches!(
+            submit(&storage, &calls, Some("k"), "b", None).await,
+            Err(ApiError::Conflict(_))
+        ));
+        // Keys are independent of each other, and no key means no dedup.
+        assert_eq!(
+            submit(&storage, &calls, Some("j"), "b", None)
+                .await
+                .unwrap(),
+            2
+        );
+        assert_eq!(submit(&storage, &calls, None, "a", None).await.unwrap(), 3);
+        assert_eq!(submit(&storage, &calls, None, "a", None).await.unwrap(), 4);
+
+        // A rejected request releases its key for the retry.
+        let rejected = Some(ApiError::BadRequest("rejected".into()));
+        assert!(submit(&storage, &calls, Some("f"), "a", rejected)
+            .await
+            .is_err());
+        assert_eq!(
+            submit(&storage, &calls, Some("f"), "a", None)
+                .await
+                .unwrap(),
+            6
+        );
+
+        // One that failed internally may have had side effects, so it is not
+        // run again.
+        let failed = Some(ApiError::Internal("storage down".into()));
+        assert!(submit(&storage, &calls, Some("i"), "a", failed)
+            .await
+            .is_err());
+        assert!(matches!(
+            submit(&storage, &calls, Some("i"), "a", None).await,
+            Err(ApiError::Conflict(_))
+        ));
+        assert_eq!(calls.load(Ordering::SeqCst), 7);
+    }
+
+    #[tokio::test]
+    async fn claims_conflict_until_they_expire() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let calls = AtomicU32::new(0);
+        let now = current_unix_timestamp();
+        let claim = |created_at| StoredIdempotencyKey {
+            key: "tasks:requester:k".into(),
+            request_hash: record_hash(&serde_json::to_vec(&"a").unwrap()),
+            response: None,
+            created_at,
+        };
+
+        // An unfinished claim holds the key for the whole TTL.
+        storage
+            .reserve_idempotency_key(claim(now - IDEMPOTENCY_KEY_TTL + 60), 0)
+            .await
+            .unwrap();
+        assert!(matches!(
+            submit(&storage, &calls, Some("k"), "a", None).await,
+            Err(ApiError::Conflict(_))
+        ));
+
+        storage
+            .release_idempotency_key("tasks:requester:k")
+            .await
+            .unwrap();
+        storage
+            .reserve_idempotency_key(claim(now - IDEMPOTENCY_KEY_TTL - 1), 0)
+            .await
+            .unwrap();
+        assert_eq!(
+            submit(&storage, &calls, Some("k"), "a", None)
+                .await
+                .unwrap(),
+            1
+        );
+    }
+}
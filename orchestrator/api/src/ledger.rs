@@ -0,0 +1,1062 @@
+//! Internal double-entry ledger.
+//!
+//! Every movement of funds is a `StoredLedgerTransaction` whose postings sum
+//! to zero. Requesters and agents hold accounts under `user:<id>` (see
+//! [`user_account`]), so no id can name a system account. A task's escrow is
+//! held in `escrow:<task_id>` and fees accrue to [`TREASURY_ACCOUNT`].
+//! Operators credit user accounts from [`EXTERNAL_ACCOUNT`] (see [`fund`]).
+//! Escrow, fees, deposits and stake are only taken from accounts holding
+//! them, checked atomically with the booking (see
+//! `Storage::insert_funded_ledger_transaction`); penalties and refunds owed
+//! by an agent may still take its account below zero.
+//!
+//! Tasks with `Budget::escrow_required` lock their budget (once per
+//! redundant agent) in escrow at submission and are paid from there; other
+//! tasks pay straight from the requester. Agents are paid per the task's
+//! `PaymentSchedule`:
+//! - `Upfront`: in full once allocated;
+//! - `Milestone`: each tranche once its completion is approved;
+//! - `OnCompletion` and `Milestone`: whatever is outstanding once their result
+//!   is accepted;
+//! - `Streaming(rate)`: `rate` per second from allocation to completion,
+//!   capped at the clearing payment.
+//!
+//! Fees, bid deposits and slashes are priced by `ainur_core::StandardEconomics`:
+//! - submitting a task, placing a bid and filing a dispute each pay the
+//!   treasury a fee on the task budget, and payments pay one on the amount
+//!   paid;
+//! - every bid locks a deposit on the task budget out of its agent's bonded
+//!   stake (see [`crate::staking`]) in `deposit:<bid_id>`, returned to the
+//!   stake once the bid loses or the task is over;
+//! - agents that fail a task or breach their SLA are slashed a share of their
+//!   payment, taken from their deposit first, then from their stake and
+//!   their unbonding stake.
+//!
+//! Agents that breach the guarantees of their bid also refund the requester
+//! per their refund policy, withheld from their payment while it is still in
+//! escrow. Guarantees are therefore enforced before a task settles.
+//!
+//! Raising a task's budget while it is open locks the difference in escrow
+//! too. Whatever is left in escrow when the task settles, fails, expires or
+//! is cancelled goes back to the requester.
+
+use std::sync::Arc;
+
+use ainur_core::{
+    EconomicMechanism, PaymentSchedule, StandardEconomics, TransactionType, Violation,
+};
+
+use crate::error::ApiError;
+use crate::model::{
+    AgentSettlement, LedgerTransactionKind, LedgerView, StoredAllocation, StoredBid,
+    StoredLedgerTransaction, StoredResult, StoredTask, StoredViolation,
+};
+use crate::staking::{stake_account, unbonding_account};
+use crate::storage::Storage;
+
+/// Account collecting transaction fees and slashed funds.
+pub const TREASURY_ACCOUNT: &str = "treasury";
+
+/// Counterpart of funds credited to user accounts from outside the ledger.
+pub const EXTERNAL_ACCOUNT: &str = "external";
+
+const GUARANTEE_REFUND: &str = "guarantee refund";
+const DISPUTE_REFUND: &str = "dispute refund";
+const DISPUTE_SLASH: &str = "dispute slash";
+
+/// Account of requester or agent `id`.
+pub fn user_account(id: &str) -> String {
+    format!("user:{id}")
+}
+
+/// Account holding the escrowed budget of `task_id`.
+pub fn escrow_account(task_id: &str) -> String {
+    format!("escrow:{task_id}")
+}
+
+/// Account holding the deposit locked with bid `bid_id`.
+pub fn deposit_account(bid_id: &str) -> String {
+    format!("deposit:{bid_id}")
+}
+
+/// Fee on a `transaction_type` moving `amount`.
+pub fn fee(transaction_type: TransactionType, amount: u128) -> Result<u128, ApiError> {
+    Ok(StandardEconomics::new(amount).calculate_fees(transaction_type)?)
+}
+
+/// Balance and history of `account`.
+pub async fn account(storage: &Arc<dyn Storage>, account: &str) -> Result<LedgerView, ApiError> {
+    let transactions = storage.get_ledger_transactions_for_account(account).await?;
+    Ok(LedgerView::from_transactions(account, &transactions))
+}
+
+/// Credit `amount` from outside the ledger to `account`, which must be a
+/// user account.
+pub async fn fund(
+    storage: &Arc<dyn Storage>,
+    account: &str,
+    amount: u128,
+    now: u64,
+) -> Result<LedgerView, ApiError> {
+    if !account.starts_with("user:") {
+        return Err(ApiError::BadRequest(format!(
+            "only user accounts can be funded, not {account}"
+        )));
+    }
+    if amount == 0 {
+        return Err(ApiError::BadRequest(
+            "funding amount must be greater than zero".into(),
+        ));
+    }
+    move_funds(
+        storage,
+        LedgerTransactionKind::Funding,
+        None,
+        "account funded",
+        (EXTERNAL_ACCOUNT, account),
+        amount,
+        now,
+    )
+    .await?;
+    self::account(storage, account).await
+}
+
+/// Lock the budget of a newly submitted task in escrow, if it requires one.
+pub async fn lock_escrow(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    now: u64,
+) -> Result<(), ApiError> {
+    if !task.task.budget.escrow_required {
+        return Ok(());
+    }
+    let amount = task
+        .task
+        .budget
+        .max_cost
+        .checked_mul(task.redundancy() as u128)
+        .ok_or_else(|| ApiError::BadRequest("escrowed budget overflows".into()))?;
+    let requester = task.requester_account();
+    let escrow = escrow_account(&task.id);
+    spend(
+        storage,
+        LedgerTransactionKind::EscrowLock,
+        task,
+        "escrow locked",
+        (&requester, &escrow),
+        amount,
+        now,
+    )
+    .await
+}
+
+/// Lock the extra escrow of a task whose budget was raised from
+/// `previous_budget`, if it requires one.
+pub async fn raise_escrow(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    previous_budget: u128,
+    now: u64,
+) -> Result<(), ApiError> {
+    if !task.task.budget.escrow_required {
+        return Ok(());
+    }
+    let amount = escrow_raise(task, previous_budget)?;
+    let requester = task.requester_account();
+    let escrow = escrow_account(&task.id);
+    spend(
+        storage,
+        LedgerTransactionKind::EscrowLock,
+        task,
+        "escrow raised",
+        (&requester, &escrow),
+        amount,
+        now,
+    )
+    .await
+}
+
+/// Hand back the escrow [`raise_escrow`] locked for an amendment that could
+/// not be stored. Nothing is moved once the escrow has been released.
+pub async fn return_raised_escrow(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    previous_budget: u128,
+    now: u64,
+) -> Result<(), ApiError> {
+    if !task.task.budget.escrow_required {
+        return Ok(());
+    }
+    let amount = escrow_raise(task, previous_budget)?;
+    let requester = task.requester_account();
+    let escrow = escrow_account(&task.id);
+    move_funded(
+        storage,
+        LedgerTransactionKind::Refund,
+        Some(&task.id),
+        "escrow raise returned",
+        (&escrow, &requester),
+        amount,
+        now,
+    )
+    .await?;
+    Ok(())
+}
+
+fn escrow_raise(task: &StoredTask, previous_budget: u128) -> Result<u128, ApiError> {
+    task.task
+        .budget
+        .max_cost
+        .saturating_sub(previous_budget)
+        .checked_mul(task.redundancy() as u128)
+        .ok_or_else(|| ApiError::BadRequest("escrowed budget overflows".into()))
+}
+
+/// Charge `payer` the fee on `transaction_type` for `task`, priced on the
+/// task budget.
+pub async fn charge_fee(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    payer: &str,
+    transaction_type: TransactionType,
+    now: u64,
+) -> Result<(), ApiError> {
+    let amount = fee(transaction_type, task.task.budget.max_cost)?;
+    let memo = format!("{transaction_type:?} fee");
+    spend(
+        storage,
+        LedgerTransactionKind::Fee,
+        task,
+        &memo,
+        (payer, TREASURY_ACCOUNT),
+        amount,
+        now,
+    )
+    .await
+}
+
+/// Hand back what submitting `task` booked when the submission failed: its
+/// escrow and, if it was charged, the submission fee.
+pub async fn unwind_submission(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    now: u64,
+) -> Result<(), ApiError> {
+    release_escrow(storage, task, now).await?;
+    refund_fee(
+        storage,
+        task,
+        &task.requester_account(),
+        TransactionType::TaskSubmission,
+        now,
+    )
+    .await
+}
+
+/// Return the fee `payer` was charged on `transaction_type` for `task`, if
+/// it was charged and not yet returned.
+pub async fn refund_fee(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    payer: &str,
+    transaction_type: TransactionType,
+    now: u64,
+) -> Result<(), ApiError> {
+    let memo = format!("{transaction_type:?} fee");
+    let refund_memo = format!("{memo} returned");
+    let transactions = storage.get_ledger_transactions_for_account(payer).await?;
+    let booked = |kind: LedgerTransactionKind, memo: &str| {
+        transactions.iter().find(|tx| {
+            tx.kind == kind && tx.task_id.as_deref() == Some(&task.id) && tx.memo == memo
+        })
+    };
+    let Some(charged) = booked(LedgerTransactionKind::Fee, &memo) else {
+        return Ok(());
+    };
+    if booked(LedgerTransactionKind::Refund, &refund_memo).is_some() {
+        return Ok(());
+    }
+    transfer(
+        storage,
+        LedgerTransactionKind::Refund,
+        task,
+        &refund_memo,
+        (TREASURY_ACCOUNT, payer),
+        charged.amount,
+        now,
+    )
+    .await
+}
+
+/// Lock the deposit for `bid` out of its agent's stake and charge its
+/// placement fee. The deposit is released again if the fee cannot be
+/// charged.
+pub async fn lock_bid_deposit(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    bid: &StoredBid,
+    now: u64,
+) -> Result<(), ApiError> {
+    let deposit = StandardEconomics::new(task.task.budget.max_cost).bid_deposit();
+    spend(
+        storage,
+        LedgerTransactionKind::Deposit,
+        task,
+        "bid deposit locked",
+        (&stake_account(&bid.agent_id), &deposit_account(&bid.id)),
+        deposit,
+        now,
+    )
+    .await?;
+    let charged = charge_fee(
+        storage,
+        task,
+        &user_account(&bid.agent_id),
+        TransactionType::BidPlacement,
+        now,
+    )
+    .await;
+    if charged.is_err() {
+        release_bid_deposit(storage, task, bid, now).await?;
+    }
+    charged
+}
+
+/// Hand back what [`lock_bid_deposit`] booked for `bid` when the bid could
+/// not be stored: its deposit and its placement fee. Call it once, and only
+/// after the deposit was locked.
+pub async fn unwind_bid(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    bid: &StoredBid,
+    now: u64,
+) -> Result<(), ApiError> {
+    release_bid_deposit(storage, task, bid, now).await?;
+    return_fee(
+        storage,
+        task,
+        &user_account(&bid.agent_id),
+        TransactionType::BidPlacement,
+        now,
+    )
+    .await
+}
+
+/// Return the fee [`charge_fee`] just charged `payer` on `transaction_type`
+/// for `task`, for a request that failed after paying it.
+pub async fn return_fee(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    payer: &str,
+    transaction_type: TransactionType,
+    now: u64,
+) -> Result<(), ApiError> {
+    transfer(
+        storage,
+        LedgerTransactionKind::Refund,
+        task,
+        &format!("{transaction_type:?} fee returned"),
+        (TREASURY_ACCOUNT, payer),
+        fee(transaction_type, task.task.budget.max_cost)?,
+        now,
+    )
+    .await
+}
+
+/// Return what is left of `bid`'s deposit to its agent's stake.
+pub async fn release_bid_deposit(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    bid: &StoredBid,
+    now: u64,
+) -> Result<(), ApiError> {
+    let deposit = deposit_account(&bid.id);
+    let remaining = balance(storage, &deposit).await?;
+    if remaining <= 0 {
+        return Ok(());
+    }
+    transfer(
+        storage,
+        LedgerTransactionKind::Refund,
+        task,
+        "bid deposit released",
+        (&deposit, &stake_account(&bid.agent_id)),
+        remaining as u128,
+        now,
+    )
+    .await
+}
+
+/// Slash `allocation`'s agent for `violation`, a share of its payment taken
+/// as [`penalize`] does.
+/// Each kind of violation is slashed once per task and agent.
+pub async fn slash(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    allocation: &StoredAllocation,
+    violation: &Violation,
+    now: u64,
+) -> Result<(), ApiError> {
+    let amount = StandardEconomics::new(allocation.payment).calculate_penalty(violation)?;
+    let memo = format!(
+        "{} slash",
+        StoredViolation::kind_of(violation).replace('_', " ")
+    );
+    penalize(
+        storage,
+        task,
+        &allocation.agent_id,
+        Some(&allocation.bid_id),
+        &memo,
+        amount,
+        now,
+    )
+    .await
+}
+
+/// Take `amount` from `agent_id` into the treasury for `task`, once per task
+/// and `memo`: out of the deposit of `bid_id` first, then the agent's stake,
+/// its unbonding stake, and finally its account.
+async fn penalize(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    agent_id: &str,
+    bid_id: Option<&str>,
+    memo: &str,
+    amount: u128,
+    now: u64,
+) -> Result<(), ApiError> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let agent = user_account(agent_id);
+    let deposit = bid_id.map(deposit_account);
+    let stake = stake_account(agent_id);
+    let unbonding = unbonding_account(agent_id);
+    // Whichever accounts the penalty came out of record the earlier slash.
+    for account in deposit.iter().chain([&stake, &unbonding, &agent]) {
+        let slashed = storage
+            .get_ledger_transactions_for_account(account)
+            .await?
+            .iter()
+            .any(|tx| {
+                tx.kind == LedgerTransactionKind::Slash
+                    && tx.task_id.as_deref() == Some(&task.id)
+                    && tx.memo == memo
+            });
+        if slashed {
+            return Ok(());
+        }
+    }
+
+    let mut remaining = amount;
+    let mut postings = Vec::new();
+    for account in deposit.iter().chain([&stake, &unbonding]) {
+        let taken = remaining.min(balance(storage, account).await?.max(0) as u128);
+        postings.push((account.as_str(), -signed(taken)?));
+        remaining -= taken;
+    }
+    postings.push((&agent, -signed(remaining)?));
+    postings.push((TREASURY_ACCOUNT, signed(amount)?));
+    let tx = StoredLedgerTransaction::new(
+        LedgerTransactionKind::Slash,
+        Some(&task.id),
+        memo,
+        amount,
+        &postings,
+        now,
+    )?;
+    storage.insert_ledger_transaction(tx).await
+}
+
+/// Refund the requester `amount` for `allocation`'s agent breaching the
+/// guarantees of its bid, once per allocation. On escrowed tasks the refund
+/// is withheld from whatever is still owed to the agent in escrow, so it has
+/// to be booked before the agent is paid; the rest comes out of the agent's
+/// account.
+pub async fn refund_breach(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    allocation: &StoredAllocation,
+    amount: u128,
+    now: u64,
+) -> Result<(), ApiError> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let memo = guarantee_refund_memo(allocation);
+    let requester = task.requester_account();
+    let refunded = storage
+        .get_ledger_transactions_for_account(&requester)
+        .await?
+        .iter()
+        .any(|tx| {
+            tx.kind == LedgerTransactionKind::Refund
+                && tx.task_id.as_deref() == Some(&task.id)
+                && tx.memo == memo
+        });
+    if refunded {
+        return Ok(());
+    }
+
+    let agent = user_account(&allocation.agent_id);
+    let escrow = escrow_account(&task.id);
+    let withheld = if task.task.budget.escrow_required {
+        let owed = allocation
+            .payment
+            .saturating_sub(paid(storage, task, allocation).await?);
+        amount
+            .min(owed)
+            .min(balance(storage, &escrow).await?.max(0) as u128)
+    } else {
+        0
+    };
+    let tx = StoredLedgerTransaction::new(
+        LedgerTransactionKind::Refund,
+        Some(&task.id),
+        memo,
+        amount,
+        &[
+            (&escrow, -signed(withheld)?),
+            (&agent, -signed(amount - withheld)?),
+            (&requester, signed(amount)?),
+        ],
+        now,
+    )?;
+    storage.insert_ledger_transaction(tx).await
+}
+
+fn guarantee_refund_memo(allocation: &StoredAllocation) -> String {
+    format!("{GUARANTEE_REFUND} for allocation {}", allocation.id)
+}
+
+/// Pay freshly allocated agents of `Upfront` tasks.
+pub async fn pay_upfront(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    allocations: &[StoredAllocation],
+    now: u64,
+) -> Result<(), ApiError> {
+    if task.task.budget.payment_schedule != PaymentSchedule::Upfront {
+        return Ok(());
+    }
+    for allocation in allocations {
+        pay(storage, task, allocation, allocation.payment, now).await?;
+    }
+    Ok(())
+}
+
+/// Pay `allocation`'s agent its `approved` milestone tranches, net of what
+/// it was already paid.
+pub async fn pay_milestones(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    allocation: &StoredAllocation,
+    approved: u128,
+    now: u64,
+) -> Result<(), ApiError> {
+    pay(storage, task, allocation, approved, now).await
+}
+
+/// Pay the agents whose results were `accepted` what the schedule still owes
+/// them, then return the rest of the escrow to the requester.
+pub async fn settle(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    accepted: &[&StoredResult],
+    now: u64,
+) -> Result<(), ApiError> {
+    let allocations = storage.get_allocations_for_task(&task.id).await?;
+    for result in accepted {
+        // Locally executed tasks have no allocation and nothing to pay.
+        let Some(allocation) = allocations.iter().find(|a| a.agent_id == result.agent_id) else {
+            continue;
+        };
+        let due = match task.task.budget.payment_schedule {
+            PaymentSchedule::Streaming(rate) => {
+                let elapsed = result
+                    .result
+                    .completed_at
+                    .saturating_sub(allocation.allocated_at);
+                rate.saturating_mul(elapsed as u128).min(allocation.payment)
+            }
+            _ => allocation.payment,
+        };
+        pay(storage, task, allocation, due, now).await?;
+    }
+    release_escrow(storage, task, now).await
+}
+
+/// Return whatever is left in the task's escrow to the requester, and the
+/// bid deposits to their agents.
+pub async fn release_escrow(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    now: u64,
+) -> Result<(), ApiError> {
+    for bid in storage.get_bids_for_task(&task.id).await? {
+        release_bid_deposit(storage, task, &bid, now).await?;
+    }
+    if !task.task.budget.escrow_required {
+        return Ok(());
+    }
+    let escrow = escrow_account(&task.id);
+    let remaining = balance(storage, &escrow).await?;
+    if remaining <= 0 {
+        return Ok(());
+    }
+    let requester = task.requester_account();
+    transfer(
+        storage,
+        LedgerTransactionKind::Refund,
+        task,
+        "escrow released",
+        (&escrow, &requester),
+        remaining as u128,
+        now,
+    )
+    .await
+}
+
+/// Move the refunds and slashes of a dispute resolution out of the agents'
+/// accounts. Amounts already booked for the task are not booked again, so a
+/// resolution that failed part way can be retried.
+pub async fn settle_dispute(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    settlements: &[AgentSettlement],
+    now: u64,
+) -> Result<(), ApiError> {
+    let requester = task.requester_account();
+    let allocations = storage.get_allocations_for_task(&task.id).await?;
+    for settlement in settlements {
+        let agent = user_account(&settlement.agent_id);
+        let booked = storage.get_ledger_transactions_for_account(&agent).await?;
+        let already = |kind: LedgerTransactionKind, memo: &str| {
+            booked.iter().any(|tx| {
+                tx.kind == kind && tx.task_id.as_deref() == Some(&task.id) && tx.memo == memo
+            })
+        };
+        if !already(LedgerTransactionKind::Refund, DISPUTE_REFUND) {
+            transfer(
+                storage,
+                LedgerTransactionKind::Refund,
+                task,
+                DISPUTE_REFUND,
+                (&agent, &requester),
+                settlement.refund,
+                now,
+            )
+            .await?;
+        }
+        let bid_id = allocations
+            .iter()
+            .find(|a| a.agent_id == settlement.agent_id)
+            .map(|a| a.bid_id.as_str());
+        penalize(
+            storage,
+            task,
+            &settlement.agent_id,
+            bid_id,
+            DISPUTE_SLASH,
+            settlement.slashed,
+            now,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Pay `allocation`'s agent up to `due` in total for the task, less the
+/// transaction fee. Payments out of escrow are capped at what it holds.
+async fn pay(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    allocation: &StoredAllocation,
+    due: u128,
+    now: u64,
+) -> Result<(), ApiError> {
+    let agent = user_account(&allocation.agent_id);
+    let settled = paid(storage, task, allocation)
+        .await?
+        .saturating_add(withheld(storage, task, allocation).await?);
+    let mut amount = due.saturating_sub(settled);
+
+    let source = if task.task.budget.escrow_required {
+        let escrow = escrow_account(&task.id);
+        amount = amount.min(balance(storage, &escrow).await?.max(0) as u128);
+        escrow
+    } else {
+        task.requester_account()
+    };
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let fee = fee(TransactionType::ResultSubmission, amount)?;
+    let tx = StoredLedgerTransaction::new(
+        LedgerTransactionKind::Payment,
+        Some(&task.id),
+        format!("payment for allocation {}", allocation.id),
+        amount,
+        &[
+            (&source, -signed(amount)?),
+            (&agent, signed(amount - fee)?),
+            (TREASURY_ACCOUNT, signed(fee)?),
+        ],
+        now,
+    )?;
+    storage.insert_ledger_transaction(tx).await
+}
+
+/// What `allocation`'s agent has been paid for `task` so far.
+async fn paid(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    allocation: &StoredAllocation,
+) -> Result<u128, ApiError> {
+    Ok(storage
+        .get_ledger_transactions_for_account(&user_account(&allocation.agent_id))
+        .await?
+        .iter()
+        .filter(|tx| {
+            tx.kind == LedgerTransactionKind::Payment && tx.task_id.as_deref() == Some(&task.id)
+        })
+        .map(|tx| tx.amount)
+        .sum())
+}
+
+/// What was withheld in escrow from `allocation`'s payment to refund a
+/// breach of its guarantees (see [`refund_breach`]).
+async fn withheld(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    allocation: &StoredAllocation,
+) -> Result<u128, ApiError> {
+    if !task.task.budget.escrow_required {
+        return Ok(0);
+    }
+    let escrow = escrow_account(&task.id);
+    let memo = guarantee_refund_memo(allocation);
+    Ok(storage
+        .get_ledger_transactions_for_account(&escrow)
+        .await?
+        .iter()
+        .filter(|tx| tx.kind == LedgerTransactionKind::Refund && tx.memo == memo)
+        .flat_map(|tx| &tx.postings)
+        .filter(|posting| posting.account == escrow)
+        .map(|posting| posting.amount.unsigned_abs())
+        .sum())
+}
+
+async fn transfer(
+    storage: &Arc<dyn Storage>,
+    kind: LedgerTransactionKind,
+    task: &StoredTask,
+    memo: &str,
+    accounts: (&str, &str),
+    amount: u128,
+    now: u64,
+) -> Result<(), ApiError> {
+    move_funds(storage, kind, Some(&task.id), memo, accounts, amount, now).await
+}
+
+/// Move `amount` from one account to another; nothing is recorded for a zero
+/// amount or a move from an account to itself, e.g. a refund from an agent
+/// to itself as requester.
+pub(crate) async fn move_funds(
+    storage: &Arc<dyn Storage>,
+    kind: LedgerTransactionKind,
+    task_id: Option<&str>,
+    memo: &str,
+    (from, to): (&str, &str),
+    amount: u128,
+    now: u64,
+) -> Result<(), ApiError> {
+    if amount == 0 || from == to {
+        return Ok(());
+    }
+    let amount_signed = signed(amount)?;
+    let tx = StoredLedgerTransaction::new(
+        kind,
+        task_id,
+        memo,
+        amount,
+        &[(from, -amount_signed), (to, amount_signed)],
+        now,
+    )?;
+    storage.insert_ledger_transaction(tx).await
+}
+
+/// [`move_funds`] out of an account that must hold `amount`, returning
+/// whether it did. The check and the move are atomic.
+pub(crate) async fn move_funded(
+    storage: &Arc<dyn Storage>,
+    kind: LedgerTransactionKind,
+    task_id: Option<&str>,
+    memo: &str,
+    (from, to): (&str, &str),
+    amount: u128,
+    now: u64,
+) -> Result<bool, ApiError> {
+    if amount == 0 || from == to {
+        return Ok(true);
+    }
+    let amount_signed = signed(amount)?;
+    let tx = StoredLedgerTransaction::new(
+        kind,
+        task_id,
+        memo,
+        amount,
+        &[(from, -amount_signed), (to, amount_signed)],
+        now,
+    )?;
+    storage.insert_funded_ledger_transaction(tx, from).await
+}
+
+/// [`transfer`] out of an account that must hold `amount`.
+async fn spend(
+    storage: &Arc<dyn Storage>,
+    kind: LedgerTransactionKind,
+    task: &StoredTask,
+    memo: &str,
+    (from, to): (&str, &str),
+    amount: u128,
+    now: u64,
+) -> Result<(), ApiError> {
+    if move_funded(storage, kind, Some(&task.id), memo, (from, to), amount, now).await? {
+        return Ok(());
+    }
+    let available = balance(storage, from).await?.max(0);
+    Err(ApiError::BadRequest(format!(
+        "insufficient funds in {from}: {amount} needed, {available} available"
+    )))
+}
+
+pub(crate) async fn balance(storage: &Arc<dyn Storage>, account: &str) -> Result<i128, ApiError> {
+    let transactions = storage.get_ledger_transactions_for_account(account).await?;
+    Ok(transactions.iter().map(|tx| tx.posted_to(account)).sum())
+}
+
+fn signed(amount: u128) -> Result<i128, ApiError> {
+    i128::try_from(amount).map_err(|_| ApiError::BadRequest("amount exceeds i128".into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{
+        current_unix_timestamp, BidSubmissionRequest, ResultSubmissionRequest,
+        TaskSubmissionRequest,
+    };
+    use crate::storage::InMemoryStorage;
+    use uuid::Uuid;
+
+    async fn allocated_task(
+        storage: &Arc<dyn Storage>,
+        requester_id: &str,
+        schedule: PaymentSchedule,
+        payment: u128,
+    ) -> StoredTask {
+        let task = StoredTask::from_submission(TaskSubmissionRequest {
+            client_task_id: None,
+            requester_id: requester_id.into(),
+            description: "pay me".into(),
+            task_type: "echo".into(),
+            input_base64: String::new(),
+            max_budget: 1_000_000,
+            deadline: 0,
+            bid_window_secs: None,
+            domain: None,
+            requirements: Default::default(),
+            output_format: None,
+            verification_level: None,
+            payment_schedule: Some(schedule),
+            milestones: Vec::new(),
+            escrow_required: None,
+            signature: None,
+        })
+        .unwrap();
+        storage.insert_task(task.clone()).await.unwrap();
+        fund(storage, &task.requester_account(), 1_000_000, 0)
+            .await
+            .unwrap();
+        lock_escrow(storage, &task, 0).await.unwrap();
+        storage
+            .insert_allocation(StoredAllocation {
+                id: Uuid::new_v4().to_string(),
+                task_id: task.id.clone(),
+                agent_id: "agent".into(),
+                bid_id: Uuid::new_v4().to_string(),
+                payment,
+                social_welfare: 0,
+                allocated_at: 0,
+            })
+            .await
+            .unwrap();
+        task
+    }
+
+    fn result(task: &StoredTask, completed_at: u64) -> StoredResult {
+        let mut result = StoredResult::from_submission(
+            ResultSubmissionRequest {
+                task_id: task.id.clone(),
+                agent_id: "agent".into(),
+                output_base64: String::new(),
+                signature: None,
+            },
+            task,
+        )
+        .unwrap();
+        result.result.completed_at = completed_at;
+        result
+    }
+
+    async fn balance_of(storage: &Arc<dyn Storage>, id: &str) -> i128 {
+        account(storage, id).await.unwrap().balance
+    }
+
+    #[tokio::test]
+    async fn settling_pays_the_agent_less_fees_and_refunds_the_rest() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let task = allocated_task(
+            &storage,
+            "requester",
+            PaymentSchedule::OnCompletion,
+            600_000,
+        )
+        .await;
+        let escrow = escrow_account(&task.id);
+        assert_eq!(balance_of(&storage, &escrow).await, 1_000_000);
+        assert_eq!(balance_of(&storage, "user:requester").await, 0);
+
+        let now = current_unix_timestamp();
+        settle(&storage, &task, &[&result(&task, now)], now)
+            .await
+            .unwrap();
+        assert_eq!(
+            fee(TransactionType::ResultSubmission, 600_000).unwrap(),
+            600
+        );
+        assert_eq!(balance_of(&storage, "user:agent").await, 599_400);
+        assert_eq!(balance_of(&storage, TREASURY_ACCOUNT).await, 600);
+        assert_eq!(balance_of(&storage, &escrow).await, 0);
+        assert_eq!(balance_of(&storage, "user:requester").await, 400_000);
+
+        // Settling again owes nothing more.
+        settle(&storage, &task, &[&result(&task, now)], now)
+            .await
+            .unwrap();
+        assert_eq!(balance_of(&storage, "user:agent").await, 599_400);
+        assert_eq!(
+            account(&storage, "user:agent").await.unwrap().entries.len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn streaming_pays_for_elapsed_time_only() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let task = allocated_task(
+            &storage,
+            "requester",
+            PaymentSchedule::Streaming(1_000),
+            600_000,
+        )
+        .await;
+
+        settle(&storage, &task, &[&result(&task, 100)], 100)
+            .await
+            .unwrap();
+        assert_eq!(balance_of(&storage, "user:agent").await, 99_900);
+        assert_eq!(balance_of(&storage, "user:requester").await, 900_000);
+    }
+
+    #[tokio::test]
+    async fn user_accounts_never_alias_system_accounts() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let task = allocated_task(&storage, "treasury", PaymentSchedule::OnCompletion, 0).await;
+        assert_eq!(balance_of(&storage, "user:treasury").await, 0);
+        assert_eq!(balance_of(&storage, TREASURY_ACCOUNT).await, 0);
+        release_escrow(&storage, &task, 1).await.unwrap();
+        assert_eq!(balance_of(&storage, "user:treasury").await, 1_000_000);
+
+        // An agent working on its own task moves nothing between its
+        // accounts but still pays the fee.
+        let task = allocated_task(&storage, "agent", PaymentSchedule::OnCompletion, 600_000).await;
+        let now = current_unix_timestamp();
+        settle(&storage, &task, &[&result(&task, now)], now)
+            .await
+            .unwrap();
+        let refund = AgentSettlement {
+            agent_id: "agent".into(),
+            refund: 600_000,
+            slashed: 0,
+        };
+        settle_dispute(&storage, &task, &[refund], now)
+            .await
+            .unwrap();
+        assert_eq!(balance_of(&storage, "user:agent").await, 1_000_000 - 600);
+        assert_eq!(balance_of(&storage, &escrow_account(&task.id)).await, 0);
+    }
+
+    #[tokio::test]
+    async fn failed_submissions_get_their_escrow_and_fee_back() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let task = allocated_task(&storage, "requester", PaymentSchedule::OnCompletion, 0).await;
+        let submission_fee = TransactionType::TaskSubmission;
+        // Nothing is charged to an account that cannot pay.
+        assert!(
+            charge_fee(&storage, &task, "user:requester", submission_fee, 0)
+                .await
+                .is_err()
+        );
+        fund(&storage, "user:requester", 1_000, 0).await.unwrap();
+        charge_fee(&storage, &task, "user:requester", submission_fee, 0)
+            .await
+            .unwrap();
+        assert_eq!(balance_of(&storage, "user:requester").await, 0);
+
+        for _ in 0..2 {
+            unwind_submission(&storage, &task, 1).await.unwrap();
+            assert_eq!(balance_of(&storage, "user:requester").await, 1_001_000);
+            assert_eq!(balance_of(&storage, TREASURY_ACCOUNT).await, 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn bids_that_cannot_be_stored_get_their_deposit_and_fee_back() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let task = allocated_task(&storage, "requester", PaymentSchedule::OnCompletion, 0).await;
+        let bid = StoredBid::from_submission(
+            BidSubmissionRequest {
+                task_id: task.id.clone(),
+                agent_id: "agent".into(),
+                value: Some(500_000),
+                commitment: None,
+                quality_score: 90,
+                completion_time: 60,
+                guarantees: Vec::new(),
+                signature: None,
+            },
+            &task,
+        )
+        .unwrap();
+        // The deposit comes out of the stake and the fee out of the account,
+        // so the agent needs both.
+        assert!(lock_bid_deposit(&storage, &task, &bid, 0).await.is_err());
+        fund(&storage, "user:agent", 11_000, 0).await.unwrap();
+        crate::staking::bond(&storage, "agent", 10_000, 0)
+            .await
+            .unwrap();
+        lock_bid_deposit(&storage, &task, &bid, 0).await.unwrap();
+        assert_eq!(balance_of(&storage, "stake:agent").await, 0);
+        assert_eq!(balance_of(&storage, "user:agent").await, 0);
+
+        unwind_bid(&storage, &task, &bid, 1).await.unwrap();
+        assert_eq!(balance_of(&storage, "stake:agent").await, 10_000);
+        assert_eq!(balance_of(&storage, "user:agent").await, 1_000);
+        assert_eq!(balance_of(&storage, TREASURY_ACCOUNT).await, 0);
+        assert_eq!(balance_of(&storage, &deposit_account(&bid.id)).await, 0);
+    }
+}
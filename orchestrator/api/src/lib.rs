@@ -1,7 +1,23 @@
+pub mod allocator;
+pub mod amendments;
+pub mod auth;
 #[cfg(feature = "chain-bridge")]
 pub mod chain;
 pub mod config;
+pub mod disputes;
 pub mod error;
+pub mod events;
 pub mod execution;
+pub mod guarantees;
+pub mod idempotency;
+pub mod ledger;
+pub mod matching;
+pub mod milestones;
 pub mod model;
+pub mod reputation;
+pub mod signing;
+pub mod staking;
 pub mod storage;
+pub mod sweeper;
+pub mod verification;
+pub mod webhooks;
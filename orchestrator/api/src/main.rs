@@ -10,21 +10,45 @@
 //! end‑to‑end types and API ergonomics before wiring the Temporal chain and
 //! networking layers underneath.
 
+use ainur_core::{AgentReputation, AuctionError, CoreError, TransactionType, VerifierRegistry};
+use ainur_orchestrator_api::allocator::Allocator;
+use ainur_orchestrator_api::amendments;
+use ainur_orchestrator_api::auth::{self, ApiKeys};
 #[cfg(feature = "chain-bridge")]
 use ainur_orchestrator_api::chain;
 use ainur_orchestrator_api::config::AppConfig;
 #[cfg(feature = "wasm-engine")]
 use ainur_orchestrator_api::config::ExecutionEngineKind;
+use ainur_orchestrator_api::disputes::{self, Arbiters};
 use ainur_orchestrator_api::error::ApiError;
+use ainur_orchestrator_api::events::EventBus;
 use ainur_orchestrator_api::execution::{
     execute_and_build_result, ExecutionEngine, LocalEchoEngine,
 };
+use ainur_orchestrator_api::guarantees;
+use ainur_orchestrator_api::idempotency;
+use ainur_orchestrator_api::ledger;
+use ainur_orchestrator_api::matching;
+use ainur_orchestrator_api::milestones;
+#[cfg(feature = "chain-bridge")]
+use ainur_orchestrator_api::model::chain_verification_level;
 use ainur_orchestrator_api::model::{
-    AgentRegistrationRequest, BidSubmissionRequest, BidView, ChainCursorView, DashboardView,
-    OutboundExtrinsicRequest, OutboxEnqueueResponse, OutboxQuery, OutboxStatusView,
-    ResponseWithCorrelation, ResultSubmissionRequest, ResultView, StoredBid, StoredResult,
-    StoredTask, SyncStatusView, TaskStatus, TaskSubmissionRequest, TaskView,
+    current_unix_timestamp, parse_domain, AgentQuery, AgentRegistrationRequest,
+    AgentReputationView, AgreementStatus, AllocationView, BidQuery, BidRevealRequest,
+    BidSubmissionRequest, BidView, ChainCursorView, DashboardView, DisputeFilingRequest,
+    DisputeResolutionRequest, DisputeView, EventKind, EventQuery, FundingRequest, LedgerView,
+    MilestoneCompletionRequest, MilestoneCompletionView, MilestoneReviewRequest, MilestoneView,
+    OutboundExtrinsicRequest, OutboxEnqueueResponse, OutboxQuery, OutboxStatusView, Page,
+    RankedAgentView, RequesterRegistrationRequest, ResponseWithCorrelation,
+    ResultSubmissionRequest, ResultView, StakeRequest, StakeView, StakeWithdrawalRequest,
+    StoredBid, StoredNotification, StoredRequester, StoredResult, StoredTask,
+    StoredWebhookDelivery, SyncStatusView, TaskCancelRequest, TaskQuery, TaskStatus,
+    TaskSubmissionRequest, TaskUpdateRequest, TaskView, TopReputationQuery, VerificationView,
+    WebhookQuery, WebhookRegistrationRequest, WebhookView,
 };
+use ainur_orchestrator_api::reputation;
+use ainur_orchestrator_api::signing;
+use ainur_orchestrator_api::staking;
 #[cfg(feature = "chain-bridge")]
 use ainur_orchestrator_api::storage::ChainEventSink;
 #[cfg(feature = "postgres")]
@@ -32,17 +56,31 @@ use ainur_orchestrator_api::storage::PostgresStorage;
 use ainur_orchestrator_api::storage::{
     bid_to_view, result_to_view, task_to_view, InMemoryStorage, Storage,
 };
+use ainur_orchestrator_api::sweeper::DeadlineSweeper;
+use ainur_orchestrator_api::verification;
+use ainur_orchestrator_api::webhooks::{self, WebhookDispatcher};
 use axum::{
-    extract::{Path, Query, State},
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::HeaderMap,
+    middleware,
+    response::{
+        sse::{Event as SseEvent, KeepAlive, Sse},
+        Response,
+    },
     routing::{get, post},
     Json, Router,
 };
-use blake3;
 use hex;
 use metrics_exporter_prometheus::PrometheusBuilder;
+use serde::Serialize;
 #[cfg(feature = "postgres")]
 use sqlx::{Pool, Postgres, Row};
-use std::{net::SocketAddr, sync::Arc};
+use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::ReceiverStream, Stream, StreamExt};
 use tower_http::limit::RequestBodyLimitLayer;
 use tracing::{error, info, warn};
 use uuid::Uuid;
@@ -62,6 +100,10 @@ struct AppState {
     chain_sink: Arc<dyn ChainEventSink>,
     #[cfg(feature = "postgres")]
     pg_pool: Option<Pool<Postgres>>,
+    require_signatures: bool,
+    arbiters: Arbiters,
+    verifiers: Arc<VerifierRegistry>,
+    events: EventBus,
 }
 
 impl AppState {
@@ -146,6 +188,15 @@ impl AppState {
             _ => Arc::new(LocalEchoEngine::default()),
         };
 
+        let arbiters = Arbiters::parse(config.dispute_arbiters.as_deref().unwrap_or_default())
+            .unwrap_or_else(|err| {
+                eprintln!("invalid DISPUTE_ARBITERS: {err}");
+                std::process::exit(1);
+            });
+        if !arbiters.is_enabled() {
+            warn!("DISPUTE_ARBITERS not set; disputes cannot be resolved");
+        }
+
         Self {
             storage,
             engine,
@@ -153,8 +204,24 @@ impl AppState {
             chain_sink,
             #[cfg(feature = "postgres")]
             pg_pool,
+            require_signatures: config.require_signatures,
+            arbiters,
+            verifiers: Arc::new(VerifierRegistry::default()),
+            events: EventBus::default(),
         }
     }
+
+    /// Publish a lifecycle event for `task_id` carrying `data` as its payload.
+    fn publish<T: Serialize>(
+        &self,
+        kind: EventKind,
+        task_id: &str,
+        agent_id: Option<&str>,
+        data: &T,
+    ) {
+        let data = serde_json::to_value(data).unwrap_or(serde_json::Value::Null);
+        self.events.publish(kind, task_id, agent_id, data);
+    }
 }
 
 #[tokio::main]
@@ -169,6 +236,7 @@ async fn main() {
         if let Some(ws) = config.chain_ws_url.clone() {
             let storage = state.storage.clone();
             let sink = state.chain_sink.clone();
+            let events = state.events.clone();
             let metadata_path = config.chain_metadata_path.clone();
             #[cfg(feature = "postgres")]
             let pg_pool = state.pg_pool.clone();
@@ -176,7 +244,7 @@ async fn main() {
             let pg_pool_for_outbox = pg_pool.clone();
             tokio::spawn(async move {
                 if let Err(err) =
-                    chain::run_chain_replay(ws, metadata_path, storage, sink, pg_pool).await
+                    chain::run_chain_replay(ws, metadata_path, storage, sink, pg_pool, events).await
                 {
                     warn!("chain replay worker exited: {err}");
                 }
@@ -211,6 +279,42 @@ async fn main() {
         }
     }
 
+    {
+        let allocator = Allocator::new(state.storage.clone())
+            .with_pricing(config.auction_pricing)
+            .with_events(state.events.clone());
+        #[cfg(feature = "chain-bridge")]
+        let allocator = allocator.with_chain_sink(state.chain_sink.clone());
+        #[cfg(all(feature = "chain-bridge", feature = "postgres"))]
+        let allocator = allocator.with_pg_pool(state.pg_pool.clone());
+        let poll_ms = config.allocator_poll_ms;
+        tokio::spawn(async move {
+            if let Err(err) = allocator.run(poll_ms).await {
+                warn!("allocator worker exited: {err}");
+            }
+        });
+    }
+
+    {
+        let sweeper = DeadlineSweeper::new(state.storage.clone());
+        let poll_ms = config.sweeper_poll_ms;
+        tokio::spawn(async move {
+            if let Err(err) = sweeper.run(poll_ms).await {
+                warn!("deadline sweeper exited: {err}");
+            }
+        });
+    }
+
+    {
+        let dispatcher = WebhookDispatcher::new(state.storage.clone(), state.events.clone());
+        let poll_ms = config.webhook_poll_ms;
+        tokio::spawn(async move {
+            if let Err(err) = dispatcher.run(poll_ms).await {
+                warn!("webhook dispatcher exited: {err}");
+            }
+        });
+    }
+
     // Metrics endpoint (Prometheus text format) if configured.
     if let Some(bind) = config.metrics_bind.clone() {
         let builder = PrometheusBuilder::new();
@@ -244,12 +348,48 @@ async fn main() {
         .route("/v1/sync/status", get(get_sync_status))
         .route("/v1/agents", get(list_agents).post(register_agent))
         .route("/v1/agents/:id", get(get_agent))
+        .route("/v1/agents/:id/reputation", get(get_agent_reputation))
+        .route(
+            "/v1/agents/:id/stake",
+            get(get_agent_stake).post(bond_agent_stake),
+        )
+        .route("/v1/agents/:id/unbond", post(unbond_agent_stake))
+        .route("/v1/agents/:id/withdraw", post(withdraw_agent_stake))
+        .route("/v1/agents/:id/notifications", get(get_agent_notifications))
+        .route("/v1/requesters", post(register_requester))
+        .route("/v1/reputation/top", get(get_top_reputation))
         .route("/v1/tasks", get(list_tasks).post(submit_task))
-        .route("/v1/tasks/:id", get(get_task))
+        .route("/v1/tasks/:id", get(get_task).patch(update_task))
+        .route("/v1/tasks/:id/cancel", post(cancel_task))
         .route("/v1/bids", post(submit_bid))
+        .route("/v1/bids/:id/reveal", post(reveal_bid))
         .route("/v1/tasks/:id/bids", get(get_bids_for_task))
+        .route("/v1/tasks/:id/allocation", get(get_task_allocation))
+        .route("/v1/tasks/:id/eligible-agents", get(get_eligible_agents))
         .route("/v1/results", post(submit_result))
         .route("/v1/tasks/:id/result", get(get_task_result))
+        .route("/v1/tasks/:id/verification", get(get_task_verification))
+        .route(
+            "/v1/tasks/:id/disputes",
+            get(get_task_disputes).post(file_dispute),
+        )
+        .route("/v1/disputes/:id", get(get_dispute))
+        .route("/v1/disputes/:id/resolve", post(resolve_dispute))
+        .route("/v1/tasks/:id/milestones", get(get_task_milestones))
+        .route(
+            "/v1/tasks/:id/milestones/:milestone_id/completions",
+            post(complete_milestone),
+        )
+        .route(
+            "/v1/milestone-completions/:id/review",
+            post(review_milestone),
+        )
+        .route("/v1/accounts/:id/ledger", get(get_account_ledger))
+        .route("/v1/accounts/:id/fund", post(fund_account))
+        .route("/v1/events", get(stream_events))
+        .route("/v1/events/ws", get(stream_events_ws))
+        .route("/v1/webhooks", get(list_webhooks).post(register_webhook))
+        .route("/v1/webhooks/:id/deliveries", get(get_webhook_deliveries))
         .route("/v1/tasks/:id/execute-local", post(execute_task_local));
 
     #[cfg(feature = "chain-bridge")]
@@ -261,7 +401,20 @@ async fn main() {
         .route("/v1/outbox", get(list_outbox))
         .route("/v1/outbox/:id", get(get_outbox_status));
 
+    let api_keys =
+        ApiKeys::parse(config.api_keys.as_deref().unwrap_or_default()).unwrap_or_else(|err| {
+            eprintln!("invalid API_KEYS: {err}");
+            std::process::exit(1);
+        });
+    if !api_keys.is_enabled() {
+        warn!("API_KEYS not set; operator and admin endpoints are disabled, the rest are open");
+    }
+
     let app = app
+        .route_layer(middleware::from_fn_with_state(
+            api_keys,
+            auth::require_api_key,
+        ))
         .with_state(state)
         .layer(RequestBodyLimitLayer::new(REQ_BODY_LIMIT_BYTES));
 
@@ -308,24 +461,28 @@ async fn register_agent(
     State(state): State<AppState>,
     Json(payload): Json<AgentRegistrationRequest>,
 ) -> Result<Json<ResponseWithCorrelation<AgentRegistrationRequest>>, ApiError> {
-    if payload.id.trim().is_empty() {
-        return Err(ApiError::BadRequest("agent id must not be empty".into()));
-    }
-    if payload.label.trim().is_empty() {
-        return Err(ApiError::BadRequest("agent label must not be empty".into()));
-    }
+    payload.validate()?;
+    signing::authenticate_registration(&state.storage, &payload, state.require_signatures).await?;
 
     state.storage.register_agent(payload.clone()).await?;
     let correlation: Option<String> = {
         #[cfg(feature = "chain-bridge")]
         {
             let correlation_id = Uuid::new_v4().to_string();
-            let payload_json = serde_json::json!({
+            let mut payload_json = serde_json::json!({
                 "did": payload.id,
-                "capabilities": [],
-                "metadata": payload.label,
-                "verification_level": "best_effort"
+                "capabilities": payload.capability_tags(),
+                "metadata": serde_json::json!({
+                    "label": payload.label,
+                    "public_key": payload.public_key,
+                    "endpoints": payload.endpoints,
+                })
+                .to_string(),
+                "verification_level": chain_verification_level(&payload.verification_level)
             });
+            if let Some(attestation) = &payload.attestation {
+                payload_json["attestation"] = serde_json::json!(attestation);
+            }
             if let Err(err) = chain::validate_outbox_payload(
                 "AgentRegistry",
                 "register_agent",
@@ -356,13 +513,25 @@ async fn register_agent(
 
     Ok(Json(ResponseWithCorrelation {
         correlation_id: correlation,
-        data: AgentRegistrationRequest {
-            id: payload.id,
-            label: payload.label,
-        },
+        data: payload,
     }))
 }
 
+async fn register_requester(
+    State(state): State<AppState>,
+    Json(payload): Json<RequesterRegistrationRequest>,
+) -> Result<Json<StoredRequester>, ApiError> {
+    if payload.id.trim().is_empty() {
+        return Err(ApiError::BadRequest(
+            "requester id must not be empty".into(),
+        ));
+    }
+    signing::authenticate_requester_registration(&state.storage, &payload).await?;
+    let requester = StoredRequester::from_registration(payload, current_unix_timestamp());
+    state.storage.upsert_requester(requester.clone()).await?;
+    Ok(Json(requester))
+}
+
 async fn get_agent(
     State(state): State<AppState>,
     Path(id): Path<String>,
@@ -371,34 +540,216 @@ async fn get_agent(
     Ok(Json(agent))
 }
 
+async fn get_agent_reputation(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<AgentReputationView>, ApiError> {
+    let now = current_unix_timestamp();
+    let mut record = match reputation::agent_reputation(&state.storage, &id, now).await? {
+        Some(record) => record,
+        // Registered agents without any results yet start at the initial score.
+        None => {
+            let _ = state.storage.get_agent(&id).await?;
+            AgentReputation::new(now)
+        }
+    };
+    record.reputation.stake = staking::stake(&state.storage, &id, now).await?.bonded();
+    Ok(Json(AgentReputationView::new(id, record)))
+}
+
+async fn get_agent_stake(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<StakeView>, ApiError> {
+    let _ = state.storage.get_agent(&id).await?;
+    Ok(Json(
+        staking::stake(&state.storage, &id, current_unix_timestamp()).await?,
+    ))
+}
+
+async fn bond_agent_stake(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<StakeRequest>,
+) -> Result<Json<StakeView>, ApiError> {
+    signing::authenticate(
+        &state.storage,
+        &id,
+        &payload.bond_message(&id),
+        payload.signature.as_ref(),
+        state.require_signatures,
+    )
+    .await?;
+    let _ = state.storage.get_agent(&id).await?;
+    let view = staking::bond(
+        &state.storage,
+        &id,
+        payload.amount,
+        current_unix_timestamp(),
+    )
+    .await?;
+    Ok(Json(view))
+}
+
+async fn get_agent_notifications(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<StoredNotification>>, ApiError> {
+    let _ = state.storage.get_agent(&id).await?;
+    Ok(Json(state.storage.get_notifications_for_agent(&id).await?))
+}
+
+async fn unbond_agent_stake(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<StakeRequest>,
+) -> Result<Json<StakeView>, ApiError> {
+    signing::authenticate(
+        &state.storage,
+        &id,
+        &payload.unbond_message(&id),
+        payload.signature.as_ref(),
+        state.require_signatures,
+    )
+    .await?;
+    let view = staking::unbond(
+        &state.storage,
+        &id,
+        payload.amount,
+        current_unix_timestamp(),
+    )
+    .await?;
+    Ok(Json(view))
+}
+
+async fn withdraw_agent_stake(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<StakeWithdrawalRequest>,
+) -> Result<Json<StakeView>, ApiError> {
+    signing::authenticate(
+        &state.storage,
+        &id,
+        &payload.signed_message(&id),
+        payload.signature.as_ref(),
+        state.require_signatures,
+    )
+    .await?;
+    Ok(Json(
+        staking::withdraw(&state.storage, &id, current_unix_timestamp()).await?,
+    ))
+}
+
+async fn get_top_reputation(
+    State(state): State<AppState>,
+    Query(query): Query<TopReputationQuery>,
+) -> Result<Json<Vec<RankedAgentView>>, ApiError> {
+    let domain = query.domain.as_deref().map(parse_domain);
+    let count = query.count.unwrap_or(10).clamp(1, 100);
+    let ranked = reputation::top_agents(
+        &state.storage,
+        domain.as_ref(),
+        count,
+        current_unix_timestamp(),
+    )
+    .await?;
+    Ok(Json(ranked))
+}
+
 async fn list_agents(
     State(state): State<AppState>,
-) -> Result<Json<Vec<AgentRegistrationRequest>>, ApiError> {
-    let agents = state.storage.list_agents().await?;
+    Query(query): Query<AgentQuery>,
+) -> Result<Json<Page<AgentRegistrationRequest>>, ApiError> {
+    let agents = state.storage.list_agents_page(&query.page()?).await?;
     Ok(Json(agents))
 }
 
 async fn submit_task(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(payload): Json<TaskSubmissionRequest>,
 ) -> Result<Json<ResponseWithCorrelation<TaskView>>, ApiError> {
+    signing::verify_request(
+        &state.storage,
+        &payload.requester_id,
+        &payload.signed_message()?,
+        payload.signature.as_ref(),
+        state.require_signatures,
+    )
+    .await?;
+    // A client_task_id doubles as the idempotency key, in its own namespace.
+    let (endpoint, key) = match &payload.client_task_id {
+        Some(client_task_id) => ("client_tasks", Some(client_task_id.clone())),
+        None => ("tasks", idempotency::key_from_headers(&headers)?),
+    };
+    let request = payload.clone();
+    let response = idempotency::once(
+        &state.storage,
+        endpoint,
+        &request.requester_id,
+        key,
+        &request,
+        || async {
+            signing::consume_nonce(
+                &state.storage,
+                &request.requester_id,
+                request.signature.as_ref(),
+            )
+            .await?;
+            create_task(&state, payload).await
+        },
+    )
+    .await?;
+    Ok(Json(response))
+}
+
+async fn create_task(
+    state: &AppState,
+    payload: TaskSubmissionRequest,
+) -> Result<ResponseWithCorrelation<TaskView>, ApiError> {
     let stored = StoredTask::from_submission(payload)?;
-    let spec_hash = blake3::hash(&stored.task.specification.input);
+    verification::ensure_supported(&state.verifiers, &stored.task.verification_level)?;
+    let spec_hash = stored.task.specification.spec_hash();
     let view = task_to_view(&stored);
-
-    state.storage.insert_task(stored).await?;
+    #[cfg(feature = "chain-bridge")]
+    let chain_level = chain_verification_level(&stored.task.verification_level);
+
+    // Funds are booked before the task is stored, and handed back if
+    // anything fails, so no task is ever visible without its escrow and fee.
+    let opened = async {
+        ledger::lock_escrow(&state.storage, &stored, stored.created_at).await?;
+        ledger::charge_fee(
+            &state.storage,
+            &stored,
+            &stored.requester_account(),
+            TransactionType::TaskSubmission,
+            stored.created_at,
+        )
+        .await?;
+        state.storage.insert_task(stored.clone()).await
+    }
+    .await;
+    if let Err(err) = opened {
+        if let Err(unwind) =
+            ledger::unwind_submission(&state.storage, &stored, stored.created_at).await
+        {
+            warn!("failed to return funds of task {}: {unwind}", stored.id);
+        }
+        return Err(err);
+    }
+    state.publish(EventKind::TaskCreated, &view.id, None, &view);
 
     let mut correlation: Option<String> = None;
     #[cfg(feature = "chain-bridge")]
     {
-        // Derive a spec hash from the input bytes for a deterministic link to the chain task.
-        let spec_hex = format!("0x{}", hex::encode(spec_hash.as_bytes()));
+        // The canonical spec hash links this task to the chain task byte-for-byte.
+        let spec_hex = format!("0x{}", hex::encode(spec_hash));
         let correlation_id = Uuid::new_v4().to_string();
         let payload_json = serde_json::json!({
             "spec_hash": spec_hex,
             "budget": view.max_budget,
             "deadline": view.deadline,
-            "verification_level": "best_effort"
+            "verification_level": chain_level
         });
         if let Err(err) = chain::validate_outbox_payload(
             "TaskMarket",
@@ -421,10 +772,10 @@ async fn submit_task(
         }
     }
 
-    Ok(Json(ResponseWithCorrelation {
+    Ok(ResponseWithCorrelation {
         correlation_id: correlation,
         data: view,
-    }))
+    })
 }
 
 async fn get_task(
@@ -435,52 +786,217 @@ async fn get_task(
     Ok(Json(task_to_view(&stored)))
 }
 
-async fn list_tasks(State(state): State<AppState>) -> Result<Json<Vec<TaskView>>, ApiError> {
-    let tasks = state.storage.list_tasks().await?;
-    Ok(Json(tasks.iter().map(task_to_view).collect()))
+async fn cancel_task(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<TaskCancelRequest>,
+) -> Result<Json<ResponseWithCorrelation<TaskView>>, ApiError> {
+    signing::authenticate(
+        &state.storage,
+        &payload.requester_id,
+        &payload.signed_message(&id),
+        payload.signature.as_ref(),
+        state.require_signatures,
+    )
+    .await?;
+    let task = amendments::cancel(&state.storage, &id, payload, current_unix_timestamp()).await?;
+    let view = task_to_view(&task);
+
+    let mut correlation: Option<String> = None;
+    #[cfg(feature = "chain-bridge")]
+    {
+        let correlation_id = Uuid::new_v4().to_string();
+        let (task_chain_id, _) =
+            chain::lookup_chain_ids(state.pg_pool.as_ref(), &view.id, &view.requester_id).await;
+        let payload_json = serde_json::json!({
+            "task_id": task_chain_id,
+        });
+        if let Err(err) = chain::validate_outbox_payload(
+            "TaskMarket",
+            "cancel_task",
+            Some(payload_json.to_string().as_str()),
+        ) {
+            warn!("skipping outbox enqueue for task cancel: {err}");
+        } else if let Err(err) = chain::record_outbound_extrinsic(
+            state.chain_sink.clone(),
+            &correlation_id,
+            "TaskMarket",
+            "cancel_task",
+            Some(&payload_json.to_string()),
+        )
+        .await
+        {
+            warn!("failed to enqueue task cancel: {err}");
+        } else {
+            correlation = Some(correlation_id);
+        }
+    }
+
+    Ok(Json(ResponseWithCorrelation {
+        correlation_id: correlation,
+        data: view,
+    }))
+}
+
+async fn update_task(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<TaskUpdateRequest>,
+) -> Result<Json<ResponseWithCorrelation<TaskView>>, ApiError> {
+    signing::authenticate(
+        &state.storage,
+        &payload.requester_id,
+        &payload.signed_message(&id),
+        payload.signature.as_ref(),
+        state.require_signatures,
+    )
+    .await?;
+    #[cfg(feature = "chain-bridge")]
+    let (budget, deadline) = (payload.max_budget, payload.deadline);
+    let task = amendments::update(&state.storage, &id, payload, current_unix_timestamp()).await?;
+    let view = task_to_view(&task);
+
+    let mut correlation: Option<String> = None;
+    #[cfg(feature = "chain-bridge")]
+    {
+        let correlation_id = Uuid::new_v4().to_string();
+        let (task_chain_id, _) =
+            chain::lookup_chain_ids(state.pg_pool.as_ref(), &view.id, &view.requester_id).await;
+        let payload_json = serde_json::json!({
+            "task_id": task_chain_id,
+            "budget": budget,
+            "deadline": deadline,
+        });
+        if let Err(err) = chain::validate_outbox_payload(
+            "TaskMarket",
+            "update_task",
+            Some(payload_json.to_string().as_str()),
+        ) {
+            warn!("skipping outbox enqueue for task update: {err}");
+        } else if let Err(err) = chain::record_outbound_extrinsic(
+            state.chain_sink.clone(),
+            &correlation_id,
+            "TaskMarket",
+            "update_task",
+            Some(&payload_json.to_string()),
+        )
+        .await
+        {
+            warn!("failed to enqueue task update: {err}");
+        } else {
+            correlation = Some(correlation_id);
+        }
+    }
+
+    Ok(Json(ResponseWithCorrelation {
+        correlation_id: correlation,
+        data: view,
+    }))
+}
+
+async fn list_tasks(
+    State(state): State<AppState>,
+    Query(query): Query<TaskQuery>,
+) -> Result<Json<Page<TaskView>>, ApiError> {
+    let tasks = state
+        .storage
+        .list_tasks(&query.filter()?, &query.page()?)
+        .await?;
+    Ok(Json(tasks.map(|t| task_to_view(&t))))
 }
 
 async fn submit_bid(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(payload): Json<BidSubmissionRequest>,
 ) -> Result<Json<ResponseWithCorrelation<BidView>>, ApiError> {
-    let task = state.storage.get_task(&payload.task_id).await?;
+    signing::verify_request(
+        &state.storage,
+        &payload.agent_id,
+        &payload.signed_message()?,
+        payload.signature.as_ref(),
+        state.require_signatures,
+    )
+    .await?;
+    let key = idempotency::key_from_headers(&headers)?;
+    let request = payload.clone();
+    let response = idempotency::once(
+        &state.storage,
+        "bids",
+        &request.agent_id,
+        key,
+        &request,
+        || async {
+            signing::consume_nonce(
+                &state.storage,
+                &request.agent_id,
+                request.signature.as_ref(),
+            )
+            .await?;
+            place_bid(&state, payload).await
+        },
+    )
+    .await?;
+    Ok(Json(response))
+}
+
+async fn place_bid(
+    state: &AppState,
+    payload: BidSubmissionRequest,
+) -> Result<ResponseWithCorrelation<BidView>, ApiError> {
+    let mut task = state.storage.get_task(&payload.task_id).await?;
     let stored_bid = StoredBid::from_submission(payload, &task)?;
+    task.ensure_not_overdue(stored_bid.created_at)?;
+    if task.bid_window_closed(stored_bid.created_at) {
+        return Err(CoreError::from(AuctionError::BiddingExpired).into());
+    }
+    matching::ensure_eligible(&state.storage, &task, &stored_bid.agent_id).await?;
+    // Held until the bid is stored, so concurrent bids and unbonding by the
+    // agent cannot all pass the stake and open-bid checks.
+    let agent_lock = state.storage.lock_agent(&stored_bid.agent_id).await?;
+    staking::ensure_can_bid(
+        &state.storage,
+        &task,
+        &stored_bid.agent_id,
+        stored_bid.created_at,
+    )
+    .await?;
     let view = bid_to_view(&stored_bid);
 
-    state.storage.insert_bid(stored_bid).await?;
+    // The first bid moves the task from `open` to `bidding`; bids on tasks
+    // that are no longer open for bids are rejected.
+    let previous = task.status;
+    task.transition_to(TaskStatus::Bidding)?;
+    // The deposit is locked before the bid is stored, and handed back if the
+    // bid cannot be, e.g. because the agent already bid on the task.
+    let now = stored_bid.created_at;
+    ledger::lock_bid_deposit(&state.storage, &task, &stored_bid, now).await?;
+    if let Err(err) = state.storage.insert_bid(stored_bid.clone()).await {
+        ledger::unwind_bid(&state.storage, &task, &stored_bid, now).await?;
+        return Err(err);
+    }
+    drop(agent_lock);
+    if previous != task.status {
+        state.storage.update_task(&mut task).await?;
+    }
+    state.publish(
+        EventKind::BidReceived,
+        &view.task_id,
+        Some(&view.agent_id),
+        &view,
+    );
 
     let mut correlation: Option<String> = None;
     #[cfg(feature = "chain-bridge")]
     {
         let correlation_id = Uuid::new_v4().to_string();
-        let commitment_hash =
-            blake3::hash(format!("{}:{}", view.task_id, view.agent_id).as_bytes());
-        let commitment_hex = format!("0x{}", hex::encode(commitment_hash.as_bytes()));
         // Try to pick up chain ids if already known.
-        let (task_chain_id, agent_chain_id) = if let Some(pool) = state.pg_pool.clone() {
-            let t_id: i64 = sqlx::query_scalar("SELECT chain_task_id FROM tasks WHERE id = $1")
-                .bind(&view.task_id)
-                .fetch_optional(&pool)
-                .await
-                .ok()
-                .flatten()
-                .unwrap_or(0);
-            let a_id: i64 = sqlx::query_scalar("SELECT chain_agent_id FROM agents WHERE id = $1")
-                .bind(&view.agent_id)
-                .fetch_optional(&pool)
-                .await
-                .ok()
-                .flatten()
-                .unwrap_or(0);
-            (t_id, a_id)
-        } else {
-            (0, 0)
-        };
+        let (task_chain_id, agent_chain_id) =
+            chain::lookup_chain_ids(state.pg_pool.as_ref(), &view.task_id, &view.agent_id).await;
         let payload_json = serde_json::json!({
             "task_id": task_chain_id,
             "agent_id": agent_chain_id,
-            "commitment": commitment_hex,
+            "commitment": stored_bid.commitment,
             "estimated_duration": view.completion_time,
         });
         if let Err(err) = chain::validate_outbox_payload(
@@ -501,66 +1017,277 @@ async fn submit_bid(
             warn!("failed to enqueue bid submit: {err}");
         } else {
             correlation = Some(correlation_id);
+            // Open bids are revealed immediately with the server-side nonce.
+            if stored_bid.revealed {
+                let _ = enqueue_bid_reveal(state, &stored_bid).await;
+            }
         }
     }
 
+    Ok(ResponseWithCorrelation {
+        correlation_id: correlation,
+        data: view,
+    })
+}
+
+/// Reveal a sealed bid by disclosing the value and nonce behind its
+/// commitment. Reveals are accepted until the task's reveal phase ends.
+async fn reveal_bid(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<BidRevealRequest>,
+) -> Result<Json<ResponseWithCorrelation<BidView>>, ApiError> {
+    let mut bid = state.storage.get_bid(&id).await?;
+    let task = state.storage.get_task(&bid.task_id).await?;
+    let now = current_unix_timestamp();
+    task.ensure_not_overdue(now)?;
+    if !task.status.is_open_for_bids() || task.reveal_window_closed(now) {
+        return Err(CoreError::from(AuctionError::BiddingExpired).into());
+    }
+
+    bid.reveal(&payload)?;
+    state.storage.update_bid(bid.clone()).await?;
+    let view = bid_to_view(&bid);
+
+    #[cfg(feature = "chain-bridge")]
+    let correlation = enqueue_bid_reveal(&state, &bid).await;
+    #[cfg(not(feature = "chain-bridge"))]
+    let correlation: Option<String> = None;
+
     Ok(Json(ResponseWithCorrelation {
         correlation_id: correlation,
         data: view,
     }))
 }
 
+/// Enqueue `TaskMarket::reveal_bid` for a revealed bid, returning the
+/// correlation id on success.
+#[cfg(feature = "chain-bridge")]
+async fn enqueue_bid_reveal(state: &AppState, bid: &StoredBid) -> Option<String> {
+    let nonce = bid.nonce.as_deref()?;
+    let Ok(cost) = u64::try_from(bid.bid.value) else {
+        warn!("skipping outbox enqueue for reveal: bid value exceeds u64");
+        return None;
+    };
+    let (task_chain_id, agent_chain_id) =
+        chain::lookup_chain_ids(state.pg_pool.as_ref(), &bid.task_id, &bid.agent_id).await;
+    let correlation_id = Uuid::new_v4().to_string();
+    let payload_json = serde_json::json!({
+        "task_id": task_chain_id,
+        "agent_id": agent_chain_id,
+        "cost": cost,
+        "nonce": nonce,
+    });
+    if let Err(err) = chain::validate_outbox_payload(
+        "TaskMarket",
+        "reveal_bid",
+        Some(payload_json.to_string().as_str()),
+    ) {
+        warn!("skipping outbox enqueue for reveal: {err}");
+        None
+    } else if let Err(err) = chain::record_outbound_extrinsic(
+        state.chain_sink.clone(),
+        &correlation_id,
+        "TaskMarket",
+        "reveal_bid",
+        Some(&payload_json.to_string()),
+    )
+    .await
+    {
+        warn!("failed to enqueue bid reveal: {err}");
+        None
+    } else {
+        Some(correlation_id)
+    }
+}
+
 async fn get_bids_for_task(
     State(state): State<AppState>,
     Path(id): Path<String>,
-) -> Result<Json<Vec<BidView>>, ApiError> {
+    Query(query): Query<BidQuery>,
+) -> Result<Json<Page<BidView>>, ApiError> {
     // Ensure the task exists; otherwise, return 404.
     let _ = state.storage.get_task(&id).await?;
 
-    let bids = state.storage.get_bids_for_task(&id).await?;
-    Ok(Json(bids.iter().map(bid_to_view).collect()))
+    let bids = state
+        .storage
+        .list_bids_for_task(&id, &query.filter(), &query.page()?)
+        .await?;
+    Ok(Json(bids.map(|b| bid_to_view(&b))))
+}
+
+async fn get_task_allocation(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<AllocationView>, ApiError> {
+    // Ensure the task exists.
+    let _ = state.storage.get_task(&id).await?;
+
+    let allocations = state.storage.get_allocations_for_task(&id).await?;
+    AllocationView::from_stored(&allocations)
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("task {id} has not been allocated")))
+}
+
+async fn get_eligible_agents(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<AgentRegistrationRequest>>, ApiError> {
+    let task = state.storage.get_task(&id).await?;
+    let agents = matching::eligible_agents(&state.storage, &task).await?;
+    Ok(Json(agents))
 }
 
 async fn submit_result(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(payload): Json<ResultSubmissionRequest>,
 ) -> Result<Json<ResponseWithCorrelation<ResultView>>, ApiError> {
+    signing::verify_request(
+        &state.storage,
+        &payload.agent_id,
+        &payload.signed_message()?,
+        payload.signature.as_ref(),
+        state.require_signatures,
+    )
+    .await?;
+    let key = idempotency::key_from_headers(&headers)?;
+    let request = payload.clone();
+    let response = idempotency::once(
+        &state.storage,
+        "results",
+        &request.agent_id,
+        key,
+        &request,
+        || async {
+            signing::consume_nonce(
+                &state.storage,
+                &request.agent_id,
+                request.signature.as_ref(),
+            )
+            .await?;
+            report_result(&state, payload).await
+        },
+    )
+    .await?;
+    Ok(Json(response))
+}
+
+async fn report_result(
+    state: &AppState,
+    payload: ResultSubmissionRequest,
+) -> Result<ResponseWithCorrelation<ResultView>, ApiError> {
     let mut task = state.storage.get_task(&payload.task_id).await?;
 
-    let stored_result = StoredResult::from_submission(payload, &task)?;
-    // Mark the task as completed and persist the updated record.
-    task.status = TaskStatus::Completed;
-    state.storage.upsert_task(task).await?;
+    let mut stored_result = StoredResult::from_submission(payload, &task)?;
+    task.ensure_not_overdue(stored_result.created_at)?;
+    // Only the agents the allocator picked may report results. Tasks allocated
+    // any other way (on chain, or to the local engine through `execute-local`,
+    // which stores its result directly) take none through this endpoint.
+    let allocations = state.storage.get_allocations_for_task(&task.id).await?;
+    if !allocations
+        .iter()
+        .any(|a| a.agent_id == stored_result.agent_id)
+    {
+        return Err(ApiError::BadRequest(format!(
+            "agent {} is not allocated to task {}",
+            stored_result.agent_id, task.id
+        )));
+    }
+    let mut collected = state.storage.get_results_for_task(&task.id).await?;
+    if collected
+        .iter()
+        .any(|r| r.agent_id == stored_result.agent_id)
+    {
+        return Err(ApiError::BadRequest(format!(
+            "agent {} already reported a result for task {}",
+            stored_result.agent_id, task.id
+        )));
+    }
+    // Results are only accepted for allocated tasks; an allocated task is
+    // implicitly moved through `executing` when its first result arrives.
+    if task.status != TaskStatus::Executing {
+        task.transition_to(TaskStatus::Executing)?;
+    }
+    let outcome = verification::verify_result(&state.verifiers, &task, &stored_result.result)?;
+    stored_result.quality = Some(outcome.confidence);
 
     let view = result_to_view(&stored_result);
+    #[cfg(feature = "chain-bridge")]
+    let result_hash = stored_result.result.result_hash();
+
+    collected.push(stored_result.clone());
+    let report = verification::agreement(&task, &collected);
+    match report.status {
+        AgreementStatus::Pending => {}
+        AgreementStatus::Agreed => task.transition_to(TaskStatus::Completed)?,
+        AgreementStatus::Disagreed => task.transition_to(TaskStatus::Failed)?,
+    }
 
+    // Settle before storing anything: settlement only books what is still
+    // owed, so if storing the result fails the agent can resubmit it.
+    let now = current_unix_timestamp();
+    match report.status {
+        AgreementStatus::Pending => {}
+        AgreementStatus::Agreed => {
+            let accepted: Vec<&StoredResult> = collected
+                .iter()
+                .filter(|r| verification::agrees(&report, r))
+                .collect();
+            // Breaches are slashed from the bid deposits and refunded out of
+            // the escrow, so they are enforced before settling releases both.
+            for result in &accepted {
+                guarantees::enforce(&state.storage, &task, result, now).await?;
+            }
+            ledger::settle(&state.storage, &task, &accepted, now).await?;
+        }
+        AgreementStatus::Disagreed => ledger::release_escrow(&state.storage, &task, now).await?,
+    }
     state.storage.insert_result(stored_result).await?;
+    state.storage.update_task(&mut task).await?;
+    state.publish(
+        EventKind::ResultSubmitted,
+        &view.task_id,
+        Some(&view.agent_id),
+        &view,
+    );
+    if report.status == AgreementStatus::Agreed {
+        state.publish(
+            EventKind::TaskCompleted,
+            &task.id,
+            None,
+            &task_to_view(&task),
+        );
+    }
+
+    // Once settled, agreeing results count as delivered and dissenting ones
+    // as failures.
+    if report.status != AgreementStatus::Pending {
+        for result in &collected {
+            let outcome = if verification::agrees(&report, result) {
+                reputation::record_result(&state.storage, &task, result).await
+            } else {
+                reputation::record_failure(
+                    &state.storage,
+                    &task,
+                    &result.agent_id,
+                    result.created_at,
+                )
+                .await
+            };
+            if let Err(err) = outcome {
+                warn!("failed to update reputation for {}: {err}", result.agent_id);
+            }
+        }
+    }
 
     let mut correlation: Option<String> = None;
     #[cfg(feature = "chain-bridge")]
     {
         let correlation_id = Uuid::new_v4().to_string();
-        let result_hash = blake3::hash(&view.output_base64.as_bytes());
-        let result_hex = format!("0x{}", hex::encode(result_hash.as_bytes()));
-        let (task_chain_id, agent_chain_id) = if let Some(pool) = state.pg_pool.clone() {
-            let t_id: i64 = sqlx::query_scalar("SELECT chain_task_id FROM tasks WHERE id = $1")
-                .bind(&view.task_id)
-                .fetch_optional(&pool)
-                .await
-                .ok()
-                .flatten()
-                .unwrap_or(0);
-            let a_id: i64 = sqlx::query_scalar("SELECT chain_agent_id FROM agents WHERE id = $1")
-                .bind(&view.agent_id)
-                .fetch_optional(&pool)
-                .await
-                .ok()
-                .flatten()
-                .unwrap_or(0);
-            (t_id, a_id)
-        } else {
-            (0, 0)
-        };
+        let result_hex = format!("0x{}", hex::encode(result_hash));
+        let (task_chain_id, agent_chain_id) =
+            chain::lookup_chain_ids(state.pg_pool.as_ref(), &view.task_id, &view.agent_id).await;
         let payload_json = serde_json::json!({
             "task_id": task_chain_id,
             "agent_id": agent_chain_id,
@@ -588,21 +1315,288 @@ async fn submit_result(
         }
     }
 
-    Ok(Json(ResponseWithCorrelation {
+    Ok(ResponseWithCorrelation {
         correlation_id: correlation,
         data: view,
-    }))
+    })
 }
 
 async fn get_task_result(
     State(state): State<AppState>,
     Path(id): Path<String>,
 ) -> Result<Json<ResultView>, ApiError> {
+    let task = state.storage.get_task(&id).await?;
+
+    let results = state.storage.get_results_for_task(&id).await?;
+    let stored = verification::accepted_result(&task, &results)
+        .ok_or_else(|| ApiError::NotFound(format!("no accepted result for task {id}")))?;
+    Ok(Json(result_to_view(stored)))
+}
+
+async fn get_task_verification(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<VerificationView>, ApiError> {
+    let task = state.storage.get_task(&id).await?;
+    let results = state.storage.get_results_for_task(&id).await?;
+    Ok(Json(verification::agreement(&task, &results)))
+}
+
+async fn get_task_milestones(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<MilestoneView>>, ApiError> {
+    Ok(Json(milestones::progress(&state.storage, &id).await?))
+}
+
+async fn complete_milestone(
+    State(state): State<AppState>,
+    Path((id, milestone_id)): Path<(String, String)>,
+    Json(payload): Json<MilestoneCompletionRequest>,
+) -> Result<Json<MilestoneCompletionView>, ApiError> {
+    signing::authenticate(
+        &state.storage,
+        &payload.agent_id,
+        &payload.signed_message(&id, &milestone_id)?,
+        payload.signature.as_ref(),
+        state.require_signatures,
+    )
+    .await?;
+    let completion = milestones::complete(
+        &state.storage,
+        &id,
+        &milestone_id,
+        payload,
+        current_unix_timestamp(),
+    )
+    .await?;
+    Ok(Json(MilestoneCompletionView::from_stored(&completion)))
+}
+
+async fn review_milestone(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<MilestoneReviewRequest>,
+) -> Result<Json<MilestoneCompletionView>, ApiError> {
+    signing::authenticate(
+        &state.storage,
+        &payload.requester_id,
+        &payload.signed_message(&id),
+        payload.signature.as_ref(),
+        state.require_signatures,
+    )
+    .await?;
+    let completion =
+        milestones::review(&state.storage, &id, payload, current_unix_timestamp()).await?;
+    Ok(Json(MilestoneCompletionView::from_stored(&completion)))
+}
+
+async fn get_account_ledger(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<LedgerView>, ApiError> {
+    Ok(Json(ledger::account(&state.storage, &id).await?))
+}
+
+async fn fund_account(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<FundingRequest>,
+) -> Result<Json<LedgerView>, ApiError> {
+    Ok(Json(
+        ledger::fund(
+            &state.storage,
+            &id,
+            payload.amount,
+            current_unix_timestamp(),
+        )
+        .await?,
+    ))
+}
+
+async fn register_webhook(
+    State(state): State<AppState>,
+    Json(payload): Json<WebhookRegistrationRequest>,
+) -> Result<Json<WebhookView>, ApiError> {
+    signing::authenticate(
+        &state.storage,
+        &payload.requester_id,
+        &payload.signed_message(),
+        payload.signature.as_ref(),
+        state.require_signatures,
+    )
+    .await?;
+    let webhook = webhooks::register(&state.storage, payload, current_unix_timestamp()).await?;
+    // The secret is only ever returned here.
+    let mut view = WebhookView::from_stored(&webhook);
+    view.secret = Some(webhook.secret);
+    Ok(Json(view))
+}
+
+async fn list_webhooks(
+    State(state): State<AppState>,
+    Query(query): Query<WebhookQuery>,
+) -> Result<Json<Vec<WebhookView>>, ApiError> {
+    signing::authenticate(
+        &state.storage,
+        &query.requester_id,
+        &query.signed_message(None),
+        query.signature().as_ref(),
+        true,
+    )
+    .await?;
+    let webhooks = state
+        .storage
+        .get_webhooks_for_requester(&query.requester_id)
+        .await?;
+    Ok(Json(
+        webhooks.iter().map(WebhookView::from_stored).collect(),
+    ))
+}
+
+async fn get_webhook_deliveries(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<WebhookQuery>,
+) -> Result<Json<Vec<StoredWebhookDelivery>>, ApiError> {
+    signing::authenticate(
+        &state.storage,
+        &query.requester_id,
+        &query.signed_message(Some(&id)),
+        query.signature().as_ref(),
+        true,
+    )
+    .await?;
+    let webhook = state.storage.get_webhook(&id).await?;
+    if webhook.requester_id != query.requester_id {
+        return Err(ApiError::Forbidden(format!(
+            "webhook {id} belongs to another requester"
+        )));
+    }
+    Ok(Json(state.storage.get_webhook_deliveries(&id).await?))
+}
+
+/// Server-sent event stream of task lifecycle events. A `Last-Event-ID`
+/// header, as sent by reconnecting `EventSource` clients, takes precedence
+/// over the `last_event_id` query parameter.
+async fn stream_events(
+    State(state): State<AppState>,
+    Query(mut query): Query<EventQuery>,
+    headers: HeaderMap,
+) -> Sse<impl Stream<Item = Result<SseEvent, Infallible>>> {
+    if let Some(last_id) = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+    {
+        query.last_event_id = Some(last_id);
+    }
+    let mut subscription = state.events.subscribe(query);
+    let (tx, rx) = mpsc::channel(16);
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                event = subscription.next() => match event {
+                    Some(event) => {
+                        if tx.send(event).await.is_err() {
+                            break;
+                        }
+                    }
+                    None => break,
+                },
+                // Stop as soon as the client goes away, even when idle.
+                _ = tx.closed() => break,
+            }
+        }
+    });
+    let stream = ReceiverStream::new(rx).map(|event| {
+        let sse = SseEvent::default()
+            .id(event.id.to_string())
+            .event(event.kind.as_str());
+        Ok(sse.json_data(&event).unwrap_or_default())
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// WebSocket variant of [`stream_events`]: each event is sent as a JSON text
+/// message.
+async fn stream_events_ws(
+    State(state): State<AppState>,
+    Query(query): Query<EventQuery>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let mut subscription = state.events.subscribe(query);
+    ws.on_upgrade(move |mut socket: WebSocket| async move {
+        loop {
+            tokio::select! {
+                event = subscription.next() => {
+                    let Some(event) = event else { break };
+                    let Ok(text) = serde_json::to_string(&event) else { continue };
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                message = socket.recv() => match message {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+    })
+}
+
+async fn file_dispute(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<DisputeFilingRequest>,
+) -> Result<Json<DisputeView>, ApiError> {
+    signing::authenticate(
+        &state.storage,
+        &payload.requester_id,
+        &payload.signed_message(&id)?,
+        payload.signature.as_ref(),
+        state.require_signatures,
+    )
+    .await?;
+    let dispute = disputes::file(&state.storage, &id, payload, current_unix_timestamp()).await?;
+    Ok(Json(DisputeView::from_stored(&dispute)))
+}
+
+async fn get_task_disputes(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<DisputeView>>, ApiError> {
     // Ensure the task exists.
     let _ = state.storage.get_task(&id).await?;
 
-    let stored = state.storage.get_result_for_task(&id).await?;
-    Ok(Json(result_to_view(&stored)))
+    let disputes = state.storage.get_disputes_for_task(&id).await?;
+    Ok(Json(
+        disputes.iter().map(DisputeView::from_stored).collect(),
+    ))
+}
+
+async fn get_dispute(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<DisputeView>, ApiError> {
+    let dispute = state.storage.get_dispute(&id).await?;
+    Ok(Json(DisputeView::from_stored(&dispute)))
+}
+
+async fn resolve_dispute(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<DisputeResolutionRequest>,
+) -> Result<Json<DisputeView>, ApiError> {
+    let dispute = disputes::resolve(
+        &state.storage,
+        &state.arbiters,
+        &id,
+        payload,
+        current_unix_timestamp(),
+    )
+    .await?;
+    Ok(Json(DisputeView::from_stored(&dispute)))
 }
 
 async fn get_dashboard(State(state): State<AppState>) -> Result<Json<DashboardView>, ApiError> {
@@ -718,16 +1712,58 @@ async fn execute_task_local(
     Path(id): Path<String>,
 ) -> Result<Json<ResultView>, ApiError> {
     let mut task = state.storage.get_task(&id).await?;
+    task.ensure_not_overdue(current_unix_timestamp())?;
+    if task.redundancy() > 1 {
+        return Err(ApiError::BadRequest(format!(
+            "task {id} requires {} independent executors",
+            task.redundancy()
+        )));
+    }
 
-    let stored_result = execute_and_build_result(&state.engine, &mut task, "local-echo".into())?;
+    // The local engine acts as the executing agent: an unallocated task is
+    // allocated to it directly, then walked through `executing`.
+    if task.status.is_open_for_bids() {
+        task.transition_to(TaskStatus::Allocated)?;
+    }
+    task.transition_to(TaskStatus::Executing)?;
+    state.storage.update_task(&mut task).await?;
+
+    let mut stored_result =
+        match execute_and_build_result(&state.engine, &mut task, "local-echo".into()) {
+            Ok(result) => result,
+            Err(err) => {
+                task.transition_to(TaskStatus::Failed)?;
+                state.storage.update_task(&mut task).await?;
+                ledger::release_escrow(&state.storage, &task, current_unix_timestamp()).await?;
+                return Err(err);
+            }
+        };
+    match verification::verify_result(&state.verifiers, &task, &stored_result.result) {
+        Ok(outcome) => stored_result.quality = Some(outcome.confidence),
+        Err(err) => {
+            task.transition_to(TaskStatus::Failed)?;
+            state.storage.update_task(&mut task).await?;
+            ledger::release_escrow(&state.storage, &task, current_unix_timestamp()).await?;
+            return Err(err);
+        }
+    }
 
-    // Mark task as completed; `execute_and_build_result` already updated it via
-    // `StoredResult::from_submission`.
-    task.status = TaskStatus::Completed;
-    state.storage.upsert_task(task).await?;
+    task.transition_to(TaskStatus::Completed)?;
+    state.storage.update_task(&mut task).await?;
+    // The local engine is not paid; the whole escrow goes back.
+    ledger::release_escrow(&state.storage, &task, current_unix_timestamp()).await?;
+    state.publish(
+        EventKind::TaskCompleted,
+        &task.id,
+        None,
+        &task_to_view(&task),
+    );
 
     let view = result_to_view(&stored_result);
 
+    if let Err(err) = reputation::record_result(&state.storage, &task, &stored_result).await {
+        warn!("failed to update reputation for {}: {err}", view.agent_id);
+    }
     state.storage.insert_result(stored_result).await?;
 
     Ok(Json(view))
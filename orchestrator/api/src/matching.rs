@@ -0,0 +1,113 @@
+//! Eligibility of registered agents for a task.
+//!
+//! Task requirements are checked against each agent's registered
+//! capabilities and resources through `ainur_core::AgentProfile`. Tasks
+//! without requirements stay open to every agent, registered or not.
+
+use std::sync::Arc;
+
+use ainur_core::{eligible_agents as eligible_profiles, CoreError};
+
+use crate::error::ApiError;
+use crate::model::{AgentRegistrationRequest, StoredTask};
+use crate::storage::Storage;
+
+/// Registered agents able to execute `task`.
+pub async fn eligible_agents(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+) -> Result<Vec<AgentRegistrationRequest>, ApiError> {
+    let agents = storage.list_agents().await?;
+    let profiles: Vec<_> = agents.iter().map(|a| a.profile()).collect();
+    let eligible: Vec<_> = eligible_profiles(&task.task, &profiles)
+        .into_iter()
+        .map(|p| p.agent_id)
+        .collect();
+    Ok(agents
+        .into_iter()
+        .zip(profiles)
+        .filter(|(_, profile)| eligible.contains(&profile.agent_id))
+        .map(|(agent, _)| agent)
+        .collect())
+}
+
+/// Reject `agent_id` with `CoreError::MissingCapability` unless it may take
+/// `task`.
+pub async fn ensure_eligible(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    agent_id: &str,
+) -> Result<(), ApiError> {
+    let requirements = &task.task.requirements;
+    if requirements.is_unconstrained() {
+        return Ok(());
+    }
+    let agent = match storage.get_agent(agent_id).await {
+        Ok(agent) => agent,
+        Err(ApiError::NotFound(_)) => {
+            return Err(CoreError::MissingCapability(format!(
+                "agent {agent_id} must register its capabilities to bid on task {}",
+                task.id
+            ))
+            .into())
+        }
+        Err(err) => return Err(err),
+    };
+    agent.profile().check(requirements)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{TaskRequirements, TaskSubmissionRequest};
+    use crate::storage::InMemoryStorage;
+    use ainur_core::{Capability, TEEType};
+
+    #[tokio::test]
+    async fn filters_agents_by_registered_capabilities() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let mut tee = AgentRegistrationRequest::new("tee-agent", "tee");
+        tee.capabilities = vec![Capability::TEE(TEEType::SGX)];
+        storage.register_agent(tee).await.unwrap();
+        storage
+            .register_agent(AgentRegistrationRequest::new("plain-agent", "plain"))
+            .await
+            .unwrap();
+
+        let task = StoredTask::from_submission(TaskSubmissionRequest {
+            client_task_id: None,
+            requester_id: "requester".into(),
+            description: "needs a TEE".into(),
+            task_type: "echo".into(),
+            input_base64: String::new(),
+            max_budget: 100,
+            deadline: 0,
+            bid_window_secs: None,
+            domain: None,
+            requirements: TaskRequirements {
+                capabilities: vec![Capability::TEE(TEEType::SGX)],
+                ..Default::default()
+            },
+            output_format: None,
+            verification_level: None,
+            payment_schedule: None,
+            milestones: Vec::new(),
+            escrow_required: None,
+            signature: None,
+        })
+        .unwrap();
+
+        let eligible = eligible_agents(&storage, &task).await.unwrap();
+        assert_eq!(eligible.len(), 1);
+        assert_eq!(eligible[0].id, "tee-agent");
+
+        assert!(ensure_eligible(&storage, &task, "tee-agent").await.is_ok());
+        for agent in ["plain-agent", "unregistered"] {
+            assert!(matches!(
+                ensure_eligible(&storage, &task, agent).await,
+                Err(ApiError::BadRequest(msg)) if msg.contains("Missing capability")
+            ));
+        }
+    }
+}
@@ -0,0 +1,335 @@
+//! Milestone progress and partial payments.
+//!
+//! Tasks paid on a `PaymentSchedule::Milestone` schedule can be followed
+//! while they run: allocated agents report each milestone complete with
+//! partial output, and the requester approves or rejects the report.
+//! Approval pays the agent its tranche of the milestone, scaled to its
+//! clearing payment; a rejected milestone may be reported again. Tranches
+//! still unpaid when the final result is accepted are settled with it.
+
+use std::sync::Arc;
+
+use ainur_core::{rescale_tranches, Milestone, PaymentSchedule};
+
+use crate::error::ApiError;
+use crate::ledger;
+use crate::model::{
+    agent_key, milestone_id, MilestoneCompletionRequest, MilestoneCompletionStatus,
+    MilestoneCompletionView, MilestoneReviewRequest, MilestoneView, StoredMilestoneCompletion,
+    StoredTask, TaskStatus,
+};
+use crate::storage::Storage;
+
+/// Record `request` as the completion of `milestone` on `task_id`.
+pub async fn complete(
+    storage: &Arc<dyn Storage>,
+    task_id: &str,
+    milestone: &str,
+    request: MilestoneCompletionRequest,
+    now: u64,
+) -> Result<StoredMilestoneCompletion, ApiError> {
+    let mut task = storage.get_task(task_id).await?;
+    let tranches = tranches(&task);
+    if !tranches.iter().any(|(m, _)| milestone_id(m) == milestone) {
+        return Err(ApiError::NotFound(format!(
+            "milestone {milestone} not found on task {task_id}"
+        )));
+    }
+    task.ensure_not_overdue(now)?;
+
+    let allocation = storage
+        .get_allocations_for_task(task_id)
+        .await?
+        .into_iter()
+        .find(|a| a.agent_id == request.agent_id)
+        .ok_or_else(|| {
+            ApiError::BadRequest(format!(
+                "agent {} is not allocated to task {task_id}",
+                request.agent_id
+            ))
+        })?;
+    let reported = storage
+        .get_milestone_completions_for_task(task_id)
+        .await?
+        .into_iter()
+        .any(|c| {
+            c.milestone_id == milestone
+                && c.agent_id == request.agent_id
+                && c.status != MilestoneCompletionStatus::Rejected
+        });
+    if reported {
+        return Err(ApiError::BadRequest(format!(
+            "agent {} already reported milestone {milestone}",
+            request.agent_id
+        )));
+    }
+    // Reporting progress moves an allocated task to `executing`.
+    let started = task.status != TaskStatus::Executing;
+    if started {
+        task.transition_to(TaskStatus::Executing)?;
+    }
+
+    let tranche = rescale_tranches(&tranches, allocation.payment)
+        .into_iter()
+        .find(|(m, _)| milestone_id(m) == milestone)
+        .map(|(_, amount)| amount)
+        .unwrap_or(0);
+    let completion =
+        StoredMilestoneCompletion::from_request(request, task_id, milestone, tranche, now)?;
+    storage
+        .upsert_milestone_completion(completion.clone())
+        .await?;
+    if started {
+        storage.update_task(&mut task).await?;
+    }
+    Ok(completion)
+}
+
+/// Approve or reject the pending completion `completion_id`, paying its
+/// tranche when approved.
+pub async fn review(
+    storage: &Arc<dyn Storage>,
+    completion_id: &str,
+    request: MilestoneReviewRequest,
+    now: u64,
+) -> Result<StoredMilestoneCompletion, ApiError> {
+    let mut completion = storage.get_milestone_completion(completion_id).await?;
+    let task = storage.get_task(&completion.task_id).await?;
+    if agent_key(&request.requester_id) != task.task.requester {
+        return Err(ApiError::Forbidden(format!(
+            "only the requester of task {} may review its milestones",
+            task.id
+        )));
+    }
+    if completion.status != MilestoneCompletionStatus::Pending {
+        return Err(ApiError::BadRequest(format!(
+            "milestone completion {completion_id} was already reviewed"
+        )));
+    }
+
+    completion.status = if request.approved {
+        MilestoneCompletionStatus::Approved
+    } else {
+        MilestoneCompletionStatus::Rejected
+    };
+    completion.notes = request.notes;
+    completion.reviewed_at = Some(now);
+    storage
+        .upsert_milestone_completion(completion.clone())
+        .await?;
+
+    if request.approved {
+        let approved: u128 = storage
+            .get_milestone_completions_for_task(&task.id)
+            .await?
+            .iter()
+            .filter(|c| {
+                c.agent_id == completion.agent_id && c.status == MilestoneCompletionStatus::Approved
+            })
+            .map(|c| c.tranche)
+            .sum();
+        let allocation = storage
+            .get_allocations_for_task(&task.id)
+            .await?
+            .into_iter()
+            .find(|a| a.agent_id == completion.agent_id)
+            .ok_or_else(|| {
+                ApiError::Internal(format!(
+                    "task {}: no allocation for agent {}",
+                    task.id, completion.agent_id
+                ))
+            })?;
+        ledger::pay_milestones(storage, &task, &allocation, approved, now).await?;
+    }
+    Ok(completion)
+}
+
+/// Milestones of `task_id` with their completions; empty for tasks on other
+/// payment schedules.
+pub async fn progress(
+    storage: &Arc<dyn Storage>,
+    task_id: &str,
+) -> Result<Vec<MilestoneView>, ApiError> {
+    let task = storage.get_task(task_id).await?;
+    let completions = storage.get_milestone_completions_for_task(task_id).await?;
+    Ok(tranches(&task)
+        .into_iter()
+        .map(|(milestone, amount)| {
+            let id = milestone_id(&milestone);
+            MilestoneView {
+                completions: completions
+                    .iter()
+                    .filter(|c| c.milestone_id == id)
+                    .map(MilestoneCompletionView::from_stored)
+                    .collect(),
+                id,
+                description: milestone.description,
+                criteria: milestone.criteria,
+                amount,
+            }
+        })
+        .collect())
+}
+
+fn tranches(task: &StoredTask) -> Vec<(Milestone, u128)> {
+    match &task.task.budget.payment_schedule {
+        PaymentSchedule::Milestone(tranches) => tranches.clone(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{
+        current_unix_timestamp, MilestoneSubmission, StoredAllocation, TaskSubmissionRequest,
+    };
+    use crate::storage::InMemoryStorage;
+    use base64::{engine::general_purpose, Engine as _};
+    use uuid::Uuid;
+
+    async fn allocated_task(storage: &Arc<dyn Storage>) -> StoredTask {
+        let milestone = |description: &str, amount| MilestoneSubmission {
+            description: description.into(),
+            criteria: String::new(),
+            amount,
+        };
+        let mut task = StoredTask::from_submission(TaskSubmissionRequest {
+            client_task_id: None,
+            requester_id: "requester".into(),
+            description: "train a model".into(),
+            task_type: "training".into(),
+            input_base64: String::new(),
+            max_budget: 1_000_000,
+            deadline: 0,
+            bid_window_secs: None,
+            domain: None,
+            requirements: Default::default(),
+            output_format: None,
+            verification_level: None,
+            payment_schedule: None,
+            milestones: vec![milestone("epoch 1", 400_000), milestone("epoch 2", 600_000)],
+            escrow_required: None,
+            signature: None,
+        })
+        .unwrap();
+        task.transition_to(TaskStatus::Allocated).unwrap();
+        storage.insert_task(task.clone()).await.unwrap();
+        ledger::fund(storage, "user:requester", 1_000_000, 0)
+            .await
+            .unwrap();
+        ledger::lock_escrow(storage, &task, 0).await.unwrap();
+        storage
+            .insert_allocation(StoredAllocation {
+                id: Uuid::new_v4().to_string(),
+                task_id: task.id.clone(),
+                agent_id: "agent".into(),
+                bid_id: Uuid::new_v4().to_string(),
+                payment: 500_000,
+                social_welfare: 0,
+                allocated_at: 0,
+            })
+            .await
+            .unwrap();
+        task
+    }
+
+    fn report(agent: &str) -> MilestoneCompletionRequest {
+        MilestoneCompletionRequest {
+            agent_id: agent.into(),
+            output_base64: general_purpose::STANDARD.encode("checkpoint"),
+            signature: None,
+        }
+    }
+
+    fn verdict(requester: &str, approved: bool) -> MilestoneReviewRequest {
+        MilestoneReviewRequest {
+            requester_id: requester.into(),
+            approved,
+            notes: None,
+            signature: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn approved_milestones_release_their_tranche() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let task = allocated_task(&storage).await;
+        let now = current_unix_timestamp();
+        let milestones = progress(&storage, &task.id).await.unwrap();
+        assert_eq!(milestones.len(), 2);
+        let first = milestones[0].id.clone();
+
+        assert!(
+            complete(&storage, &task.id, &first, report("stranger"), now)
+                .await
+                .is_err()
+        );
+        let completion = complete(&storage, &task.id, &first, report("agent"), now)
+            .await
+            .unwrap();
+        // The tranche is scaled to the agent's clearing payment.
+        assert_eq!(completion.tranche, 200_000);
+        assert_eq!(
+            storage.get_task(&task.id).await.unwrap().status,
+            TaskStatus::Executing
+        );
+        assert!(complete(&storage, &task.id, &first, report("agent"), now)
+            .await
+            .is_err());
+
+        assert!(matches!(
+            review(&storage, &completion.id, verdict("agent", true), now).await,
+            Err(ApiError::Forbidden(_))
+        ));
+        review(&storage, &completion.id, verdict("requester", true), now)
+            .await
+            .unwrap();
+        let agent = ledger::account(&storage, "user:agent").await.unwrap();
+        assert_eq!(
+            agent.balance,
+            200_000
+                - ledger::fee(ainur_core::TransactionType::ResultSubmission, 200_000).unwrap()
+                    as i128
+        );
+        assert!(
+            review(&storage, &completion.id, verdict("requester", true), now)
+                .await
+                .is_err()
+        );
+
+        let progress = progress(&storage, &task.id).await.unwrap();
+        assert_eq!(
+            progress[0].completions[0].status,
+            MilestoneCompletionStatus::Approved
+        );
+        assert!(progress[1].completions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn rejected_milestones_can_be_reported_again() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let task = allocated_task(&storage).await;
+        let now = current_unix_timestamp();
+        let second = progress(&storage, &task.id).await.unwrap()[1].id.clone();
+
+        let completion = complete(&storage, &task.id, &second, report("agent"), now)
+            .await
+            .unwrap();
+        review(&storage, &completion.id, verdict("requester", false), now)
+            .await
+            .unwrap();
+        assert_eq!(
+            ledger::account(&storage, "user:agent")
+                .await
+                .unwrap()
+                .balance,
+            0
+        );
+
+        let retry = complete(&storage, &task.id, &second, report("agent"), now)
+            .await
+            .unwrap();
+        assert_eq!(retry.tranche, 300_000);
+    }
+}
@@ -1,31 +1,440 @@
 use ainur_core::{
-    AgentId, Bid, Budget, Requirements, Task, TaskResult, TaskSpec, VerificationLevel,
+    bid_commitment, constants, hash_of, AgentId, AgentProfile, AgentReputation, AgentResources,
+    Bid, Budget, Capability, CoreError, DisputeResolution, Domain, Guarantee, HardwareType,
+    Milestone, OutputFormat, PaymentSchedule, RefundPolicy, Reputation, ReputationEvent,
+    Requirements, SignedMessage, SignedRequest, TEEType, Task, TaskResult, TaskSpec,
+    VerificationLevel, Violation, ZKSystem, NONCE_LENGTH,
 };
 use base64::{engine::general_purpose, Engine as _};
+use ed25519_dalek::{SigningKey, VerifyingKey};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::error::ApiError;
+use crate::ledger::user_account;
 
 /// Client‑facing payload for registering an agent with the orchestrator.
 ///
-/// For now this contains only an opaque identifier and a human‑readable label.
-/// Additional capability and reputation fields will be introduced once we
-/// connect this surface to the Temporal chain and reputation subsystem.
+/// Everything beyond `id` and `label` is optional so existing clients keep
+/// working; agents registered that way advertise no capabilities.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentRegistrationRequest {
     /// Stable agent identifier (client‑defined).
     pub id: String,
     /// Human‑readable label for observability.
     pub label: String,
+    /// Structured capabilities (TEE, ZK proof systems, models, hardware, ...).
+    #[serde(default)]
+    pub capabilities: Vec<Capability>,
+    /// Domains the agent offers services in.
+    #[serde(default)]
+    pub domains: Vec<Domain>,
+    /// Hex-encoded 32-byte ed25519 public key used to authenticate the agent.
+    #[serde(default)]
+    pub public_key: Option<String>,
+    /// Service endpoints (`http(s)://` or `ws(s)://` URLs) the agent accepts
+    /// work on.
+    #[serde(default)]
+    pub endpoints: Vec<String>,
+    /// Strongest verification level the agent can provide.
+    #[serde(default = "default_agent_verification")]
+    pub verification_level: VerificationLevel,
+    /// Opaque attestation report backing TEE capabilities.
+    #[serde(default)]
+    pub attestation: Option<String>,
+    /// Resources available to the agent, matched against task requirements.
+    #[serde(default)]
+    pub resources: AgentResources,
+    /// Signature over `SignedMessage::RegisterAgent` by the key already
+    /// registered for `id`. Not stored.
+    #[serde(default, skip_serializing)]
+    pub signature: Option<RequestSignature>,
+}
+
+fn default_agent_verification() -> VerificationLevel {
+    VerificationLevel::BestEffort
+}
+
+impl AgentRegistrationRequest {
+    /// Registration with no capabilities beyond an identifier and label.
+    pub fn new(id: impl Into<String>, label: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            label: label.into(),
+            capabilities: Vec::new(),
+            domains: Vec::new(),
+            public_key: None,
+            endpoints: Vec::new(),
+            verification_level: default_agent_verification(),
+            attestation: None,
+            resources: AgentResources::default(),
+            signature: None,
+        }
+    }
+
+    /// Canonical content signed by the agent's current key.
+    pub fn signed_message(&self) -> Result<SignedMessage, ApiError> {
+        let public_key = self
+            .public_key
+            .as_deref()
+            .map(|key| parse_hex_32(key, "public_key"))
+            .transpose()?;
+        Ok(SignedMessage::RegisterAgent {
+            agent_id: self.id.clone(),
+            label: self.label.clone(),
+            capabilities: self.capabilities.clone(),
+            domains: self.domains.clone(),
+            public_key,
+            endpoints: self.endpoints.clone(),
+            verification_level: self.verification_level.clone(),
+            attestation: self.attestation.clone(),
+            resources: self.resources.clone(),
+        })
+    }
+
+    /// What the agent offers for capability matching.
+    pub fn profile(&self) -> AgentProfile {
+        AgentProfile {
+            agent_id: agent_key(&self.id),
+            capabilities: self.capabilities.clone(),
+            resources: self.resources.clone(),
+        }
+    }
+
+    /// Check the registration before it is stored.
+    pub fn validate(&self) -> Result<(), ApiError> {
+        if self.id.trim().is_empty() {
+            return Err(ApiError::BadRequest("agent id must not be empty".into()));
+        }
+        if self.label.trim().is_empty() {
+            return Err(ApiError::BadRequest("agent label must not be empty".into()));
+        }
+        if self.capabilities.len() > MAX_AGENT_CAPABILITIES {
+            return Err(ApiError::BadRequest(format!(
+                "at most {MAX_AGENT_CAPABILITIES} capabilities may be registered"
+            )));
+        }
+        if self.endpoints.len() > MAX_AGENT_ENDPOINTS {
+            return Err(ApiError::BadRequest(format!(
+                "at most {MAX_AGENT_ENDPOINTS} endpoints may be registered"
+            )));
+        }
+        for endpoint in &self.endpoints {
+            let valid_scheme = ["http://", "https://", "ws://", "wss://"]
+                .iter()
+                .any(|scheme| endpoint.starts_with(scheme));
+            if !valid_scheme || endpoint.len() > 256 {
+                return Err(ApiError::BadRequest(format!(
+                    "invalid agent endpoint {endpoint}"
+                )));
+            }
+        }
+        let has_tee = self
+            .capabilities
+            .iter()
+            .any(|c| matches!(c, Capability::TEE(_)));
+        if matches!(
+            self.verification_level,
+            VerificationLevel::TEEAttested | VerificationLevel::TEEWithZK
+        ) && !has_tee
+        {
+            return Err(ApiError::BadRequest(
+                "TEE verification requires a TEE capability".into(),
+            ));
+        }
+        self.verifying_key()?;
+        Ok(())
+    }
+
+    /// Decoded ed25519 public key, if one was registered.
+    pub fn verifying_key(&self) -> Result<Option<VerifyingKey>, ApiError> {
+        self.public_key.as_deref().map(parse_public_key).transpose()
+    }
+
+    /// Capability strings as carried by `AgentRegistry::register_agent`.
+    /// Domains are advertised as `domain:<name>` entries.
+    pub fn capability_tags(&self) -> Vec<String> {
+        self.capabilities
+            .iter()
+            .map(capability_tag)
+            .chain(
+                self.domains
+                    .iter()
+                    .map(|d| format!("domain:{}", domain_name(d))),
+            )
+            .collect()
+    }
+}
+
+/// Payload for registering a requester's public key, or rotating it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequesterRegistrationRequest {
+    pub id: String,
+    /// Hex ed25519 key the requester's requests are verified against.
+    pub public_key: String,
+    /// Signature over `SignedMessage::RegisterRequester`, made with the key
+    /// on record or, on a first registration, with `public_key` itself.
+    #[serde(default)]
+    pub signature: Option<RequestSignature>,
+}
+
+impl RequesterRegistrationRequest {
+    /// Canonical content signed by the requester.
+    pub fn signed_message(&self) -> Result<SignedMessage, ApiError> {
+        Ok(SignedMessage::RegisterRequester {
+            requester_id: self.id.clone(),
+            public_key: parse_hex_32(&self.public_key, "public_key")?,
+        })
+    }
+
+    pub fn verifying_key(&self) -> Result<VerifyingKey, ApiError> {
+        parse_public_key(&self.public_key)
+    }
+}
+
+/// A requester's registered public key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredRequester {
+    pub id: String,
+    pub public_key: String,
+    pub registered_at: u64,
+}
+
+impl StoredRequester {
+    pub fn from_registration(request: RequesterRegistrationRequest, now: u64) -> Self {
+        Self {
+            id: request.id,
+            public_key: request.public_key,
+            registered_at: now,
+        }
+    }
+
+    pub fn verifying_key(&self) -> Result<VerifyingKey, ApiError> {
+        parse_public_key(&self.public_key)
+    }
+}
+
+/// Upper bound on capabilities per agent, matching the chain's bounded vec.
+pub const MAX_AGENT_CAPABILITIES: usize = 32;
+
+/// Upper bound on service endpoints per agent.
+pub const MAX_AGENT_ENDPOINTS: usize = 8;
+
+/// Upper bound on milestones per task.
+pub const MAX_TASK_MILESTONES: usize = 32;
+
+/// Upper bound on guarantees per bid.
+pub const MAX_BID_GUARANTEES: usize = 8;
+
+/// Flat `kind:detail` encoding of a capability, e.g. `tee:sgx` or
+/// `hardware:nvidia_gpu:a100`.
+pub fn capability_tag(capability: &Capability) -> String {
+    match capability {
+        Capability::TEE(tee) => format!(
+            "tee:{}",
+            match tee {
+                TEEType::SGX => "sgx",
+                TEEType::SEV => "sev",
+                TEEType::TrustZone => "trustzone",
+                TEEType::Other(name) => name,
+            }
+        ),
+        Capability::ZKProof(system) => format!(
+            "zk:{}",
+            match system {
+                ZKSystem::Groth16 => "groth16",
+                ZKSystem::PLONK => "plonk",
+                ZKSystem::STARK => "stark",
+                ZKSystem::Bulletproofs => "bulletproofs",
+                ZKSystem::Other(name) => name,
+            }
+        ),
+        Capability::Model(model) => format!("model:{model}"),
+        Capability::Hardware(hardware) => {
+            let (kind, model) = match hardware {
+                HardwareType::NvidiaGPU(m) => ("nvidia_gpu", m),
+                HardwareType::AmdGPU(m) => ("amd_gpu", m),
+                HardwareType::TPU(m) => ("tpu", m),
+                HardwareType::FPGA(m) => ("fpga", m),
+                HardwareType::ASIC(m) => ("asic", m),
+                HardwareType::Other(m) => ("other", m),
+            };
+            format!("hardware:{kind}:{model}")
+        }
+        Capability::Location(location) => format!("location:{location}"),
+        Capability::Custom(key, value) => format!("custom:{key}={value}"),
+    }
+}
+
+/// Name of a verification level as understood by the chain pallets.
+pub fn chain_verification_level(level: &VerificationLevel) -> &'static str {
+    match level {
+        VerificationLevel::None | VerificationLevel::BestEffort => "best_effort",
+        VerificationLevel::Consensus(_) => "redundant",
+        VerificationLevel::TEEAttested | VerificationLevel::TEEWithZK => "tee",
+        VerificationLevel::ZKProof => "zksnark",
+    }
 }
 
-/// Minimal status enum for tasks managed by the orchestrator.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+/// Lifecycle of a task managed by the orchestrator.
+///
+/// The happy path is `Open -> Bidding -> Allocated -> Executing -> Completed`.
+/// Unallocated tasks may be cancelled or expire, allocated tasks may fail or
+/// expire, and a completed task may be disputed. Transitions are enforced by
+/// [`StoredTask::transition_to`]; handlers and workers should not assign
+/// `StoredTask::status` directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum TaskStatus {
-    Pending,
+    /// Accepting bids; no bid received yet. Rows written before the lifecycle
+    /// was introduced used `pending` for this state.
+    #[serde(alias = "pending")]
+    Open,
+    /// At least one bid has been received and the bid window is still open.
+    Bidding,
+    /// A winning agent has been selected.
+    Allocated,
+    /// The allocated agent is executing the task.
+    Executing,
+    /// A result was accepted.
     Completed,
+    /// Execution failed or the allocated agent missed the deadline.
+    Failed,
+    /// The deadline passed before the task was allocated.
+    Expired,
+    /// Withdrawn by the requester before allocation.
+    Cancelled,
+    /// The accepted result is being contested.
+    Disputed,
+}
+
+impl TaskStatus {
+    /// Stable string representation, shared by the JSON surface and the
+    /// Postgres `tasks.status` column.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskStatus::Open => "open",
+            TaskStatus::Bidding => "bidding",
+            TaskStatus::Allocated => "allocated",
+            TaskStatus::Executing => "executing",
+            TaskStatus::Completed => "completed",
+            TaskStatus::Failed => "failed",
+            TaskStatus::Expired => "expired",
+            TaskStatus::Cancelled => "cancelled",
+            TaskStatus::Disputed => "disputed",
+        }
+    }
+
+    /// Parse the representation produced by [`TaskStatus::as_str`]. The legacy
+    /// `pending` value maps to [`TaskStatus::Open`].
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "open" | "pending" => Some(TaskStatus::Open),
+            "bidding" => Some(TaskStatus::Bidding),
+            "allocated" => Some(TaskStatus::Allocated),
+            "executing" => Some(TaskStatus::Executing),
+            "completed" => Some(TaskStatus::Completed),
+            "failed" => Some(TaskStatus::Failed),
+            "expired" => Some(TaskStatus::Expired),
+            "cancelled" => Some(TaskStatus::Cancelled),
+            "disputed" => Some(TaskStatus::Disputed),
+            _ => None,
+        }
+    }
+
+    /// Whether the task still accepts bids.
+    pub fn is_open_for_bids(&self) -> bool {
+        matches!(self, TaskStatus::Open | TaskStatus::Bidding)
+    }
+
+    /// Whether no further transitions are possible.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            TaskStatus::Failed | TaskStatus::Expired | TaskStatus::Cancelled
+        )
+    }
+
+    /// Whether moving from `self` to `next` is a legal lifecycle step.
+    pub fn can_transition_to(&self, next: TaskStatus) -> bool {
+        use TaskStatus::*;
+        matches!(
+            (self, next),
+            (Open, Bidding | Allocated | Cancelled | Expired)
+                | (Bidding, Bidding | Allocated | Cancelled | Expired)
+                | (Allocated, Executing | Failed | Expired)
+                | (Executing, Completed | Failed | Expired)
+                | (Completed, Disputed)
+                | (Disputed, Completed | Failed)
+        )
+    }
+
+    /// Validate a transition, returning the new status on success.
+    pub fn transition_to(self, next: TaskStatus) -> Result<TaskStatus, CoreError> {
+        if self.can_transition_to(next) {
+            Ok(next)
+        } else {
+            Err(CoreError::InvalidStateTransition {
+                from: self.as_str().to_string(),
+                to: next.as_str().to_string(),
+            })
+        }
+    }
+}
+
+impl std::fmt::Display for TaskStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Signature over a request's `SignedMessage`, bound to a single-use nonce
+/// and an expiry as an `ainur_core::SignedRequest`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestSignature {
+    /// Hex-encoded 16-byte nonce the signer has not used before.
+    pub nonce: String,
+    /// Unix time after which the signature is rejected.
+    pub expires_at: u64,
+    /// Hex-encoded ed25519 signature over `SignedRequest::signing_bytes()`.
+    pub value: String,
+}
+
+impl RequestSignature {
+    /// Sign `message` with `key`.
+    pub fn sign(
+        message: SignedMessage,
+        nonce: [u8; NONCE_LENGTH],
+        expires_at: u64,
+        key: &SigningKey,
+    ) -> Self {
+        let request = SignedRequest {
+            message,
+            nonce,
+            expires_at,
+        };
+        Self {
+            nonce: hex::encode(nonce),
+            expires_at,
+            value: hex::encode(request.sign(key)),
+        }
+    }
+
+    /// The signed request for `message`.
+    pub fn request(&self, message: SignedMessage) -> Result<SignedRequest, ApiError> {
+        let raw = self.nonce.strip_prefix("0x").unwrap_or(&self.nonce);
+        let nonce = hex::decode(raw)
+            .ok()
+            .and_then(|bytes| <[u8; NONCE_LENGTH]>::try_from(bytes).ok())
+            .ok_or_else(|| {
+                ApiError::Unauthorized(format!("nonce must be {NONCE_LENGTH} hex-encoded bytes"))
+            })?;
+        Ok(SignedRequest {
+            message,
+            nonce,
+            expires_at: self.expires_at,
+        })
+    }
 }
 
 /// Payload for submitting a task into the coordination layer.
@@ -48,6 +457,110 @@ pub struct TaskSubmissionRequest {
     pub max_budget: u128,
     /// Deadline as a Unix timestamp (seconds).
     pub deadline: u64,
+    /// Length of the bidding window in seconds; defaults to
+    /// `constants::auction::DEFAULT_BID_DURATION`.
+    #[serde(default)]
+    pub bid_window_secs: Option<u64>,
+    /// Domain of expertise the task belongs to; feeds agent specializations.
+    #[serde(default)]
+    pub domain: Option<Domain>,
+    /// Resources and capabilities an agent needs to bid on the task.
+    #[serde(default)]
+    pub requirements: TaskRequirements,
+    /// Expected output format; `Structured` carries a JSON schema that
+    /// results are checked against. Defaults to `Binary`.
+    #[serde(default)]
+    pub output_format: Option<OutputFormat>,
+    /// How results must be verified before they are accepted. Defaults to
+    /// `BestEffort`.
+    #[serde(default)]
+    pub verification_level: Option<VerificationLevel>,
+    /// How the winning agents are paid. Defaults to `OnCompletion`;
+    /// `Milestone` tranches must add up to `max_budget`.
+    #[serde(default)]
+    pub payment_schedule: Option<PaymentSchedule>,
+    /// Shorthand for a `Milestone` payment schedule with generated milestone
+    /// ids. Cannot be combined with `payment_schedule`.
+    #[serde(default)]
+    pub milestones: Vec<MilestoneSubmission>,
+    /// Whether the budget is locked in escrow at submission. Defaults to
+    /// `true`.
+    #[serde(default)]
+    pub escrow_required: Option<bool>,
+    /// Signature over the request's `SignedMessage`, required when the
+    /// signer has a registered public key.
+    #[serde(default)]
+    pub signature: Option<RequestSignature>,
+}
+
+impl TaskSubmissionRequest {
+    /// Canonical content signed by the requester.
+    pub fn signed_message(&self) -> Result<SignedMessage, ApiError> {
+        Ok(SignedMessage::SubmitTask {
+            client_task_id: self.client_task_id.clone(),
+            requester_id: self.requester_id.clone(),
+            description: self.description.clone(),
+            task_type: self.task_type.clone(),
+            input: decode_base64(&self.input_base64, "input_base64")?,
+            max_budget: self.max_budget,
+            deadline: self.deadline,
+            bid_window_secs: self.bid_window_secs,
+            domain: self.domain.clone(),
+            requirements: self.requirements.clone().into(),
+            output_format: self.output_format.clone(),
+            verification_level: self.verification_level.clone(),
+            payment_schedule: self.payment_schedule.clone(),
+            escrow_required: self.escrow_required,
+            milestones: self
+                .milestones
+                .iter()
+                .map(|m| (m.description.clone(), m.criteria.clone(), m.amount))
+                .collect(),
+        })
+    }
+}
+
+/// Milestone defined at task submission, paid `amount` of the budget once
+/// approved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MilestoneSubmission {
+    pub description: String,
+    #[serde(default)]
+    pub criteria: String,
+    pub amount: u128,
+}
+
+/// Execution requirements accepted at task submission. Every field is
+/// optional; omitted fields place no constraint on agents.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskRequirements {
+    /// Minimum memory in bytes.
+    #[serde(default)]
+    pub min_memory: Option<u64>,
+    /// Minimum CPU cores.
+    #[serde(default)]
+    pub min_cpu_cores: Option<u32>,
+    /// Whether GPU-class acceleration is required.
+    #[serde(default)]
+    pub gpu_required: bool,
+    /// Minimum bandwidth in bytes/second.
+    #[serde(default)]
+    pub min_bandwidth: Option<u64>,
+    /// Capabilities the agent must advertise.
+    #[serde(default)]
+    pub capabilities: Vec<Capability>,
+}
+
+impl From<TaskRequirements> for Requirements {
+    fn from(r: TaskRequirements) -> Self {
+        Requirements {
+            min_memory: r.min_memory,
+            min_cpu_cores: r.min_cpu_cores,
+            gpu_required: r.gpu_required,
+            min_bandwidth: r.min_bandwidth,
+            capabilities: r.capabilities,
+        }
+    }
 }
 
 /// Internal representation of a task stored by the orchestrator.
@@ -58,9 +571,23 @@ pub struct TaskSubmissionRequest {
 pub struct StoredTask {
     pub id: String,
     pub client_task_id: Option<String>,
+    /// Requester identifier as submitted; `task.requester` is its key.
+    #[serde(default)]
+    pub requester_id: String,
     pub task: Task,
     pub status: TaskStatus,
     pub created_at: u64,
+    /// Per-task bid window override (seconds).
+    #[serde(default)]
+    pub bid_window_secs: Option<u64>,
+    #[serde(default)]
+    pub domain: Option<Domain>,
+    /// Bumped on every write; [`Storage::update_task`] only stores a task
+    /// whose version is still current.
+    ///
+    /// [`Storage::update_task`]: crate::storage::Storage::update_task
+    #[serde(default)]
+    pub version: u64,
 }
 
 /// Internal representation of a bid stored by the orchestrator.
@@ -71,6 +598,21 @@ pub struct StoredBid {
     pub agent_id: String,
     pub bid: Bid,
     pub created_at: u64,
+    /// `0x`-prefixed hex of
+    /// `ainur_core::bid_commitment(task_id, agent_id, value, nonce)`.
+    #[serde(default)]
+    pub commitment: Option<String>,
+    /// `0x`-prefixed hex nonce, known once the bid is revealed.
+    #[serde(default)]
+    pub nonce: Option<String>,
+    /// Whether `bid.value` is known. Sealed bids carry a placeholder value of
+    /// zero until revealed.
+    #[serde(default = "default_revealed")]
+    pub revealed: bool,
+}
+
+fn default_revealed() -> bool {
+    true
 }
 
 /// Internal representation of a task result stored by the orchestrator.
@@ -81,6 +623,56 @@ pub struct StoredResult {
     pub agent_id: String,
     pub result: TaskResult,
     pub created_at: u64,
+    /// Verifier confidence in the result (0-100), once verified.
+    #[serde(default)]
+    pub quality: Option<u32>,
+}
+
+/// Protocol violation recorded against an agent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredViolation {
+    pub id: String,
+    pub agent_id: String,
+    pub task_id: String,
+    pub violation: Violation,
+    pub recorded_at: u64,
+}
+
+impl StoredViolation {
+    /// Short label for the violation kind, used for storage and dedup.
+    pub fn kind(&self) -> &'static str {
+        Self::kind_of(&self.violation)
+    }
+
+    /// Short label for the kind of `violation`.
+    pub fn kind_of(violation: &Violation) -> &'static str {
+        match violation {
+            Violation::TaskFailure(_) => "task_failure",
+            Violation::FalseInformation(_) => "false_information",
+            Violation::SLAViolation(_) => "sla_violation",
+            Violation::Other(_) => "other",
+        }
+    }
+}
+
+/// Persisted reputation of an agent, keyed by its orchestrator identifier.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredReputation {
+    pub agent_id: String,
+    pub record: AgentReputation,
+}
+
+/// Winning agent and payment chosen by the allocator when a task's bid window
+/// closes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredAllocation {
+    pub id: String,
+    pub task_id: String,
+    pub agent_id: String,
+    pub bid_id: String,
+    pub payment: u128,
+    pub social_welfare: u128,
+    pub allocated_at: u64,
 }
 
 /// Public view of a task returned by the API.
@@ -92,6 +684,8 @@ pub struct TaskView {
     pub status: TaskStatus,
     pub deadline: u64,
     pub max_budget: u128,
+    pub bid_window_closes_at: u64,
+    pub requirements: Requirements,
 }
 
 /// Public view of a bid.
@@ -100,9 +694,13 @@ pub struct BidView {
     pub id: String,
     pub task_id: String,
     pub agent_id: String,
-    pub value: u128,
+    /// Bid value; hidden until a sealed bid is revealed.
+    pub value: Option<u128>,
     pub quality_score: u32,
     pub completion_time: u64,
+    pub guarantees: Vec<Guarantee>,
+    pub commitment: Option<String>,
+    pub revealed: bool,
 }
 
 /// Public view of a task result.
@@ -113,6 +711,130 @@ pub struct ResultView {
     pub agent_id: String,
     pub output_base64: String,
     pub completed_at: u64,
+    pub quality: Option<u32>,
+}
+
+/// Public view of a task allocation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllocationView {
+    pub task_id: String,
+    pub allocated_at: u64,
+    pub social_welfare: u128,
+    pub winners: Vec<AllocatedAgentView>,
+}
+
+/// A single winning agent within an allocation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllocatedAgentView {
+    pub agent_id: String,
+    pub bid_id: String,
+    pub payment: u128,
+}
+
+/// Agreement among the results collected for a task.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerificationView {
+    pub task_id: String,
+    pub verification_level: VerificationLevel,
+    /// Independent results the task asks for.
+    pub required: u32,
+    /// Agreeing results needed to accept an output.
+    pub threshold: u32,
+    pub received: u32,
+    pub status: AgreementStatus,
+    /// Hex `hash_of` the accepted output, once agreed.
+    pub accepted_output_hash: Option<String>,
+    /// Results grouped by identical output, largest group first.
+    pub groups: Vec<OutputGroupView>,
+}
+
+/// Whether the collected results have settled on an output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgreementStatus {
+    /// Not enough results yet; agreement is still possible.
+    Pending,
+    /// An output reached the threshold.
+    Agreed,
+    /// No output can reach the threshold with the results still outstanding.
+    Disagreed,
+}
+
+/// Agents that reported the same output.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputGroupView {
+    pub output_hash: String,
+    pub agents: Vec<String>,
+}
+
+/// Public view of an agent's reputation and its recent assessments.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentReputationView {
+    pub agent_id: String,
+    pub overall: u32,
+    pub reputation: Reputation,
+    /// Most recent assessments, oldest first.
+    pub history: Vec<ReputationEvent>,
+    pub updated_at: u64,
+}
+
+impl AgentReputationView {
+    pub fn new(agent_id: String, record: AgentReputation) -> Self {
+        Self {
+            agent_id,
+            overall: record.reputation.overall(),
+            reputation: record.reputation,
+            history: record.history,
+            updated_at: record.updated_at,
+        }
+    }
+}
+
+/// A single entry in the reputation leaderboard.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RankedAgentView {
+    pub rank: usize,
+    pub agent_id: String,
+    pub overall: u32,
+    pub reputation: Reputation,
+}
+
+/// Query parameters for the reputation leaderboard.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TopReputationQuery {
+    /// Domain to rank by (`nlp`, `vision`, `data_analysis`, `codegen`,
+    /// `scientific`, `creative`, or any other name). Ranks by overall
+    /// reputation when omitted.
+    pub domain: Option<String>,
+    pub count: Option<usize>,
+}
+
+/// Name of a domain as used in query strings and capability tags; the
+/// inverse of [`parse_domain`].
+pub fn domain_name(domain: &Domain) -> String {
+    match domain {
+        Domain::NLP => "nlp".into(),
+        Domain::Vision => "vision".into(),
+        Domain::DataAnalysis => "data_analysis".into(),
+        Domain::CodeGen => "codegen".into(),
+        Domain::Scientific => "scientific".into(),
+        Domain::Creative => "creative".into(),
+        Domain::Other(name) => name.clone(),
+    }
+}
+
+/// Parse a domain name as used in query strings. Unknown names map to
+/// `Domain::Other`.
+pub fn parse_domain(name: &str) -> Domain {
+    match name.to_ascii_lowercase().replace(['-', '_'], "").as_str() {
+        "nlp" => Domain::NLP,
+        "vision" => Domain::Vision,
+        "dataanalysis" => Domain::DataAnalysis,
+        "codegen" => Domain::CodeGen,
+        "scientific" => Domain::Scientific,
+        "creative" => Domain::Creative,
+        _ => Domain::Other(name.to_string()),
+    }
 }
 
 /// Request payload to enqueue an outbound extrinsic into the chain outbox.
@@ -153,64 +875,371 @@ pub struct OutboxQuery {
     pub offset: Option<i64>,
 }
 
-/// Generic response wrapper that can carry a correlation id plus payload.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ResponseWithCorrelation<T> {
-    pub correlation_id: Option<String>,
-    pub data: T,
-}
+/// Page size used when a list request does not set `limit`.
+pub const DEFAULT_PAGE_SIZE: usize = 50;
 
-/// Aggregate counts for the dashboard surface.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DashboardView {
-    pub total_agents: usize,
-    pub total_tasks: usize,
-    pub completed_tasks: usize,
-    pub pending_tasks: usize,
+/// Upper bound on the page size of list requests.
+pub const MAX_PAGE_SIZE: usize = 200;
+
+/// Position in a listing, handed to clients as an opaque `cursor`: the sort
+/// key of the last item of the previous page. Agents are ordered by id alone
+/// and leave `created_at` at zero.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageCursor {
+    pub created_at: u64,
+    pub id: String,
 }
 
-/// Observability payload for chain sync/outbox state.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SyncStatusView {
-    pub chain_cursor: Option<ChainCursorView>,
-    pub outbox_pending: Option<i64>,
-    pub outbox_failed: Option<i64>,
-    pub outbox_dead: Option<i64>,
+impl PageCursor {
+    pub fn encode(&self) -> String {
+        general_purpose::URL_SAFE_NO_PAD.encode(format!("{}:{}", self.created_at, self.id))
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, ApiError> {
+        let invalid = || ApiError::BadRequest("cursor is invalid".to_string());
+        let raw = general_purpose::URL_SAFE_NO_PAD
+            .decode(cursor)
+            .map_err(|_| invalid())?;
+        let raw = String::from_utf8(raw).map_err(|_| invalid())?;
+        let (created_at, id) = raw.split_once(':').ok_or_else(invalid)?;
+        Ok(Self {
+            created_at: created_at.parse().map_err(|_| invalid())?,
+            id: id.to_string(),
+        })
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ChainCursorView {
-    pub block: u64,
-    pub event_index: u32,
+/// Where a page starts and how many items it holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageRequest {
+    /// Items after this cursor; the first page when `None`.
+    pub after: Option<PageCursor>,
+    pub limit: usize,
 }
 
-impl TaskView {
-    pub fn from_stored(stored: &StoredTask) -> Self {
-        Self {
-            id: stored.id.clone(),
-            requester_id: requester_hex(&stored.task),
-            description: stored.task.specification.description.clone(),
-            status: stored.status,
-            deadline: stored.task.deadline,
-            max_budget: stored.task.budget.max_cost,
+impl PageRequest {
+    /// Page from the `cursor` and `limit` query parameters.
+    pub fn from_query(cursor: Option<&str>, limit: Option<usize>) -> Result<Self, ApiError> {
+        let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE);
+        if limit == 0 || limit > MAX_PAGE_SIZE {
+            return Err(ApiError::BadRequest(format!(
+                "limit must be between 1 and {MAX_PAGE_SIZE}"
+            )));
         }
+        Ok(Self {
+            after: cursor.map(PageCursor::decode).transpose()?,
+            limit,
+        })
     }
 }
 
-impl BidView {
-    pub fn from_stored(stored: &StoredBid) -> Self {
-        Self {
-            id: stored.id.clone(),
-            task_id: stored.task_id.clone(),
-            agent_id: stored.agent_id.clone(),
-            value: stored.bid.value,
-            quality_score: stored.bid.quality_score,
-            completion_time: stored.bid.completion_time,
-        }
-    }
+/// One page of a listing. `next_cursor` fetches the following page and is
+/// absent on the last one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
 }
 
-impl ResultView {
+impl<T> Page<T> {
+    /// Page of `items` fetched with one extra item beyond `limit`, which
+    /// tells whether another page follows.
+    pub fn from_overfetch(
+        mut items: Vec<T>,
+        limit: usize,
+        cursor: impl Fn(&T) -> PageCursor,
+    ) -> Self {
+        let next_cursor = if items.len() > limit {
+            items.truncate(limit);
+            items.last().map(|item| cursor(item).encode())
+        } else {
+            None
+        };
+        Self { items, next_cursor }
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+        }
+    }
+}
+
+/// Query parameters for `GET /v1/tasks`. Time ranges are inclusive Unix
+/// timestamps (seconds).
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TaskQuery {
+    pub status: Option<String>,
+    pub requester_id: Option<String>,
+    pub task_type: Option<String>,
+    pub created_from: Option<u64>,
+    pub created_to: Option<u64>,
+    pub deadline_from: Option<u64>,
+    pub deadline_to: Option<u64>,
+    pub cursor: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl TaskQuery {
+    pub fn filter(&self) -> Result<TaskFilter, ApiError> {
+        let status = self
+            .status
+            .as_deref()
+            .map(|s| {
+                TaskStatus::parse(s)
+                    .ok_or_else(|| ApiError::BadRequest(format!("unknown task status {s}")))
+            })
+            .transpose()?;
+        Ok(TaskFilter {
+            status,
+            requester_id: self.requester_id.clone(),
+            task_type: self.task_type.clone(),
+            created_from: self.created_from,
+            created_to: self.created_to,
+            deadline_from: self.deadline_from,
+            deadline_to: self.deadline_to,
+        })
+    }
+
+    pub fn page(&self) -> Result<PageRequest, ApiError> {
+        PageRequest::from_query(self.cursor.as_deref(), self.limit)
+    }
+}
+
+/// Task listing filter; unset fields match every task. Deadline bounds only
+/// match tasks that have a deadline.
+#[derive(Debug, Clone, Default)]
+pub struct TaskFilter {
+    pub status: Option<TaskStatus>,
+    pub requester_id: Option<String>,
+    pub task_type: Option<String>,
+    pub created_from: Option<u64>,
+    pub created_to: Option<u64>,
+    pub deadline_from: Option<u64>,
+    pub deadline_to: Option<u64>,
+}
+
+impl TaskFilter {
+    pub fn matches(&self, task: &StoredTask) -> bool {
+        let deadline = task.task.deadline;
+        let has_deadline_bound = self.deadline_from.is_some() || self.deadline_to.is_some();
+        self.status.is_none_or(|s| task.status == s)
+            && self
+                .requester_id
+                .as_deref()
+                .is_none_or(|r| task.task.requester == agent_key(r))
+            && self.task_type.as_deref().is_none_or(|t| {
+                task.task.specification.task_type == ainur_core::TaskType::Custom(t.to_string())
+            })
+            && self.created_from.is_none_or(|from| task.created_at >= from)
+            && self.created_to.is_none_or(|to| task.created_at <= to)
+            && !(has_deadline_bound && deadline == 0)
+            && self.deadline_from.is_none_or(|from| deadline >= from)
+            && self.deadline_to.is_none_or(|to| deadline <= to)
+    }
+}
+
+/// Query parameters for `GET /v1/agents`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AgentQuery {
+    pub cursor: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl AgentQuery {
+    pub fn page(&self) -> Result<PageRequest, ApiError> {
+        PageRequest::from_query(self.cursor.as_deref(), self.limit)
+    }
+}
+
+/// Query parameters for `GET /v1/tasks/:id/bids`. The value range is
+/// inclusive; sealed bids count as zero until revealed.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct BidQuery {
+    pub agent_id: Option<String>,
+    pub min_value: Option<u64>,
+    pub max_value: Option<u64>,
+    pub cursor: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl BidQuery {
+    pub fn filter(&self) -> BidFilter {
+        BidFilter {
+            agent_id: self.agent_id.clone(),
+            min_value: self.min_value.map(u128::from),
+            max_value: self.max_value.map(u128::from),
+        }
+    }
+
+    pub fn page(&self) -> Result<PageRequest, ApiError> {
+        PageRequest::from_query(self.cursor.as_deref(), self.limit)
+    }
+}
+
+/// Bid listing filter; unset fields match every bid.
+#[derive(Debug, Clone, Default)]
+pub struct BidFilter {
+    pub agent_id: Option<String>,
+    pub min_value: Option<u128>,
+    pub max_value: Option<u128>,
+}
+
+impl BidFilter {
+    pub fn matches(&self, bid: &StoredBid) -> bool {
+        self.agent_id.as_deref().is_none_or(|a| bid.agent_id == a)
+            && self.min_value.is_none_or(|min| bid.bid.value >= min)
+            && self.max_value.is_none_or(|max| bid.bid.value <= max)
+    }
+}
+
+/// Kind of a task lifecycle event published on the event stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    TaskCreated,
+    BidReceived,
+    TaskAllocated,
+    ResultSubmitted,
+    TaskCompleted,
+    /// A `TaskMarket` event for the task was finalized on chain.
+    ChainFinalized,
+}
+
+impl EventKind {
+    /// Stable string representation, used as the SSE event name.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::TaskCreated => "task_created",
+            EventKind::BidReceived => "bid_received",
+            EventKind::TaskAllocated => "task_allocated",
+            EventKind::ResultSubmitted => "result_submitted",
+            EventKind::TaskCompleted => "task_completed",
+            EventKind::ChainFinalized => "chain_finalized",
+        }
+    }
+}
+
+/// A task lifecycle event. `id` increases by one per event published, so
+/// clients resume a stream from the last id they saw.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskEvent {
+    pub id: u64,
+    pub kind: EventKind,
+    pub task_id: String,
+    /// Agent the event concerns: the bidder, the allocated agent or the
+    /// agent reporting a result.
+    pub agent_id: Option<String>,
+    pub data: serde_json::Value,
+    pub created_at: u64,
+}
+
+/// Query parameters for `GET /v1/events` and `GET /v1/events/ws`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct EventQuery {
+    /// Only events about this task.
+    pub task_id: Option<String>,
+    /// Only events concerning this agent.
+    pub agent_id: Option<String>,
+    /// Resume after this event; the `Last-Event-ID` header takes precedence.
+    pub last_event_id: Option<u64>,
+}
+
+impl EventQuery {
+    pub fn matches(&self, event: &TaskEvent) -> bool {
+        self.task_id.as_deref().is_none_or(|t| event.task_id == t)
+            && self
+                .agent_id
+                .as_deref()
+                .is_none_or(|a| event.agent_id.as_deref() == Some(a))
+    }
+}
+
+/// Generic response wrapper that can carry a correlation id plus payload.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseWithCorrelation<T> {
+    pub correlation_id: Option<String>,
+    pub data: T,
+}
+
+/// Aggregate counts for the dashboard surface.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DashboardView {
+    pub total_agents: usize,
+    pub total_tasks: usize,
+    pub completed_tasks: usize,
+    pub pending_tasks: usize,
+}
+
+/// Observability payload for chain sync/outbox state.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncStatusView {
+    pub chain_cursor: Option<ChainCursorView>,
+    pub outbox_pending: Option<i64>,
+    pub outbox_failed: Option<i64>,
+    pub outbox_dead: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainCursorView {
+    pub block: u64,
+    pub event_index: u32,
+}
+
+impl TaskView {
+    pub fn from_stored(stored: &StoredTask) -> Self {
+        Self {
+            id: stored.id.clone(),
+            requester_id: requester_hex(&stored.task),
+            description: stored.task.specification.description.clone(),
+            status: stored.status,
+            deadline: stored.task.deadline,
+            max_budget: stored.task.budget.max_cost,
+            bid_window_closes_at: stored.bid_window_closes_at(),
+            requirements: stored.task.requirements.clone(),
+        }
+    }
+}
+
+impl BidView {
+    pub fn from_stored(stored: &StoredBid) -> Self {
+        Self {
+            id: stored.id.clone(),
+            task_id: stored.task_id.clone(),
+            agent_id: stored.agent_id.clone(),
+            value: stored.revealed.then_some(stored.bid.value),
+            quality_score: stored.bid.quality_score,
+            completion_time: stored.bid.completion_time,
+            guarantees: stored.bid.guarantees.clone(),
+            commitment: stored.commitment.clone(),
+            revealed: stored.revealed,
+        }
+    }
+}
+
+impl AllocationView {
+    /// Build a view from the allocation rows of a single task. Returns `None`
+    /// when the task has not been allocated yet.
+    pub fn from_stored(allocations: &[StoredAllocation]) -> Option<Self> {
+        let first = allocations.first()?;
+        Some(Self {
+            task_id: first.task_id.clone(),
+            allocated_at: first.allocated_at,
+            social_welfare: first.social_welfare,
+            winners: allocations
+                .iter()
+                .map(|a| AllocatedAgentView {
+                    agent_id: a.agent_id.clone(),
+                    bid_id: a.bid_id.clone(),
+                    payment: a.payment,
+                })
+                .collect(),
+        })
+    }
+}
+
+impl ResultView {
     pub fn from_stored(stored: &StoredResult) -> Self {
         Self {
             id: stored.id.clone(),
@@ -218,6 +1247,7 @@ impl ResultView {
             agent_id: stored.agent_id.clone(),
             output_base64: general_purpose::STANDARD.encode(&stored.result.output),
             completed_at: stored.result.completed_at,
+            quality: stored.quality,
         }
     }
 }
@@ -225,40 +1255,272 @@ impl ResultView {
 impl StoredTask {
     /// Create a new stored task from a submission payload, performing all
     /// necessary decoding and validation.
-    pub fn from_submission(submission: TaskSubmissionRequest) -> Result<Self, ApiError> {
+    pub fn from_submission(mut submission: TaskSubmissionRequest) -> Result<Self, ApiError> {
         if submission.description.trim().is_empty() {
             return Err(ApiError::BadRequest(
                 "task description must not be empty".to_string(),
             ));
         }
 
+        if submission.requirements.capabilities.len() > MAX_AGENT_CAPABILITIES {
+            return Err(ApiError::BadRequest(format!(
+                "at most {MAX_AGENT_CAPABILITIES} required capabilities may be given"
+            )));
+        }
+
+        if let Some(window) = submission.bid_window_secs {
+            if window < constants::auction::MIN_BID_DURATION {
+                return Err(ApiError::BadRequest(format!(
+                    "bid_window_secs must be at least {}",
+                    constants::auction::MIN_BID_DURATION
+                )));
+            }
+        }
+
+        if !submission.milestones.is_empty() {
+            if submission.payment_schedule.is_some() {
+                return Err(ApiError::BadRequest(
+                    "milestones cannot be combined with payment_schedule".to_string(),
+                ));
+            }
+            let tranches = std::mem::take(&mut submission.milestones)
+                .into_iter()
+                .map(|m| {
+                    let milestone = Milestone {
+                        id: Uuid::new_v4().into_bytes(),
+                        description: m.description,
+                        criteria: m.criteria,
+                    };
+                    (milestone, m.amount)
+                })
+                .collect();
+            submission.payment_schedule = Some(PaymentSchedule::Milestone(tranches));
+        }
+
+        match &submission.payment_schedule {
+            Some(PaymentSchedule::Milestone(tranches)) => {
+                if tranches.len() > MAX_TASK_MILESTONES {
+                    return Err(ApiError::BadRequest(format!(
+                        "at most {MAX_TASK_MILESTONES} milestones may be defined"
+                    )));
+                }
+                let distinct = tranches
+                    .iter()
+                    .enumerate()
+                    .all(|(i, (m, _))| tranches[..i].iter().all(|(other, _)| other.id != m.id));
+                if !distinct {
+                    return Err(ApiError::BadRequest(
+                        "milestone ids must be unique".to_string(),
+                    ));
+                }
+                let total = tranches
+                    .iter()
+                    .try_fold(0u128, |acc, (_, amount)| acc.checked_add(*amount));
+                if tranches.is_empty() || total != Some(submission.max_budget) {
+                    return Err(ApiError::BadRequest(
+                        "milestone tranches must add up to max_budget".to_string(),
+                    ));
+                }
+            }
+            Some(PaymentSchedule::Streaming(0)) => {
+                return Err(ApiError::BadRequest(
+                    "streaming rate must be positive".to_string(),
+                ));
+            }
+            _ => {}
+        }
+
         let raw_input = general_purpose::STANDARD
             .decode(&submission.input_base64)
             .map_err(|_| ApiError::BadRequest("input_base64 must be valid base64".to_string()))?;
 
-        let task = build_core_task(&submission, raw_input);
+        let uuid = Uuid::new_v4();
+        let task = build_core_task(&submission, raw_input, uuid.as_u128());
 
-        let id = Uuid::new_v4().to_string();
+        let id = uuid.to_string();
         let created_at = current_unix_timestamp();
 
         Ok(Self {
             id,
             client_task_id: submission.client_task_id,
+            requester_id: submission.requester_id,
             task,
-            status: TaskStatus::Pending,
+            status: TaskStatus::Open,
             created_at,
+            bid_window_secs: submission.bid_window_secs,
+            domain: submission.domain,
+            version: 0,
         })
     }
+
+    /// Unix timestamp (seconds) at which the bidding window closes.
+    pub fn bid_window_closes_at(&self) -> u64 {
+        let window = self
+            .bid_window_secs
+            .unwrap_or(constants::auction::DEFAULT_BID_DURATION);
+        self.created_at.saturating_add(window)
+    }
+
+    /// Whether the bidding window has closed as of `now`.
+    pub fn bid_window_closed(&self, now: u64) -> bool {
+        now >= self.bid_window_closes_at()
+    }
+
+    /// Whether the task's deadline has passed as of `now`. A deadline of 0
+    /// means the task has none.
+    pub fn is_overdue(&self, now: u64) -> bool {
+        self.task.deadline != 0 && now > self.task.deadline
+    }
+
+    /// Reject work arriving after the task's deadline.
+    pub fn ensure_not_overdue(&self, now: u64) -> Result<(), CoreError> {
+        if self.is_overdue(now) {
+            return Err(CoreError::DeadlineExceeded {
+                deadline: self.task.deadline,
+            });
+        }
+        Ok(())
+    }
+
+    /// Unix timestamp (seconds) after which sealed bids can no longer be
+    /// revealed.
+    pub fn reveal_window_closes_at(&self) -> u64 {
+        self.bid_window_closes_at()
+            .saturating_add(constants::auction::DEFAULT_REVEAL_DURATION)
+    }
+
+    /// Whether the reveal phase has ended as of `now`.
+    pub fn reveal_window_closed(&self, now: u64) -> bool {
+        now >= self.reveal_window_closes_at()
+    }
+
+    /// Number of agents the task is allocated to: `n` for `Consensus(n)`
+    /// tasks, otherwise one.
+    pub fn redundancy(&self) -> usize {
+        match self.task.verification_level {
+            VerificationLevel::Consensus(n) => usize::from(n).max(1),
+            _ => 1,
+        }
+    }
+
+    /// Ledger account of the requester. Tasks stored before requester ids
+    /// were kept fall back to the hex of the requester key.
+    pub fn requester_account(&self) -> String {
+        if self.requester_id.is_empty() {
+            user_account(&requester_hex(&self.task))
+        } else {
+            user_account(&self.requester_id)
+        }
+    }
+
+    /// Move the task to `next`, rejecting illegal lifecycle steps with
+    /// `CoreError::InvalidStateTransition`.
+    pub fn transition_to(&mut self, next: TaskStatus) -> Result<(), CoreError> {
+        self.status = self.status.transition_to(next)?;
+        Ok(())
+    }
+}
+
+/// Payload for cancelling a task that is still open for bids.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskCancelRequest {
+    pub requester_id: String,
+    /// Passed on to the task's bidders.
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Signature over `SignedMessage::CancelTask`.
+    #[serde(default)]
+    pub signature: Option<RequestSignature>,
+}
+
+impl TaskCancelRequest {
+    /// Canonical content signed by the requester.
+    pub fn signed_message(&self, task_id: &str) -> SignedMessage {
+        SignedMessage::CancelTask {
+            task_id: task_id.to_string(),
+            requester_id: self.requester_id.clone(),
+        }
+    }
+}
+
+/// Payload for raising the budget or extending the deadline of a task that
+/// is still open for bids. Omitted fields are left unchanged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskUpdateRequest {
+    pub requester_id: String,
+    #[serde(default)]
+    pub max_budget: Option<u128>,
+    #[serde(default)]
+    pub deadline: Option<u64>,
+    /// Signature over `SignedMessage::UpdateTask`.
+    #[serde(default)]
+    pub signature: Option<RequestSignature>,
+}
+
+impl TaskUpdateRequest {
+    /// Canonical content signed by the requester.
+    pub fn signed_message(&self, task_id: &str) -> SignedMessage {
+        SignedMessage::UpdateTask {
+            task_id: task_id.to_string(),
+            requester_id: self.requester_id.clone(),
+            max_budget: self.max_budget,
+            deadline: self.deadline,
+        }
+    }
 }
 
 /// Payload for submitting a bid for a task.
+///
+/// Open bids carry `value` directly. Sealed bids instead carry a `commitment`
+/// (see [`ainur_core::bid_commitment`]) and disclose the value later through a
+/// [`BidRevealRequest`].
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BidSubmissionRequest {
     pub task_id: String,
     pub agent_id: String,
-    pub value: u128,
+    #[serde(default)]
+    pub value: Option<u128>,
+    /// Hex-encoded `ainur_core::bid_commitment(task_id, agent_id, value,
+    /// nonce)` for a sealed bid.
+    #[serde(default)]
+    pub commitment: Option<String>,
     pub quality_score: u32,
     pub completion_time: u64,
+    /// Completion time, quality and refund commitments, checked once the
+    /// result arrives.
+    #[serde(default)]
+    pub guarantees: Vec<Guarantee>,
+    /// Signature over the request's `SignedMessage`, required when the
+    /// signer has a registered public key.
+    #[serde(default)]
+    pub signature: Option<RequestSignature>,
+}
+
+impl BidSubmissionRequest {
+    /// Canonical content signed by the bidding agent.
+    pub fn signed_message(&self) -> Result<SignedMessage, ApiError> {
+        Ok(SignedMessage::SubmitBid {
+            task_id: self.task_id.clone(),
+            agent_id: self.agent_id.clone(),
+            value: self.value,
+            commitment: self
+                .commitment
+                .as_deref()
+                .map(|c| parse_hex_32(c, "commitment"))
+                .transpose()?,
+            quality_score: self.quality_score,
+            completion_time: self.completion_time,
+            guarantees: self.guarantees.clone(),
+        })
+    }
+}
+
+/// Payload for revealing a sealed bid.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BidRevealRequest {
+    pub value: u128,
+    /// Hex-encoded 32-byte nonce used when computing the commitment.
+    pub nonce: String,
 }
 
 impl StoredBid {
@@ -272,7 +1534,33 @@ impl StoredBid {
             ));
         }
 
-        let bid = build_core_bid(&submission, &task.task);
+        // Open bids get a server-side nonce so they can still be committed
+        // and revealed on chain.
+        let (value, commitment, nonce, revealed) =
+            match (submission.value, submission.commitment.as_deref()) {
+                (Some(value), None) => {
+                    let nonce = *blake3::hash(Uuid::new_v4().as_bytes()).as_bytes();
+                    let commitment = bid_commitment(&task.id, &submission.agent_id, value, &nonce);
+                    (value, to_hex(&commitment), Some(to_hex(&nonce)), true)
+                }
+                (None, Some(commitment)) => {
+                    let commitment = parse_hex_32(commitment, "commitment")?;
+                    (0, to_hex(&commitment), None, false)
+                }
+                (Some(_), Some(_)) => {
+                    return Err(ApiError::BadRequest(
+                        "provide either value or commitment, not both".to_string(),
+                    ))
+                }
+                (None, None) => {
+                    return Err(ApiError::BadRequest(
+                        "value or commitment is required".to_string(),
+                    ))
+                }
+            };
+
+        validate_guarantees(&submission.guarantees)?;
+        let bid = build_core_bid(&submission, &task.task, value);
 
         let id = Uuid::new_v4().to_string();
         let created_at = current_unix_timestamp();
@@ -283,8 +1571,41 @@ impl StoredBid {
             agent_id: submission.agent_id,
             bid,
             created_at,
+            commitment: Some(commitment),
+            nonce,
+            revealed,
         })
     }
+
+    /// Reveal a sealed bid, checking the preimage against the stored commitment.
+    pub fn reveal(&mut self, reveal: &BidRevealRequest) -> Result<(), ApiError> {
+        if self.revealed {
+            return Err(ApiError::BadRequest(format!(
+                "bid {} is already revealed",
+                self.id
+            )));
+        }
+        let commitment = self.commitment.as_deref().ok_or_else(|| {
+            ApiError::BadRequest(format!("bid {} has no commitment to reveal", self.id))
+        })?;
+        let nonce = parse_hex_32(&reveal.nonce, "nonce")?;
+        if to_hex(&bid_commitment(
+            &self.task_id,
+            &self.agent_id,
+            reveal.value,
+            &nonce,
+        )) != commitment
+        {
+            return Err(ApiError::BadRequest(
+                "value and nonce do not match the bid commitment".to_string(),
+            ));
+        }
+
+        self.bid.value = reveal.value;
+        self.nonce = Some(to_hex(&nonce));
+        self.revealed = true;
+        Ok(())
+    }
 }
 
 /// Payload for submitting a task result.
@@ -293,6 +1614,21 @@ pub struct ResultSubmissionRequest {
     pub task_id: String,
     pub agent_id: String,
     pub output_base64: String,
+    /// Signature over the request's `SignedMessage`, required when the
+    /// signer has a registered public key.
+    #[serde(default)]
+    pub signature: Option<RequestSignature>,
+}
+
+impl ResultSubmissionRequest {
+    /// Canonical content signed by the executing agent.
+    pub fn signed_message(&self) -> Result<SignedMessage, ApiError> {
+        Ok(SignedMessage::SubmitResult {
+            task_id: self.task_id.clone(),
+            agent_id: self.agent_id.clone(),
+            output: decode_base64(&self.output_base64, "output_base64")?,
+        })
+    }
 }
 
 impl StoredResult {
@@ -321,97 +1657,994 @@ impl StoredResult {
             agent_id: submission.agent_id,
             result,
             created_at,
+            quality: None,
         })
     }
 }
 
-fn build_core_task(submission: &TaskSubmissionRequest, input: Vec<u8>) -> Task {
-    let requester_hash = blake3::hash(submission.requester_id.as_bytes());
-    let mut requester_bytes = [0u8; 32];
-    requester_bytes.copy_from_slice(&requester_hash.as_bytes()[..32]);
-    let requester = AgentId::new(requester_bytes);
-
-    let specification = TaskSpec {
-        description: submission.description.clone(),
-        task_type: ainur_core::TaskType::Custom(submission.task_type.clone()),
-        input,
-        output_format: ainur_core::OutputFormat::Binary,
-        metadata: Vec::new(),
-    };
+/// Evidence attached to a dispute.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvidenceSubmission {
+    pub description: String,
+    pub content_base64: String,
+}
 
-    let requirements = Requirements {
-        min_memory: None,
-        min_cpu_cores: None,
-        gpu_required: false,
-        min_bandwidth: None,
-        capabilities: Vec::new(),
-    };
+/// Payload for contesting the result of a completed task.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisputeFilingRequest {
+    pub requester_id: String,
+    pub reason: String,
+    #[serde(default)]
+    pub evidence: Vec<EvidenceSubmission>,
+    /// Signature by the requester over [`SignedMessage::FileDispute`].
+    #[serde(default)]
+    pub signature: Option<RequestSignature>,
+}
 
-    let budget = Budget {
-        max_cost: submission.max_budget,
-        payment_schedule: ainur_core::PaymentSchedule::OnCompletion,
-        escrow_required: true,
-    };
+impl DisputeFilingRequest {
+    /// Canonical content signed by the requester.
+    pub fn signed_message(&self, task_id: &str) -> Result<SignedMessage, ApiError> {
+        let evidence = self
+            .evidence
+            .iter()
+            .map(|e| {
+                decode_base64(&e.content_base64, "evidence content_base64").map(|c| hash_of(&c))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(SignedMessage::FileDispute {
+            task_id: task_id.to_string(),
+            requester_id: self.requester_id.clone(),
+            reason: self.reason.clone(),
+            evidence,
+        })
+    }
+}
 
-    // TaskId is opaque at this layer. We derive it from a fresh UUID.
-    let mut task_bytes = [0u8; 32];
-    task_bytes[..16].copy_from_slice(Uuid::new_v4().as_bytes());
-    let task_id = ainur_core::TaskId::new(task_bytes);
+/// Payload for settling an open dispute, signed by a configured arbiter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisputeResolutionRequest {
+    pub arbiter_id: String,
+    pub resolution: DisputeResolution,
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Signature over `SignedMessage::ResolveDispute` by the arbiter's
+    /// configured key.
+    #[serde(default)]
+    pub signature: Option<RequestSignature>,
+}
 
-    Task {
-        id: task_id,
-        requester,
-        specification,
-        requirements,
-        budget,
-        deadline: submission.deadline,
-        verification_level: VerificationLevel::BestEffort,
+impl DisputeResolutionRequest {
+    /// Canonical content signed by the arbiter.
+    pub fn signed_message(&self, dispute_id: &str) -> SignedMessage {
+        SignedMessage::ResolveDispute {
+            dispute_id: dispute_id.to_string(),
+            arbiter_id: self.arbiter_id.clone(),
+            resolution: self.resolution,
+            notes: self.notes.clone(),
+        }
     }
 }
 
-fn build_core_bid(submission: &BidSubmissionRequest, task: &Task) -> Bid {
-    let executor_hash = blake3::hash(submission.agent_id.as_bytes());
-    let mut executor_bytes = [0u8; 32];
-    executor_bytes.copy_from_slice(&executor_hash.as_bytes()[..32]);
-    let agent_id = AgentId::new(executor_bytes);
+/// Whether a dispute still awaits resolution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeStatus {
+    Open,
+    Resolved,
+}
 
-    Bid {
-        agent_id,
-        task_id: task.id,
-        value: submission.value,
-        quality_score: submission.quality_score,
-        completion_time: submission.completion_time,
-        guarantees: Vec::new(),
+impl DisputeStatus {
+    /// Stable string representation, shared by the JSON surface and the
+    /// Postgres `disputes.status` column.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DisputeStatus::Open => "open",
+            DisputeStatus::Resolved => "resolved",
+        }
     }
 }
 
-fn build_core_result(
-    submission: &ResultSubmissionRequest,
-    task: &Task,
-    output: Vec<u8>,
-) -> TaskResult {
-    let executor_hash = blake3::hash(submission.agent_id.as_bytes());
-    let mut executor_bytes = [0u8; 32];
-    executor_bytes.copy_from_slice(&executor_hash.as_bytes()[..32]);
-    let executor = AgentId::new(executor_bytes);
-
-    TaskResult {
-        task_id: task.id,
-        executor,
-        output,
-        proof: None,
-        resources_used: ainur_core::ResourceUsage {
-            cpu_time_ms: 0,
-            memory_bytes: 0,
-            storage_bytes: 0,
-            bandwidth_bytes: 0,
-            gpu_time_ms: None,
-        },
-        completed_at: current_unix_timestamp(),
+/// Name of a dispute resolution as stored in `disputes.resolution`.
+pub fn resolution_name(resolution: &DisputeResolution) -> &'static str {
+    match resolution {
+        DisputeResolution::Uphold => "uphold",
+        DisputeResolution::Refund => "refund",
+        DisputeResolution::Slash => "slash",
     }
 }
 
-fn current_unix_timestamp() -> u64 {
+/// Evidence item as stored with its dispute.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredEvidence {
+    pub description: String,
+    pub content_base64: String,
+    /// Hex `hash_of` the decoded content.
+    pub content_hash: String,
+    pub size: usize,
+}
+
+/// Amounts a resolution moved for one of the task's agents.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentSettlement {
+    pub agent_id: String,
+    pub refund: u128,
+    pub slashed: u128,
+}
+
+/// Dispute over a task's result. Disputes mirrored from the chain's
+/// `Commitments::CommitmentDisputed` event carry the commitment id, and the
+/// local task when the chain task is mirrored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredDispute {
+    pub id: String,
+    pub task_id: Option<String>,
+    pub requester_id: String,
+    pub reason: String,
+    pub evidence: Vec<StoredEvidence>,
+    pub status: DisputeStatus,
+    pub resolution: Option<DisputeResolution>,
+    #[serde(default)]
+    pub settlements: Vec<AgentSettlement>,
+    pub notes: Option<String>,
+    pub filed_at: u64,
+    pub resolved_at: Option<u64>,
+    #[serde(default)]
+    pub chain_commitment_id: Option<u64>,
+}
+
+impl StoredDispute {
+    /// Validate a filing against `task` and build the open dispute.
+    pub fn from_filing(
+        filing: DisputeFilingRequest,
+        task: &StoredTask,
+        filed_at: u64,
+    ) -> Result<Self, ApiError> {
+        if filing.reason.trim().is_empty() {
+            return Err(ApiError::BadRequest(
+                "dispute reason must not be empty".to_string(),
+            ));
+        }
+        if filing.evidence.len() > constants::dispute::MAX_EVIDENCE_ITEMS {
+            return Err(ApiError::BadRequest(format!(
+                "at most {} evidence items may be attached",
+                constants::dispute::MAX_EVIDENCE_ITEMS
+            )));
+        }
+        let mut evidence = Vec::with_capacity(filing.evidence.len());
+        for item in filing.evidence {
+            let content = decode_base64(&item.content_base64, "evidence content_base64")?;
+            if content.len() > constants::dispute::MAX_EVIDENCE_SIZE {
+                return Err(ApiError::BadRequest(format!(
+                    "evidence items must be at most {} bytes",
+                    constants::dispute::MAX_EVIDENCE_SIZE
+                )));
+            }
+            evidence.push(StoredEvidence {
+                description: item.description,
+                content_base64: item.content_base64,
+                content_hash: format!("0x{}", hex::encode(hash_of(&content))),
+                size: content.len(),
+            });
+        }
+
+        Ok(Self {
+            id: Uuid::new_v4().to_string(),
+            task_id: Some(task.id.clone()),
+            requester_id: filing.requester_id,
+            reason: filing.reason,
+            evidence,
+            status: DisputeStatus::Open,
+            resolution: None,
+            settlements: Vec::new(),
+            notes: None,
+            filed_at,
+            resolved_at: None,
+            chain_commitment_id: None,
+        })
+    }
+}
+
+/// Public view of a dispute. Evidence is listed by hash and size.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisputeView {
+    pub id: String,
+    pub task_id: Option<String>,
+    pub requester_id: String,
+    pub reason: String,
+    pub evidence: Vec<EvidenceView>,
+    pub status: DisputeStatus,
+    pub resolution: Option<DisputeResolution>,
+    pub settlements: Vec<AgentSettlement>,
+    pub notes: Option<String>,
+    pub filed_at: u64,
+    pub resolved_at: Option<u64>,
+    pub chain_commitment_id: Option<u64>,
+}
+
+/// A dispute's evidence item without its content.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvidenceView {
+    pub description: String,
+    pub content_hash: String,
+    pub size: usize,
+}
+
+impl DisputeView {
+    pub fn from_stored(stored: &StoredDispute) -> Self {
+        Self {
+            id: stored.id.clone(),
+            task_id: stored.task_id.clone(),
+            requester_id: stored.requester_id.clone(),
+            reason: stored.reason.clone(),
+            evidence: stored
+                .evidence
+                .iter()
+                .map(|e| EvidenceView {
+                    description: e.description.clone(),
+                    content_hash: e.content_hash.clone(),
+                    size: e.size,
+                })
+                .collect(),
+            status: stored.status,
+            resolution: stored.resolution,
+            settlements: stored.settlements.clone(),
+            notes: stored.notes.clone(),
+            filed_at: stored.filed_at,
+            resolved_at: stored.resolved_at,
+            chain_commitment_id: stored.chain_commitment_id,
+        }
+    }
+}
+
+/// Why funds moved between ledger accounts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerTransactionKind {
+    /// A task's budget locked in its escrow account.
+    EscrowLock,
+    /// An agent paid for its work, less the transaction fee.
+    Payment,
+    /// Funds returned to a requester.
+    Refund,
+    /// A penalty taken from an agent.
+    Slash,
+    /// A transaction fee paid to the treasury.
+    Fee,
+    /// A deposit locked with a bid.
+    Deposit,
+    /// Stake bonded by an agent.
+    Bond,
+    /// Bonded stake moved to unbonding.
+    Unbond,
+    /// Unbonded stake returned to its agent.
+    Withdrawal,
+    /// Funds credited to a user account by an operator.
+    Funding,
+}
+
+impl LedgerTransactionKind {
+    /// Stable string representation, shared by the JSON surface and the
+    /// Postgres `ledger_transactions.kind` column.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LedgerTransactionKind::EscrowLock => "escrow_lock",
+            LedgerTransactionKind::Payment => "payment",
+            LedgerTransactionKind::Refund => "refund",
+            LedgerTransactionKind::Slash => "slash",
+            LedgerTransactionKind::Fee => "fee",
+            LedgerTransactionKind::Deposit => "deposit",
+            LedgerTransactionKind::Bond => "bond",
+            LedgerTransactionKind::Unbond => "unbond",
+            LedgerTransactionKind::Withdrawal => "withdrawal",
+            LedgerTransactionKind::Funding => "funding",
+        }
+    }
+}
+
+/// One account's side of a ledger transaction; credits are positive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerPosting {
+    pub account: String,
+    pub amount: i128,
+}
+
+/// A balanced movement of funds: its postings sum to zero.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredLedgerTransaction {
+    pub id: String,
+    pub task_id: Option<String>,
+    pub kind: LedgerTransactionKind,
+    /// Gross amount moved, fees included.
+    pub amount: u128,
+    pub memo: String,
+    pub postings: Vec<LedgerPosting>,
+    pub created_at: u64,
+}
+
+impl StoredLedgerTransaction {
+    /// Build a transaction from `(account, amount)` postings. Postings to the
+    /// same account are merged and zero postings dropped; the rest must sum
+    /// to zero.
+    pub fn new(
+        kind: LedgerTransactionKind,
+        task_id: Option<&str>,
+        memo: impl Into<String>,
+        amount: u128,
+        postings: &[(&str, i128)],
+        created_at: u64,
+    ) -> Result<Self, ApiError> {
+        let overflow = || ApiError::BadRequest("ledger amount overflow".to_string());
+        let mut merged: Vec<LedgerPosting> = Vec::with_capacity(postings.len());
+        let mut total: i128 = 0;
+        for (account, amount) in postings {
+            total = total.checked_add(*amount).ok_or_else(overflow)?;
+            match merged.iter_mut().find(|p| p.account == *account) {
+                Some(posting) => {
+                    posting.amount = posting.amount.checked_add(*amount).ok_or_else(overflow)?
+                }
+                None => merged.push(LedgerPosting {
+                    account: (*account).to_string(),
+                    amount: *amount,
+                }),
+            }
+        }
+        if total != 0 {
+            return Err(ApiError::Internal(format!(
+                "unbalanced ledger transaction ({total})"
+            )));
+        }
+        merged.retain(|p| p.amount != 0);
+
+        Ok(Self {
+            id: Uuid::new_v4().to_string(),
+            task_id: task_id.map(str::to_string),
+            kind,
+            amount,
+            memo: memo.into(),
+            postings: merged,
+            created_at,
+        })
+    }
+
+    /// Net amount posted to `account`.
+    pub fn posted_to(&self, account: &str) -> i128 {
+        self.postings
+            .iter()
+            .filter(|p| p.account == account)
+            .map(|p| p.amount)
+            .sum()
+    }
+}
+
+/// Balance and history of a ledger account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerView {
+    pub account: String,
+    /// Net position; requesters funding tasks run negative.
+    pub balance: i128,
+    pub entries: Vec<LedgerEntryView>,
+}
+
+/// One transaction as seen from a single account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEntryView {
+    pub transaction_id: String,
+    pub task_id: Option<String>,
+    pub kind: LedgerTransactionKind,
+    pub memo: String,
+    pub amount: i128,
+    pub created_at: u64,
+}
+
+impl LedgerView {
+    /// View of `account` from its transactions, oldest first.
+    pub fn from_transactions(account: &str, transactions: &[StoredLedgerTransaction]) -> Self {
+        let entries: Vec<LedgerEntryView> = transactions
+            .iter()
+            .map(|tx| LedgerEntryView {
+                transaction_id: tx.id.clone(),
+                task_id: tx.task_id.clone(),
+                kind: tx.kind,
+                memo: tx.memo.clone(),
+                amount: tx.posted_to(account),
+                created_at: tx.created_at,
+            })
+            .collect();
+        Self {
+            account: account.to_string(),
+            balance: entries.iter().map(|e| e.amount).sum(),
+            entries,
+        }
+    }
+}
+
+/// Payload for bonding or unbonding agent stake.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StakeRequest {
+    pub amount: u128,
+    /// Signature over `SignedMessage::BondStake` or
+    /// `SignedMessage::UnbondStake`.
+    #[serde(default)]
+    pub signature: Option<RequestSignature>,
+}
+
+impl StakeRequest {
+    /// Message the agent signs to bond `amount`.
+    pub fn bond_message(&self, agent_id: &str) -> SignedMessage {
+        SignedMessage::BondStake {
+            agent_id: agent_id.to_string(),
+            amount: self.amount,
+        }
+    }
+
+    /// Message the agent signs to start unbonding `amount`.
+    pub fn unbond_message(&self, agent_id: &str) -> SignedMessage {
+        SignedMessage::UnbondStake {
+            agent_id: agent_id.to_string(),
+            amount: self.amount,
+        }
+    }
+}
+
+/// Payload for withdrawing an agent's unbonded stake.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StakeWithdrawalRequest {
+    /// Signature over `SignedMessage::WithdrawStake`.
+    #[serde(default)]
+    pub signature: Option<RequestSignature>,
+}
+
+impl StakeWithdrawalRequest {
+    /// Message the agent signs to withdraw its matured stake.
+    pub fn signed_message(&self, agent_id: &str) -> SignedMessage {
+        SignedMessage::WithdrawStake {
+            agent_id: agent_id.to_string(),
+        }
+    }
+}
+
+/// Payload for crediting funds to a user account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FundingRequest {
+    pub amount: u128,
+}
+
+/// An agent's stake.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StakeView {
+    pub agent_id: String,
+    /// Bonded stake not locked in bid deposits.
+    pub free: u128,
+    /// Bonded stake locked in the deposits of bids on running tasks.
+    pub locked: u128,
+    /// Stake waiting out the unbonding period.
+    pub unbonding: u128,
+    /// Unbonded stake that can be withdrawn now.
+    pub withdrawable: u128,
+    /// Bids on tasks still taking bids.
+    pub open_bids: usize,
+}
+
+impl StakeView {
+    /// All bonded stake, free or locked.
+    pub fn bonded(&self) -> u128 {
+        self.free.saturating_add(self.locked)
+    }
+}
+
+/// Identifier of a milestone as exposed by the API: its 16-byte id as a
+/// UUID string.
+pub fn milestone_id(milestone: &Milestone) -> String {
+    Uuid::from_bytes(milestone.id).to_string()
+}
+
+/// Payload for reporting a milestone as complete, with partial output.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MilestoneCompletionRequest {
+    pub agent_id: String,
+    pub output_base64: String,
+    /// Signature over `SignedMessage::CompleteMilestone`.
+    #[serde(default)]
+    pub signature: Option<RequestSignature>,
+}
+
+impl MilestoneCompletionRequest {
+    /// Canonical content signed by the agent.
+    pub fn signed_message(
+        &self,
+        task_id: &str,
+        milestone_id: &str,
+    ) -> Result<SignedMessage, ApiError> {
+        Ok(SignedMessage::CompleteMilestone {
+            task_id: task_id.to_string(),
+            milestone_id: milestone_id.to_string(),
+            agent_id: self.agent_id.clone(),
+            output: decode_base64(&self.output_base64, "output_base64")?,
+        })
+    }
+}
+
+/// Payload for approving or rejecting a milestone completion.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MilestoneReviewRequest {
+    pub requester_id: String,
+    pub approved: bool,
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Signature over `SignedMessage::ReviewMilestone`.
+    #[serde(default)]
+    pub signature: Option<RequestSignature>,
+}
+
+impl MilestoneReviewRequest {
+    /// Canonical content signed by the requester.
+    pub fn signed_message(&self, completion_id: &str) -> SignedMessage {
+        SignedMessage::ReviewMilestone {
+            completion_id: completion_id.to_string(),
+            requester_id: self.requester_id.clone(),
+            approved: self.approved,
+        }
+    }
+}
+
+/// Review state of a milestone completion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MilestoneCompletionStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl MilestoneCompletionStatus {
+    /// Stable string representation, shared by the JSON surface and the
+    /// Postgres `milestone_completions.status` column.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MilestoneCompletionStatus::Pending => "pending",
+            MilestoneCompletionStatus::Approved => "approved",
+            MilestoneCompletionStatus::Rejected => "rejected",
+        }
+    }
+}
+
+/// An agent's report that it reached a milestone. `tranche` is the agent's
+/// share of the milestone, scaled to its clearing payment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredMilestoneCompletion {
+    pub id: String,
+    pub task_id: String,
+    pub milestone_id: String,
+    pub agent_id: String,
+    pub output_base64: String,
+    pub output_hash: String,
+    pub tranche: u128,
+    pub status: MilestoneCompletionStatus,
+    pub notes: Option<String>,
+    pub submitted_at: u64,
+    pub reviewed_at: Option<u64>,
+}
+
+impl StoredMilestoneCompletion {
+    /// Build a pending completion of `milestone_id` on `task_id`.
+    pub fn from_request(
+        request: MilestoneCompletionRequest,
+        task_id: &str,
+        milestone_id: &str,
+        tranche: u128,
+        submitted_at: u64,
+    ) -> Result<Self, ApiError> {
+        let output = decode_base64(&request.output_base64, "output_base64")?;
+        Ok(Self {
+            id: Uuid::new_v4().to_string(),
+            task_id: task_id.to_string(),
+            milestone_id: milestone_id.to_string(),
+            agent_id: request.agent_id,
+            output_base64: request.output_base64,
+            output_hash: format!("0x{}", hex::encode(hash_of(&output))),
+            tranche,
+            status: MilestoneCompletionStatus::Pending,
+            notes: None,
+            submitted_at,
+            reviewed_at: None,
+        })
+    }
+}
+
+/// Public view of a milestone completion, including its partial output.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MilestoneCompletionView {
+    pub id: String,
+    pub agent_id: String,
+    pub output_base64: String,
+    pub output_hash: String,
+    pub tranche: u128,
+    pub status: MilestoneCompletionStatus,
+    pub notes: Option<String>,
+    pub submitted_at: u64,
+    pub reviewed_at: Option<u64>,
+}
+
+impl MilestoneCompletionView {
+    pub fn from_stored(stored: &StoredMilestoneCompletion) -> Self {
+        Self {
+            id: stored.id.clone(),
+            agent_id: stored.agent_id.clone(),
+            output_base64: stored.output_base64.clone(),
+            output_hash: stored.output_hash.clone(),
+            tranche: stored.tranche,
+            status: stored.status,
+            notes: stored.notes.clone(),
+            submitted_at: stored.submitted_at,
+            reviewed_at: stored.reviewed_at,
+        }
+    }
+}
+
+/// A task milestone with the completions reported against it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MilestoneView {
+    pub id: String,
+    pub description: String,
+    pub criteria: String,
+    /// Share of the budget the milestone is worth.
+    pub amount: u128,
+    pub completions: Vec<MilestoneCompletionView>,
+}
+
+/// What an agent is being notified of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// A task the agent bid on was cancelled by its requester.
+    TaskCancelled,
+    /// A task the agent bid on had its budget or deadline changed.
+    TaskUpdated,
+}
+
+impl NotificationKind {
+    /// Stable string representation, shared by the JSON surface and the
+    /// Postgres `agent_notifications.kind` column.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationKind::TaskCancelled => "task_cancelled",
+            NotificationKind::TaskUpdated => "task_updated",
+        }
+    }
+}
+
+/// A notice to an agent about a task it bid on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredNotification {
+    pub id: String,
+    pub agent_id: String,
+    pub task_id: String,
+    pub kind: NotificationKind,
+    pub message: String,
+    pub created_at: u64,
+}
+
+/// Payload for registering a webhook. Without `task_id` the webhook covers
+/// every task of the requester; an empty `events` list subscribes to every
+/// event kind.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookRegistrationRequest {
+    pub requester_id: String,
+    /// `http://` or `https://` endpoint receiving `POST`ed events.
+    pub url: String,
+    #[serde(default)]
+    pub task_id: Option<String>,
+    #[serde(default)]
+    pub events: Vec<EventKind>,
+    /// Signature over `SignedMessage::RegisterWebhook`.
+    #[serde(default)]
+    pub signature: Option<RequestSignature>,
+}
+
+impl WebhookRegistrationRequest {
+    /// Canonical content signed by the requester.
+    pub fn signed_message(&self) -> SignedMessage {
+        SignedMessage::RegisterWebhook {
+            requester_id: self.requester_id.clone(),
+            url: self.url.clone(),
+            task_id: self.task_id.clone(),
+            events: self.events.iter().map(|e| e.as_str().to_string()).collect(),
+        }
+    }
+}
+
+/// A requester's webhook. `secret` keys the HMAC over each delivery and is
+/// only disclosed when the webhook is registered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredWebhook {
+    pub id: String,
+    pub requester_id: String,
+    pub url: String,
+    pub task_id: Option<String>,
+    pub events: Vec<EventKind>,
+    pub secret: String,
+    pub created_at: u64,
+}
+
+impl StoredWebhook {
+    pub fn from_registration(
+        request: WebhookRegistrationRequest,
+        secret: String,
+        now: u64,
+    ) -> Result<Self, ApiError> {
+        let valid_scheme = ["http://", "https://"]
+            .iter()
+            .any(|scheme| request.url.starts_with(scheme));
+        if !valid_scheme || request.url.len() > 256 {
+            return Err(ApiError::BadRequest(format!(
+                "invalid webhook url {}",
+                request.url
+            )));
+        }
+        let mut events = request.events;
+        events.sort_by_key(|e| e.as_str());
+        events.dedup();
+        Ok(Self {
+            id: Uuid::new_v4().to_string(),
+            requester_id: request.requester_id,
+            url: request.url,
+            task_id: request.task_id,
+            events,
+            secret,
+            created_at: now,
+        })
+    }
+
+    /// Whether `event`, about one of the requester's tasks, is delivered here.
+    pub fn matches(&self, event: &TaskEvent) -> bool {
+        self.task_id.as_deref().is_none_or(|t| event.task_id == t)
+            && (self.events.is_empty() || self.events.contains(&event.kind))
+    }
+}
+
+/// Public view of a webhook. `secret` is only set in the registration
+/// response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookView {
+    pub id: String,
+    pub requester_id: String,
+    pub url: String,
+    pub task_id: Option<String>,
+    pub events: Vec<EventKind>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    pub created_at: u64,
+}
+
+impl WebhookView {
+    pub fn from_stored(webhook: &StoredWebhook) -> Self {
+        Self {
+            id: webhook.id.clone(),
+            requester_id: webhook.requester_id.clone(),
+            url: webhook.url.clone(),
+            task_id: webhook.task_id.clone(),
+            events: webhook.events.clone(),
+            secret: None,
+            created_at: webhook.created_at,
+        }
+    }
+}
+
+/// Query parameters for `GET /v1/webhooks` and
+/// `GET /v1/webhooks/:id/deliveries`: the requester and the parts of its
+/// signature over `SignedMessage::ListWebhooks`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookQuery {
+    pub requester_id: String,
+    pub nonce: Option<String>,
+    pub expires_at: Option<u64>,
+    /// Hex-encoded ed25519 signature, as `RequestSignature::value`.
+    pub signature: Option<String>,
+}
+
+impl WebhookQuery {
+    /// Message the requester signs to list its webhooks, or the deliveries
+    /// of `webhook_id`.
+    pub fn signed_message(&self, webhook_id: Option<&str>) -> SignedMessage {
+        SignedMessage::ListWebhooks {
+            requester_id: self.requester_id.clone(),
+            webhook_id: webhook_id.map(str::to_string),
+        }
+    }
+
+    /// The signature, if every part of it was given.
+    pub fn signature(&self) -> Option<RequestSignature> {
+        Some(RequestSignature {
+            nonce: self.nonce.clone()?,
+            expires_at: self.expires_at?,
+            value: self.signature.clone()?,
+        })
+    }
+}
+
+/// Where a webhook delivery stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookDeliveryStatus {
+    /// Waiting for its first attempt or a retry.
+    Pending,
+    /// The endpoint answered with a 2xx status.
+    Delivered,
+    /// Every attempt failed; no more retries.
+    Dead,
+}
+
+impl WebhookDeliveryStatus {
+    /// Stable string representation, shared by the JSON surface and the
+    /// Postgres `webhook_deliveries.status` column.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookDeliveryStatus::Pending => "pending",
+            WebhookDeliveryStatus::Delivered => "delivered",
+            WebhookDeliveryStatus::Dead => "dead",
+        }
+    }
+}
+
+/// One attempt at delivering an event: the HTTP status the endpoint
+/// answered with, or why no answer was received.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookAttempt {
+    pub attempted_at: u64,
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+}
+
+/// An event queued for delivery to a webhook, with its attempt log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredWebhookDelivery {
+    pub id: String,
+    pub webhook_id: String,
+    pub event: TaskEvent,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: Vec<WebhookAttempt>,
+    /// Earliest time of the next attempt while `pending`.
+    pub next_attempt_at: u64,
+    pub created_at: u64,
+}
+
+/// A claimed idempotency key. `response` is set once the request that
+/// claimed the key has succeeded; until then the key is in progress.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredIdempotencyKey {
+    /// Endpoint, principal and client key, e.g. `tasks:requester-1:abc`.
+    pub key: String,
+    /// Hex blake3 hash of the request body.
+    pub request_hash: String,
+    pub response: Option<serde_json::Value>,
+    pub created_at: u64,
+}
+
+fn build_core_task(submission: &TaskSubmissionRequest, input: Vec<u8>, salt: u128) -> Task {
+    let requester = agent_key(&submission.requester_id);
+
+    let specification = TaskSpec {
+        description: submission.description.clone(),
+        task_type: ainur_core::TaskType::Custom(submission.task_type.clone()),
+        input,
+        output_format: submission
+            .output_format
+            .clone()
+            .unwrap_or(OutputFormat::Binary),
+        metadata: Vec::new(),
+    };
+
+    let requirements = Requirements::from(submission.requirements.clone());
+
+    let budget = Budget {
+        max_cost: submission.max_budget,
+        payment_schedule: submission
+            .payment_schedule
+            .clone()
+            .unwrap_or(PaymentSchedule::OnCompletion),
+        escrow_required: submission.escrow_required.unwrap_or(true),
+    };
+
+    let mut task = Task {
+        id: ainur_core::TaskId::new([0u8; 32]),
+        requester,
+        specification,
+        requirements,
+        budget,
+        deadline: submission.deadline,
+        verification_level: submission
+            .verification_level
+            .clone()
+            .unwrap_or(VerificationLevel::BestEffort),
+    };
+    // The orchestrator UUID salts the content hash so identical submissions
+    // still get distinct ids.
+    task.id = task.derive_id(salt);
+    task
+}
+
+fn build_core_bid(submission: &BidSubmissionRequest, task: &Task, value: u128) -> Bid {
+    let agent_id = agent_key(&submission.agent_id);
+
+    Bid {
+        agent_id,
+        task_id: task.id,
+        value,
+        quality_score: submission.quality_score,
+        completion_time: submission.completion_time,
+        guarantees: submission.guarantees.clone(),
+    }
+}
+
+fn validate_guarantees(guarantees: &[Guarantee]) -> Result<(), ApiError> {
+    if guarantees.len() > MAX_BID_GUARANTEES {
+        return Err(ApiError::BadRequest(format!(
+            "at most {MAX_BID_GUARANTEES} guarantees may be offered"
+        )));
+    }
+    let max_score = constants::reputation::MAX_SCORE;
+    let mut refund_policies = 0;
+    for guarantee in guarantees {
+        let percents = match guarantee {
+            Guarantee::QualityScore(score) if *score > max_score => {
+                return Err(ApiError::BadRequest(format!(
+                    "guaranteed quality score must be at most {max_score}"
+                )))
+            }
+            Guarantee::RefundPolicy(RefundPolicy::Partial(percent)) => vec![*percent],
+            Guarantee::RefundPolicy(RefundPolicy::TimeBased(steps)) => {
+                steps.iter().map(|(_, percent)| *percent).collect()
+            }
+            _ => Vec::new(),
+        };
+        if percents.iter().any(|p| *p > 100) {
+            return Err(ApiError::BadRequest(
+                "refund percentages must be at most 100".to_string(),
+            ));
+        }
+        if matches!(guarantee, Guarantee::RefundPolicy(_)) {
+            refund_policies += 1;
+        }
+    }
+    if refund_policies > 1 {
+        return Err(ApiError::BadRequest(
+            "at most one refund policy may be offered".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn build_core_result(
+    submission: &ResultSubmissionRequest,
+    task: &Task,
+    output: Vec<u8>,
+) -> TaskResult {
+    let executor = agent_key(&submission.agent_id);
+
+    TaskResult {
+        task_id: task.id,
+        executor,
+        output,
+        proof: None,
+        resources_used: ainur_core::ResourceUsage {
+            cpu_time_ms: 0,
+            memory_bytes: 0,
+            storage_bytes: 0,
+            bandwidth_bytes: 0,
+            gpu_time_ms: None,
+        },
+        completed_at: current_unix_timestamp(),
+    }
+}
+
+/// Protocol-level `AgentId` for an orchestrator agent identifier: the
+/// blake3 hash of the identifier string.
+pub fn agent_key(id: &str) -> AgentId {
+    AgentId::new(*blake3::hash(id.as_bytes()).as_bytes())
+}
+
+/// Current Unix time in seconds.
+pub fn current_unix_timestamp() -> u64 {
     use std::time::{SystemTime, UNIX_EPOCH};
 
     match SystemTime::now().duration_since(UNIX_EPOCH) {
@@ -422,6 +2655,30 @@ fn current_unix_timestamp() -> u64 {
 
 /// Convenience helper to obtain a hex‑encoded representation of the requester
 /// id for observability in API responses.
+fn to_hex(bytes: &[u8; 32]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+fn decode_base64(value: &str, field: &'static str) -> Result<Vec<u8>, ApiError> {
+    general_purpose::STANDARD
+        .decode(value)
+        .map_err(|_| ApiError::BadRequest(format!("{field} must be valid base64")))
+}
+
+fn parse_hex_32(value: &str, field: &'static str) -> Result<[u8; 32], ApiError> {
+    let raw = value.strip_prefix("0x").unwrap_or(value);
+    let bytes = hex::decode(raw)
+        .map_err(|_| ApiError::BadRequest(format!("{field} must be hex encoded")))?;
+    bytes
+        .try_into()
+        .map_err(|_| ApiError::BadRequest(format!("{field} must be 32 bytes")))
+}
+
+fn parse_public_key(value: &str) -> Result<VerifyingKey, ApiError> {
+    VerifyingKey::from_bytes(&parse_hex_32(value, "public_key")?)
+        .map_err(|_| ApiError::BadRequest("public_key is not a valid ed25519 key".into()))
+}
+
 fn requester_hex(task: &Task) -> String {
     let bytes = task.requester.as_bytes();
     let mut out = String::with_capacity(bytes.len() * 2);
@@ -431,3 +2688,296 @@ fn requester_hex(task: &Task) -> String {
     }
     out
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn agent_registration_validation_and_tags() {
+        let legacy: AgentRegistrationRequest =
+            serde_json::from_str(r#"{"id":"a","label":"agent"}"#).unwrap();
+        assert!(legacy.validate().is_ok());
+        assert!(legacy.capability_tags().is_empty());
+
+        let mut agent = AgentRegistrationRequest::new("a", "agent");
+        agent.capabilities = vec![
+            Capability::TEE(TEEType::SGX),
+            Capability::Hardware(HardwareType::NvidiaGPU("a100".into())),
+        ];
+        agent.domains = vec![Domain::CodeGen];
+        agent.verification_level = VerificationLevel::TEEAttested;
+        agent.public_key = Some(to_hex(
+            &ed25519_dalek::SigningKey::from_bytes(&[7u8; 32])
+                .verifying_key()
+                .to_bytes(),
+        ));
+        agent.endpoints = vec!["https://agent.example/v1".into()];
+        assert!(agent.validate().is_ok());
+        assert_eq!(
+            agent.capability_tags(),
+            ["tee:sgx", "hardware:nvidia_gpu:a100", "domain:codegen"]
+        );
+
+        agent.endpoints = vec!["ftp://agent.example".into()];
+        assert!(agent.validate().is_err());
+        agent.endpoints.clear();
+        agent.public_key = Some("0x1234".into());
+        assert!(agent.validate().is_err());
+        agent.public_key = None;
+        agent.capabilities.remove(0);
+        assert!(agent.validate().is_err());
+    }
+
+    #[test]
+    fn task_status_happy_path() {
+        let mut status = TaskStatus::Open;
+        for next in [
+            TaskStatus::Bidding,
+            TaskStatus::Bidding,
+            TaskStatus::Allocated,
+            TaskStatus::Executing,
+            TaskStatus::Completed,
+            TaskStatus::Disputed,
+            TaskStatus::Completed,
+        ] {
+            status = status.transition_to(next).unwrap();
+        }
+        assert_eq!(status, TaskStatus::Completed);
+    }
+
+    #[test]
+    fn task_status_rejects_illegal_moves() {
+        let err = TaskStatus::Open
+            .transition_to(TaskStatus::Completed)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid state transition: open -> completed"
+        );
+        assert!(TaskStatus::Allocated
+            .transition_to(TaskStatus::Cancelled)
+            .is_err());
+        for terminal in [
+            TaskStatus::Failed,
+            TaskStatus::Expired,
+            TaskStatus::Cancelled,
+        ] {
+            assert!(terminal.is_terminal());
+            assert!(terminal.transition_to(TaskStatus::Open).is_err());
+        }
+    }
+
+    #[test]
+    fn task_status_string_roundtrip() {
+        for status in [
+            TaskStatus::Open,
+            TaskStatus::Bidding,
+            TaskStatus::Allocated,
+            TaskStatus::Executing,
+            TaskStatus::Completed,
+            TaskStatus::Failed,
+            TaskStatus::Expired,
+            TaskStatus::Cancelled,
+            TaskStatus::Disputed,
+        ] {
+            assert_eq!(TaskStatus::parse(status.as_str()), Some(status));
+        }
+        assert_eq!(TaskStatus::parse("pending"), Some(TaskStatus::Open));
+        let legacy: TaskStatus = serde_json::from_str("\"pending\"").unwrap();
+        assert_eq!(legacy, TaskStatus::Open);
+    }
+
+    #[test]
+    fn sealed_bid_reveal_checks_preimage() {
+        let task = StoredTask::from_submission(TaskSubmissionRequest {
+            client_task_id: None,
+            requester_id: "requester".into(),
+            description: "sealed".into(),
+            task_type: "echo".into(),
+            input_base64: String::new(),
+            max_budget: 100,
+            deadline: 0,
+            bid_window_secs: None,
+            domain: None,
+            requirements: Default::default(),
+            output_format: None,
+            verification_level: None,
+            payment_schedule: None,
+            milestones: Vec::new(),
+            escrow_required: None,
+            signature: None,
+        })
+        .unwrap();
+        let nonce = [7u8; 32];
+        let sealed = |agent_id: &str| {
+            StoredBid::from_submission(
+                BidSubmissionRequest {
+                    task_id: task.id.clone(),
+                    agent_id: agent_id.into(),
+                    value: None,
+                    commitment: Some(hex::encode(bid_commitment(&task.id, "agent", 42, &nonce))),
+                    quality_score: 80,
+                    completion_time: 60,
+                    guarantees: Vec::new(),
+                    signature: None,
+                },
+                &task,
+            )
+            .unwrap()
+        };
+        let mut bid = sealed("agent");
+        assert!(!bid.revealed);
+        assert_eq!(BidView::from_stored(&bid).value, None);
+
+        let wrong = BidRevealRequest {
+            value: 41,
+            nonce: hex::encode(nonce),
+        };
+        assert!(bid.reveal(&wrong).is_err());
+        assert!(!bid.revealed);
+
+        let right = BidRevealRequest {
+            value: 42,
+            nonce: format!("0x{}", hex::encode(nonce)),
+        };
+        // Another agent copying the commitment cannot reveal it.
+        let mut copied = sealed("copycat");
+        assert!(copied.reveal(&right).is_err());
+
+        bid.reveal(&right).unwrap();
+        assert_eq!(bid.bid.value, 42);
+        assert!(bid.reveal(&right).is_err());
+    }
+
+    #[test]
+    fn task_signature_covers_every_submitted_field() {
+        let submission = TaskSubmissionRequest {
+            client_task_id: None,
+            requester_id: "requester".into(),
+            description: "signed".into(),
+            task_type: "echo".into(),
+            input_base64: String::new(),
+            max_budget: 100,
+            deadline: 1_000,
+            bid_window_secs: None,
+            domain: None,
+            requirements: Default::default(),
+            output_format: None,
+            verification_level: None,
+            payment_schedule: None,
+            milestones: Vec::new(),
+            escrow_required: None,
+            signature: None,
+        };
+        let signed = submission.signed_message().unwrap();
+        let edits: Vec<fn(&mut TaskSubmissionRequest)> = vec![
+            |s| s.client_task_id = Some("client-1".into()),
+            |s| s.bid_window_secs = Some(30),
+            |s| s.domain = Some(Domain::CodeGen),
+            |s| s.requirements.gpu_required = true,
+            |s| s.output_format = Some(OutputFormat::Json),
+            |s| s.verification_level = Some(VerificationLevel::Consensus(3)),
+            |s| s.payment_schedule = Some(PaymentSchedule::Upfront),
+            |s| s.escrow_required = Some(false),
+            |s| {
+                s.milestones.push(MilestoneSubmission {
+                    description: "draft".into(),
+                    criteria: String::new(),
+                    amount: 100,
+                })
+            },
+        ];
+        for edit in edits {
+            let mut edited = submission.clone();
+            edit(&mut edited);
+            assert_ne!(edited.signed_message().unwrap(), signed);
+        }
+    }
+
+    #[test]
+    fn webhook_queries_carry_a_complete_signature() {
+        let mut query = WebhookQuery {
+            requester_id: "requester".into(),
+            nonce: Some("00".repeat(16)),
+            expires_at: Some(60),
+            signature: None,
+        };
+        assert!(query.signature().is_none());
+        query.signature = Some("ab".into());
+        let signature = query.signature().unwrap();
+        assert_eq!((signature.expires_at, signature.value.as_str()), (60, "ab"));
+        // Listing webhooks and reading one's deliveries are signed apart.
+        assert_ne!(
+            query.signed_message(None),
+            query.signed_message(Some("hook"))
+        );
+    }
+
+    #[tokio::test]
+    async fn task_listing_pages_through_filtered_tasks() {
+        use crate::storage::{InMemoryStorage, Storage};
+
+        let storage = InMemoryStorage::default();
+        for (i, task_type) in ["echo", "echo", "sum", "echo", "echo"].iter().enumerate() {
+            let mut task = StoredTask::from_submission(TaskSubmissionRequest {
+                client_task_id: None,
+                requester_id: "requester".into(),
+                description: format!("task {i}"),
+                task_type: task_type.to_string(),
+                input_base64: String::new(),
+                max_budget: 100,
+                deadline: if i == 4 { 0 } else { 1_000 },
+                bid_window_secs: None,
+                domain: None,
+                requirements: Default::default(),
+                output_format: None,
+                verification_level: None,
+                payment_schedule: None,
+                milestones: Vec::new(),
+                escrow_required: None,
+                signature: None,
+            })
+            .unwrap();
+            task.created_at = 100 + i as u64;
+            storage.insert_task(task).await.unwrap();
+        }
+
+        let query = TaskQuery {
+            task_type: Some("echo".into()),
+            limit: Some(2),
+            ..Default::default()
+        };
+        let filter = query.filter().unwrap();
+        let first = storage
+            .list_tasks(&filter, &query.page().unwrap())
+            .await
+            .unwrap();
+        let created: Vec<u64> = first.items.iter().map(|t| t.created_at).collect();
+        assert_eq!(created, vec![104, 103]);
+
+        let next = TaskQuery {
+            cursor: first.next_cursor,
+            ..query
+        };
+        let second = storage
+            .list_tasks(&filter, &next.page().unwrap())
+            .await
+            .unwrap();
+        let created: Vec<u64> = second.items.iter().map(|t| t.created_at).collect();
+        assert_eq!(created, vec![101, 100]);
+        assert!(second.next_cursor.is_none());
+
+        // Deadline bounds skip tasks without a deadline.
+        let with_deadline = TaskFilter {
+            deadline_to: Some(2_000),
+            ..Default::default()
+        };
+        let all = PageRequest::from_query(None, None).unwrap();
+        let page = storage.list_tasks(&with_deadline, &all).await.unwrap();
+        assert_eq!(page.items.len(), 4);
+
+        assert!(PageRequest::from_query(Some("not a cursor"), None).is_err());
+        assert!(PageRequest::from_query(None, Some(MAX_PAGE_SIZE + 1)).is_err());
+    }
+}
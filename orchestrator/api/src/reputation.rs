@@ -0,0 +1,275 @@
+//! Agent reputation upkeep.
+//!
+//! Accepted results and missed deadlines are turned into
+//! `ainur_core::Assessment`s and folded into the agent's persisted reputation
+//! through `ainur_core::ReputationLedger`. Storage is the source of truth; a
+//! ledger is rebuilt from the stored record for every update so the in-memory
+//! and Postgres backends behave the same.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use ainur_core::{
+    AgentId, AgentReputation, Assessment, Domain, ReputationLedger, ReputationSystem, TaskId,
+};
+
+use crate::error::ApiError;
+use crate::model::{agent_key, RankedAgentView, StoredReputation, StoredResult, StoredTask};
+use crate::storage::Storage;
+
+/// Fold an accepted result into the executing agent's reputation.
+pub async fn record_result(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    result: &StoredResult,
+) -> Result<StoredReputation, ApiError> {
+    let allocation = storage
+        .get_allocations_for_task(&task.id)
+        .await?
+        .into_iter()
+        .find(|a| a.agent_id == result.agent_id);
+
+    let mut assessment = Assessment::from_result(&result.result);
+    assessment.domain = task.domain.clone();
+    assessment.on_time = !task.is_overdue(result.result.completed_at);
+    if let Some(allocation) = allocation {
+        let budget = task.task.budget.max_cost;
+        if budget > 0 {
+            let saved = budget.saturating_sub(allocation.payment);
+            assessment.cost_efficiency = (saved * 100 / budget) as u32;
+        }
+        if let Ok(bid) = storage.get_bid(&allocation.bid_id).await {
+            let elapsed = result
+                .result
+                .completed_at
+                .saturating_sub(allocation.allocated_at);
+            assessment.speed = speed_score(bid.bid.completion_time, elapsed);
+        }
+    }
+
+    record(
+        storage,
+        &result.agent_id,
+        task.task.id,
+        result.result.completed_at,
+        assessment,
+    )
+    .await
+}
+
+/// Record a task the agent was allocated but never delivered.
+pub async fn record_failure(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    agent_id: &str,
+    now: u64,
+) -> Result<StoredReputation, ApiError> {
+    let assessment = Assessment {
+        quality: 0,
+        on_time: false,
+        speed: 0,
+        cost_efficiency: 0,
+        domain: task.domain.clone(),
+    };
+    record(storage, agent_id, task.task.id, now, assessment).await
+}
+
+async fn record(
+    storage: &Arc<dyn Storage>,
+    agent_id: &str,
+    task_id: TaskId,
+    timestamp: u64,
+    assessment: Assessment,
+) -> Result<StoredReputation, ApiError> {
+    let key = agent_key(agent_id);
+    let mut ledger = ReputationLedger::new();
+    if let Some(stored) = storage.get_reputation(agent_id).await? {
+        ledger.insert(key, stored.record);
+    }
+    ledger.record(key, task_id, timestamp, assessment)?;
+
+    let record = ledger
+        .get(&key)
+        .cloned()
+        .ok_or_else(|| ApiError::Internal(format!("reputation for {agent_id} not recorded")))?;
+    let stored = StoredReputation {
+        agent_id: agent_id.to_string(),
+        record,
+    };
+    storage.upsert_reputation(stored.clone()).await?;
+    Ok(stored)
+}
+
+/// Current reputation of `agent_id` with decay applied up to `now`, or
+/// `None` if nothing has been recorded for the agent.
+pub async fn agent_reputation(
+    storage: &Arc<dyn Storage>,
+    agent_id: &str,
+    now: u64,
+) -> Result<Option<AgentReputation>, ApiError> {
+    let Some(stored) = storage.get_reputation(agent_id).await? else {
+        return Ok(None);
+    };
+    let key = agent_key(agent_id);
+    let mut ledger = ReputationLedger::new();
+    ledger.insert(key, stored.record);
+    ledger.advance_to(now);
+    Ok(ledger.get(&key).cloned())
+}
+
+/// Best `count` agents as of `now`, ranked by specialization in `domain`
+/// through `ReputationSystem::get_top_agents`, or by overall reputation when
+/// no domain is given.
+pub async fn top_agents(
+    storage: &Arc<dyn Storage>,
+    domain: Option<&Domain>,
+    count: usize,
+    now: u64,
+) -> Result<Vec<RankedAgentView>, ApiError> {
+    let mut ledger = ReputationLedger::new();
+    let mut ids: HashMap<AgentId, String> = HashMap::new();
+    for stored in storage.list_reputations().await? {
+        let key = agent_key(&stored.agent_id);
+        ledger.insert(key, stored.record);
+        ids.insert(key, stored.agent_id);
+    }
+    ledger.advance_to(now);
+
+    let ranked = match domain {
+        Some(domain) => ledger.get_top_agents(domain, count)?,
+        None => ledger.top_overall(count),
+    };
+    Ok(ranked
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, (key, reputation))| {
+            Some(RankedAgentView {
+                rank: i + 1,
+                agent_id: ids.get(&key)?.clone(),
+                overall: reputation.overall(),
+                reputation,
+            })
+        })
+        .collect())
+}
+
+/// 100 when the work took no longer than promised, scaled down in proportion
+/// to the overrun otherwise.
+fn speed_score(promised: u64, elapsed: u64) -> u32 {
+    if elapsed <= promised {
+        100
+    } else {
+        (promised.saturating_mul(100) / elapsed) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ResultSubmissionRequest, TaskSubmissionRequest};
+    use crate::storage::InMemoryStorage;
+    use ainur_core::{constants, Domain};
+
+    #[tokio::test]
+    async fn results_and_failures_update_stored_reputation() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let task = StoredTask::from_submission(TaskSubmissionRequest {
+            client_task_id: None,
+            requester_id: "requester".into(),
+            description: "rate me".into(),
+            task_type: "echo".into(),
+            input_base64: String::new(),
+            max_budget: 100,
+            deadline: 0,
+            bid_window_secs: None,
+            domain: Some(Domain::NLP),
+            requirements: Default::default(),
+            output_format: None,
+            verification_level: None,
+            payment_schedule: None,
+            milestones: Vec::new(),
+            escrow_required: None,
+            signature: None,
+        })
+        .unwrap();
+        let result = StoredResult::from_submission(
+            ResultSubmissionRequest {
+                task_id: task.id.clone(),
+                agent_id: "agent-a".into(),
+                output_base64: String::new(),
+                signature: None,
+            },
+            &task,
+        )
+        .unwrap();
+
+        let stored = record_result(&storage, &task, &result).await.unwrap();
+        let reputation = &stored.record.reputation;
+        assert!(reputation.quality > constants::reputation::INITIAL_SCORE);
+        assert!(reputation.specialization(&Domain::NLP).is_some());
+
+        let after = record_failure(&storage, &task, "agent-a", result.created_at)
+            .await
+            .unwrap();
+        assert!(after.record.reputation.reliability < reputation.reliability);
+        let persisted = storage.get_reputation("agent-a").await.unwrap().unwrap();
+        assert_eq!(persisted.record.history.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn leaderboard_ranks_by_domain_then_overall() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let assessment = |quality, domain: Option<Domain>| Assessment {
+            quality,
+            on_time: true,
+            speed: 100,
+            cost_efficiency: 50,
+            domain,
+        };
+        let task_id = TaskId::new([1u8; 32]);
+        record(
+            &storage,
+            "nlp-good",
+            task_id,
+            10,
+            assessment(100, Some(Domain::NLP)),
+        )
+        .await
+        .unwrap();
+        record(
+            &storage,
+            "nlp-poor",
+            task_id,
+            10,
+            assessment(20, Some(Domain::NLP)),
+        )
+        .await
+        .unwrap();
+        record(&storage, "generalist", task_id, 10, assessment(100, None))
+            .await
+            .unwrap();
+
+        let nlp = top_agents(&storage, Some(&Domain::NLP), 10, 10)
+            .await
+            .unwrap();
+        let ids: Vec<_> = nlp.iter().map(|r| r.agent_id.as_str()).collect();
+        assert_eq!(ids, ["nlp-good", "nlp-poor"]);
+        assert_eq!(nlp[0].rank, 1);
+
+        let overall = top_agents(&storage, None, 2, 10).await.unwrap();
+        assert_eq!(overall.len(), 2);
+        assert_ne!(overall[0].agent_id, "nlp-poor");
+
+        assert!(agent_reputation(&storage, "unknown", 10)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn speed_scales_with_overrun() {
+        assert_eq!(speed_score(60, 30), 100);
+        assert_eq!(speed_score(60, 120), 50);
+        assert_eq!(speed_score(0, 10), 0);
+    }
+}
@@ -0,0 +1,468 @@
+//! Request authentication.
+//!
+//! Task submissions, bids and results carry an ed25519 signature over an
+//! `ainur_core::SignedRequest`: the request's `SignedMessage`, a single-use
+//! nonce and an expiry. Requester messages are checked against the key
+//! registered with `POST /v1/requesters` and agent messages against the key
+//! registered with `POST /v1/agents`. The nonce is remembered until the
+//! signature expires so the request cannot be replayed. Unsigned requests are
+//! refused unless signatures are turned off (`REQUIRE_SIGNATURES=false`), and
+//! even then only from signers without a registered key.
+
+use std::sync::Arc;
+
+use ainur_core::SignedMessage;
+use ed25519_dalek::VerifyingKey;
+
+use crate::error::ApiError;
+use crate::model::{
+    current_unix_timestamp, AgentRegistrationRequest, RequestSignature,
+    RequesterRegistrationRequest,
+};
+use crate::storage::Storage;
+
+/// Furthest ahead a signature may expire, bounding how long nonces are kept.
+pub const MAX_SIGNATURE_TTL: u64 = 600;
+
+/// Verify `signature` over `message` for `signer_id` and consume its nonce.
+pub async fn authenticate(
+    storage: &Arc<dyn Storage>,
+    signer_id: &str,
+    message: &SignedMessage,
+    signature: Option<&RequestSignature>,
+    require_signatures: bool,
+) -> Result<(), ApiError> {
+    verify_request(storage, signer_id, message, signature, require_signatures).await?;
+    consume_nonce(storage, signer_id, signature).await
+}
+
+/// Verify `signature` over `message` for `signer_id` without consuming its
+/// nonce. Idempotent submissions consume it once they claim their key, so
+/// retries with the same key replay the stored response instead.
+pub async fn verify_request(
+    storage: &Arc<dyn Storage>,
+    signer_id: &str,
+    message: &SignedMessage,
+    signature: Option<&RequestSignature>,
+    require_signatures: bool,
+) -> Result<(), ApiError> {
+    let key = registered_key(storage, signer_id, message).await?;
+    match (key, signature) {
+        (Some(key), Some(signature)) => verify(signer_id, &key, message, signature),
+        (Some(_), None) => Err(ApiError::Unauthorized(format!(
+            "{signer_id} has a registered public key; the request must be signed"
+        ))),
+        (None, Some(_)) => Err(ApiError::Unauthorized(format!(
+            "{signer_id} has no registered public key to verify the signature"
+        ))),
+        (None, None) if require_signatures => Err(ApiError::Unauthorized(format!(
+            "requests from {signer_id} must be signed by a registered key"
+        ))),
+        (None, None) => Ok(()),
+    }
+}
+
+/// Key `signer_id` signs `message` with, looked up among requesters or
+/// agents depending on who sends that kind of request.
+async fn registered_key(
+    storage: &Arc<dyn Storage>,
+    signer_id: &str,
+    message: &SignedMessage,
+) -> Result<Option<VerifyingKey>, ApiError> {
+    let key = if signed_by_requester(message) {
+        storage
+            .get_requester(signer_id)
+            .await
+            .and_then(|requester| requester.verifying_key().map(Some))
+    } else {
+        storage
+            .get_agent(signer_id)
+            .await
+            .and_then(|agent| agent.verifying_key())
+    };
+    match key {
+        Err(ApiError::NotFound(_)) => Ok(None),
+        key => key,
+    }
+}
+
+fn signed_by_requester(message: &SignedMessage) -> bool {
+    matches!(
+        message,
+        SignedMessage::SubmitTask { .. }
+            | SignedMessage::FileDispute { .. }
+            | SignedMessage::ReviewMilestone { .. }
+            | SignedMessage::CancelTask { .. }
+            | SignedMessage::UpdateTask { .. }
+            | SignedMessage::RegisterWebhook { .. }
+            | SignedMessage::ListWebhooks { .. }
+            | SignedMessage::RegisterRequester { .. }
+    )
+}
+
+/// Record the nonce of a verified `signature`, rejecting one `signer_id`
+/// already used. Unsigned requests have nothing to record.
+pub async fn consume_nonce(
+    storage: &Arc<dyn Storage>,
+    signer_id: &str,
+    signature: Option<&RequestSignature>,
+) -> Result<(), ApiError> {
+    let Some(signature) = signature else {
+        return Ok(());
+    };
+    let nonce = signature
+        .nonce
+        .strip_prefix("0x")
+        .unwrap_or(&signature.nonce);
+    let fresh = storage
+        .use_request_nonce(
+            signer_id,
+            &nonce.to_ascii_lowercase(),
+            signature.expires_at,
+            current_unix_timestamp(),
+        )
+        .await?;
+    if !fresh {
+        return Err(ApiError::Unauthorized(format!(
+            "{signer_id} already used nonce {nonce}"
+        )));
+    }
+    Ok(())
+}
+
+/// Authenticate an agent registration. Agents without a key on record sign
+/// it with the key being registered, proving they hold it; with signatures
+/// turned off they may also register unsigned. Once an agent has a key, any
+/// re-registration, including a key rotation, must be signed by that key.
+pub async fn authenticate_registration(
+    storage: &Arc<dyn Storage>,
+    registration: &AgentRegistrationRequest,
+    require_signatures: bool,
+) -> Result<(), ApiError> {
+    let current = match storage.get_agent(&registration.id).await {
+        Ok(agent) => agent.verifying_key()?,
+        Err(ApiError::NotFound(_)) => None,
+        Err(err) => return Err(err),
+    };
+    let message = registration.signed_message()?;
+    let signature = registration.signature.as_ref();
+    match (current, signature) {
+        (Some(key), Some(signature)) => verify(&registration.id, &key, &message, signature)?,
+        (Some(_), None) => {
+            return Err(ApiError::Unauthorized(format!(
+                "{} is registered with a public key; re-registration must be signed by it",
+                registration.id
+            )))
+        }
+        (None, Some(signature)) => match registration.verifying_key()? {
+            Some(key) => verify(&registration.id, &key, &message, signature)?,
+            None => {
+                return Err(ApiError::Unauthorized(format!(
+                    "{} has no public key to verify the signature",
+                    registration.id
+                )))
+            }
+        },
+        (None, None) if require_signatures => {
+            return Err(ApiError::Unauthorized(format!(
+                "{} must sign its registration with the key being registered",
+                registration.id
+            )))
+        }
+        (None, None) => {}
+    }
+    consume_nonce(storage, &registration.id, signature).await
+}
+
+/// Authenticate a requester key registration: signed with the key on record,
+/// or on a first registration with the key being registered.
+pub async fn authenticate_requester_registration(
+    storage: &Arc<dyn Storage>,
+    registration: &RequesterRegistrationRequest,
+) -> Result<(), ApiError> {
+    let message = registration.signed_message()?;
+    let key = match storage.get_requester(&registration.id).await {
+        Ok(requester) => requester.verifying_key()?,
+        Err(ApiError::NotFound(_)) => registration.verifying_key()?,
+        Err(err) => return Err(err),
+    };
+    let Some(signature) = registration.signature.as_ref() else {
+        return Err(ApiError::Unauthorized(format!(
+            "{}: requester registrations must be signed",
+            registration.id
+        )));
+    };
+    verify(&registration.id, &key, &message, signature)?;
+    consume_nonce(storage, &registration.id, Some(signature)).await
+}
+
+/// Verify `signature` over `message` against `key`, checking its expiry but
+/// not its nonce.
+pub fn verify(
+    signer_id: &str,
+    key: &VerifyingKey,
+    message: &SignedMessage,
+    signature: &RequestSignature,
+) -> Result<(), ApiError> {
+    let now = current_unix_timestamp();
+    if signature.expires_at < now {
+        return Err(ApiError::Unauthorized(format!(
+            "{signer_id}: signature expired at {}",
+            signature.expires_at
+        )));
+    }
+    if signature.expires_at > now + MAX_SIGNATURE_TTL {
+        return Err(ApiError::Unauthorized(format!(
+            "{signer_id}: signatures may expire at most {MAX_SIGNATURE_TTL}s ahead"
+        )));
+    }
+    let request = signature.request(message.clone())?;
+    let bytes = hex::decode(
+        signature
+            .value
+            .strip_prefix("0x")
+            .unwrap_or(&signature.value),
+    )
+    .map_err(|_| ApiError::Unauthorized("signature must be hex encoded".into()))?;
+    request
+        .verify(key.as_bytes(), &bytes)
+        .map_err(|err| ApiError::Unauthorized(format!("{signer_id}: {err}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ResultSubmissionRequest, StoredRequester};
+    use crate::storage::InMemoryStorage;
+    use ed25519_dalek::SigningKey;
+
+    fn sign(message: &SignedMessage, nonce: u8, key: &SigningKey) -> RequestSignature {
+        let expires_at = current_unix_timestamp() + 60;
+        RequestSignature::sign(message.clone(), [nonce; 16], expires_at, key)
+    }
+
+    #[tokio::test]
+    async fn verifies_against_registered_key() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let key = SigningKey::from_bytes(&[9u8; 32]);
+        let mut agent = AgentRegistrationRequest::new("signed-agent", "signed");
+        agent.public_key = Some(hex::encode(key.verifying_key().to_bytes()));
+        storage.register_agent(agent).await.unwrap();
+
+        let mut request = ResultSubmissionRequest {
+            task_id: "task".into(),
+            agent_id: "signed-agent".into(),
+            output_base64: "aGk=".into(),
+            signature: None,
+        };
+        let message = request.signed_message().unwrap();
+        let signature = sign(&message, 1, &key);
+
+        assert!(
+            authenticate(&storage, "signed-agent", &message, Some(&signature), true)
+                .await
+                .is_ok()
+        );
+        let mut forged = sign(&message, 2, &key);
+        forged.value = "00".repeat(64);
+        for signature in [None, Some(forged)] {
+            assert!(matches!(
+                authenticate(
+                    &storage,
+                    "signed-agent",
+                    &message,
+                    signature.as_ref(),
+                    false
+                )
+                .await,
+                Err(ApiError::Unauthorized(_))
+            ));
+        }
+
+        // A valid signature does not carry over to different content.
+        let signature = sign(&message, 3, &key);
+        request.output_base64 = "aG8=".into();
+        let tampered = request.signed_message().unwrap();
+        assert!(
+            authenticate(&storage, "signed-agent", &tampered, Some(&signature), false)
+                .await
+                .is_err()
+        );
+
+        // Unknown signers may stay unsigned unless signatures are required.
+        assert!(authenticate(&storage, "anonymous", &message, None, false)
+            .await
+            .is_ok());
+        assert!(authenticate(&storage, "anonymous", &message, None, true)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn signed_requests_cannot_be_replayed() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let mut agent = AgentRegistrationRequest::new("replayed-agent", "replayed");
+        agent.public_key = Some(hex::encode(key.verifying_key().to_bytes()));
+        storage.register_agent(agent).await.unwrap();
+        let message = SignedMessage::UnbondStake {
+            agent_id: "replayed-agent".into(),
+            amount: 10,
+        };
+
+        let signature = sign(&message, 1, &key);
+        authenticate(&storage, "replayed-agent", &message, Some(&signature), true)
+            .await
+            .unwrap();
+        assert!(matches!(
+            authenticate(&storage, "replayed-agent", &message, Some(&signature), true).await,
+            Err(ApiError::Unauthorized(_))
+        ));
+
+        // Verifying alone leaves the nonce for the submission to consume.
+        let signature = sign(&message, 2, &key);
+        for _ in 0..2 {
+            verify_request(&storage, "replayed-agent", &message, Some(&signature), true)
+                .await
+                .unwrap();
+        }
+        consume_nonce(&storage, "replayed-agent", Some(&signature))
+            .await
+            .unwrap();
+        assert!(consume_nonce(&storage, "replayed-agent", Some(&signature))
+            .await
+            .is_err());
+
+        // Expired signatures and ones outliving MAX_SIGNATURE_TTL are refused.
+        let now = current_unix_timestamp();
+        for expires_at in [now - 1, now + MAX_SIGNATURE_TTL + 60] {
+            let signature = RequestSignature::sign(message.clone(), [3; 16], expires_at, &key);
+            assert!(matches!(
+                authenticate(&storage, "replayed-agent", &message, Some(&signature), true).await,
+                Err(ApiError::Unauthorized(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn only_the_registered_key_can_re_register() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let owner = SigningKey::from_bytes(&[5u8; 32]);
+        let attacker = SigningKey::from_bytes(&[6u8; 32]);
+        let mut registration = AgentRegistrationRequest::new("owned-agent", "owned");
+        registration.public_key = Some(hex::encode(owner.verifying_key().to_bytes()));
+        // A first registration proves possession of the key unless
+        // signatures are turned off.
+        assert!(matches!(
+            authenticate_registration(&storage, &registration, true).await,
+            Err(ApiError::Unauthorized(_))
+        ));
+        authenticate_registration(&storage, &registration, false)
+            .await
+            .unwrap();
+        registration.signature = Some(sign(&registration.signed_message().unwrap(), 3, &owner));
+        authenticate_registration(&storage, &registration, true)
+            .await
+            .unwrap();
+        storage.register_agent(registration.clone()).await.unwrap();
+
+        // Swapping in another key, unsigned or signed by that key, fails.
+        let mut hijack = registration.clone();
+        hijack.public_key = Some(hex::encode(attacker.verifying_key().to_bytes()));
+        assert!(matches!(
+            authenticate_registration(&storage, &hijack, true).await,
+            Err(ApiError::Unauthorized(_))
+        ));
+        hijack.signature = Some(sign(&hijack.signed_message().unwrap(), 1, &attacker));
+        assert!(matches!(
+            authenticate_registration(&storage, &hijack, true).await,
+            Err(ApiError::Unauthorized(_))
+        ));
+
+        // The owner can rotate its key.
+        let mut rotation = hijack.clone();
+        rotation.signature = Some(sign(&rotation.signed_message().unwrap(), 2, &owner));
+        authenticate_registration(&storage, &rotation, true)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn requester_requests_are_checked_against_the_requester_key() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let owner = SigningKey::from_bytes(&[11u8; 32]);
+        let attacker = SigningKey::from_bytes(&[12u8; 32]);
+        let mut registration = RequesterRegistrationRequest {
+            id: "requester".into(),
+            public_key: hex::encode(owner.verifying_key().to_bytes()),
+            signature: None,
+        };
+        // Registrations prove possession of the key being registered.
+        assert!(matches!(
+            authenticate_requester_registration(&storage, &registration).await,
+            Err(ApiError::Unauthorized(_))
+        ));
+        registration.signature = Some(sign(&registration.signed_message().unwrap(), 1, &attacker));
+        assert!(matches!(
+            authenticate_requester_registration(&storage, &registration).await,
+            Err(ApiError::Unauthorized(_))
+        ));
+        registration.signature = Some(sign(&registration.signed_message().unwrap(), 2, &owner));
+        authenticate_requester_registration(&storage, &registration)
+            .await
+            .unwrap();
+        storage
+            .upsert_requester(StoredRequester::from_registration(registration, 0))
+            .await
+            .unwrap();
+
+        // An agent registered under the same id does not vouch for it.
+        let mut agent = AgentRegistrationRequest::new("requester", "squatter");
+        agent.public_key = Some(hex::encode(attacker.verifying_key().to_bytes()));
+        storage.register_agent(agent).await.unwrap();
+        let message = SignedMessage::CancelTask {
+            task_id: "task".into(),
+            requester_id: "requester".into(),
+        };
+        assert!(matches!(
+            authenticate(
+                &storage,
+                "requester",
+                &message,
+                Some(&sign(&message, 3, &attacker)),
+                true
+            )
+            .await,
+            Err(ApiError::Unauthorized(_))
+        ));
+        assert!(matches!(
+            authenticate(&storage, "requester", &message, None, false).await,
+            Err(ApiError::Unauthorized(_))
+        ));
+        authenticate(
+            &storage,
+            "requester",
+            &message,
+            Some(&sign(&message, 4, &owner)),
+            true,
+        )
+        .await
+        .unwrap();
+
+        // Rotating the key is signed by the key on record.
+        let mut rotation = RequesterRegistrationRequest {
+            id: "requester".into(),
+            public_key: hex::encode(attacker.verifying_key().to_bytes()),
+            signature: None,
+        };
+        rotation.signature = Some(sign(&rotation.signed_message().unwrap(), 5, &attacker));
+        assert!(matches!(
+            authenticate_requester_registration(&storage, &rotation).await,
+            Err(ApiError::Unauthorized(_))
+        ));
+        rotation.signature = Some(sign(&rotation.signed_message().unwrap(), 6, &owner));
+        authenticate_requester_registration(&storage, &rotation)
+            .await
+            .unwrap();
+    }
+}
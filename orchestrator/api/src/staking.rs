@@ -0,0 +1,405 @@
+//! Agent stake.
+//!
+//! Agents put stake behind their bids. Bonding moves funds from the agent's
+//! ledger account `user:<agent_id>`, which must hold them (see
+//! [`ledger::fund`]), into `stake:<agent_id>`; bidding requires
+//! `constants::economics::MIN_AGENT_STAKE` bonded, and each bid locks its
+//! deposit out of the free stake until the bid loses or the task is over
+//! (see [`crate::ledger`]). An agent may have at most
+//! `constants::auction::MAX_CONCURRENT_BIDS` bids on tasks still taking bids.
+//!
+//! Unbonding moves free stake to `unbonding:<agent_id>`, where it stays for
+//! `constants::economics::UNBONDING_PERIOD` before it can be withdrawn back
+//! to the agent's account. Unbonding stake can still be slashed, and an agent
+//! with live bids cannot unbond below the minimum stake. Bids and unbonding
+//! take the agent's lock (see [`Storage::lock_agent`]), so their checks and
+//! bookings do not interleave.
+
+use std::sync::Arc;
+
+use ainur_core::{constants, AuctionError, CoreError, ReputationError, StandardEconomics};
+
+use crate::error::ApiError;
+use crate::ledger::{self, deposit_account};
+use crate::model::{LedgerTransactionKind, StakeView, StoredTask, TaskStatus};
+use crate::storage::Storage;
+
+/// Tasks whose bids count towards the concurrent bid limit.
+const OPEN: [TaskStatus; 2] = [TaskStatus::Open, TaskStatus::Bidding];
+
+/// Tasks whose bid deposits may still be locked.
+const RUNNING: [TaskStatus; 5] = [
+    TaskStatus::Open,
+    TaskStatus::Bidding,
+    TaskStatus::Allocated,
+    TaskStatus::Executing,
+    TaskStatus::Disputed,
+];
+
+/// Account holding the free bonded stake of `agent_id`.
+pub fn stake_account(agent_id: &str) -> String {
+    format!("stake:{agent_id}")
+}
+
+/// Account holding the unbonding stake of `agent_id`.
+pub fn unbonding_account(agent_id: &str) -> String {
+    format!("unbonding:{agent_id}")
+}
+
+/// Bond `amount` of `agent_id`'s funds as stake. The agent's account must
+/// hold at least `amount`.
+pub async fn bond(
+    storage: &Arc<dyn Storage>,
+    agent_id: &str,
+    amount: u128,
+    now: u64,
+) -> Result<StakeView, ApiError> {
+    if amount == 0 {
+        return Err(ApiError::BadRequest(
+            "stake amount must be greater than zero".into(),
+        ));
+    }
+    let account = ledger::user_account(agent_id);
+    let bonded = ledger::move_funded(
+        storage,
+        LedgerTransactionKind::Bond,
+        None,
+        "stake bonded",
+        (&account, &stake_account(agent_id)),
+        amount,
+        now,
+    )
+    .await?;
+    if !bonded {
+        let available = ledger::balance(storage, &account).await?.max(0) as u128;
+        return Err(insufficient(amount, available));
+    }
+    stake(storage, agent_id, now).await
+}
+
+/// Start unbonding `amount` of `agent_id`'s free stake. While any of its
+/// bids still has a deposit locked, the agent keeps at least
+/// `constants::economics::MIN_AGENT_STAKE` bonded.
+pub async fn unbond(
+    storage: &Arc<dyn Storage>,
+    agent_id: &str,
+    amount: u128,
+    now: u64,
+) -> Result<StakeView, ApiError> {
+    if amount == 0 {
+        return Err(ApiError::BadRequest(
+            "stake amount must be greater than zero".into(),
+        ));
+    }
+    let _lock = storage.lock_agent(agent_id).await?;
+    let view = stake(storage, agent_id, now).await?;
+    let min = constants::economics::MIN_AGENT_STAKE;
+    if view.locked > 0 && view.bonded().saturating_sub(amount) < min {
+        return Err(ApiError::BadRequest(format!(
+            "agent {agent_id} has live bids and must keep {min} bonded; {} is bonded",
+            view.bonded()
+        )));
+    }
+    let account = stake_account(agent_id);
+    let unbonded = ledger::move_funded(
+        storage,
+        LedgerTransactionKind::Unbond,
+        None,
+        "stake unbonding",
+        (&account, &unbonding_account(agent_id)),
+        amount,
+        now,
+    )
+    .await?;
+    if !unbonded {
+        let available = ledger::balance(storage, &account).await?.max(0) as u128;
+        return Err(insufficient(amount, available));
+    }
+    stake(storage, agent_id, now).await
+}
+
+/// Return `agent_id`'s stake that finished unbonding to its account.
+pub async fn withdraw(
+    storage: &Arc<dyn Storage>,
+    agent_id: &str,
+    now: u64,
+) -> Result<StakeView, ApiError> {
+    let view = stake(storage, agent_id, now).await?;
+    ledger::move_funds(
+        storage,
+        LedgerTransactionKind::Withdrawal,
+        None,
+        "stake withdrawn",
+        (
+            &unbonding_account(agent_id),
+            &ledger::user_account(agent_id),
+        ),
+        view.withdrawable,
+        now,
+    )
+    .await?;
+    stake(storage, agent_id, now).await
+}
+
+/// `agent_id`'s stake as of `now`.
+pub async fn stake(
+    storage: &Arc<dyn Storage>,
+    agent_id: &str,
+    now: u64,
+) -> Result<StakeView, ApiError> {
+    let free = ledger::balance(storage, &stake_account(agent_id)).await?;
+    let mut locked: i128 = 0;
+    for bid in storage.get_bids_for_agent(agent_id, &RUNNING).await? {
+        locked += ledger::balance(storage, &deposit_account(&bid.id))
+            .await?
+            .max(0);
+    }
+    let open_bids = storage.get_bids_for_agent(agent_id, &OPEN).await?.len();
+
+    // Unbonding amounts mature in order, so whatever matured and was not yet
+    // withdrawn can be.
+    let unbonding = unbonding_account(agent_id);
+    let transactions = storage
+        .get_ledger_transactions_for_account(&unbonding)
+        .await?;
+    let (mut pending, mut matured) = (0i128, 0i128);
+    for tx in &transactions {
+        let posted = tx.posted_to(&unbonding);
+        pending += posted;
+        match tx.kind {
+            LedgerTransactionKind::Unbond
+                if tx.created_at + constants::economics::UNBONDING_PERIOD <= now =>
+            {
+                matured += posted
+            }
+            LedgerTransactionKind::Withdrawal => matured += posted,
+            _ => {}
+        }
+    }
+
+    Ok(StakeView {
+        agent_id: agent_id.to_string(),
+        free: free.max(0) as u128,
+        locked: locked as u128,
+        unbonding: pending.max(0) as u128,
+        withdrawable: matured.clamp(0, pending.max(0)) as u128,
+        open_bids,
+    })
+}
+
+/// Check that `agent_id` may bid on `task`: enough stake bonded, room for
+/// another open bid, and enough free stake for the bid deposit. Callers hold
+/// [`Storage::lock_agent`] until the bid is stored.
+pub async fn ensure_can_bid(
+    storage: &Arc<dyn Storage>,
+    task: &StoredTask,
+    agent_id: &str,
+    now: u64,
+) -> Result<(), ApiError> {
+    let view = stake(storage, agent_id, now).await?;
+    if view.bonded() < constants::economics::MIN_AGENT_STAKE {
+        return Err(insufficient(
+            constants::economics::MIN_AGENT_STAKE,
+            view.bonded(),
+        ));
+    }
+    if view.open_bids >= constants::auction::MAX_CONCURRENT_BIDS {
+        return Err(CoreError::from(AuctionError::InvalidBid(format!(
+            "agent {agent_id} already has {} open bids",
+            view.open_bids
+        )))
+        .into());
+    }
+    let deposit = StandardEconomics::new(task.task.budget.max_cost).bid_deposit();
+    if view.free < deposit {
+        return Err(insufficient(deposit, view.free));
+    }
+    Ok(())
+}
+
+fn insufficient(required: u128, available: u128) -> ApiError {
+    CoreError::from(ReputationError::InsufficientStake {
+        required,
+        available,
+    })
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{BidSubmissionRequest, StoredAllocation, StoredBid, TaskSubmissionRequest};
+    use crate::storage::InMemoryStorage;
+    use ainur_core::Violation;
+
+    const MIN: u128 = constants::economics::MIN_AGENT_STAKE;
+
+    async fn open_task(storage: &Arc<dyn Storage>) -> StoredTask {
+        let task = StoredTask::from_submission(TaskSubmissionRequest {
+            client_task_id: None,
+            requester_id: "requester".into(),
+            description: "stake me".into(),
+            task_type: "echo".into(),
+            input_base64: String::new(),
+            max_budget: 1_000_000,
+            deadline: 0,
+            bid_window_secs: None,
+            domain: None,
+            requirements: Default::default(),
+            output_format: None,
+            verification_level: None,
+            payment_schedule: None,
+            milestones: Vec::new(),
+            escrow_required: None,
+            signature: None,
+        })
+        .unwrap();
+        storage.insert_task(task.clone()).await.unwrap();
+        task
+    }
+
+    async fn place_bid(storage: &Arc<dyn Storage>, task: &StoredTask) -> StoredBid {
+        let bid = StoredBid::from_submission(
+            BidSubmissionRequest {
+                task_id: task.id.clone(),
+                agent_id: "agent".into(),
+                value: Some(500_000),
+                commitment: None,
+                quality_score: 90,
+                completion_time: 60,
+                guarantees: Vec::new(),
+                signature: None,
+            },
+            task,
+        )
+        .unwrap();
+        storage.insert_bid(bid.clone()).await.unwrap();
+        ledger::lock_bid_deposit(storage, task, &bid, 0)
+            .await
+            .unwrap();
+        bid
+    }
+
+    #[tokio::test]
+    async fn bidding_requires_stake_and_locks_a_deposit() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let task = open_task(&storage).await;
+        assert!(matches!(
+            ensure_can_bid(&storage, &task, "agent", 0).await,
+            Err(ApiError::BadRequest(_))
+        ));
+
+        // The stake and the bid placement fee.
+        ledger::fund(&storage, "user:agent", MIN + 1_000, 0)
+            .await
+            .unwrap();
+        bond(&storage, "agent", MIN, 0).await.unwrap();
+        ensure_can_bid(&storage, &task, "agent", 0).await.unwrap();
+        let bid = place_bid(&storage, &task).await;
+        let view = stake(&storage, "agent", 0).await.unwrap();
+        assert_eq!((view.free, view.locked), (MIN - 10_000, 10_000));
+        assert_eq!(view.bonded(), MIN);
+        assert_eq!(view.open_bids, 1);
+
+        // Free stake below the minimum still bids while the deposit is locked.
+        ensure_can_bid(&storage, &task, "agent", 0).await.unwrap();
+        ledger::release_bid_deposit(&storage, &task, &bid, 0)
+            .await
+            .unwrap();
+        let view = stake(&storage, "agent", 0).await.unwrap();
+        assert_eq!((view.free, view.locked), (MIN, 0));
+    }
+
+    #[tokio::test]
+    async fn live_bids_keep_the_minimum_bonded_and_unbonding_stake_is_slashable() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let task = open_task(&storage).await;
+        ledger::fund(&storage, "user:agent", MIN + 1_500, 0)
+            .await
+            .unwrap();
+        bond(&storage, "agent", MIN + 500, 0).await.unwrap();
+        let bid = place_bid(&storage, &task).await;
+
+        // Only the stake above the minimum may leave while the bid is live.
+        assert!(matches!(
+            unbond(&storage, "agent", 501, 0).await,
+            Err(ApiError::BadRequest(_))
+        ));
+        unbond(&storage, "agent", 500, 0).await.unwrap();
+        ledger::release_bid_deposit(&storage, &task, &bid, 0)
+            .await
+            .unwrap();
+        let view = unbond(&storage, "agent", MIN, 0).await.unwrap();
+        assert_eq!((view.free, view.unbonding), (0, MIN + 500));
+
+        // A slash reaches the stake that is still unbonding.
+        let allocation = StoredAllocation {
+            id: "allocation".into(),
+            task_id: task.id.clone(),
+            agent_id: "agent".into(),
+            bid_id: bid.id.clone(),
+            payment: 100_000,
+            social_welfare: 0,
+            allocated_at: 0,
+        };
+        let failure = Violation::TaskFailure(task.task.id);
+        ledger::slash(&storage, &task, &allocation, &failure, 0)
+            .await
+            .unwrap();
+        let view = stake(&storage, "agent", 0).await.unwrap();
+        assert_eq!(view.unbonding, MIN + 500 - 10_000);
+        assert_eq!(
+            ledger::account(&storage, "user:agent")
+                .await
+                .unwrap()
+                .balance,
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn unbonded_stake_is_withdrawable_after_the_unbonding_period() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let period = constants::economics::UNBONDING_PERIOD;
+        assert!(matches!(
+            bond(&storage, "agent", 1_000, 0).await,
+            Err(ApiError::BadRequest(_))
+        ));
+        ledger::fund(&storage, "user:agent", 1_000, 0)
+            .await
+            .unwrap();
+        bond(&storage, "agent", 1_000, 0).await.unwrap();
+        assert!(bond(&storage, "agent", 1, 0).await.is_err());
+        assert!(unbond(&storage, "agent", 1_001, 0).await.is_err());
+
+        let view = unbond(&storage, "agent", 400, 10).await.unwrap();
+        assert_eq!(
+            (view.free, view.unbonding, view.withdrawable),
+            (600, 400, 0)
+        );
+        unbond(&storage, "agent", 100, 20).await.unwrap();
+
+        // Nothing leaves before the period is over.
+        withdraw(&storage, "agent", period).await.unwrap();
+        assert_eq!(
+            ledger::account(&storage, "user:agent")
+                .await
+                .unwrap()
+                .balance,
+            0
+        );
+
+        let view = withdraw(&storage, "agent", period + 15).await.unwrap();
+        assert_eq!((view.unbonding, view.withdrawable), (100, 0));
+        assert_eq!(
+            ledger::account(&storage, "user:agent")
+                .await
+                .unwrap()
+                .balance,
+            400
+        );
+
+        let view = stake(&storage, "agent", period + 20).await.unwrap();
+        assert_eq!(view.withdrawable, 100);
+    }
+}
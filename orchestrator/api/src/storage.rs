@@ -1,16 +1,24 @@
+use std::any::Any;
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use async_trait::async_trait;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 
 use crate::error::ApiError;
 use crate::model::{
-    AgentRegistrationRequest, BidView, ResultView, StoredBid, StoredResult, StoredTask, TaskView,
+    AgentRegistrationRequest, BidFilter, BidView, Page, PageCursor, PageRequest, ResultView,
+    StoredAllocation, StoredBid, StoredDispute, StoredIdempotencyKey, StoredLedgerTransaction,
+    StoredMilestoneCompletion, StoredNotification, StoredReputation, StoredRequester, StoredResult,
+    StoredTask, StoredViolation, StoredWebhook, StoredWebhookDelivery, TaskFilter, TaskStatus,
+    TaskView, WebhookDeliveryStatus,
 };
 use base64::{engine::general_purpose, Engine as _};
 
 #[cfg(feature = "postgres")]
 use {
+    crate::model::{agent_key, chain_verification_level, domain_name, resolution_name},
     sqlx::{postgres::PgPoolOptions, Pool, Postgres, Row},
     tracing::info,
     uuid::Uuid,
@@ -21,28 +29,236 @@ pub trait Storage: Send + Sync {
     async fn register_agent(&self, agent: AgentRegistrationRequest) -> Result<(), ApiError>;
     async fn get_agent(&self, id: &str) -> Result<AgentRegistrationRequest, ApiError>;
     async fn list_agents(&self) -> Result<Vec<AgentRegistrationRequest>, ApiError>;
+    /// A page of agents ordered by id.
+    async fn list_agents_page(
+        &self,
+        page: &PageRequest,
+    ) -> Result<Page<AgentRegistrationRequest>, ApiError>;
+
+    async fn upsert_requester(&self, requester: StoredRequester) -> Result<(), ApiError>;
+    async fn get_requester(&self, id: &str) -> Result<StoredRequester, ApiError>;
 
     async fn insert_task(&self, task: StoredTask) -> Result<(), ApiError>;
+    /// Store `task` unconditionally, bumping its stored version.
     async fn upsert_task(&self, task: StoredTask) -> Result<(), ApiError>;
+    /// Store `task` if the stored copy is still at `task.version`, then bump
+    /// `task.version`. A task written since it was read is a conflict, so
+    /// concurrent status changes cannot overwrite each other.
+    async fn update_task(&self, task: &mut StoredTask) -> Result<(), ApiError>;
     async fn get_task(&self, id: &str) -> Result<StoredTask, ApiError>;
-    async fn list_tasks(&self) -> Result<Vec<StoredTask>, ApiError>;
+    /// A page of the tasks matching `filter`, newest first.
+    async fn list_tasks(
+        &self,
+        filter: &TaskFilter,
+        page: &PageRequest,
+    ) -> Result<Page<StoredTask>, ApiError>;
+    async fn list_tasks_with_status(
+        &self,
+        statuses: &[TaskStatus],
+    ) -> Result<Vec<StoredTask>, ApiError>;
 
     async fn insert_bid(&self, bid: StoredBid) -> Result<(), ApiError>;
+    async fn get_bid(&self, id: &str) -> Result<StoredBid, ApiError>;
+    async fn update_bid(&self, bid: StoredBid) -> Result<(), ApiError>;
     async fn get_bids_for_task(&self, task_id: &str) -> Result<Vec<StoredBid>, ApiError>;
+    /// A page of the bids on `task_id` matching `filter`, oldest first.
+    async fn list_bids_for_task(
+        &self,
+        task_id: &str,
+        filter: &BidFilter,
+        page: &PageRequest,
+    ) -> Result<Page<StoredBid>, ApiError>;
+    /// Bids placed by `agent_id` on tasks currently in one of `statuses`.
+    async fn get_bids_for_agent(
+        &self,
+        agent_id: &str,
+        statuses: &[TaskStatus],
+    ) -> Result<Vec<StoredBid>, ApiError>;
 
     async fn insert_result(&self, result: StoredResult) -> Result<(), ApiError>;
-    async fn get_result_for_task(&self, task_id: &str) -> Result<StoredResult, ApiError>;
+    /// Every result reported for the task, oldest first. `Consensus(n)` tasks
+    /// collect one result per allocated agent.
+    async fn get_results_for_task(&self, task_id: &str) -> Result<Vec<StoredResult>, ApiError>;
+
+    async fn insert_allocation(&self, allocation: StoredAllocation) -> Result<(), ApiError>;
+    async fn get_allocations_for_task(
+        &self,
+        task_id: &str,
+    ) -> Result<Vec<StoredAllocation>, ApiError>;
+
+    async fn insert_violation(&self, violation: StoredViolation) -> Result<(), ApiError>;
+    async fn get_violations_for_agent(
+        &self,
+        agent_id: &str,
+    ) -> Result<Vec<StoredViolation>, ApiError>;
+
+    async fn insert_notification(&self, notification: StoredNotification) -> Result<(), ApiError>;
+    /// Notifications sent to `agent_id`, oldest first.
+    async fn get_notifications_for_agent(
+        &self,
+        agent_id: &str,
+    ) -> Result<Vec<StoredNotification>, ApiError>;
+
+    async fn insert_webhook(&self, webhook: StoredWebhook) -> Result<(), ApiError>;
+    async fn get_webhook(&self, id: &str) -> Result<StoredWebhook, ApiError>;
+    /// Webhooks registered by `requester_id`, oldest first.
+    async fn get_webhooks_for_requester(
+        &self,
+        requester_id: &str,
+    ) -> Result<Vec<StoredWebhook>, ApiError>;
+    async fn upsert_webhook_delivery(
+        &self,
+        delivery: StoredWebhookDelivery,
+    ) -> Result<(), ApiError>;
+    /// Pending deliveries whose next attempt is due at `now`, oldest first.
+    async fn get_due_webhook_deliveries(
+        &self,
+        now: u64,
+    ) -> Result<Vec<StoredWebhookDelivery>, ApiError>;
+    /// Deliveries queued for `webhook_id`, oldest first.
+    async fn get_webhook_deliveries(
+        &self,
+        webhook_id: &str,
+    ) -> Result<Vec<StoredWebhookDelivery>, ApiError>;
+
+    /// Claim `record.key`, returning the existing record instead when the
+    /// key is already claimed. Keys claimed before `expired_before` are
+    /// dropped first.
+    async fn reserve_idempotency_key(
+        &self,
+        record: StoredIdempotencyKey,
+        expired_before: u64,
+    ) -> Result<Option<StoredIdempotencyKey>, ApiError>;
+    /// Store the response of the request that claimed `key`.
+    async fn complete_idempotency_key(
+        &self,
+        key: &str,
+        response: serde_json::Value,
+    ) -> Result<(), ApiError>;
+    /// Drop a claim so the key can be used again.
+    async fn release_idempotency_key(&self, key: &str) -> Result<(), ApiError>;
+
+    /// Record `nonce` as used by `signer_id` until `expires_at`, returning
+    /// `false` when it already is. Nonces that expired before `now` are
+    /// forgotten.
+    async fn use_request_nonce(
+        &self,
+        signer_id: &str,
+        nonce: &str,
+        expires_at: u64,
+        now: u64,
+    ) -> Result<bool, ApiError>;
+
+    async fn upsert_dispute(&self, dispute: StoredDispute) -> Result<(), ApiError>;
+    async fn get_dispute(&self, id: &str) -> Result<StoredDispute, ApiError>;
+    async fn get_disputes_for_task(&self, task_id: &str) -> Result<Vec<StoredDispute>, ApiError>;
+
+    async fn upsert_milestone_completion(
+        &self,
+        completion: StoredMilestoneCompletion,
+    ) -> Result<(), ApiError>;
+    async fn get_milestone_completion(
+        &self,
+        id: &str,
+    ) -> Result<StoredMilestoneCompletion, ApiError>;
+    /// Completions reported for `task_id`, oldest first.
+    async fn get_milestone_completions_for_task(
+        &self,
+        task_id: &str,
+    ) -> Result<Vec<StoredMilestoneCompletion>, ApiError>;
+
+    /// Record a balanced ledger transaction atomically.
+    async fn insert_ledger_transaction(
+        &self,
+        transaction: StoredLedgerTransaction,
+    ) -> Result<(), ApiError>;
+    /// Record a balanced ledger transaction unless it would leave `account`
+    /// with a negative balance, returning whether it was recorded. The
+    /// balance check and the insert are atomic.
+    async fn insert_funded_ledger_transaction(
+        &self,
+        transaction: StoredLedgerTransaction,
+        account: &str,
+    ) -> Result<bool, ApiError>;
+    /// Take the lock on `agent_id`'s stake and bids, held until the returned
+    /// guard is dropped. Checks of an agent's stake or open bids that lead to
+    /// a booking hold it across both, so concurrent requests cannot pass the
+    /// same check.
+    async fn lock_agent(&self, agent_id: &str) -> Result<AgentLock, ApiError>;
+    /// Transactions posting to `account`, oldest first.
+    async fn get_ledger_transactions_for_account(
+        &self,
+        account: &str,
+    ) -> Result<Vec<StoredLedgerTransaction>, ApiError>;
+
+    async fn upsert_reputation(&self, reputation: StoredReputation) -> Result<(), ApiError>;
+    async fn get_reputation(&self, agent_id: &str) -> Result<Option<StoredReputation>, ApiError>;
+    async fn list_reputations(&self) -> Result<Vec<StoredReputation>, ApiError>;
 
     async fn dashboard_counts(&self) -> Result<(usize, usize, usize, usize), ApiError>;
 }
 
+fn agent_cursor(agent: &AgentRegistrationRequest) -> PageCursor {
+    PageCursor {
+        created_at: 0,
+        id: agent.id.clone(),
+    }
+}
+
+fn task_cursor(task: &StoredTask) -> PageCursor {
+    PageCursor {
+        created_at: task.created_at,
+        id: task.id.clone(),
+    }
+}
+
+/// Agents bid at most once per task, on every backend.
+fn duplicate_bid(bid: &StoredBid) -> ApiError {
+    ApiError::Conflict(format!(
+        "agent {} already bid on task {}",
+        bid.agent_id, bid.task_id
+    ))
+}
+
+fn stale_task(task: &StoredTask) -> ApiError {
+    ApiError::Conflict(format!(
+        "task {} was changed concurrently; retry the request",
+        task.id
+    ))
+}
+
+fn bid_cursor(bid: &StoredBid) -> PageCursor {
+    PageCursor {
+        created_at: bid.created_at,
+        id: bid.id.clone(),
+    }
+}
+
+/// Guard returned by [`Storage::lock_agent`]; dropping it releases the lock.
+pub struct AgentLock {
+    _guard: Box<dyn Any + Send>,
+}
+
 /// In-memory storage used for development and tests.
 #[derive(Default)]
 pub struct InMemoryStorage {
     agents: RwLock<HashMap<String, AgentRegistrationRequest>>,
+    requesters: RwLock<HashMap<String, StoredRequester>>,
     tasks: RwLock<HashMap<String, StoredTask>>,
     bids: RwLock<HashMap<String, StoredBid>>,
     results: RwLock<HashMap<String, StoredResult>>,
+    allocations: RwLock<HashMap<String, StoredAllocation>>,
+    violations: RwLock<HashMap<String, StoredViolation>>,
+    notifications: RwLock<Vec<StoredNotification>>,
+    webhooks: RwLock<Vec<StoredWebhook>>,
+    webhook_deliveries: RwLock<Vec<StoredWebhookDelivery>>,
+    idempotency_keys: RwLock<HashMap<String, StoredIdempotencyKey>>,
+    request_nonces: RwLock<HashMap<(String, String), u64>>,
+    disputes: RwLock<HashMap<String, StoredDispute>>,
+    milestone_completions: RwLock<HashMap<String, StoredMilestoneCompletion>>,
+    ledger: RwLock<Vec<StoredLedgerTransaction>>,
+    agent_locks: Mutex<HashMap<String, Arc<Mutex<()>>>>,
+    reputations: RwLock<HashMap<String, StoredReputation>>,
     cursor: RwLock<Option<(u64, u32)>>,
 }
 
@@ -67,18 +283,61 @@ impl Storage for InMemoryStorage {
         Ok(agents.values().cloned().collect())
     }
 
+    async fn list_agents_page(
+        &self,
+        page: &PageRequest,
+    ) -> Result<Page<AgentRegistrationRequest>, ApiError> {
+        let agents = self.agents.read().await;
+        let mut out: Vec<AgentRegistrationRequest> = agents
+            .values()
+            .filter(|a| page.after.as_ref().is_none_or(|c| a.id > c.id))
+            .cloned()
+            .collect();
+        out.sort_by(|a, b| a.id.cmp(&b.id));
+        out.truncate(page.limit + 1);
+        Ok(Page::from_overfetch(out, page.limit, agent_cursor))
+    }
+
+    async fn upsert_requester(&self, requester: StoredRequester) -> Result<(), ApiError> {
+        let mut requesters = self.requesters.write().await;
+        requesters.insert(requester.id.clone(), requester);
+        Ok(())
+    }
+
+    async fn get_requester(&self, id: &str) -> Result<StoredRequester, ApiError> {
+        let requesters = self.requesters.read().await;
+        requesters
+            .get(id)
+            .cloned()
+            .ok_or_else(|| ApiError::NotFound(format!("requester {id} not found")))
+    }
+
     async fn insert_task(&self, task: StoredTask) -> Result<(), ApiError> {
         let mut tasks = self.tasks.write().await;
         tasks.insert(task.id.clone(), task);
         Ok(())
     }
 
-    async fn upsert_task(&self, task: StoredTask) -> Result<(), ApiError> {
+    async fn upsert_task(&self, mut task: StoredTask) -> Result<(), ApiError> {
         let mut tasks = self.tasks.write().await;
+        task.version = tasks.get(&task.id).map_or(0, |t| t.version + 1);
         tasks.insert(task.id.clone(), task);
         Ok(())
     }
 
+    async fn update_task(&self, task: &mut StoredTask) -> Result<(), ApiError> {
+        let mut tasks = self.tasks.write().await;
+        let Some(stored) = tasks.get_mut(&task.id) else {
+            return Err(ApiError::NotFound(format!("task {} not found", task.id)));
+        };
+        if stored.version != task.version {
+            return Err(stale_task(task));
+        }
+        task.version += 1;
+        *stored = task.clone();
+        Ok(())
+    }
+
     async fn get_task(&self, id: &str) -> Result<StoredTask, ApiError> {
         let tasks = self.tasks.read().await;
         tasks
@@ -87,17 +346,70 @@ impl Storage for InMemoryStorage {
             .ok_or_else(|| ApiError::NotFound(format!("task {id} not found")))
     }
 
-    async fn list_tasks(&self) -> Result<Vec<StoredTask>, ApiError> {
+    async fn list_tasks(
+        &self,
+        filter: &TaskFilter,
+        page: &PageRequest,
+    ) -> Result<Page<StoredTask>, ApiError> {
+        let tasks = self.tasks.read().await;
+        let mut out: Vec<StoredTask> = tasks
+            .values()
+            .filter(|t| {
+                filter.matches(t)
+                    && page
+                        .after
+                        .as_ref()
+                        .is_none_or(|c| (t.created_at, &t.id) < (c.created_at, &c.id))
+            })
+            .cloned()
+            .collect();
+        out.sort_by(|a, b| (b.created_at, &b.id).cmp(&(a.created_at, &a.id)));
+        out.truncate(page.limit + 1);
+        Ok(Page::from_overfetch(out, page.limit, task_cursor))
+    }
+
+    async fn list_tasks_with_status(
+        &self,
+        statuses: &[TaskStatus],
+    ) -> Result<Vec<StoredTask>, ApiError> {
         let tasks = self.tasks.read().await;
-        Ok(tasks.values().cloned().collect())
+        Ok(tasks
+            .values()
+            .filter(|t| statuses.contains(&t.status))
+            .cloned()
+            .collect())
     }
 
     async fn insert_bid(&self, bid: StoredBid) -> Result<(), ApiError> {
         let mut bids = self.bids.write().await;
+        if bids
+            .values()
+            .any(|b| b.id == bid.id || (b.task_id == bid.task_id && b.agent_id == bid.agent_id))
+        {
+            return Err(duplicate_bid(&bid));
+        }
         bids.insert(bid.id.clone(), bid);
         Ok(())
     }
 
+    async fn get_bid(&self, id: &str) -> Result<StoredBid, ApiError> {
+        let bids = self.bids.read().await;
+        bids.get(id)
+            .cloned()
+            .ok_or_else(|| ApiError::NotFound(format!("bid {id} not found")))
+    }
+
+    async fn update_bid(&self, bid: StoredBid) -> Result<(), ApiError> {
+        let mut bids = self.bids.write().await;
+        match bids.get_mut(&bid.id) {
+            Some(existing) => {
+                *existing = bid;
+                Ok(())
+            }
+            None => Err(ApiError::NotFound(format!("bid {} not found", bid.id))),
+        }
+    }
+
     async fn get_bids_for_task(&self, task_id: &str) -> Result<Vec<StoredBid>, ApiError> {
         let bids = self.bids.read().await;
         Ok(bids
@@ -107,19 +419,373 @@ impl Storage for InMemoryStorage {
             .collect())
     }
 
+    async fn list_bids_for_task(
+        &self,
+        task_id: &str,
+        filter: &BidFilter,
+        page: &PageRequest,
+    ) -> Result<Page<StoredBid>, ApiError> {
+        let bids = self.bids.read().await;
+        let mut out: Vec<StoredBid> = bids
+            .values()
+            .filter(|b| {
+                b.task_id == task_id
+                    && filter.matches(b)
+                    && page
+                        .after
+                        .as_ref()
+                        .is_none_or(|c| (b.created_at, &b.id) > (c.created_at, &c.id))
+            })
+            .cloned()
+            .collect();
+        out.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+        out.truncate(page.limit + 1);
+        Ok(Page::from_overfetch(out, page.limit, bid_cursor))
+    }
+
+    async fn get_bids_for_agent(
+        &self,
+        agent_id: &str,
+        statuses: &[TaskStatus],
+    ) -> Result<Vec<StoredBid>, ApiError> {
+        let tasks = self.tasks.read().await;
+        let bids = self.bids.read().await;
+        Ok(bids
+            .values()
+            .filter(|b| {
+                b.agent_id == agent_id
+                    && tasks
+                        .get(&b.task_id)
+                        .is_some_and(|t| statuses.contains(&t.status))
+            })
+            .cloned()
+            .collect())
+    }
+
     async fn insert_result(&self, result: StoredResult) -> Result<(), ApiError> {
         let mut results = self.results.write().await;
+        // One result per task and agent; a resubmission replaces the old one.
+        results.retain(|_, r| !(r.task_id == result.task_id && r.agent_id == result.agent_id));
         results.insert(result.id.clone(), result);
         Ok(())
     }
 
-    async fn get_result_for_task(&self, task_id: &str) -> Result<StoredResult, ApiError> {
+    async fn get_results_for_task(&self, task_id: &str) -> Result<Vec<StoredResult>, ApiError> {
         let results = self.results.read().await;
-        results
+        let mut out: Vec<StoredResult> = results
+            .values()
+            .filter(|r| r.task_id == task_id)
+            .cloned()
+            .collect();
+        out.sort_by_key(|r| r.created_at);
+        Ok(out)
+    }
+
+    async fn insert_allocation(&self, allocation: StoredAllocation) -> Result<(), ApiError> {
+        let mut allocations = self.allocations.write().await;
+        let duplicate = allocations
+            .values()
+            .any(|a| a.task_id == allocation.task_id && a.agent_id == allocation.agent_id);
+        if !duplicate {
+            allocations.insert(allocation.id.clone(), allocation);
+        }
+        Ok(())
+    }
+
+    async fn get_allocations_for_task(
+        &self,
+        task_id: &str,
+    ) -> Result<Vec<StoredAllocation>, ApiError> {
+        let allocations = self.allocations.read().await;
+        Ok(allocations
+            .values()
+            .filter(|a| a.task_id == task_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn insert_violation(&self, violation: StoredViolation) -> Result<(), ApiError> {
+        let mut violations = self.violations.write().await;
+        let duplicate = violations.values().any(|v| {
+            v.task_id == violation.task_id
+                && v.agent_id == violation.agent_id
+                && v.kind() == violation.kind()
+        });
+        if !duplicate {
+            violations.insert(violation.id.clone(), violation);
+        }
+        Ok(())
+    }
+
+    async fn get_violations_for_agent(
+        &self,
+        agent_id: &str,
+    ) -> Result<Vec<StoredViolation>, ApiError> {
+        let violations = self.violations.read().await;
+        let mut out: Vec<StoredViolation> = violations
+            .values()
+            .filter(|v| v.agent_id == agent_id)
+            .cloned()
+            .collect();
+        out.sort_by_key(|v| v.recorded_at);
+        Ok(out)
+    }
+
+    async fn insert_notification(&self, notification: StoredNotification) -> Result<(), ApiError> {
+        let mut notifications = self.notifications.write().await;
+        notifications.push(notification);
+        Ok(())
+    }
+
+    async fn get_notifications_for_agent(
+        &self,
+        agent_id: &str,
+    ) -> Result<Vec<StoredNotification>, ApiError> {
+        let notifications = self.notifications.read().await;
+        Ok(notifications
+            .iter()
+            .filter(|n| n.agent_id == agent_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn insert_webhook(&self, webhook: StoredWebhook) -> Result<(), ApiError> {
+        let mut webhooks = self.webhooks.write().await;
+        webhooks.push(webhook);
+        Ok(())
+    }
+
+    async fn get_webhook(&self, id: &str) -> Result<StoredWebhook, ApiError> {
+        let webhooks = self.webhooks.read().await;
+        webhooks
+            .iter()
+            .find(|w| w.id == id)
+            .cloned()
+            .ok_or_else(|| ApiError::NotFound(format!("webhook {id} not found")))
+    }
+
+    async fn get_webhooks_for_requester(
+        &self,
+        requester_id: &str,
+    ) -> Result<Vec<StoredWebhook>, ApiError> {
+        let webhooks = self.webhooks.read().await;
+        Ok(webhooks
+            .iter()
+            .filter(|w| w.requester_id == requester_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn upsert_webhook_delivery(
+        &self,
+        delivery: StoredWebhookDelivery,
+    ) -> Result<(), ApiError> {
+        let mut deliveries = self.webhook_deliveries.write().await;
+        match deliveries.iter_mut().find(|d| d.id == delivery.id) {
+            Some(existing) => *existing = delivery,
+            None => deliveries.push(delivery),
+        }
+        Ok(())
+    }
+
+    async fn get_due_webhook_deliveries(
+        &self,
+        now: u64,
+    ) -> Result<Vec<StoredWebhookDelivery>, ApiError> {
+        let deliveries = self.webhook_deliveries.read().await;
+        let mut out: Vec<StoredWebhookDelivery> = deliveries
+            .iter()
+            .filter(|d| d.status == WebhookDeliveryStatus::Pending && d.next_attempt_at <= now)
+            .cloned()
+            .collect();
+        out.sort_by_key(|d| d.next_attempt_at);
+        Ok(out)
+    }
+
+    async fn get_webhook_deliveries(
+        &self,
+        webhook_id: &str,
+    ) -> Result<Vec<StoredWebhookDelivery>, ApiError> {
+        let deliveries = self.webhook_deliveries.read().await;
+        Ok(deliveries
+            .iter()
+            .filter(|d| d.webhook_id == webhook_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn reserve_idempotency_key(
+        &self,
+        record: StoredIdempotencyKey,
+        expired_before: u64,
+    ) -> Result<Option<StoredIdempotencyKey>, ApiError> {
+        let mut keys = self.idempotency_keys.write().await;
+        keys.retain(|_, existing| existing.created_at >= expired_before);
+        if let Some(existing) = keys.get(&record.key) {
+            return Ok(Some(existing.clone()));
+        }
+        keys.insert(record.key.clone(), record);
+        Ok(None)
+    }
+
+    async fn complete_idempotency_key(
+        &self,
+        key: &str,
+        response: serde_json::Value,
+    ) -> Result<(), ApiError> {
+        let mut keys = self.idempotency_keys.write().await;
+        let record = keys
+            .get_mut(key)
+            .ok_or_else(|| ApiError::NotFound(format!("idempotency key {key} not found")))?;
+        record.response = Some(response);
+        Ok(())
+    }
+
+    async fn release_idempotency_key(&self, key: &str) -> Result<(), ApiError> {
+        let mut keys = self.idempotency_keys.write().await;
+        keys.remove(key);
+        Ok(())
+    }
+
+    async fn use_request_nonce(
+        &self,
+        signer_id: &str,
+        nonce: &str,
+        expires_at: u64,
+        now: u64,
+    ) -> Result<bool, ApiError> {
+        let mut nonces = self.request_nonces.write().await;
+        nonces.retain(|_, expiry| *expiry >= now);
+        match nonces.entry((signer_id.to_string(), nonce.to_string())) {
+            Entry::Occupied(_) => Ok(false),
+            Entry::Vacant(entry) => {
+                entry.insert(expires_at);
+                Ok(true)
+            }
+        }
+    }
+
+    async fn upsert_dispute(&self, dispute: StoredDispute) -> Result<(), ApiError> {
+        let mut disputes = self.disputes.write().await;
+        disputes.insert(dispute.id.clone(), dispute);
+        Ok(())
+    }
+
+    async fn get_dispute(&self, id: &str) -> Result<StoredDispute, ApiError> {
+        let disputes = self.disputes.read().await;
+        disputes
+            .get(id)
+            .cloned()
+            .ok_or_else(|| ApiError::NotFound(format!("dispute {id} not found")))
+    }
+
+    async fn get_disputes_for_task(&self, task_id: &str) -> Result<Vec<StoredDispute>, ApiError> {
+        let disputes = self.disputes.read().await;
+        let mut out: Vec<StoredDispute> = disputes
+            .values()
+            .filter(|d| d.task_id.as_deref() == Some(task_id))
+            .cloned()
+            .collect();
+        out.sort_by_key(|d| d.filed_at);
+        Ok(out)
+    }
+
+    async fn upsert_milestone_completion(
+        &self,
+        completion: StoredMilestoneCompletion,
+    ) -> Result<(), ApiError> {
+        let mut completions = self.milestone_completions.write().await;
+        completions.insert(completion.id.clone(), completion);
+        Ok(())
+    }
+
+    async fn get_milestone_completion(
+        &self,
+        id: &str,
+    ) -> Result<StoredMilestoneCompletion, ApiError> {
+        let completions = self.milestone_completions.read().await;
+        completions
+            .get(id)
+            .cloned()
+            .ok_or_else(|| ApiError::NotFound(format!("milestone completion {id} not found")))
+    }
+
+    async fn get_milestone_completions_for_task(
+        &self,
+        task_id: &str,
+    ) -> Result<Vec<StoredMilestoneCompletion>, ApiError> {
+        let completions = self.milestone_completions.read().await;
+        let mut out: Vec<StoredMilestoneCompletion> = completions
             .values()
-            .find(|r| r.task_id == task_id)
+            .filter(|c| c.task_id == task_id)
+            .cloned()
+            .collect();
+        out.sort_by_key(|c| c.submitted_at);
+        Ok(out)
+    }
+
+    async fn insert_ledger_transaction(
+        &self,
+        transaction: StoredLedgerTransaction,
+    ) -> Result<(), ApiError> {
+        let mut ledger = self.ledger.write().await;
+        ledger.push(transaction);
+        Ok(())
+    }
+
+    async fn insert_funded_ledger_transaction(
+        &self,
+        transaction: StoredLedgerTransaction,
+        account: &str,
+    ) -> Result<bool, ApiError> {
+        let mut ledger = self.ledger.write().await;
+        let balance: i128 = ledger.iter().map(|tx| tx.posted_to(account)).sum();
+        if balance + transaction.posted_to(account) < 0 {
+            return Ok(false);
+        }
+        ledger.push(transaction);
+        Ok(true)
+    }
+
+    async fn lock_agent(&self, agent_id: &str) -> Result<AgentLock, ApiError> {
+        let lock = self
+            .agent_locks
+            .lock()
+            .await
+            .entry(agent_id.to_string())
+            .or_default()
+            .clone();
+        Ok(AgentLock {
+            _guard: Box::new(lock.lock_owned().await),
+        })
+    }
+
+    async fn get_ledger_transactions_for_account(
+        &self,
+        account: &str,
+    ) -> Result<Vec<StoredLedgerTransaction>, ApiError> {
+        let ledger = self.ledger.read().await;
+        Ok(ledger
+            .iter()
+            .filter(|tx| tx.postings.iter().any(|p| p.account == account))
             .cloned()
-            .ok_or_else(|| ApiError::NotFound(format!("no result for task {task_id}")))
+            .collect())
+    }
+
+    async fn upsert_reputation(&self, reputation: StoredReputation) -> Result<(), ApiError> {
+        let mut reputations = self.reputations.write().await;
+        reputations.insert(reputation.agent_id.clone(), reputation);
+        Ok(())
+    }
+
+    async fn get_reputation(&self, agent_id: &str) -> Result<Option<StoredReputation>, ApiError> {
+        let reputations = self.reputations.read().await;
+        Ok(reputations.get(agent_id).cloned())
+    }
+
+    async fn list_reputations(&self) -> Result<Vec<StoredReputation>, ApiError> {
+        let reputations = self.reputations.read().await;
+        Ok(reputations.values().cloned().collect())
     }
 
     async fn dashboard_counts(&self) -> Result<(usize, usize, usize, usize), ApiError> {
@@ -128,9 +794,20 @@ impl Storage for InMemoryStorage {
         let total_tasks = tasks.len();
         let completed = tasks
             .values()
-            .filter(|t| matches!(t.status, crate::model::TaskStatus::Completed))
+            .filter(|t| matches!(t.status, TaskStatus::Completed))
+            .count();
+        let pending = tasks
+            .values()
+            .filter(|t| {
+                matches!(
+                    t.status,
+                    TaskStatus::Open
+                        | TaskStatus::Bidding
+                        | TaskStatus::Allocated
+                        | TaskStatus::Executing
+                )
+            })
             .count();
-        let pending = total_tasks.saturating_sub(completed);
         Ok((agents.len(), total_tasks, completed, pending))
     }
 }
@@ -255,71 +932,192 @@ impl PostgresStorage {
     }
 
     fn status_to_str(status: crate::model::TaskStatus) -> &'static str {
-        match status {
-            crate::model::TaskStatus::Pending => "pending",
-            crate::model::TaskStatus::Completed => "completed",
-        }
+        status.as_str()
     }
 
     fn str_to_status(s: &str) -> Result<crate::model::TaskStatus, ApiError> {
-        match s {
-            "pending" => Ok(crate::model::TaskStatus::Pending),
-            "completed" => Ok(crate::model::TaskStatus::Completed),
-            other => Err(ApiError::Internal(format!("unknown task status {other}"))),
-        }
+        TaskStatus::parse(s).ok_or_else(|| ApiError::Internal(format!("unknown task status {s}")))
     }
 
     fn serialize<T: serde::Serialize>(value: &T) -> Result<serde_json::Value, ApiError> {
         serde_json::to_value(value).map_err(|e| ApiError::Internal(e.to_string()))
     }
-}
 
-#[cfg(feature = "postgres")]
-#[async_trait]
-impl Storage for PostgresStorage {
-    async fn register_agent(&self, agent: AgentRegistrationRequest) -> Result<(), ApiError> {
+    /// Insert `transaction` and its entries as part of `tx`.
+    async fn insert_ledger_rows(
+        tx: &mut sqlx::Transaction<'_, Postgres>,
+        transaction: &StoredLedgerTransaction,
+    ) -> Result<(), ApiError> {
+        let transaction_uuid = Self::parse_uuid(&transaction.id, "ledger transaction id")?;
+        let task_uuid = transaction
+            .task_id
+            .as_deref()
+            .map(|id| Self::parse_uuid(id, "ledger task_id"))
+            .transpose()?;
+        let stored_json = Self::serialize(transaction)?;
+
+        // Amounts exceed BIGINT, so they are bound as text and cast.
         sqlx::query(
             r#"
-            INSERT INTO agents (id, label)
-            VALUES ($1, $2)
-            ON CONFLICT (id) DO UPDATE SET label = EXCLUDED.label
+            INSERT INTO ledger_transactions (id, task_id, kind, amount, created_at, stored_json)
+            VALUES ($1, $2, $3, $4::NUMERIC, to_timestamp($5), $6)
             "#,
         )
-        .bind(&agent.id)
-        .bind(&agent.label)
-        .execute(&self.pool)
+        .bind(transaction_uuid)
+        .bind(task_uuid)
+        .bind(transaction.kind.as_str())
+        .bind(transaction.amount.to_string())
+        .bind(transaction.created_at as i64)
+        .bind(stored_json)
+        .execute(&mut **tx)
         .await
-        .map_err(|e| ApiError::Internal(format!("failed to upsert agent: {e}")))?;
+        .map_err(|e| ApiError::Internal(format!("failed to insert ledger transaction: {e}")))?;
+        for posting in &transaction.postings {
+            sqlx::query(
+                r#"
+                INSERT INTO ledger_entries (transaction_id, account, amount)
+                VALUES ($1, $2, $3::NUMERIC)
+                "#,
+            )
+            .bind(transaction_uuid)
+            .bind(&posting.account)
+            .bind(posting.amount.to_string())
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| ApiError::Internal(format!("failed to insert ledger entry: {e}")))?;
+        }
+        Ok(())
+    }
+
+    /// Agents mirrored from chain events have no stored registration and
+    /// come back with only their id and label.
+    fn agent_from_row(row: &sqlx::postgres::PgRow) -> Result<AgentRegistrationRequest, ApiError> {
+        match row.get::<Option<serde_json::Value>, _>("stored_json") {
+            Some(json) => serde_json::from_value(json)
+                .map_err(|e| ApiError::Internal(format!("failed to decode agent: {e}"))),
+            None => Ok(AgentRegistrationRequest::new(
+                row.get::<String, _>("id"),
+                row.get::<String, _>("label"),
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "postgres")]
+#[async_trait]
+impl Storage for PostgresStorage {
+    async fn register_agent(&self, agent: AgentRegistrationRequest) -> Result<(), ApiError> {
+        let stored_json = Self::serialize(&agent)?;
+        let capabilities = Self::serialize(&agent.capability_tags())?;
+        let domains = Self::serialize(&agent.domains.iter().map(domain_name).collect::<Vec<_>>())?;
+        let endpoints = Self::serialize(&agent.endpoints)?;
+        sqlx::query(
+            r#"
+            INSERT INTO agents (id, label, capabilities, domains, public_key, endpoints, verification_level, stored_json)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (id) DO UPDATE SET
+                label = EXCLUDED.label,
+                capabilities = EXCLUDED.capabilities,
+                domains = EXCLUDED.domains,
+                public_key = EXCLUDED.public_key,
+                endpoints = EXCLUDED.endpoints,
+                verification_level = EXCLUDED.verification_level,
+                stored_json = EXCLUDED.stored_json,
+                updated_at = now()
+            "#,
+        )
+        .bind(&agent.id)
+        .bind(&agent.label)
+        .bind(capabilities)
+        .bind(domains)
+        .bind(&agent.public_key)
+        .bind(endpoints)
+        .bind(chain_verification_level(&agent.verification_level))
+        .bind(stored_json)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to upsert agent: {e}")))?;
         Ok(())
     }
 
     async fn get_agent(&self, id: &str) -> Result<AgentRegistrationRequest, ApiError> {
-        let row = sqlx::query("SELECT id, label FROM agents WHERE id = $1")
+        let row = sqlx::query("SELECT id, label, stored_json FROM agents WHERE id = $1")
             .bind(id)
             .fetch_optional(&self.pool)
             .await
             .map_err(|e| ApiError::Internal(format!("failed to fetch agent: {e}")))?;
 
         let row = row.ok_or_else(|| ApiError::NotFound(format!("agent {id} not found")))?;
-        Ok(AgentRegistrationRequest {
-            id: row.get::<String, _>("id"),
-            label: row.get::<String, _>("label"),
-        })
+        Self::agent_from_row(&row)
     }
 
     async fn list_agents(&self) -> Result<Vec<AgentRegistrationRequest>, ApiError> {
-        let rows = sqlx::query("SELECT id, label FROM agents ORDER BY created_at DESC")
-            .fetch_all(&self.pool)
+        let rows =
+            sqlx::query("SELECT id, label, stored_json FROM agents ORDER BY created_at DESC")
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| ApiError::Internal(format!("failed to list agents: {e}")))?;
+
+        rows.iter().map(Self::agent_from_row).collect()
+    }
+
+    async fn list_agents_page(
+        &self,
+        page: &PageRequest,
+    ) -> Result<Page<AgentRegistrationRequest>, ApiError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, label, stored_json FROM agents
+            WHERE ($1::text IS NULL OR id > $1)
+            ORDER BY id ASC
+            LIMIT $2
+            "#,
+        )
+        .bind(page.after.as_ref().map(|c| c.id.as_str()))
+        .bind(page.limit as i64 + 1)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to list agents: {e}")))?;
+
+        let agents = rows
+            .iter()
+            .map(Self::agent_from_row)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Page::from_overfetch(agents, page.limit, agent_cursor))
+    }
+
+    async fn upsert_requester(&self, requester: StoredRequester) -> Result<(), ApiError> {
+        let stored_json = Self::serialize(&requester)?;
+        sqlx::query(
+            r#"
+            INSERT INTO requesters (id, public_key, registered_at, stored_json)
+            VALUES ($1, $2, to_timestamp($3), $4)
+            ON CONFLICT (id) DO UPDATE SET
+                public_key = EXCLUDED.public_key,
+                registered_at = EXCLUDED.registered_at,
+                stored_json = EXCLUDED.stored_json
+            "#,
+        )
+        .bind(&requester.id)
+        .bind(&requester.public_key)
+        .bind(requester.registered_at as i64)
+        .bind(stored_json)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to upsert requester: {e}")))?;
+        Ok(())
+    }
+
+    async fn get_requester(&self, id: &str) -> Result<StoredRequester, ApiError> {
+        let row = sqlx::query("SELECT stored_json FROM requesters WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
             .await
-            .map_err(|e| ApiError::Internal(format!("failed to list agents: {e}")))?;
+            .map_err(|e| ApiError::Internal(format!("failed to fetch requester: {e}")))?;
 
-        Ok(rows
-            .into_iter()
-            .map(|row| AgentRegistrationRequest {
-                id: row.get::<String, _>("id"),
-                label: row.get::<String, _>("label"),
-            })
-            .collect())
+        let row = row.ok_or_else(|| ApiError::NotFound(format!("requester {id} not found")))?;
+        serde_json::from_value(row.get("stored_json"))
+            .map_err(|e| ApiError::Internal(format!("failed to decode requester: {e}")))
     }
 
     async fn insert_task(&self, task: StoredTask) -> Result<(), ApiError> {
@@ -387,7 +1185,8 @@ impl Storage for PostgresStorage {
                 deadline = EXCLUDED.deadline,
                 status = EXCLUDED.status,
                 updated_at = now(),
-                stored_json = EXCLUDED.stored_json
+                version = tasks.version + 1,
+                stored_json = jsonb_set(EXCLUDED.stored_json, '{version}', to_jsonb(tasks.version + 1))
             "#,
         )
         .bind(task_uuid)
@@ -408,6 +1207,47 @@ impl Storage for PostgresStorage {
         Ok(())
     }
 
+    async fn update_task(&self, task: &mut StoredTask) -> Result<(), ApiError> {
+        let task_uuid = Self::parse_uuid(&task.id, "task id")?;
+        let budget: i64 = task
+            .task
+            .budget
+            .max_cost
+            .try_into()
+            .map_err(|_| ApiError::BadRequest("max_cost exceeds i64".into()))?;
+        let mut next = task.clone();
+        next.version += 1;
+        let stored_json = Self::serialize(&next)?;
+        let updated = sqlx::query(
+            r#"
+            UPDATE tasks SET
+                max_budget = $2,
+                deadline = to_timestamp($3),
+                status = $4,
+                updated_at = now(),
+                version = version + 1,
+                stored_json = $5
+            WHERE id = $1 AND version = $6
+            "#,
+        )
+        .bind(task_uuid)
+        .bind(budget)
+        .bind(task.task.deadline as i64)
+        .bind(Self::status_to_str(task.status))
+        .bind(stored_json)
+        .bind(task.version as i64)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to update task: {e}")))?;
+        if updated.rows_affected() == 0 {
+            // Missing tasks are reported as such; anything else is stale.
+            self.get_task(&task.id).await?;
+            return Err(stale_task(task));
+        }
+        *task = next;
+        Ok(())
+    }
+
     async fn get_task(&self, id: &str) -> Result<StoredTask, ApiError> {
         let task_uuid = Self::parse_uuid(id, "task id")?;
         let row = sqlx::query("SELECT stored_json, status FROM tasks WHERE id = $1")
@@ -426,11 +1266,74 @@ impl Storage for PostgresStorage {
         Ok(task)
     }
 
-    async fn list_tasks(&self) -> Result<Vec<StoredTask>, ApiError> {
-        let rows = sqlx::query("SELECT stored_json, status FROM tasks ORDER BY created_at DESC")
-            .fetch_all(&self.pool)
-            .await
-            .map_err(|e| ApiError::Internal(format!("failed to list tasks: {e}")))?;
+    async fn list_tasks(
+        &self,
+        filter: &TaskFilter,
+        page: &PageRequest,
+    ) -> Result<Page<StoredTask>, ApiError> {
+        let after_id = page
+            .after
+            .as_ref()
+            .map(|c| Self::parse_uuid(&c.id, "cursor"))
+            .transpose()?;
+        let requester = filter
+            .requester_id
+            .as_deref()
+            .map(|r| agent_key(r).as_bytes().to_vec());
+        // A deadline of 0 (stored as the epoch) means the task has none.
+        let rows = sqlx::query(
+            r#"
+            SELECT stored_json, status FROM tasks
+            WHERE ($1::text IS NULL OR status = $1)
+              AND ($2::bytea IS NULL OR requester_id = $2)
+              AND ($3::text IS NULL OR task_type = $3)
+              AND ($4::bigint IS NULL OR created_at >= to_timestamp($4))
+              AND ($5::bigint IS NULL OR created_at <= to_timestamp($5))
+              AND (($6::bigint IS NULL AND $7::bigint IS NULL) OR deadline > to_timestamp(0))
+              AND ($6::bigint IS NULL OR deadline >= to_timestamp($6))
+              AND ($7::bigint IS NULL OR deadline <= to_timestamp($7))
+              AND ($8::bigint IS NULL OR (created_at, id) < (to_timestamp($8), $9::uuid))
+            ORDER BY created_at DESC, id DESC
+            LIMIT $10
+            "#,
+        )
+        .bind(filter.status.map(Self::status_to_str))
+        .bind(requester)
+        .bind(&filter.task_type)
+        .bind(filter.created_from.map(|t| t as i64))
+        .bind(filter.created_to.map(|t| t as i64))
+        .bind(filter.deadline_from.map(|t| t as i64))
+        .bind(filter.deadline_to.map(|t| t as i64))
+        .bind(page.after.as_ref().map(|c| c.created_at as i64))
+        .bind(after_id)
+        .bind(page.limit as i64 + 1)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to list tasks: {e}")))?;
+
+        let mut out = Vec::with_capacity(rows.len());
+        for row in rows {
+            let mut task: StoredTask = serde_json::from_value(row.get("stored_json"))
+                .map_err(|e| ApiError::Internal(format!("failed to decode task: {e}")))?;
+            let status_str: String = row.get("status");
+            task.status = Self::str_to_status(&status_str)?;
+            out.push(task);
+        }
+        Ok(Page::from_overfetch(out, page.limit, task_cursor))
+    }
+
+    async fn list_tasks_with_status(
+        &self,
+        statuses: &[TaskStatus],
+    ) -> Result<Vec<StoredTask>, ApiError> {
+        let statuses: Vec<&str> = statuses.iter().map(|s| s.as_str()).collect();
+        let rows = sqlx::query(
+            "SELECT stored_json, status FROM tasks WHERE status = ANY($1) ORDER BY created_at ASC",
+        )
+        .bind(&statuses)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to list tasks: {e}")))?;
 
         let mut out = Vec::with_capacity(rows.len());
         for row in rows {
@@ -452,11 +1355,11 @@ impl Storage for PostgresStorage {
             .value
             .try_into()
             .map_err(|_| ApiError::BadRequest("bid value exceeds i64".into()))?;
-        sqlx::query(
+        let inserted = sqlx::query(
             r#"
-            INSERT INTO bids (id, task_id, agent_id, value, quality_score, completion_time, created_at, stored_json)
-            VALUES ($1, $2, $3, $4, $5, $6, to_timestamp($7), $8)
-            ON CONFLICT (id) DO NOTHING
+            INSERT INTO bids (id, task_id, agent_id, value, quality_score, completion_time, created_at, stored_json, commitment, nonce)
+            VALUES ($1, $2, $3, $4, $5, $6, to_timestamp($7), $8, $9, $10)
+            ON CONFLICT DO NOTHING
             "#,
         )
         .bind(bid_uuid)
@@ -467,9 +1370,51 @@ impl Storage for PostgresStorage {
         .bind(bid.bid.completion_time as i64)
         .bind(bid.created_at as i64)
         .bind(stored_json)
+        .bind(&bid.commitment)
+        .bind(&bid.nonce)
         .execute(&self.pool)
         .await
         .map_err(|e| ApiError::Internal(format!("failed to insert bid: {e}")))?;
+        // Either the id or (task_id, agent_id) is taken.
+        if inserted.rows_affected() == 0 {
+            return Err(duplicate_bid(&bid));
+        }
+        Ok(())
+    }
+
+    async fn get_bid(&self, id: &str) -> Result<StoredBid, ApiError> {
+        let bid_uuid = Self::parse_uuid(id, "bid id")?;
+        let row = sqlx::query("SELECT stored_json FROM bids WHERE id = $1")
+            .bind(bid_uuid)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| ApiError::Internal(format!("failed to fetch bid: {e}")))?;
+
+        let row = row.ok_or_else(|| ApiError::NotFound(format!("bid {id} not found")))?;
+        serde_json::from_value(row.get("stored_json"))
+            .map_err(|e| ApiError::Internal(format!("failed to decode bid: {e}")))
+    }
+
+    async fn update_bid(&self, bid: StoredBid) -> Result<(), ApiError> {
+        let bid_uuid = Self::parse_uuid(&bid.id, "bid id")?;
+        let stored_json = Self::serialize(&bid)?;
+        let bid_value: i64 = bid
+            .bid
+            .value
+            .try_into()
+            .map_err(|_| ApiError::BadRequest("bid value exceeds i64".into()))?;
+        let updated =
+            sqlx::query("UPDATE bids SET value = $2, nonce = $3, stored_json = $4 WHERE id = $1")
+                .bind(bid_uuid)
+                .bind(bid_value)
+                .bind(&bid.nonce)
+                .bind(stored_json)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| ApiError::Internal(format!("failed to update bid: {e}")))?;
+        if updated.rows_affected() == 0 {
+            return Err(ApiError::NotFound(format!("bid {} not found", bid.id)));
+        }
         Ok(())
     }
 
@@ -490,6 +1435,80 @@ impl Storage for PostgresStorage {
         Ok(out)
     }
 
+    async fn list_bids_for_task(
+        &self,
+        task_id: &str,
+        filter: &BidFilter,
+        page: &PageRequest,
+    ) -> Result<Page<StoredBid>, ApiError> {
+        let task_uuid = Self::parse_uuid(task_id, "task id")?;
+        let after_id = page
+            .after
+            .as_ref()
+            .map(|c| Self::parse_uuid(&c.id, "cursor"))
+            .transpose()?;
+        let value_bound = |v: u128| i64::try_from(v).unwrap_or(i64::MAX);
+        let rows = sqlx::query(
+            r#"
+            SELECT stored_json FROM bids
+            WHERE task_id = $1
+              AND ($2::text IS NULL OR stored_json->>'agent_id' = $2)
+              AND ($3::bigint IS NULL OR value >= $3)
+              AND ($4::bigint IS NULL OR value <= $4)
+              AND ($5::bigint IS NULL OR (created_at, id) > (to_timestamp($5), $6::uuid))
+            ORDER BY created_at ASC, id ASC
+            LIMIT $7
+            "#,
+        )
+        .bind(task_uuid)
+        .bind(&filter.agent_id)
+        .bind(filter.min_value.map(value_bound))
+        .bind(filter.max_value.map(value_bound))
+        .bind(page.after.as_ref().map(|c| c.created_at as i64))
+        .bind(after_id)
+        .bind(page.limit as i64 + 1)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to list bids: {e}")))?;
+
+        let mut out = Vec::with_capacity(rows.len());
+        for row in rows {
+            let bid: StoredBid = serde_json::from_value(row.get("stored_json"))
+                .map_err(|e| ApiError::Internal(format!("failed to decode bid: {e}")))?;
+            out.push(bid);
+        }
+        Ok(Page::from_overfetch(out, page.limit, bid_cursor))
+    }
+
+    async fn get_bids_for_agent(
+        &self,
+        agent_id: &str,
+        statuses: &[TaskStatus],
+    ) -> Result<Vec<StoredBid>, ApiError> {
+        let statuses: Vec<&str> = statuses.iter().map(|s| s.as_str()).collect();
+        let rows = sqlx::query(
+            r#"
+            SELECT b.stored_json
+            FROM bids b
+            JOIN tasks t ON t.id = b.task_id
+            WHERE b.stored_json->>'agent_id' = $1 AND t.status = ANY($2)
+            "#,
+        )
+        .bind(agent_id)
+        .bind(&statuses)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to fetch bids: {e}")))?;
+
+        let mut out = Vec::with_capacity(rows.len());
+        for row in rows {
+            let bid: StoredBid = serde_json::from_value(row.get("stored_json"))
+                .map_err(|e| ApiError::Internal(format!("failed to decode bid: {e}")))?;
+            out.push(bid);
+        }
+        Ok(out)
+    }
+
     async fn insert_result(&self, result: StoredResult) -> Result<(), ApiError> {
         let result_uuid = Self::parse_uuid(&result.id, "result id")?;
         let task_uuid = Self::parse_uuid(&result.task_id, "result task_id")?;
@@ -510,9 +1529,8 @@ impl Storage for PostgresStorage {
             r#"
             INSERT INTO results (id, task_id, agent_id, output_base64, completed_at, proof, stored_json)
             VALUES ($1, $2, $3, $4, to_timestamp($5), $6, $7)
-            ON CONFLICT (task_id) DO UPDATE SET
+            ON CONFLICT (task_id, agent_id) DO UPDATE SET
                 id = EXCLUDED.id,
-                agent_id = EXCLUDED.agent_id,
                 output_base64 = EXCLUDED.output_base64,
                 completed_at = EXCLUDED.completed_at,
                 proof = EXCLUDED.proof,
@@ -533,18 +1551,709 @@ impl Storage for PostgresStorage {
         Ok(())
     }
 
-    async fn get_result_for_task(&self, task_id: &str) -> Result<StoredResult, ApiError> {
+    async fn get_results_for_task(&self, task_id: &str) -> Result<Vec<StoredResult>, ApiError> {
         let task_uuid = Self::parse_uuid(task_id, "task id")?;
-        let row = sqlx::query("SELECT stored_json FROM results WHERE task_id = $1")
-            .bind(task_uuid)
+        let rows = sqlx::query(
+            "SELECT stored_json FROM results WHERE task_id = $1 ORDER BY created_at ASC, id ASC",
+        )
+        .bind(task_uuid)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to fetch results: {e}")))?;
+
+        let mut out = Vec::with_capacity(rows.len());
+        for row in rows {
+            let result: StoredResult = serde_json::from_value(row.get("stored_json"))
+                .map_err(|e| ApiError::Internal(format!("failed to decode result: {e}")))?;
+            out.push(result);
+        }
+        Ok(out)
+    }
+
+    async fn insert_allocation(&self, allocation: StoredAllocation) -> Result<(), ApiError> {
+        let allocation_uuid = Self::parse_uuid(&allocation.id, "allocation id")?;
+        let task_uuid = Self::parse_uuid(&allocation.task_id, "allocation task_id")?;
+        let bid_uuid = Self::parse_uuid(&allocation.bid_id, "allocation bid_id")?;
+        let stored_json = Self::serialize(&allocation)?;
+        let payment: i64 = allocation
+            .payment
+            .try_into()
+            .map_err(|_| ApiError::BadRequest("payment exceeds i64".into()))?;
+        sqlx::query(
+            r#"
+            INSERT INTO allocations (id, task_id, agent_id, bid_id, payment, allocated_at, stored_json)
+            VALUES ($1, $2, $3, $4, $5, to_timestamp($6), $7)
+            ON CONFLICT (task_id, agent_id) DO NOTHING
+            "#,
+        )
+        .bind(allocation_uuid)
+        .bind(task_uuid)
+        .bind(&allocation.agent_id)
+        .bind(bid_uuid)
+        .bind(payment)
+        .bind(allocation.allocated_at as i64)
+        .bind(stored_json)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to insert allocation: {e}")))?;
+        Ok(())
+    }
+
+    async fn get_allocations_for_task(
+        &self,
+        task_id: &str,
+    ) -> Result<Vec<StoredAllocation>, ApiError> {
+        let task_uuid = Self::parse_uuid(task_id, "task id")?;
+        let rows = sqlx::query(
+            "SELECT stored_json FROM allocations WHERE task_id = $1 ORDER BY allocated_at ASC",
+        )
+        .bind(task_uuid)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to fetch allocations: {e}")))?;
+
+        let mut out = Vec::with_capacity(rows.len());
+        for row in rows {
+            let allocation: StoredAllocation = serde_json::from_value(row.get("stored_json"))
+                .map_err(|e| ApiError::Internal(format!("failed to decode allocation: {e}")))?;
+            out.push(allocation);
+        }
+        Ok(out)
+    }
+
+    async fn insert_violation(&self, violation: StoredViolation) -> Result<(), ApiError> {
+        let violation_uuid = Self::parse_uuid(&violation.id, "violation id")?;
+        let task_uuid = Self::parse_uuid(&violation.task_id, "violation task_id")?;
+        let stored_json = Self::serialize(&violation)?;
+        sqlx::query(
+            r#"
+            INSERT INTO violations (id, agent_id, task_id, kind, recorded_at, stored_json)
+            VALUES ($1, $2, $3, $4, to_timestamp($5), $6)
+            ON CONFLICT (task_id, agent_id, kind) DO NOTHING
+            "#,
+        )
+        .bind(violation_uuid)
+        .bind(&violation.agent_id)
+        .bind(task_uuid)
+        .bind(violation.kind())
+        .bind(violation.recorded_at as i64)
+        .bind(stored_json)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to insert violation: {e}")))?;
+        Ok(())
+    }
+
+    async fn get_violations_for_agent(
+        &self,
+        agent_id: &str,
+    ) -> Result<Vec<StoredViolation>, ApiError> {
+        let rows = sqlx::query(
+            "SELECT stored_json FROM violations WHERE agent_id = $1 ORDER BY recorded_at ASC",
+        )
+        .bind(agent_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to fetch violations: {e}")))?;
+
+        let mut out = Vec::with_capacity(rows.len());
+        for row in rows {
+            let violation: StoredViolation = serde_json::from_value(row.get("stored_json"))
+                .map_err(|e| ApiError::Internal(format!("failed to decode violation: {e}")))?;
+            out.push(violation);
+        }
+        Ok(out)
+    }
+
+    async fn insert_notification(&self, notification: StoredNotification) -> Result<(), ApiError> {
+        let notification_uuid = Self::parse_uuid(&notification.id, "notification id")?;
+        let task_uuid = Self::parse_uuid(&notification.task_id, "notification task_id")?;
+        let stored_json = Self::serialize(&notification)?;
+        sqlx::query(
+            r#"
+            INSERT INTO agent_notifications (id, agent_id, task_id, kind, created_at, stored_json)
+            VALUES ($1, $2, $3, $4, to_timestamp($5), $6)
+            "#,
+        )
+        .bind(notification_uuid)
+        .bind(&notification.agent_id)
+        .bind(task_uuid)
+        .bind(notification.kind.as_str())
+        .bind(notification.created_at as i64)
+        .bind(stored_json)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to insert notification: {e}")))?;
+        Ok(())
+    }
+
+    async fn get_notifications_for_agent(
+        &self,
+        agent_id: &str,
+    ) -> Result<Vec<StoredNotification>, ApiError> {
+        let rows = sqlx::query(
+            "SELECT stored_json FROM agent_notifications WHERE agent_id = $1 ORDER BY created_at ASC, seq ASC",
+        )
+        .bind(agent_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to fetch notifications: {e}")))?;
+
+        let mut out = Vec::with_capacity(rows.len());
+        for row in rows {
+            let notification: StoredNotification = serde_json::from_value(row.get("stored_json"))
+                .map_err(|e| {
+                ApiError::Internal(format!("failed to decode notification: {e}"))
+            })?;
+            out.push(notification);
+        }
+        Ok(out)
+    }
+
+    async fn insert_webhook(&self, webhook: StoredWebhook) -> Result<(), ApiError> {
+        let webhook_uuid = Self::parse_uuid(&webhook.id, "webhook id")?;
+        let task_uuid = webhook
+            .task_id
+            .as_deref()
+            .map(|id| Self::parse_uuid(id, "webhook task_id"))
+            .transpose()?;
+        let stored_json = Self::serialize(&webhook)?;
+        sqlx::query(
+            r#"
+            INSERT INTO webhooks (id, requester_id, task_id, url, created_at, stored_json)
+            VALUES ($1, $2, $3, $4, to_timestamp($5), $6)
+            "#,
+        )
+        .bind(webhook_uuid)
+        .bind(&webhook.requester_id)
+        .bind(task_uuid)
+        .bind(&webhook.url)
+        .bind(webhook.created_at as i64)
+        .bind(stored_json)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to insert webhook: {e}")))?;
+        Ok(())
+    }
+
+    async fn get_webhook(&self, id: &str) -> Result<StoredWebhook, ApiError> {
+        let webhook_uuid = Self::parse_uuid(id, "webhook id")?;
+        let row = sqlx::query("SELECT stored_json FROM webhooks WHERE id = $1")
+            .bind(webhook_uuid)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| ApiError::Internal(format!("failed to fetch webhook: {e}")))?;
+
+        let row = row.ok_or_else(|| ApiError::NotFound(format!("webhook {id} not found")))?;
+        serde_json::from_value(row.get("stored_json"))
+            .map_err(|e| ApiError::Internal(format!("failed to decode webhook: {e}")))
+    }
+
+    async fn get_webhooks_for_requester(
+        &self,
+        requester_id: &str,
+    ) -> Result<Vec<StoredWebhook>, ApiError> {
+        let rows = sqlx::query(
+            "SELECT stored_json FROM webhooks WHERE requester_id = $1 ORDER BY created_at ASC, seq ASC",
+        )
+        .bind(requester_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to fetch webhooks: {e}")))?;
+
+        let mut out = Vec::with_capacity(rows.len());
+        for row in rows {
+            let webhook: StoredWebhook = serde_json::from_value(row.get("stored_json"))
+                .map_err(|e| ApiError::Internal(format!("failed to decode webhook: {e}")))?;
+            out.push(webhook);
+        }
+        Ok(out)
+    }
+
+    async fn upsert_webhook_delivery(
+        &self,
+        delivery: StoredWebhookDelivery,
+    ) -> Result<(), ApiError> {
+        let delivery_uuid = Self::parse_uuid(&delivery.id, "webhook delivery id")?;
+        let webhook_uuid = Self::parse_uuid(&delivery.webhook_id, "webhook delivery webhook_id")?;
+        let stored_json = Self::serialize(&delivery)?;
+        sqlx::query(
+            r#"
+            INSERT INTO webhook_deliveries (id, webhook_id, status, next_attempt_at, created_at, stored_json)
+            VALUES ($1, $2, $3, to_timestamp($4), to_timestamp($5), $6)
+            ON CONFLICT (id) DO UPDATE SET
+                status = EXCLUDED.status,
+                next_attempt_at = EXCLUDED.next_attempt_at,
+                stored_json = EXCLUDED.stored_json
+            "#,
+        )
+        .bind(delivery_uuid)
+        .bind(webhook_uuid)
+        .bind(delivery.status.as_str())
+        .bind(delivery.next_attempt_at as i64)
+        .bind(delivery.created_at as i64)
+        .bind(stored_json)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to upsert webhook delivery: {e}")))?;
+        Ok(())
+    }
+
+    async fn get_due_webhook_deliveries(
+        &self,
+        now: u64,
+    ) -> Result<Vec<StoredWebhookDelivery>, ApiError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT stored_json FROM webhook_deliveries
+            WHERE status = 'pending' AND next_attempt_at <= to_timestamp($1)
+            ORDER BY next_attempt_at ASC, seq ASC
+            "#,
+        )
+        .bind(now as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to fetch due webhook deliveries: {e}")))?;
+
+        let mut out = Vec::with_capacity(rows.len());
+        for row in rows {
+            let delivery: StoredWebhookDelivery = serde_json::from_value(row.get("stored_json"))
+                .map_err(|e| {
+                    ApiError::Internal(format!("failed to decode webhook delivery: {e}"))
+                })?;
+            out.push(delivery);
+        }
+        Ok(out)
+    }
+
+    async fn get_webhook_deliveries(
+        &self,
+        webhook_id: &str,
+    ) -> Result<Vec<StoredWebhookDelivery>, ApiError> {
+        let webhook_uuid = Self::parse_uuid(webhook_id, "webhook id")?;
+        let rows = sqlx::query(
+            "SELECT stored_json FROM webhook_deliveries WHERE webhook_id = $1 ORDER BY created_at ASC, seq ASC",
+        )
+        .bind(webhook_uuid)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to fetch webhook deliveries: {e}")))?;
+
+        let mut out = Vec::with_capacity(rows.len());
+        for row in rows {
+            let delivery: StoredWebhookDelivery = serde_json::from_value(row.get("stored_json"))
+                .map_err(|e| {
+                    ApiError::Internal(format!("failed to decode webhook delivery: {e}"))
+                })?;
+            out.push(delivery);
+        }
+        Ok(out)
+    }
+
+    async fn reserve_idempotency_key(
+        &self,
+        record: StoredIdempotencyKey,
+        expired_before: u64,
+    ) -> Result<Option<StoredIdempotencyKey>, ApiError> {
+        sqlx::query("DELETE FROM idempotency_keys WHERE created_at < to_timestamp($1)")
+            .bind(expired_before as i64)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| ApiError::Internal(format!("failed to prune idempotency keys: {e}")))?;
+        let stored_json = Self::serialize(&record)?;
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO idempotency_keys (key, request_hash, created_at, stored_json)
+            VALUES ($1, $2, to_timestamp($3), $4)
+            ON CONFLICT (key) DO NOTHING
+            "#,
+        )
+        .bind(&record.key)
+        .bind(&record.request_hash)
+        .bind(record.created_at as i64)
+        .bind(stored_json)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to reserve idempotency key: {e}")))?;
+        if inserted.rows_affected() == 1 {
+            return Ok(None);
+        }
+
+        let row = sqlx::query("SELECT stored_json FROM idempotency_keys WHERE key = $1")
+            .bind(&record.key)
             .fetch_optional(&self.pool)
             .await
-            .map_err(|e| ApiError::Internal(format!("failed to fetch result: {e}")))?;
+            .map_err(|e| ApiError::Internal(format!("failed to fetch idempotency key: {e}")))?;
+        // Released between the insert and the lookup.
+        let Some(row) = row else {
+            return Err(ApiError::Conflict(format!(
+                "idempotency key {} is in use; retry the request",
+                record.key
+            )));
+        };
+        let existing: StoredIdempotencyKey = serde_json::from_value(row.get("stored_json"))
+            .map_err(|e| ApiError::Internal(format!("failed to decode idempotency key: {e}")))?;
+        Ok(Some(existing))
+    }
 
-        let row = row.ok_or_else(|| ApiError::NotFound(format!("no result for task {task_id}")))?;
-        let result: StoredResult = serde_json::from_value(row.get("stored_json"))
-            .map_err(|e| ApiError::Internal(format!("failed to decode result: {e}")))?;
-        Ok(result)
+    async fn complete_idempotency_key(
+        &self,
+        key: &str,
+        response: serde_json::Value,
+    ) -> Result<(), ApiError> {
+        let updated = sqlx::query(
+            r#"
+            UPDATE idempotency_keys
+            SET stored_json = jsonb_set(stored_json, '{response}', $2), completed_at = now()
+            WHERE key = $1
+            "#,
+        )
+        .bind(key)
+        .bind(response)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to complete idempotency key: {e}")))?;
+        if updated.rows_affected() == 0 {
+            return Err(ApiError::NotFound(format!(
+                "idempotency key {key} not found"
+            )));
+        }
+        Ok(())
+    }
+
+    async fn release_idempotency_key(&self, key: &str) -> Result<(), ApiError> {
+        sqlx::query("DELETE FROM idempotency_keys WHERE key = $1")
+            .bind(key)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| ApiError::Internal(format!("failed to release idempotency key: {e}")))?;
+        Ok(())
+    }
+
+    async fn use_request_nonce(
+        &self,
+        signer_id: &str,
+        nonce: &str,
+        expires_at: u64,
+        now: u64,
+    ) -> Result<bool, ApiError> {
+        sqlx::query("DELETE FROM request_nonces WHERE expires_at < to_timestamp($1)")
+            .bind(now as i64)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| ApiError::Internal(format!("failed to prune request nonces: {e}")))?;
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO request_nonces (signer_id, nonce, expires_at)
+            VALUES ($1, $2, to_timestamp($3))
+            ON CONFLICT (signer_id, nonce) DO NOTHING
+            "#,
+        )
+        .bind(signer_id)
+        .bind(nonce)
+        .bind(expires_at as i64)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to record request nonce: {e}")))?;
+        Ok(inserted.rows_affected() == 1)
+    }
+
+    async fn upsert_dispute(&self, dispute: StoredDispute) -> Result<(), ApiError> {
+        let dispute_uuid = Self::parse_uuid(&dispute.id, "dispute id")?;
+        let task_uuid = dispute
+            .task_id
+            .as_deref()
+            .map(|id| Self::parse_uuid(id, "dispute task_id"))
+            .transpose()?;
+        let stored_json = Self::serialize(&dispute)?;
+        sqlx::query(
+            r#"
+            INSERT INTO disputes (id, task_id, requester_id, status, resolution, filed_at, resolved_at, chain_commitment_id, stored_json)
+            VALUES ($1, $2, $3, $4, $5, to_timestamp($6), to_timestamp($7), $8, $9)
+            ON CONFLICT (id) DO UPDATE SET
+                status = EXCLUDED.status,
+                resolution = EXCLUDED.resolution,
+                resolved_at = EXCLUDED.resolved_at,
+                stored_json = EXCLUDED.stored_json
+            "#,
+        )
+        .bind(dispute_uuid)
+        .bind(task_uuid)
+        .bind(&dispute.requester_id)
+        .bind(dispute.status.as_str())
+        .bind(dispute.resolution.as_ref().map(resolution_name))
+        .bind(dispute.filed_at as i64)
+        .bind(dispute.resolved_at.map(|t| t as i64))
+        .bind(dispute.chain_commitment_id.map(|id| id as i64))
+        .bind(stored_json)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to upsert dispute: {e}")))?;
+        Ok(())
+    }
+
+    async fn get_dispute(&self, id: &str) -> Result<StoredDispute, ApiError> {
+        let dispute_uuid = Self::parse_uuid(id, "dispute id")?;
+        let row = sqlx::query("SELECT stored_json FROM disputes WHERE id = $1")
+            .bind(dispute_uuid)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| ApiError::Internal(format!("failed to fetch dispute: {e}")))?;
+
+        let row = row.ok_or_else(|| ApiError::NotFound(format!("dispute {id} not found")))?;
+        serde_json::from_value(row.get("stored_json"))
+            .map_err(|e| ApiError::Internal(format!("failed to decode dispute: {e}")))
+    }
+
+    async fn get_disputes_for_task(&self, task_id: &str) -> Result<Vec<StoredDispute>, ApiError> {
+        let task_uuid = Self::parse_uuid(task_id, "task id")?;
+        let rows = sqlx::query(
+            "SELECT stored_json FROM disputes WHERE task_id = $1 ORDER BY filed_at ASC",
+        )
+        .bind(task_uuid)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to fetch disputes: {e}")))?;
+
+        let mut out = Vec::with_capacity(rows.len());
+        for row in rows {
+            let dispute: StoredDispute = serde_json::from_value(row.get("stored_json"))
+                .map_err(|e| ApiError::Internal(format!("failed to decode dispute: {e}")))?;
+            out.push(dispute);
+        }
+        Ok(out)
+    }
+
+    async fn upsert_milestone_completion(
+        &self,
+        completion: StoredMilestoneCompletion,
+    ) -> Result<(), ApiError> {
+        let completion_uuid = Self::parse_uuid(&completion.id, "milestone completion id")?;
+        let task_uuid = Self::parse_uuid(&completion.task_id, "milestone completion task_id")?;
+        let stored_json = Self::serialize(&completion)?;
+        sqlx::query(
+            r#"
+            INSERT INTO milestone_completions (id, task_id, milestone_id, agent_id, status, submitted_at, reviewed_at, stored_json)
+            VALUES ($1, $2, $3, $4, $5, to_timestamp($6), to_timestamp($7), $8)
+            ON CONFLICT (id) DO UPDATE SET
+                status = EXCLUDED.status,
+                reviewed_at = EXCLUDED.reviewed_at,
+                stored_json = EXCLUDED.stored_json
+            "#,
+        )
+        .bind(completion_uuid)
+        .bind(task_uuid)
+        .bind(&completion.milestone_id)
+        .bind(&completion.agent_id)
+        .bind(completion.status.as_str())
+        .bind(completion.submitted_at as i64)
+        .bind(completion.reviewed_at.map(|t| t as i64))
+        .bind(stored_json)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to upsert milestone completion: {e}")))?;
+        Ok(())
+    }
+
+    async fn get_milestone_completion(
+        &self,
+        id: &str,
+    ) -> Result<StoredMilestoneCompletion, ApiError> {
+        let completion_uuid = Self::parse_uuid(id, "milestone completion id")?;
+        let row = sqlx::query("SELECT stored_json FROM milestone_completions WHERE id = $1")
+            .bind(completion_uuid)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| {
+                ApiError::Internal(format!("failed to fetch milestone completion: {e}"))
+            })?;
+
+        let row =
+            row.ok_or_else(|| ApiError::NotFound(format!("milestone completion {id} not found")))?;
+        serde_json::from_value(row.get("stored_json"))
+            .map_err(|e| ApiError::Internal(format!("failed to decode milestone completion: {e}")))
+    }
+
+    async fn get_milestone_completions_for_task(
+        &self,
+        task_id: &str,
+    ) -> Result<Vec<StoredMilestoneCompletion>, ApiError> {
+        let task_uuid = Self::parse_uuid(task_id, "task id")?;
+        let rows = sqlx::query(
+            "SELECT stored_json FROM milestone_completions WHERE task_id = $1 ORDER BY submitted_at ASC",
+        )
+        .bind(task_uuid)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to fetch milestone completions: {e}")))?;
+
+        let mut out = Vec::with_capacity(rows.len());
+        for row in rows {
+            let completion: StoredMilestoneCompletion =
+                serde_json::from_value(row.get("stored_json")).map_err(|e| {
+                    ApiError::Internal(format!("failed to decode milestone completion: {e}"))
+                })?;
+            out.push(completion);
+        }
+        Ok(out)
+    }
+
+    async fn insert_ledger_transaction(
+        &self,
+        transaction: StoredLedgerTransaction,
+    ) -> Result<(), ApiError> {
+        let mut tx =
+            self.pool.begin().await.map_err(|e| {
+                ApiError::Internal(format!("failed to open ledger transaction: {e}"))
+            })?;
+        Self::insert_ledger_rows(&mut tx, &transaction).await?;
+        tx.commit()
+            .await
+            .map_err(|e| ApiError::Internal(format!("failed to commit ledger transaction: {e}")))?;
+        Ok(())
+    }
+
+    async fn insert_funded_ledger_transaction(
+        &self,
+        transaction: StoredLedgerTransaction,
+        account: &str,
+    ) -> Result<bool, ApiError> {
+        let mut tx =
+            self.pool.begin().await.map_err(|e| {
+                ApiError::Internal(format!("failed to open ledger transaction: {e}"))
+            })?;
+        // Funded inserts for the same account are serialized, so two of them
+        // cannot both spend the same balance.
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind(account)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| ApiError::Internal(format!("failed to lock ledger account: {e}")))?;
+        let balance: String = sqlx::query_scalar(
+            "SELECT COALESCE(SUM(amount), 0)::TEXT FROM ledger_entries WHERE account = $1",
+        )
+        .bind(account)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to fetch ledger balance: {e}")))?;
+        let balance: i128 = balance
+            .parse()
+            .map_err(|e| ApiError::Internal(format!("failed to decode ledger balance: {e}")))?;
+        if balance + transaction.posted_to(account) < 0 {
+            return Ok(false);
+        }
+        Self::insert_ledger_rows(&mut tx, &transaction).await?;
+        tx.commit()
+            .await
+            .map_err(|e| ApiError::Internal(format!("failed to commit ledger transaction: {e}")))?;
+        Ok(true)
+    }
+
+    async fn lock_agent(&self, agent_id: &str) -> Result<AgentLock, ApiError> {
+        // The advisory lock lasts as long as this otherwise empty transaction,
+        // which rolls back when the guard is dropped. Other orchestrator
+        // instances on the same database wait for it too.
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| ApiError::Internal(format!("failed to open agent lock: {e}")))?;
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind(format!("agent:{agent_id}"))
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| ApiError::Internal(format!("failed to lock agent {agent_id}: {e}")))?;
+        Ok(AgentLock {
+            _guard: Box::new(tx),
+        })
+    }
+
+    async fn get_ledger_transactions_for_account(
+        &self,
+        account: &str,
+    ) -> Result<Vec<StoredLedgerTransaction>, ApiError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT t.stored_json FROM ledger_transactions t
+            JOIN ledger_entries e ON e.transaction_id = t.id
+            WHERE e.account = $1
+            ORDER BY t.seq ASC
+            "#,
+        )
+        .bind(account)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to fetch ledger transactions: {e}")))?;
+
+        let mut out = Vec::with_capacity(rows.len());
+        for row in rows {
+            let transaction: StoredLedgerTransaction =
+                serde_json::from_value(row.get("stored_json")).map_err(|e| {
+                    ApiError::Internal(format!("failed to decode ledger transaction: {e}"))
+                })?;
+            out.push(transaction);
+        }
+        Ok(out)
+    }
+
+    async fn upsert_reputation(&self, reputation: StoredReputation) -> Result<(), ApiError> {
+        let stored_json = Self::serialize(&reputation)?;
+        let r = &reputation.record.reputation;
+        sqlx::query(
+            r#"
+            INSERT INTO agent_reputations (agent_id, quality, reliability, speed, cost_efficiency, overall, updated_at, stored_json)
+            VALUES ($1, $2, $3, $4, $5, $6, to_timestamp($7), $8)
+            ON CONFLICT (agent_id) DO UPDATE SET
+                quality = EXCLUDED.quality,
+                reliability = EXCLUDED.reliability,
+                speed = EXCLUDED.speed,
+                cost_efficiency = EXCLUDED.cost_efficiency,
+                overall = EXCLUDED.overall,
+                updated_at = EXCLUDED.updated_at,
+                stored_json = EXCLUDED.stored_json
+            "#,
+        )
+        .bind(&reputation.agent_id)
+        .bind(r.quality as i32)
+        .bind(r.reliability as i32)
+        .bind(r.speed as i32)
+        .bind(r.cost_efficiency as i32)
+        .bind(r.overall() as i32)
+        .bind(reputation.record.updated_at as i64)
+        .bind(stored_json)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ApiError::Internal(format!("failed to upsert reputation: {e}")))?;
+        Ok(())
+    }
+
+    async fn get_reputation(&self, agent_id: &str) -> Result<Option<StoredReputation>, ApiError> {
+        let row = sqlx::query("SELECT stored_json FROM agent_reputations WHERE agent_id = $1")
+            .bind(agent_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| ApiError::Internal(format!("failed to fetch reputation: {e}")))?;
+
+        row.map(|row| {
+            serde_json::from_value(row.get("stored_json"))
+                .map_err(|e| ApiError::Internal(format!("failed to decode reputation: {e}")))
+        })
+        .transpose()
+    }
+
+    async fn list_reputations(&self) -> Result<Vec<StoredReputation>, ApiError> {
+        let rows = sqlx::query("SELECT stored_json FROM agent_reputations ORDER BY overall DESC")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| ApiError::Internal(format!("failed to list reputations: {e}")))?;
+
+        let mut out = Vec::with_capacity(rows.len());
+        for row in rows {
+            let reputation: StoredReputation = serde_json::from_value(row.get("stored_json"))
+                .map_err(|e| ApiError::Internal(format!("failed to decode reputation: {e}")))?;
+            out.push(reputation);
+        }
+        Ok(out)
     }
 
     async fn dashboard_counts(&self) -> Result<(usize, usize, usize, usize), ApiError> {
@@ -553,7 +2262,7 @@ impl Storage for PostgresStorage {
             SELECT
                 COUNT(*)                           AS total_tasks,
                 COUNT(*) FILTER (WHERE status = 'completed') AS completed_tasks,
-                COUNT(*) FILTER (WHERE status = 'pending')   AS pending_tasks
+                COUNT(*) FILTER (WHERE status IN ('open', 'bidding', 'allocated', 'executing')) AS pending_tasks
             FROM tasks
             "#,
         )
@@ -692,3 +2401,102 @@ pub fn bid_to_view(bid: &StoredBid) -> BidView {
 pub fn result_to_view(result: &StoredResult) -> ResultView {
     ResultView::from_stored(result)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{BidSubmissionRequest, TaskSubmissionRequest};
+
+    #[tokio::test]
+    async fn agents_bid_once_per_task() {
+        let storage = InMemoryStorage::default();
+        let task = StoredTask::from_submission(TaskSubmissionRequest {
+            client_task_id: None,
+            requester_id: "requester".into(),
+            description: "bid once".into(),
+            task_type: "echo".into(),
+            input_base64: String::new(),
+            max_budget: 100,
+            deadline: 0,
+            bid_window_secs: None,
+            domain: None,
+            requirements: Default::default(),
+            output_format: None,
+            verification_level: None,
+            payment_schedule: None,
+            milestones: Vec::new(),
+            escrow_required: None,
+            signature: None,
+        })
+        .unwrap();
+        let bid = |agent_id: &str| {
+            StoredBid::from_submission(
+                BidSubmissionRequest {
+                    task_id: task.id.clone(),
+                    agent_id: agent_id.into(),
+                    value: Some(50),
+                    commitment: None,
+                    quality_score: 80,
+                    completion_time: 60,
+                    guarantees: Vec::new(),
+                    signature: None,
+                },
+                &task,
+            )
+            .unwrap()
+        };
+
+        storage.insert_bid(bid("agent")).await.unwrap();
+        assert!(matches!(
+            storage.insert_bid(bid("agent")).await,
+            Err(ApiError::Conflict(_))
+        ));
+        storage.insert_bid(bid("other")).await.unwrap();
+        assert_eq!(storage.get_bids_for_task(&task.id).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn stale_task_updates_conflict() {
+        let storage = InMemoryStorage::default();
+        let task = StoredTask::from_submission(TaskSubmissionRequest {
+            client_task_id: None,
+            requester_id: "requester".into(),
+            description: "update once".into(),
+            task_type: "echo".into(),
+            input_base64: String::new(),
+            max_budget: 100,
+            deadline: 0,
+            bid_window_secs: None,
+            domain: None,
+            requirements: Default::default(),
+            output_format: None,
+            verification_level: None,
+            payment_schedule: None,
+            milestones: Vec::new(),
+            escrow_required: None,
+            signature: None,
+        })
+        .unwrap();
+        storage.insert_task(task.clone()).await.unwrap();
+
+        let mut cancelled = task.clone();
+        cancelled.transition_to(TaskStatus::Cancelled).unwrap();
+        storage.update_task(&mut cancelled).await.unwrap();
+        assert_eq!(cancelled.version, 1);
+
+        let mut allocated = task;
+        allocated.transition_to(TaskStatus::Allocated).unwrap();
+        assert!(matches!(
+            storage.update_task(&mut allocated).await,
+            Err(ApiError::Conflict(_))
+        ));
+        assert_eq!(allocated.version, 0);
+        let stored = storage.get_task(&cancelled.id).await.unwrap();
+        assert_eq!((stored.status, stored.version), (TaskStatus::Cancelled, 1));
+
+        // Unconditional writes still bump the version.
+        storage.upsert_task(stored).await.unwrap();
+        assert_eq!(storage.get_task(&cancelled.id).await.unwrap().version, 2);
+        assert!(storage.update_task(&mut cancelled).await.is_err());
+    }
+}
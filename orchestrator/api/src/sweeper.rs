@@ -0,0 +1,203 @@
+//! Background sweeper that enforces task deadlines.
+//!
+//! Tasks still waiting for an allocation when their deadline passes are
+//! moved to `expired`. Allocated or executing tasks are moved to `failed`, and
+//! a `Violation::TaskFailure` is recorded against each allocated agent that
+//! has not reported a result, along with a failed assessment in its
+//! reputation and a slash through the ledger. Either way the task's escrow
+//! and bid deposits are returned.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use ainur_core::Violation;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::error::ApiError;
+use crate::ledger;
+use crate::model::{current_unix_timestamp, StoredTask, StoredViolation, TaskStatus};
+use crate::reputation;
+use crate::storage::Storage;
+
+/// Expires or fails tasks whose deadline has passed.
+pub struct DeadlineSweeper {
+    storage: Arc<dyn Storage>,
+}
+
+impl DeadlineSweeper {
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self { storage }
+    }
+
+    /// Poll forever, sweeping overdue tasks.
+    pub async fn run(self, poll_ms: u64) -> Result<(), ApiError> {
+        loop {
+            match self.sweep(current_unix_timestamp()).await {
+                Ok(0) => {}
+                Ok(n) => info!("swept {n} overdue task(s)"),
+                Err(err) => warn!("deadline sweep failed: {err}"),
+            }
+            tokio::time::sleep(Duration::from_millis(poll_ms)).await;
+        }
+    }
+
+    /// Sweep every non-terminal task that is overdue as of `now`, returning
+    /// the number of tasks moved. Failures on individual tasks are logged and
+    /// retried on the next pass.
+    pub async fn sweep(&self, now: u64) -> Result<usize, ApiError> {
+        let tasks = self
+            .storage
+            .list_tasks_with_status(&[
+                TaskStatus::Open,
+                TaskStatus::Bidding,
+                TaskStatus::Allocated,
+                TaskStatus::Executing,
+            ])
+            .await?;
+
+        let mut swept = 0;
+        for task in tasks.into_iter().filter(|t| t.is_overdue(now)) {
+            let task_id = task.id.clone();
+            match self.sweep_task(task, now).await {
+                Ok(()) => swept += 1,
+                Err(err) => warn!("failed to sweep task {task_id}: {err}"),
+            }
+        }
+        Ok(swept)
+    }
+
+    async fn sweep_task(&self, mut task: StoredTask, now: u64) -> Result<(), ApiError> {
+        if task.status.is_open_for_bids() {
+            task.transition_to(TaskStatus::Expired)?;
+            self.storage.update_task(&mut task).await?;
+            return ledger::release_escrow(&self.storage, &task, now).await;
+        }
+
+        // Violations are recorded before the status change so a failed
+        // upsert is retried without losing them; inserts are idempotent.
+        let results = self.storage.get_results_for_task(&task.id).await?;
+        let missing = self
+            .storage
+            .get_allocations_for_task(&task.id)
+            .await?
+            .into_iter()
+            .filter(|a| !results.iter().any(|r| r.agent_id == a.agent_id));
+        for allocation in missing {
+            if let Err(err) =
+                reputation::record_failure(&self.storage, &task, &allocation.agent_id, now).await
+            {
+                warn!(
+                    "failed to update reputation for {}: {err}",
+                    allocation.agent_id
+                );
+            }
+            let violation = Violation::TaskFailure(task.task.id);
+            ledger::slash(&self.storage, &task, &allocation, &violation, now).await?;
+            self.storage
+                .insert_violation(StoredViolation {
+                    id: Uuid::new_v4().to_string(),
+                    agent_id: allocation.agent_id,
+                    task_id: task.id.clone(),
+                    violation,
+                    recorded_at: now,
+                })
+                .await?;
+        }
+        task.transition_to(TaskStatus::Failed)?;
+        self.storage.update_task(&mut task).await?;
+        ledger::release_escrow(&self.storage, &task, now).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{StoredAllocation, TaskSubmissionRequest};
+    use crate::storage::InMemoryStorage;
+
+    async fn seed_task(
+        storage: &Arc<dyn Storage>,
+        deadline: u64,
+        status: TaskStatus,
+    ) -> StoredTask {
+        let mut task = StoredTask::from_submission(TaskSubmissionRequest {
+            client_task_id: None,
+            requester_id: "requester".into(),
+            description: "sweep me".into(),
+            task_type: "echo".into(),
+            input_base64: String::new(),
+            max_budget: 100,
+            deadline,
+            bid_window_secs: None,
+            domain: None,
+            requirements: Default::default(),
+            output_format: None,
+            verification_level: None,
+            payment_schedule: None,
+            milestones: Vec::new(),
+            escrow_required: None,
+            signature: None,
+        })
+        .unwrap();
+        task.status = status;
+        storage.insert_task(task.clone()).await.unwrap();
+        task
+    }
+
+    #[tokio::test]
+    async fn expires_unallocated_tasks() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let overdue = seed_task(&storage, 100, TaskStatus::Bidding).await;
+        let no_deadline = seed_task(&storage, 0, TaskStatus::Open).await;
+        let sweeper = DeadlineSweeper::new(storage.clone());
+
+        assert_eq!(sweeper.sweep(100).await.unwrap(), 0);
+        assert_eq!(sweeper.sweep(101).await.unwrap(), 1);
+
+        let stored = storage.get_task(&overdue.id).await.unwrap();
+        assert_eq!(stored.status, TaskStatus::Expired);
+        let stored = storage.get_task(&no_deadline.id).await.unwrap();
+        assert_eq!(stored.status, TaskStatus::Open);
+    }
+
+    #[tokio::test]
+    async fn fails_allocated_tasks_and_records_violation() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let task = seed_task(&storage, 100, TaskStatus::Allocated).await;
+        storage
+            .insert_allocation(StoredAllocation {
+                id: Uuid::new_v4().to_string(),
+                task_id: task.id.clone(),
+                agent_id: "agent-a".into(),
+                bid_id: Uuid::new_v4().to_string(),
+                payment: 1_000,
+                social_welfare: 90,
+                allocated_at: 50,
+            })
+            .await
+            .unwrap();
+        let sweeper = DeadlineSweeper::new(storage.clone());
+
+        assert_eq!(sweeper.sweep(200).await.unwrap(), 1);
+
+        let stored = storage.get_task(&task.id).await.unwrap();
+        assert_eq!(stored.status, TaskStatus::Failed);
+        let violations = storage.get_violations_for_agent("agent-a").await.unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(
+            violations[0].violation,
+            Violation::TaskFailure(task.task.id)
+        );
+        // Without a bid deposit the whole slash comes out of the agent.
+        let agent = ledger::account(&storage, "user:agent-a").await.unwrap();
+        assert_eq!(agent.balance, -100);
+        let treasury = ledger::account(&storage, ledger::TREASURY_ACCOUNT)
+            .await
+            .unwrap();
+        assert_eq!(treasury.balance, 100);
+
+        // Terminal tasks are left alone on later sweeps.
+        assert_eq!(sweeper.sweep(300).await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,233 @@
+//! Result verification before acceptance.
+//!
+//! Submitted results are run through the `ainur_core::VerifierRegistry` held
+//! in the application state; a result the registry rejects is answered with
+//! `CoreError::VerificationFailed` and never stored.
+//!
+//! `Consensus(n)` tasks are allocated to `n` agents. Each result is screened
+//! by the baseline verifiers on arrival and stored; the task completes once
+//! an output is reported by `ConsensusVerifier::threshold` distinct agents,
+//! and fails once no output can get there.
+
+use ainur_core::{
+    hash_of, ConsensusVerifier, CoreError, TaskResult, VerificationLevel, VerificationReport,
+    VerificationResult, VerifierRegistry,
+};
+
+use crate::error::ApiError;
+use crate::model::{AgreementStatus, OutputGroupView, StoredResult, StoredTask, VerificationView};
+
+/// Reject tasks whose verification level the registry cannot check.
+pub fn ensure_supported(
+    registry: &VerifierRegistry,
+    level: &VerificationLevel,
+) -> Result<(), ApiError> {
+    if !registry.supports(level) {
+        return Err(ApiError::BadRequest(format!(
+            "verification level {level:?} is not supported"
+        )));
+    }
+    Ok(())
+}
+
+/// Verify `result` for `task`, failing with `CoreError::VerificationFailed`
+/// when it is rejected. Results for `Consensus(n)` tasks only get the
+/// baseline checks here; agreement is assessed by [`agreement`].
+pub fn verify_result(
+    registry: &VerifierRegistry,
+    task: &StoredTask,
+    result: &TaskResult,
+) -> Result<VerificationResult, ApiError> {
+    let outcome = match task.task.verification_level {
+        VerificationLevel::Consensus(_) => registry.verify_baseline(&task.task, result)?,
+        _ => registry.verify(&task.task, result, std::slice::from_ref(result))?,
+    };
+    if !outcome.is_valid {
+        let reason = match &outcome.report {
+            VerificationReport::Detailed { notes, .. } if !notes.is_empty() => notes.clone(),
+            _ => "result rejected by verifier".into(),
+        };
+        return Err(CoreError::VerificationFailed { reason }.into());
+    }
+    Ok(outcome)
+}
+
+/// Agreement among the `results` stored for `task`. Tasks without
+/// redundancy only store verified results, so a single one is agreement.
+pub fn agreement(task: &StoredTask, results: &[StoredResult]) -> VerificationView {
+    let required = task.redundancy() as u32;
+    let collected: Vec<TaskResult> = results.iter().map(|r| r.result.clone()).collect();
+    let consensus = ConsensusVerifier::new(required as u8, collected);
+    let threshold = consensus.threshold();
+
+    let mut groups: Vec<(Vec<u8>, Vec<String>)> = Vec::new();
+    for result in results {
+        let output = &result.result.output;
+        let agents = match groups.iter_mut().find(|(o, _)| o == output) {
+            Some((_, agents)) => agents,
+            None => {
+                groups.push((output.clone(), Vec::new()));
+                &mut groups.last_mut().expect("just pushed").1
+            }
+        };
+        if !agents.contains(&result.agent_id) {
+            agents.push(result.agent_id.clone());
+        }
+    }
+    // Stable sort keeps first-reported outputs ahead on ties.
+    groups.sort_by_key(|(_, agents)| std::cmp::Reverse(agents.len()));
+
+    let received = groups.iter().map(|(_, agents)| agents.len() as u32).sum();
+    let leading = groups.first().map(|(output, _)| consensus.agreeing(output));
+    let status = match leading {
+        Some(agreeing) if agreeing >= threshold => AgreementStatus::Agreed,
+        Some(agreeing) if agreeing + required.saturating_sub(received) < threshold => {
+            AgreementStatus::Disagreed
+        }
+        _ => AgreementStatus::Pending,
+    };
+
+    let groups: Vec<OutputGroupView> = groups
+        .into_iter()
+        .map(|(output, agents)| OutputGroupView {
+            output_hash: output_hash(&output),
+            agents,
+        })
+        .collect();
+    VerificationView {
+        task_id: task.id.clone(),
+        verification_level: task.task.verification_level.clone(),
+        required,
+        threshold,
+        received,
+        status,
+        accepted_output_hash: (status == AgreementStatus::Agreed)
+            .then(|| groups[0].output_hash.clone()),
+        groups,
+    }
+}
+
+/// The result that stands for the task: the earliest one carrying the
+/// agreed output, if any.
+pub fn accepted_result<'a>(
+    task: &StoredTask,
+    results: &'a [StoredResult],
+) -> Option<&'a StoredResult> {
+    let report = agreement(task, results);
+    results.iter().find(|r| agrees(&report, r))
+}
+
+/// Whether `result` carries the output `report` settled on.
+pub fn agrees(report: &VerificationView, result: &StoredResult) -> bool {
+    report.accepted_output_hash.as_deref() == Some(output_hash(&result.result.output).as_str())
+}
+
+fn output_hash(output: &[u8]) -> String {
+    format!("0x{}", hex::encode(hash_of(output)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ResultSubmissionRequest, StoredResult, TaskSubmissionRequest};
+    use ainur_core::OutputFormat;
+    use base64::{engine::general_purpose, Engine as _};
+
+    fn task(output_format: OutputFormat) -> StoredTask {
+        task_with(output_format, None)
+    }
+
+    fn task_with(output_format: OutputFormat, level: Option<VerificationLevel>) -> StoredTask {
+        StoredTask::from_submission(TaskSubmissionRequest {
+            client_task_id: None,
+            requester_id: "requester".into(),
+            description: "verify me".into(),
+            task_type: "echo".into(),
+            input_base64: String::new(),
+            max_budget: 100,
+            deadline: 0,
+            bid_window_secs: None,
+            domain: None,
+            requirements: Default::default(),
+            output_format: Some(output_format),
+            verification_level: level,
+            payment_schedule: None,
+            milestones: Vec::new(),
+            escrow_required: None,
+            signature: None,
+        })
+        .unwrap()
+    }
+
+    fn stored(task: &StoredTask, agent: &str, output: &str) -> StoredResult {
+        StoredResult::from_submission(
+            ResultSubmissionRequest {
+                task_id: task.id.clone(),
+                agent_id: agent.into(),
+                output_base64: general_purpose::STANDARD.encode(output),
+                signature: None,
+            },
+            task,
+        )
+        .unwrap()
+    }
+
+    fn result(task: &StoredTask, output: &str) -> TaskResult {
+        stored(task, "agent", output).result
+    }
+
+    #[test]
+    fn rejects_results_failing_format_checks() {
+        let registry = VerifierRegistry::default();
+        let task = task(OutputFormat::Json);
+
+        let ok = result(&task, r#"{"ok":true}"#);
+        assert!(verify_result(&registry, &task, &ok).is_ok());
+
+        let bad = result(&task, "not json");
+        assert!(matches!(
+            verify_result(&registry, &task, &bad),
+            Err(ApiError::BadRequest(msg)) if msg.contains("not valid JSON")
+        ));
+    }
+
+    #[test]
+    fn unsupported_levels_are_rejected_at_submission() {
+        let registry = VerifierRegistry::default();
+        assert!(ensure_supported(&registry, &VerificationLevel::BestEffort).is_ok());
+        assert!(ensure_supported(&registry, &VerificationLevel::TEEAttested).is_err());
+        assert!(ensure_supported(&registry, &VerificationLevel::Consensus(3)).is_ok());
+        assert!(ensure_supported(&registry, &VerificationLevel::Consensus(0)).is_err());
+    }
+
+    #[test]
+    fn consensus_agreement_tracks_outstanding_results() {
+        let consensus = task_with(OutputFormat::Text, Some(VerificationLevel::Consensus(2)));
+        let a = stored(&consensus, "a", "42");
+        let b = stored(&consensus, "b", "42");
+        let c = stored(&consensus, "c", "41");
+
+        let report = agreement(&consensus, std::slice::from_ref(&a));
+        assert_eq!((report.required, report.threshold), (2, 2));
+        assert_eq!(report.status, AgreementStatus::Pending);
+        assert!(accepted_result(&consensus, std::slice::from_ref(&a)).is_none());
+
+        let results = [a.clone(), b.clone()];
+        let report = agreement(&consensus, &results);
+        assert_eq!(report.status, AgreementStatus::Agreed);
+        assert_eq!(report.groups[0].agents, ["a", "b"]);
+        assert_eq!(accepted_result(&consensus, &results).unwrap().id, a.id);
+
+        let report = agreement(&consensus, &[a, c.clone()]);
+        assert_eq!(report.status, AgreementStatus::Disagreed);
+        assert!(!agrees(&report, &c));
+
+        // Verified results of tasks without redundancy stand on their own.
+        let plain = task(OutputFormat::Text);
+        let only = stored(&plain, "a", "42");
+        assert_eq!(
+            agreement(&plain, std::slice::from_ref(&only)).status,
+            AgreementStatus::Agreed
+        );
+    }
+}
@@ -0,0 +1,728 @@
+//! Outbound webhooks.
+//!
+//! Requesters register endpoints for all of their tasks or for a single task.
+//! The dispatcher follows the event bus, queues a delivery for every webhook
+//! an event matches and `POST`s the event as JSON. Each request carries an
+//! HMAC-SHA256 over `"{timestamp}.{body}"` keyed with the webhook's secret.
+//! Failed deliveries are retried with exponential backoff and dead-lettered
+//! after [`MAX_DELIVERY_ATTEMPTS`]; every attempt is kept in the delivery log.
+//! Up to [`MAX_CONCURRENT_DELIVERIES`] are attempted at once, on a task of
+//! their own so slow endpoints never hold up queueing new events.
+//!
+//! Webhooks are only delivered to public addresses. Registration refuses
+//! URLs naming `localhost` or an internal IP, and delivery resolves hosts
+//! itself, skipping loopback, private, link-local and other internal
+//! addresses, and does not follow redirects.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use metrics::counter;
+use sha2::Sha256;
+use tokio::task::{JoinError, JoinSet};
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::error::ApiError;
+use crate::events::EventBus;
+use crate::model::{
+    agent_key, current_unix_timestamp, EventQuery, StoredWebhook, StoredWebhookDelivery, TaskEvent,
+    WebhookAttempt, WebhookDeliveryStatus, WebhookRegistrationRequest,
+};
+use crate::storage::Storage;
+
+/// Attempts made before a delivery is dead-lettered.
+pub const MAX_DELIVERY_ATTEMPTS: usize = 8;
+/// Delay before the first retry; it doubles with every failed attempt.
+pub const BACKOFF_BASE_SECS: u64 = 10;
+/// Longest delay between two attempts.
+pub const MAX_BACKOFF_SECS: u64 = 3_600;
+/// Deliveries attempted at the same time.
+pub const MAX_CONCURRENT_DELIVERIES: usize = 16;
+
+/// `sha256=<hex HMAC>` over the timestamp and body.
+pub const SIGNATURE_HEADER: &str = "x-ainur-signature";
+/// Unix time the request was signed at.
+pub const TIMESTAMP_HEADER: &str = "x-ainur-timestamp";
+/// Event kind, e.g. `task_completed`.
+pub const EVENT_HEADER: &str = "x-ainur-event";
+/// Delivery id; retries of the same delivery reuse it.
+pub const DELIVERY_HEADER: &str = "x-ainur-delivery";
+
+const MAX_ERROR_LEN: usize = 512;
+
+/// Register a webhook on behalf of its requester. Task-scoped webhooks may
+/// only be registered by the task's requester.
+pub async fn register(
+    storage: &Arc<dyn Storage>,
+    request: WebhookRegistrationRequest,
+    now: u64,
+) -> Result<StoredWebhook, ApiError> {
+    if let Some(task_id) = &request.task_id {
+        let task = storage.get_task(task_id).await?;
+        if agent_key(&request.requester_id) != task.task.requester {
+            return Err(ApiError::Forbidden(format!(
+                "only the requester of task {task_id} may register webhooks for it"
+            )));
+        }
+    }
+    ensure_public_url(&request.url).map_err(ApiError::BadRequest)?;
+    let secret = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let webhook = StoredWebhook::from_registration(request, secret, now)?;
+    storage.insert_webhook(webhook.clone()).await?;
+    Ok(webhook)
+}
+
+/// Check that `url` is an HTTP(S) URL whose host is neither `localhost` nor
+/// an internal IP. Hosts given by name are checked again when resolved.
+pub fn ensure_public_url(url: &str) -> Result<(), String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("invalid webhook url {url}: {e}"))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("invalid webhook url {url}"));
+    }
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| format!("webhook url {url} has no host"))?;
+    let internal = match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+        Ok(ip) => !is_public(ip),
+        Err(_) => host == "localhost" || host.ends_with(".localhost"),
+    };
+    if internal {
+        return Err(format!("webhook url {url} points at an internal address"));
+    }
+    Ok(())
+}
+
+/// Whether webhooks may be delivered to `ip`: anything but loopback,
+/// private, link-local, shared, reserved, documentation, multicast and
+/// unspecified addresses.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_public_v4(mapped),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        || a == 0
+        // Shared address space (100.64.0.0/10).
+        || (a == 100 && (b & 0xc0) == 64)
+        // IETF protocol assignments (192.0.0.0/24).
+        || (a == 192 && b == 0 && c == 0)
+        // Benchmarking (198.18.0.0/15).
+        || (a == 198 && (b & 0xfe) == 18)
+        // Reserved (240.0.0.0/4).
+        || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let segments = ip.segments();
+    // NAT64 (64:ff9b::/96) embeds an IPv4 address.
+    if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+        let [.., a, b, c, d] = ip.octets();
+        return is_public_v4(Ipv4Addr::new(a, b, c, d));
+    }
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // IPv4-compatible (::/96).
+        || segments[..6] == [0; 6]
+        // Unique local (fc00::/7).
+        || (segments[0] & 0xfe00) == 0xfc00
+        // Link-local (fe80::/10).
+        || (segments[0] & 0xffc0) == 0xfe80
+        // Documentation (2001:db8::/32).
+        || (segments[0] == 0x2001 && segments[1] == 0x0db8))
+}
+
+/// Resolves webhook hosts to their public addresses only, so a name cannot
+/// point deliveries at internal services.
+struct PublicResolver;
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| is_public(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{host} does not resolve to a public address").into());
+            }
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+/// Hex-encoded HMAC-SHA256 of `"{timestamp}.{body}"` under `secret`.
+pub fn sign(secret: &str, timestamp: u64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Delay before the attempt following `failed_attempts` failures.
+pub fn backoff_secs(failed_attempts: usize) -> u64 {
+    let doublings = failed_attempts.saturating_sub(1).min(16) as u32;
+    BACKOFF_BASE_SECS
+        .saturating_mul(1 << doublings)
+        .min(MAX_BACKOFF_SECS)
+}
+
+/// Sends a signed delivery, returning the HTTP status the endpoint answered
+/// with.
+#[async_trait]
+pub trait WebhookTransport: Send + Sync {
+    async fn post(
+        &self,
+        url: &str,
+        headers: &[(&'static str, String)],
+        body: &[u8],
+    ) -> Result<u16, String>;
+}
+
+/// Delivers over HTTP(S).
+pub struct HttpTransport {
+    client: reqwest::Client,
+}
+
+impl Default for HttpTransport {
+    fn default() -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .redirect(reqwest::redirect::Policy::none())
+            .dns_resolver(Arc::new(PublicResolver))
+            .build()
+            .unwrap_or_default();
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl WebhookTransport for HttpTransport {
+    async fn post(
+        &self,
+        url: &str,
+        headers: &[(&'static str, String)],
+        body: &[u8],
+    ) -> Result<u16, String> {
+        // Webhooks registered before the URL checks are held to them too.
+        ensure_public_url(url)?;
+        let mut request = self
+            .client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_vec());
+        for (name, value) in headers {
+            request = request.header(*name, value);
+        }
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        Ok(response.status().as_u16())
+    }
+}
+
+/// Queues events for matching webhooks and works through due deliveries.
+pub struct WebhookDispatcher {
+    storage: Arc<dyn Storage>,
+    events: EventBus,
+    transport: Arc<dyn WebhookTransport>,
+}
+
+impl WebhookDispatcher {
+    pub fn new(storage: Arc<dyn Storage>, events: EventBus) -> Self {
+        Self {
+            storage,
+            events,
+            transport: Arc::new(HttpTransport::default()),
+        }
+    }
+
+    pub fn with_transport(mut self, transport: Arc<dyn WebhookTransport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Follow the event bus forever, attempting due deliveries every
+    /// `poll_ms` on a separate task.
+    pub async fn run(self, poll_ms: u64) -> Result<(), ApiError> {
+        let dispatcher = Arc::new(self);
+        let deliveries = {
+            let dispatcher = dispatcher.clone();
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(Duration::from_millis(poll_ms));
+                ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                loop {
+                    ticker.tick().await;
+                    match dispatcher.deliver_due(current_unix_timestamp()).await {
+                        Ok(0) => {}
+                        Ok(n) => info!("attempted {n} webhook deliveries"),
+                        Err(err) => warn!("webhook delivery pass failed: {err}"),
+                    }
+                }
+            })
+        };
+        let mut subscription = dispatcher.events.subscribe(EventQuery::default());
+        while let Some(event) = subscription.next().await {
+            if let Err(err) = dispatcher.enqueue(&event).await {
+                warn!(
+                    "failed to queue webhook deliveries for event {}: {err}",
+                    event.id
+                );
+            }
+        }
+        deliveries.abort();
+        Ok(())
+    }
+
+    /// Queue `event` for every webhook of the task's requester it matches,
+    /// returning the number of deliveries queued.
+    pub async fn enqueue(&self, event: &TaskEvent) -> Result<usize, ApiError> {
+        let task = match self.storage.get_task(&event.task_id).await {
+            Ok(task) => task,
+            Err(ApiError::NotFound(_)) => return Ok(0),
+            Err(err) => return Err(err),
+        };
+        let webhooks = self
+            .storage
+            .get_webhooks_for_requester(&task.requester_id)
+            .await?;
+        let mut queued = 0;
+        for webhook in webhooks.iter().filter(|w| w.matches(event)) {
+            self.storage
+                .upsert_webhook_delivery(StoredWebhookDelivery {
+                    id: Uuid::new_v4().to_string(),
+                    webhook_id: webhook.id.clone(),
+                    event: event.clone(),
+                    status: WebhookDeliveryStatus::Pending,
+                    attempts: Vec::new(),
+                    next_attempt_at: event.created_at,
+                    created_at: event.created_at,
+                })
+                .await?;
+            queued += 1;
+        }
+        Ok(queued)
+    }
+
+    /// Attempt every delivery due at `now`, up to
+    /// [`MAX_CONCURRENT_DELIVERIES`] at a time, returning the number
+    /// attempted. Failures on individual deliveries are logged and retried
+    /// on the next pass.
+    pub async fn deliver_due(&self, now: u64) -> Result<usize, ApiError> {
+        let due = self.storage.get_due_webhook_deliveries(now).await?;
+        let mut attempts = JoinSet::new();
+        let mut attempted = 0;
+        for delivery in due {
+            if attempts.len() >= MAX_CONCURRENT_DELIVERIES {
+                if let Some(finished) = attempts.join_next().await {
+                    attempted += attempted_ok(finished);
+                }
+            }
+            let storage = self.storage.clone();
+            let transport = self.transport.clone();
+            attempts.spawn(async move {
+                let delivery_id = delivery.id.clone();
+                let outcome = attempt(&storage, transport.as_ref(), delivery, now).await;
+                (delivery_id, outcome)
+            });
+        }
+        while let Some(finished) = attempts.join_next().await {
+            attempted += attempted_ok(finished);
+        }
+        Ok(attempted)
+    }
+}
+
+type AttemptOutcome = (String, Result<WebhookDeliveryStatus, ApiError>);
+
+/// 1 if a spawned attempt was made, logging why it was not otherwise.
+fn attempted_ok(finished: Result<AttemptOutcome, JoinError>) -> usize {
+    match finished {
+        Ok((_, Ok(_))) => 1,
+        Ok((delivery_id, Err(err))) => {
+            warn!("failed to attempt webhook delivery {delivery_id}: {err}");
+            0
+        }
+        Err(err) => {
+            warn!("webhook delivery attempt panicked: {err}");
+            0
+        }
+    }
+}
+
+async fn attempt(
+    storage: &Arc<dyn Storage>,
+    transport: &dyn WebhookTransport,
+    mut delivery: StoredWebhookDelivery,
+    now: u64,
+) -> Result<WebhookDeliveryStatus, ApiError> {
+    let webhook = storage.get_webhook(&delivery.webhook_id).await?;
+    let body = serde_json::to_vec(&delivery.event)
+        .map_err(|e| ApiError::Internal(format!("failed to encode webhook event: {e}")))?;
+    let headers = [
+        (EVENT_HEADER, delivery.event.kind.as_str().to_string()),
+        (DELIVERY_HEADER, delivery.id.clone()),
+        (TIMESTAMP_HEADER, now.to_string()),
+        (
+            SIGNATURE_HEADER,
+            format!("sha256={}", sign(&webhook.secret, now, &body)),
+        ),
+    ];
+
+    let (status_code, error) = match transport.post(&webhook.url, &headers, &body).await {
+        Ok(code) if (200..300).contains(&code) => (Some(code), None),
+        Ok(code) => (Some(code), Some(format!("endpoint answered {code}"))),
+        Err(mut err) => {
+            err.truncate(MAX_ERROR_LEN);
+            (None, Some(err))
+        }
+    };
+    let failed = error.is_some();
+    delivery.attempts.push(WebhookAttempt {
+        attempted_at: now,
+        status_code,
+        error,
+    });
+
+    delivery.status = if !failed {
+        counter!("webhook_delivered_total").increment(1);
+        WebhookDeliveryStatus::Delivered
+    } else if delivery.attempts.len() >= MAX_DELIVERY_ATTEMPTS {
+        counter!("webhook_dead_total").increment(1);
+        warn!(
+            "webhook delivery {} to {} dead after {} attempts",
+            delivery.id,
+            webhook.url,
+            delivery.attempts.len()
+        );
+        WebhookDeliveryStatus::Dead
+    } else {
+        counter!("webhook_failed_total").increment(1);
+        delivery.next_attempt_at = now + backoff_secs(delivery.attempts.len());
+        WebhookDeliveryStatus::Pending
+    };
+    let status = delivery.status;
+    storage.upsert_webhook_delivery(delivery).await?;
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{EventKind, StoredTask, TaskSubmissionRequest};
+    use crate::storage::InMemoryStorage;
+    use serde_json::json;
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    type Request = (String, Vec<(&'static str, String)>, Vec<u8>);
+
+    /// Records requests and answers with scripted outcomes, then `200`.
+    #[derive(Default)]
+    struct RecordingTransport {
+        outcomes: Mutex<VecDeque<Result<u16, String>>>,
+        requests: Mutex<Vec<Request>>,
+    }
+
+    #[async_trait]
+    impl WebhookTransport for RecordingTransport {
+        async fn post(
+            &self,
+            url: &str,
+            headers: &[(&'static str, String)],
+            body: &[u8],
+        ) -> Result<u16, String> {
+            self.requests
+                .lock()
+                .unwrap()
+                .push((url.to_string(), headers.to_vec(), body.to_vec()));
+            self.outcomes.lock().unwrap().pop_front().unwrap_or(Ok(200))
+        }
+    }
+
+    async fn task_of(storage: &Arc<dyn Storage>, requester_id: &str) -> StoredTask {
+        let task = StoredTask::from_submission(TaskSubmissionRequest {
+            client_task_id: None,
+            requester_id: requester_id.into(),
+            description: "notify me".into(),
+            task_type: "echo".into(),
+            input_base64: String::new(),
+            max_budget: 1_000,
+            deadline: 0,
+            bid_window_secs: None,
+            domain: None,
+            requirements: Default::default(),
+            output_format: None,
+            verification_level: None,
+            payment_schedule: None,
+            milestones: Vec::new(),
+            escrow_required: None,
+            signature: None,
+        })
+        .unwrap();
+        storage.insert_task(task.clone()).await.unwrap();
+        task
+    }
+
+    fn registration(
+        url: &str,
+        task_id: Option<&str>,
+        events: Vec<EventKind>,
+    ) -> WebhookRegistrationRequest {
+        WebhookRegistrationRequest {
+            requester_id: "requester".into(),
+            url: url.into(),
+            task_id: task_id.map(str::to_string),
+            events,
+            signature: None,
+        }
+    }
+
+    fn header<'a>(headers: &'a [(&'static str, String)], name: &str) -> &'a str {
+        headers
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, v)| v.as_str())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn deliveries_are_signed_and_scoped_to_the_requesters_tasks() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let mine = task_of(&storage, "requester").await;
+        let other = task_of(&storage, "other").await;
+        assert!(matches!(
+            register(&storage, registration("ftp://x", None, Vec::new()), 0).await,
+            Err(ApiError::BadRequest(_))
+        ));
+        assert!(matches!(
+            register(
+                &storage,
+                registration("https://hooks.test/x", Some(&other.id), Vec::new()),
+                0
+            )
+            .await,
+            Err(ApiError::Forbidden(_))
+        ));
+        let completions = register(
+            &storage,
+            registration(
+                "https://hooks.test/all",
+                None,
+                vec![EventKind::TaskCompleted],
+            ),
+            0,
+        )
+        .await
+        .unwrap();
+        let per_task = register(
+            &storage,
+            registration("https://hooks.test/task", Some(&mine.id), Vec::new()),
+            0,
+        )
+        .await
+        .unwrap();
+
+        let bus = EventBus::default();
+        let transport = Arc::new(RecordingTransport::default());
+        let dispatcher =
+            WebhookDispatcher::new(storage.clone(), bus.clone()).with_transport(transport.clone());
+        let events = [
+            bus.publish(EventKind::BidReceived, &mine.id, Some("agent"), json!({})),
+            bus.publish(EventKind::TaskCompleted, &mine.id, None, json!({})),
+            bus.publish(EventKind::TaskCompleted, &other.id, None, json!({})),
+        ];
+        let mut queued = 0;
+        for event in &events {
+            queued += dispatcher.enqueue(event).await.unwrap();
+        }
+        assert_eq!(queued, 3);
+
+        let now = events[0].created_at + 1;
+        assert_eq!(dispatcher.deliver_due(now).await.unwrap(), 3);
+        let requests = transport.requests.lock().unwrap().clone();
+        let urls: Vec<&str> = requests.iter().map(|(url, _, _)| url.as_str()).collect();
+        assert_eq!(urls.iter().filter(|u| **u == completions.url).count(), 1);
+        assert_eq!(urls.iter().filter(|u| **u == per_task.url).count(), 2);
+        for (url, headers, body) in &requests {
+            let secret = if *url == completions.url {
+                &completions.secret
+            } else {
+                &per_task.secret
+            };
+            assert_eq!(header(headers, TIMESTAMP_HEADER), now.to_string());
+            assert_eq!(
+                header(headers, SIGNATURE_HEADER),
+                format!("sha256={}", sign(secret, now, body))
+            );
+            let event: TaskEvent = serde_json::from_slice(body).unwrap();
+            assert_eq!(event.task_id, mine.id);
+            assert_eq!(header(headers, EVENT_HEADER), event.kind.as_str());
+        }
+
+        // Delivered events are not sent again.
+        assert_eq!(dispatcher.deliver_due(now + 60).await.unwrap(), 0);
+        let log = storage.get_webhook_deliveries(&per_task.id).await.unwrap();
+        assert!(log
+            .iter()
+            .all(|d| d.status == WebhookDeliveryStatus::Delivered && d.attempts.len() == 1));
+    }
+
+    /// Holds every request until `n` are in flight at once.
+    struct GatedTransport(tokio::sync::Barrier);
+
+    #[async_trait]
+    impl WebhookTransport for GatedTransport {
+        async fn post(
+            &self,
+            _url: &str,
+            _headers: &[(&'static str, String)],
+            _body: &[u8],
+        ) -> Result<u16, String> {
+            self.0.wait().await;
+            Ok(200)
+        }
+    }
+
+    #[tokio::test]
+    async fn deliveries_are_attempted_concurrently() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let task = task_of(&storage, "requester").await;
+        for i in 0..3 {
+            register(
+                &storage,
+                registration(&format!("https://hooks.test/{i}"), None, Vec::new()),
+                0,
+            )
+            .await
+            .unwrap();
+        }
+        let bus = EventBus::default();
+        let dispatcher = WebhookDispatcher::new(storage.clone(), bus.clone())
+            .with_transport(Arc::new(GatedTransport(tokio::sync::Barrier::new(3))));
+        let event = bus.publish(EventKind::TaskCompleted, &task.id, None, json!({}));
+        assert_eq!(dispatcher.enqueue(&event).await.unwrap(), 3);
+
+        // Attempted one at a time, the first request would wait forever.
+        let attempted = tokio::time::timeout(
+            Duration::from_secs(5),
+            dispatcher.deliver_due(event.created_at),
+        )
+        .await
+        .expect("deliveries were attempted one at a time");
+        assert_eq!(attempted.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn internal_addresses_are_refused() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        for url in [
+            "http://localhost:8080/hook",
+            "http://api.localhost/hook",
+            "http://127.0.0.1/hook",
+            "http://0x7f.1/hook",
+            "http://10.1.2.3/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://100.64.0.1/hook",
+            "http://[::1]/hook",
+            "http://[::ffff:192.168.0.1]/hook",
+            "http://[fd00::1]/hook",
+            "http://[fe80::1]/hook",
+        ] {
+            assert!(
+                matches!(
+                    register(&storage, registration(url, None, Vec::new()), 0).await,
+                    Err(ApiError::BadRequest(_))
+                ),
+                "{url}"
+            );
+        }
+        assert!(ensure_public_url("https://93.184.216.34/hook").is_ok());
+        assert!(ensure_public_url("https://[2606:4700::1111]/hook").is_ok());
+
+        // Names are checked again once resolved.
+        let resolved =
+            reqwest::dns::Resolve::resolve(&PublicResolver, "localhost".parse().unwrap());
+        assert!(resolved.await.is_err());
+    }
+
+    #[tokio::test]
+    async fn failed_deliveries_back_off_and_are_dead_lettered() {
+        let storage: Arc<dyn Storage> = Arc::new(InMemoryStorage::default());
+        let task = task_of(&storage, "requester").await;
+        let webhook = register(
+            &storage,
+            registration("https://hooks.test/down", None, Vec::new()),
+            0,
+        )
+        .await
+        .unwrap();
+        let bus = EventBus::default();
+        let transport = Arc::new(RecordingTransport::default());
+        transport
+            .outcomes
+            .lock()
+            .unwrap()
+            .extend((0..MAX_DELIVERY_ATTEMPTS).map(|i| {
+                if i % 2 == 0 {
+                    Ok(503)
+                } else {
+                    Err("connection refused".to_string())
+                }
+            }));
+        let dispatcher =
+            WebhookDispatcher::new(storage.clone(), bus.clone()).with_transport(transport.clone());
+        let event = bus.publish(EventKind::TaskCompleted, &task.id, None, json!({}));
+        dispatcher.enqueue(&event).await.unwrap();
+
+        let mut now = event.created_at;
+        for attempt in 1..=MAX_DELIVERY_ATTEMPTS {
+            assert_eq!(dispatcher.deliver_due(now).await.unwrap(), 1);
+            let delivery = storage.get_webhook_deliveries(&webhook.id).await.unwrap()[0].clone();
+            assert_eq!(delivery.attempts.len(), attempt);
+            if attempt == MAX_DELIVERY_ATTEMPTS {
+                assert_eq!(delivery.status, WebhookDeliveryStatus::Dead);
+                break;
+            }
+            assert_eq!(delivery.status, WebhookDeliveryStatus::Pending);
+            assert_eq!(delivery.next_attempt_at, now + backoff_secs(attempt));
+            // Nothing is retried before the backoff elapses.
+            assert_eq!(dispatcher.deliver_due(now + 1).await.unwrap(), 0);
+            now = delivery.next_attempt_at;
+        }
+        assert_eq!(
+            dispatcher
+                .deliver_due(now + MAX_BACKOFF_SECS)
+                .await
+                .unwrap(),
+            0
+        );
+
+        let log = &storage.get_webhook_deliveries(&webhook.id).await.unwrap()[0].attempts;
+        assert_eq!(log[0].status_code, Some(503));
+        assert_eq!(log[1].status_code, None);
+        assert_eq!(log[1].error.as_deref(), Some("connection refused"));
+        assert_eq!(backoff_secs(1), BACKOFF_BASE_SECS);
+        assert_eq!(backoff_secs(3), BACKOFF_BASE_SECS * 4);
+        assert_eq!(backoff_secs(30), MAX_BACKOFF_SECS);
+    }
+}
@@ -2,78 +2,515 @@
 
 use std::env;
 
+use ainur_core::{Capability, Domain};
+use ainur_orchestrator_api::error::ApiError;
 use ainur_orchestrator_api::model::{
-    AgentRegistrationRequest, BidSubmissionRequest, ResultSubmissionRequest, StoredBid,
-    StoredResult, StoredTask, TaskSubmissionRequest,
+    current_unix_timestamp, AgentRegistrationRequest, BidFilter, BidSubmissionRequest, EventKind,
+    LedgerTransactionKind, LedgerView, NotificationKind, PageRequest, ResultSubmissionRequest,
+    StoredBid, StoredIdempotencyKey, StoredLedgerTransaction, StoredNotification, StoredRequester,
+    StoredResult, StoredTask, StoredWebhook, StoredWebhookDelivery, TaskEvent, TaskFilter,
+    TaskStatus, TaskSubmissionRequest, WebhookDeliveryStatus, WebhookRegistrationRequest,
 };
 use ainur_orchestrator_api::storage::PostgresStorage;
 use ainur_orchestrator_api::storage::Storage;
 use base64::{engine::general_purpose, Engine as _};
 
-fn db_url() -> Option<String> {
-    env::var("DATABASE_URL").ok()
-}
-
-#[tokio::test]
-#[ignore = "requires local Postgres (DATABASE_URL) and optional chain devnet"]
-async fn postgres_storage_roundtrip() {
-    let Some(url) = db_url() else {
+/// Storage on `DATABASE_URL`, or `None` to skip the test.
+async fn connect() -> Option<PostgresStorage> {
+    let Ok(url) = env::var("DATABASE_URL") else {
         eprintln!("DATABASE_URL not set; skipping integration test");
-        return;
+        return None;
     };
+    Some(
+        PostgresStorage::connect_with_pool(&url, 4, 5)
+            .await
+            .expect("connect pg"),
+    )
+}
 
-    let storage = PostgresStorage::connect_with_pool(&url, 4, 5)
-        .await
-        .expect("connect pg");
+/// Id unique to this run, so tests sharing a database do not collide.
+fn unique(prefix: &str) -> String {
+    format!("{prefix}-{}", uuid::Uuid::new_v4().simple())
+}
 
-    let agent = AgentRegistrationRequest {
-        id: "agent-1".into(),
-        label: "integration-agent".into(),
-    };
+async fn seed_agent(storage: &PostgresStorage) -> AgentRegistrationRequest {
+    let id = unique("agent");
+    let mut agent = AgentRegistrationRequest::new(&id, "integration-agent");
+    agent.capabilities = vec![Capability::Model("echo".into())];
+    agent.domains = vec![Domain::NLP];
+    agent.endpoints = vec![format!("https://{id}.example")];
     storage.register_agent(agent.clone()).await.unwrap();
-    let fetched_agent = storage.get_agent("agent-1").await.unwrap();
-    assert_eq!(fetched_agent.id, agent.id);
+    agent
+}
 
-    let task_submission = TaskSubmissionRequest {
+async fn seed_task(storage: &PostgresStorage) -> StoredTask {
+    let task = StoredTask::from_submission(TaskSubmissionRequest {
         client_task_id: Some("client-123".into()),
-        requester_id: "requester-xyz".into(),
+        requester_id: unique("requester"),
         description: "echo this payload".into(),
         task_type: "echo".into(),
         input_base64: general_purpose::STANDARD.encode(r#"{"msg":"hi"}"#),
         max_budget: 10,
         deadline: 1_700_000_000,
+        bid_window_secs: None,
+        domain: None,
+        requirements: Default::default(),
+        output_format: None,
+        verification_level: None,
+        payment_schedule: None,
+        milestones: Vec::new(),
+        escrow_required: None,
+        signature: None,
+    })
+    .unwrap();
+    storage.insert_task(task.clone()).await.unwrap();
+    task
+}
+
+fn bid_on(task: &StoredTask, agent_id: &str) -> StoredBid {
+    StoredBid::from_submission(
+        BidSubmissionRequest {
+            task_id: task.id.clone(),
+            agent_id: agent_id.into(),
+            value: Some(5),
+            commitment: None,
+            quality_score: 90,
+            completion_time: 10,
+            guarantees: Vec::new(),
+            signature: None,
+        },
+        task,
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+#[ignore = "requires local Postgres (DATABASE_URL) and optional chain devnet"]
+async fn postgres_agents_roundtrip() {
+    let Some(storage) = connect().await else {
+        return;
+    };
+    let agent = seed_agent(&storage).await;
+    let fetched = storage.get_agent(&agent.id).await.unwrap();
+    assert_eq!(fetched.id, agent.id);
+    assert_eq!(fetched.capabilities, agent.capabilities);
+    assert_eq!(fetched.domains, agent.domains);
+    assert_eq!(fetched.endpoints, agent.endpoints);
+}
+
+#[tokio::test]
+#[ignore = "requires local Postgres (DATABASE_URL) and optional chain devnet"]
+async fn postgres_requesters_roundtrip() {
+    let Some(storage) = connect().await else {
+        return;
+    };
+    let requester = StoredRequester {
+        id: unique("requester"),
+        public_key: "11".repeat(32),
+        registered_at: current_unix_timestamp(),
+    };
+    storage.upsert_requester(requester.clone()).await.unwrap();
+    let rotated = StoredRequester {
+        public_key: "22".repeat(32),
+        ..requester.clone()
+    };
+    storage.upsert_requester(rotated.clone()).await.unwrap();
+    let fetched = storage.get_requester(&requester.id).await.unwrap();
+    assert_eq!(fetched.public_key, rotated.public_key);
+    assert!(matches!(
+        storage.get_requester(&unique("requester")).await,
+        Err(ApiError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+#[ignore = "requires local Postgres (DATABASE_URL) and optional chain devnet"]
+async fn postgres_tasks_are_listed_and_updated_by_version() {
+    let Some(storage) = connect().await else {
+        return;
+    };
+    let task = seed_task(&storage).await;
+    let fetched = storage.get_task(&task.id).await.unwrap();
+    assert_eq!(
+        (fetched.id.as_str(), fetched.version),
+        (task.id.as_str(), 0)
+    );
+
+    let page = PageRequest::from_query(None, Some(1)).unwrap();
+    let listed = storage
+        .list_tasks(
+            &TaskFilter {
+                requester_id: Some(task.requester_id.clone()),
+                task_type: Some("echo".into()),
+                ..Default::default()
+            },
+            &page,
+        )
+        .await
+        .unwrap();
+    assert_eq!(listed.items.len(), 1);
+    assert_eq!(listed.items[0].requester_id, task.requester_id);
+
+    // Only the first of two writers that read the same version lands.
+    let mut cancelled = fetched.clone();
+    cancelled.transition_to(TaskStatus::Cancelled).unwrap();
+    storage.update_task(&mut cancelled).await.unwrap();
+    let mut allocated = fetched;
+    allocated.transition_to(TaskStatus::Allocated).unwrap();
+    assert!(matches!(
+        storage.update_task(&mut allocated).await,
+        Err(ApiError::Conflict(_))
+    ));
+    let stored = storage.get_task(&task.id).await.unwrap();
+    assert_eq!((stored.status, stored.version), (TaskStatus::Cancelled, 1));
+
+    // Unconditional writes bump the version too.
+    storage.upsert_task(stored).await.unwrap();
+    assert_eq!(storage.get_task(&task.id).await.unwrap().version, 2);
+}
+
+#[tokio::test]
+#[ignore = "requires local Postgres (DATABASE_URL) and optional chain devnet"]
+async fn postgres_bids_roundtrip() {
+    let Some(storage) = connect().await else {
+        return;
+    };
+    let agent = seed_agent(&storage).await;
+    let task = seed_task(&storage).await;
+    let bid = bid_on(&task, &agent.id);
+    storage.insert_bid(bid.clone()).await.unwrap();
+    // A second bid by the same agent conflicts, as with in-memory storage.
+    let mut repeat = bid.clone();
+    repeat.id = uuid::Uuid::new_v4().to_string();
+    assert!(matches!(
+        storage.insert_bid(repeat).await,
+        Err(ApiError::Conflict(_))
+    ));
+    assert_eq!(storage.get_bids_for_task(&task.id).await.unwrap().len(), 1);
+    let open_bids = storage
+        .get_bids_for_agent(&agent.id, &[TaskStatus::Open, TaskStatus::Bidding])
+        .await
+        .unwrap();
+    assert_eq!(open_bids.len(), 1);
+
+    let page = PageRequest::from_query(None, Some(1)).unwrap();
+    let listed = storage
+        .list_bids_for_task(
+            &task.id,
+            &BidFilter {
+                agent_id: Some(agent.id.clone()),
+                min_value: Some(5),
+                max_value: Some(5),
+            },
+            &page,
+        )
+        .await
+        .unwrap();
+    assert_eq!(listed.items.len(), 1);
+    assert!(listed.next_cursor.is_none());
+}
+
+#[tokio::test]
+#[ignore = "requires local Postgres (DATABASE_URL) and optional chain devnet"]
+async fn postgres_results_roundtrip() {
+    let Some(storage) = connect().await else {
+        return;
+    };
+    let agent = seed_agent(&storage).await;
+    let task = seed_task(&storage).await;
+    let result = StoredResult::from_submission(
+        ResultSubmissionRequest {
+            task_id: task.id.clone(),
+            agent_id: agent.id.clone(),
+            output_base64: general_purpose::STANDARD.encode(r#"{"msg":"hi","echo":true}"#),
+            signature: None,
+        },
+        &task,
+    )
+    .unwrap();
+    storage.insert_result(result.clone()).await.unwrap();
+
+    let fetched = storage.get_results_for_task(&task.id).await.unwrap();
+    assert_eq!(fetched.len(), 1);
+    assert_eq!(fetched[0].id, result.id);
+}
+
+#[tokio::test]
+#[ignore = "requires local Postgres (DATABASE_URL) and optional chain devnet"]
+async fn postgres_notifications_roundtrip() {
+    let Some(storage) = connect().await else {
+        return;
     };
+    let agent = seed_agent(&storage).await;
+    let task = seed_task(&storage).await;
+    storage
+        .insert_notification(StoredNotification {
+            id: uuid::Uuid::new_v4().to_string(),
+            agent_id: agent.id.clone(),
+            task_id: task.id.clone(),
+            kind: NotificationKind::TaskUpdated,
+            message: "deadline extended".into(),
+            created_at: current_unix_timestamp(),
+        })
+        .await
+        .unwrap();
+    let notifications = storage
+        .get_notifications_for_agent(&agent.id)
+        .await
+        .unwrap();
+    assert_eq!(notifications.len(), 1);
+    assert_eq!(notifications[0].kind, NotificationKind::TaskUpdated);
+}
 
-    let stored_task = StoredTask::from_submission(task_submission).unwrap();
-    let task_id = stored_task.id.clone();
-    storage.insert_task(stored_task.clone()).await.unwrap();
+#[tokio::test]
+#[ignore = "requires local Postgres (DATABASE_URL) and optional chain devnet"]
+async fn postgres_webhooks_and_deliveries_roundtrip() {
+    let Some(storage) = connect().await else {
+        return;
+    };
+    let task = seed_task(&storage).await;
+    let webhook = StoredWebhook::from_registration(
+        WebhookRegistrationRequest {
+            requester_id: task.requester_id.clone(),
+            url: "https://hooks.example/ainur".into(),
+            task_id: Some(task.id.clone()),
+            events: vec![EventKind::TaskCompleted],
+            signature: None,
+        },
+        "secret".into(),
+        current_unix_timestamp(),
+    )
+    .unwrap();
+    storage.insert_webhook(webhook.clone()).await.unwrap();
+    let webhooks = storage
+        .get_webhooks_for_requester(&task.requester_id)
+        .await
+        .unwrap();
+    assert!(webhooks.iter().any(|w| w.id == webhook.id));
 
-    let fetched_task = storage.get_task(&task_id).await.unwrap();
-    assert_eq!(fetched_task.id, task_id);
+    let mut delivery = StoredWebhookDelivery {
+        id: uuid::Uuid::new_v4().to_string(),
+        webhook_id: webhook.id.clone(),
+        event: TaskEvent {
+            id: 1,
+            kind: EventKind::TaskCompleted,
+            task_id: task.id.clone(),
+            agent_id: None,
+            data: serde_json::json!({}),
+            created_at: webhook.created_at,
+        },
+        status: WebhookDeliveryStatus::Pending,
+        attempts: Vec::new(),
+        next_attempt_at: webhook.created_at,
+        created_at: webhook.created_at,
+    };
+    storage
+        .upsert_webhook_delivery(delivery.clone())
+        .await
+        .unwrap();
+    let due = storage
+        .get_due_webhook_deliveries(webhook.created_at)
+        .await
+        .unwrap();
+    assert!(due.iter().any(|d| d.id == delivery.id));
+    delivery.status = WebhookDeliveryStatus::Delivered;
+    storage
+        .upsert_webhook_delivery(delivery.clone())
+        .await
+        .unwrap();
+    let log = storage.get_webhook_deliveries(&webhook.id).await.unwrap();
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].status, WebhookDeliveryStatus::Delivered);
+}
 
-    let bid_submission = BidSubmissionRequest {
-        task_id: task_id.clone(),
-        agent_id: agent.id.clone(),
-        value: 5,
-        quality_score: 90,
-        completion_time: 10,
+#[tokio::test]
+#[ignore = "requires local Postgres (DATABASE_URL) and optional chain devnet"]
+async fn postgres_idempotency_keys_roundtrip() {
+    let Some(storage) = connect().await else {
+        return;
+    };
+    let claim = StoredIdempotencyKey {
+        key: unique("tasks:requester"),
+        request_hash: "hash".into(),
+        response: None,
+        created_at: current_unix_timestamp(),
     };
+    assert!(storage
+        .reserve_idempotency_key(claim.clone(), 0)
+        .await
+        .unwrap()
+        .is_none());
+    storage
+        .complete_idempotency_key(&claim.key, serde_json::json!({"id": "task"}))
+        .await
+        .unwrap();
+    let existing = storage
+        .reserve_idempotency_key(claim.clone(), 0)
+        .await
+        .unwrap()
+        .expect("key already claimed");
+    assert_eq!(existing.response, Some(serde_json::json!({"id": "task"})));
+    // Once expired, the key is claimed afresh.
+    assert!(storage
+        .reserve_idempotency_key(claim.clone(), claim.created_at + 1)
+        .await
+        .unwrap()
+        .is_none());
+    storage.release_idempotency_key(&claim.key).await.unwrap();
+}
 
-    let stored_bid = StoredBid::from_submission(bid_submission, &stored_task).unwrap();
-    storage.insert_bid(stored_bid.clone()).await.unwrap();
-    let bids = storage.get_bids_for_task(&task_id).await.unwrap();
-    assert_eq!(bids.len(), 1);
+#[tokio::test]
+#[ignore = "requires local Postgres (DATABASE_URL) and optional chain devnet"]
+async fn postgres_request_nonces_are_single_use() {
+    let Some(storage) = connect().await else {
+        return;
+    };
+    let signer = unique("requester");
+    let nonce = uuid::Uuid::new_v4().simple().to_string();
+    let now = current_unix_timestamp();
+    for fresh in [true, false] {
+        assert_eq!(
+            storage
+                .use_request_nonce(&signer, &nonce, now + 60, now)
+                .await
+                .unwrap(),
+            fresh
+        );
+    }
+}
 
-    let result_submission = ResultSubmissionRequest {
-        task_id: task_id.clone(),
-        agent_id: agent.id.clone(),
-        output_base64: general_purpose::STANDARD.encode(r#"{"msg":"hi","echo":true}"#),
+#[tokio::test]
+#[ignore = "requires local Postgres (DATABASE_URL) and optional chain devnet"]
+async fn postgres_ledger_roundtrip() {
+    let Some(storage) = connect().await else {
+        return;
     };
+    let task = seed_task(&storage).await;
+    let requester = format!("user:{}", task.requester_id);
+    let balance = |transactions: &[StoredLedgerTransaction]| {
+        LedgerView::from_transactions(&requester, transactions).balance
+    };
+
+    // Ledger amounts round-trip up to BIGINT; `stored_json` holds no more.
+    let amount = i64::MAX as u128;
+    let escrow = format!("escrow:{}", task.id);
+    let lock = StoredLedgerTransaction::new(
+        LedgerTransactionKind::EscrowLock,
+        Some(&task.id),
+        "escrow locked",
+        amount,
+        &[
+            (requester.as_str(), -(amount as i128)),
+            (escrow.as_str(), amount as i128),
+        ],
+        task.created_at,
+    )
+    .unwrap();
+    storage
+        .insert_ledger_transaction(lock.clone())
+        .await
+        .unwrap();
+    let transactions = storage
+        .get_ledger_transactions_for_account(&escrow)
+        .await
+        .unwrap();
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(transactions[0].id, lock.id);
+    assert_eq!(
+        LedgerView::from_transactions(&escrow, &transactions).balance,
+        amount as i128
+    );
+
+    // Paying oneself less a fee posts once per account.
+    let payment = StoredLedgerTransaction::new(
+        LedgerTransactionKind::Payment,
+        Some(&task.id),
+        "payment to self",
+        1_000,
+        &[
+            (requester.as_str(), -1_000),
+            (requester.as_str(), 999),
+            ("treasury", 1),
+        ],
+        task.created_at,
+    )
+    .unwrap();
+    storage.insert_ledger_transaction(payment).await.unwrap();
+    let transactions = storage
+        .get_ledger_transactions_for_account(&requester)
+        .await
+        .unwrap();
+    assert_eq!(balance(&transactions), -(amount as i128) - 1);
 
-    let stored_result =
-        StoredResult::from_submission(result_submission, &stored_task.clone()).unwrap();
-    storage.insert_result(stored_result.clone()).await.unwrap();
+    // Operator funding is a ledger kind of its own.
+    let funding = StoredLedgerTransaction::new(
+        LedgerTransactionKind::Funding,
+        None,
+        "account funded",
+        amount,
+        &[
+            ("external", -(amount as i128)),
+            (requester.as_str(), amount as i128),
+        ],
+        task.created_at,
+    )
+    .unwrap();
+    storage.insert_ledger_transaction(funding).await.unwrap();
+    let transactions = storage
+        .get_ledger_transactions_for_account(&requester)
+        .await
+        .unwrap();
+    assert_eq!(balance(&transactions), -1);
+}
 
-    let fetched_result = storage.get_result_for_task(&task_id).await.unwrap();
-    assert_eq!(fetched_result.id, stored_result.id);
+#[tokio::test]
+#[ignore = "requires local Postgres (DATABASE_URL) and optional chain devnet"]
+async fn postgres_funded_transactions_need_the_funds() {
+    let Some(storage) = connect().await else {
+        return;
+    };
+    let account = format!("user:{}", unique("requester"));
+    let transfer = |amount: i128| {
+        StoredLedgerTransaction::new(
+            LedgerTransactionKind::Funding,
+            None,
+            "account funded",
+            amount as u128,
+            &[("external", -amount), (account.as_str(), amount)],
+            current_unix_timestamp(),
+        )
+        .unwrap()
+    };
+    storage
+        .insert_ledger_transaction(transfer(100))
+        .await
+        .unwrap();
+
+    let spend = |amount: i128| {
+        StoredLedgerTransaction::new(
+            LedgerTransactionKind::Fee,
+            None,
+            "fee",
+            amount as u128,
+            &[(account.as_str(), -amount), ("treasury", amount)],
+            current_unix_timestamp(),
+        )
+        .unwrap()
+    };
+    assert!(!storage
+        .insert_funded_ledger_transaction(spend(101), &account)
+        .await
+        .unwrap());
+    assert!(storage
+        .insert_funded_ledger_transaction(spend(100), &account)
+        .await
+        .unwrap());
+    let transactions = storage
+        .get_ledger_transactions_for_account(&account)
+        .await
+        .unwrap();
+    assert_eq!(
+        LedgerView::from_transactions(&account, &transactions).balance,
+        0
+    );
 }
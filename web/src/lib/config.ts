@@ -1,6 +1,9 @@
 export const ORCH_URL =
   process.env.NEXT_PUBLIC_ORCHESTRATOR_URL || "http://localhost:8080";
 
+// Read-only orchestrator API key, sent as a bearer token when set.
+export const ORCH_API_KEY = process.env.NEXT_PUBLIC_ORCHESTRATOR_API_KEY || "";
+
 export const CHAIN_WS = process.env.NEXT_PUBLIC_CHAIN_WS || "ws://127.0.0.1:9944";
 
 export const TOKEN_DECIMALS = Number(process.env.NEXT_PUBLIC_TOKEN_DECIMALS || 12);
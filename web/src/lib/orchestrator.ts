@@ -1,6 +1,6 @@
 // Orchestrator client with graceful fallback to mock data when API is unreachable.
 
-import { ORCH_URL } from "@/lib/config";
+import { ORCH_API_KEY, ORCH_URL } from "@/lib/config";
 
 type Dashboard = {
   stats: {
@@ -61,6 +61,11 @@ export type TaskDetail = {
   timeline: { title: string; ts: string; state: "done" | "active" | "pending" }[];
 };
 
+type Page<T> = {
+  items: T[];
+  next_cursor?: string | null;
+};
+
 type RawAgent = {
   id?: string;
   did?: string;
@@ -96,7 +101,11 @@ async function api<T>(path: string, init?: RequestInit, fallback?: () => Promise
   try {
     const res = await fetch(`${ORCH_URL}${path}`, {
       ...init,
-      headers: { "content-type": "application/json", ...(init?.headers || {}) },
+      headers: {
+        "content-type": "application/json",
+        ...(ORCH_API_KEY ? { authorization: `Bearer ${ORCH_API_KEY}` } : {}),
+        ...(init?.headers || {}),
+      },
     });
     if (!res.ok) throw new Error(`${res.status} ${res.statusText}`);
     return (await res.json()) as T;
@@ -141,11 +150,13 @@ export async function fetchAgents(): Promise<Agent[]> {
     latency: raw.latency,
   });
 
-  return api<RawAgent[]>("/v1/agents", undefined, async () => [
-    { id: "did:ainur:0x1234...abcd", label: "Lambda Researcher" },
-    { id: "did:ainur:0xbeef...cafe", label: "Code Sentinel" },
-    { id: "did:ainur:0x9f9f...1a1a", label: "Vision Prover" },
-  ]).then((arr) => arr.map(toAgent));
+  return api<Page<RawAgent>>("/v1/agents", undefined, async () => ({
+    items: [
+      { id: "did:ainur:0x1234...abcd", label: "Lambda Researcher" },
+      { id: "did:ainur:0xbeef...cafe", label: "Code Sentinel" },
+      { id: "did:ainur:0x9f9f...1a1a", label: "Vision Prover" },
+    ],
+  })).then((page) => page.items.map(toAgent));
 }
 
 export async function fetchTasks(): Promise<Task[]> {
@@ -158,16 +169,18 @@ export async function fetchTasks(): Promise<Task[]> {
     created: t.created_at ? new Date(t.created_at * 1000).toLocaleString() : t.created ?? "",
   });
 
-  return api<RawTask[]>("/v1/tasks", undefined, async () => [
-    {
-      id: "task-1",
-      description: "Summarize research PDF",
-      status: "Bidding",
-      max_budget: 50,
-      min_reputation: 60,
-      created_at: Date.now() / 1000,
-    },
-  ]).then((arr) => arr.map(mapTask));
+  return api<Page<RawTask>>("/v1/tasks", undefined, async () => ({
+    items: [
+      {
+        id: "task-1",
+        description: "Summarize research PDF",
+        status: "Bidding",
+        max_budget: 50,
+        min_reputation: 60,
+        created_at: Date.now() / 1000,
+      },
+    ],
+  })).then((page) => page.items.map(mapTask));
 }
 
 export async function fetchTaskDetail(id: string): Promise<TaskDetail | null> {